@@ -28,6 +28,9 @@ pub struct Args {
     /// Include rights applied to files when calculating hash
     #[arg(short, long, default_value_t = false)]
     pub mode: bool,
+    /// Include extended attributes of files when calculating hash
+    #[arg(short, long, default_value_t = false)]
+    pub xattr: bool,
     /// Regex patterns of directories and files to exclude from state and hash
     /// Note: patterns are applied to full paths, relative to mount point
     /// Example: -e "output.log" -e "\w*.rs"
@@ -3,8 +3,10 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use std::cmp::Ordering;
+use std::ffi::OsString;
 use std::fmt::{Display, Formatter};
 use std::hash::Hasher;
+use std::os::unix::ffi::OsStrExt;
 use std::os::unix::fs::MetadataExt;
 use std::path::Path;
 
@@ -17,6 +19,9 @@ use walkdir::WalkDir;
 
 pub const DIFF_FILENAME: &str = "dash-diff.txt";
 
+/// File type bits of `st_mode`.
+const S_IFMT: u32 = 0o170000;
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct FileInfo {
     /// Absolute file path (with mount `/mnt/...` prefix)
@@ -33,13 +38,26 @@ pub struct FileInfo {
     nlink: u64,
     /// Rights applied to file
     mode: u32,
+    /// File type bits of mode (`S_IFMT`), always compared
+    file_type: u32,
+    /// Digest of extended attributes names and values (sorted by name)
+    xattrs: u64,
 
     is_dir: bool,
 }
 
 impl FileInfo {
+    pub fn rel_path(&self) -> &str {
+        &self.rel_path
+    }
+
+    pub fn mode(&self) -> u32 {
+        self.mode
+    }
+
     fn add_to_hasher(&self, hasher: &mut dyn Hasher, hasher_options: &HasherOptions) {
         hasher.write(self.rel_path.as_bytes());
+        hasher.write_u32(self.file_type);
         hasher.write_u32(self.gid);
         hasher.write_u32(self.uid);
         if hasher_options.size {
@@ -51,6 +69,9 @@ impl FileInfo {
         if hasher_options.mode {
             hasher.write_u32(self.mode);
         }
+        if hasher_options.xattr {
+            hasher.write_u64(self.xattrs);
+        }
     }
 }
 
@@ -67,6 +88,7 @@ pub struct HasherOptions {
     pub file_nlink: bool,
     pub dir_nlink: bool,
     pub mode: bool,
+    pub xattr: bool,
 }
 
 impl Display for FileInfo {
@@ -107,6 +129,8 @@ pub fn calc_dir_hash(
             size: metadata.size(),
             nlink: metadata.nlink(),
             mode: metadata.mode(),
+            file_type: metadata.mode() & S_IFMT,
+            xattrs: calc_xattr_hash(entry.path())?,
             is_dir: metadata.is_dir()
         };
         file_info.add_to_hasher(&mut hasher, hasher_options);
@@ -116,6 +140,24 @@ pub fn calc_dir_hash(
     Ok((hasher.finish(), res))
 }
 
+/// Symbolic links are not followed.
+fn calc_xattr_hash(path: &Path) -> anyhow::Result<u64> {
+    let mut hasher = XxHash64::default();
+    let mut names: Vec<OsString> = xattr::list(path)
+        .with_context(|| format!("failed to list xattrs of '{}'", path.display()))?
+        .collect();
+    names.sort();
+    for name in names {
+        let value = xattr::get(path, &name)
+            .with_context(|| format!("failed to get xattr {:?} of '{}'", name, path.display()))?;
+        // names can not contain zero byte
+        hasher.write(name.as_bytes());
+        hasher.write_u8(0);
+        hasher.write(&value.unwrap_or_default());
+    }
+    Ok(hasher.finish())
+}
+
 pub fn calc_fileinfo_hash(
     vec: &Vec<FileInfo>,
     rel_path: &str,
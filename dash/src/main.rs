@@ -25,6 +25,7 @@ fn main() {
         file_nlink: args.file_nlink,
         dir_nlink: args.dir_nlink,
         mode: args.mode,
+        xattr: args.xattr,
     };
 
     let skip = RegexSet::new(args.exclude.unwrap_or(vec![])).unwrap();
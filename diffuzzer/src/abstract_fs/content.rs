@@ -28,19 +28,59 @@ impl SourceSlice {
     }
 }
 
+/// Part of file contents.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Slice {
+    /// Data written from "source".
+    Data(SourceSlice),
+    /// Range that was never written (e.g. file extended by `truncate`), reads as zeros.
+    Hole(u64),
+    /// Range that is allocated, but reads as zeros (e.g. preallocated by `fallocate`).
+    Zero(u64),
+}
+
+impl Slice {
+    pub fn size(&self) -> u64 {
+        match self {
+            Slice::Data(s) => s.size(),
+            Slice::Hole(size) | Slice::Zero(size) => *size,
+        }
+    }
+
+    /// Splits slice into two parts, first part is of size `at`.
+    fn split_at(&self, at: u64) -> (Slice, Slice) {
+        match self {
+            Slice::Data(s) => (
+                Slice::Data(SourceSlice {
+                    from: s.from,
+                    to: s.from + at - 1,
+                }),
+                Slice::Data(SourceSlice {
+                    from: s.from + at,
+                    to: s.to,
+                }),
+            ),
+            Slice::Hole(size) => (Slice::Hole(at), Slice::Hole(size - at)),
+            Slice::Zero(size) => (Slice::Zero(at), Slice::Zero(size - at)),
+        }
+    }
+}
+
 /// Emulates contents of file as a sequence of slices of some "source", without storing actual data.
 ///
 /// Example: let the "source" be "abcdefgh",
 /// the a content with slices [(1:4), (6:6), (6:6)] represents "bcdegg".
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Content {
-    slices: VecDeque<SourceSlice>,
+    slices: VecDeque<Slice>,
 }
 
 #[derive(Error, Debug, PartialEq, Eq)]
 pub enum ContentError {
     #[error("bad offset '{0}' (expected range 0..{1})")]
     BadOffset(u64, u64),
+    #[error("bad range at offset '{0}' of size '{1}' (must end before {2})")]
+    BadRange(u64, u64, u64),
 }
 
 impl Content {
@@ -51,20 +91,59 @@ impl Content {
     }
 
     #[allow(dead_code)]
-    pub fn slices(&self) -> Vec<SourceSlice> {
+    pub fn slices(&self) -> Vec<Slice> {
         self.slices.iter().map(|s| s.to_owned()).collect()
     }
 
     /// Similar to "append" mode when writing to file, writes data at the end.
     pub fn write_back(&mut self, src_offset: u64, size: u64) {
         if size > 0 {
-            self.slices.push_back(SourceSlice {
+            self.push_back(Slice::Data(SourceSlice {
                 from: src_offset,
                 to: src_offset + size - 1,
-            });
+            }));
+        }
+    }
+
+    /// Appends slice at the end, adjacent holes (or zeros) are merged.
+    fn push_back(&mut self, slice: Slice) {
+        if slice.size() == 0 {
+            return;
+        }
+        match (self.slices.back_mut(), &slice) {
+            (Some(Slice::Hole(last)), Slice::Hole(size))
+            | (Some(Slice::Zero(last)), Slice::Zero(size)) => *last += size,
+            _ => self.slices.push_back(slice),
+        }
+    }
+
+    fn append(&mut self, other: Content) {
+        for slice in other.slices {
+            self.push_back(slice);
         }
     }
 
+    /// Splits content into two parts, first part is of size `offset` (or less, if content is smaller).
+    fn split_at(&self, offset: u64) -> (Content, Content) {
+        let mut head = Content::new();
+        let mut tail = Content::new();
+        let mut curr_offset = 0;
+        for slice in self.slices.iter() {
+            let next_offset = curr_offset + slice.size();
+            if next_offset <= offset {
+                head.push_back(slice.clone());
+            } else if curr_offset >= offset {
+                tail.push_back(slice.clone());
+            } else {
+                let (fst, snd) = slice.split_at(offset - curr_offset);
+                head.push_back(fst);
+                tail.push_back(snd);
+            }
+            curr_offset = next_offset;
+        }
+        (head, tail)
+    }
+
     /// Writes some slice of "source" data, at the specified offset.
     pub fn write(
         &mut self,
@@ -72,72 +151,23 @@ impl Content {
         write_offset: u64,
         size: u64,
     ) -> Result<(), ContentError> {
-        if write_offset == self.size() {
-            self.write_back(src_offset, size);
-            return Ok(());
-        }
-        if write_offset > self.size() {
-            return Err(ContentError::BadOffset(write_offset, self.size()));
-        }
+        let mut data = Content::new();
+        data.write_back(src_offset, size);
+        self.write_content(write_offset, data)
+    }
+
+    /// Writes data (e.g. read from another content) at the specified offset.
+    pub fn write_content(&mut self, write_offset: u64, data: Content) -> Result<(), ContentError> {
         let old_size = self.size();
-        if size > 0 {
-            let mut curr_offset = 0;
-            let mut write_at_index = 0;
-            for i in 0..self.slices.len() {
-                let slice = &mut self.slices[i];
-                let next_offset = curr_offset + slice.size();
-                if curr_offset == write_offset {
-                    write_at_index = i;
-                    break;
-                } else if next_offset > write_offset {
-                    let old_size = slice.size();
-                    let fst_half_to = slice.from + (write_offset - curr_offset - 1);
-                    let snd_half_to = slice.to;
-                    slice.to = fst_half_to;
-                    self.slices.insert(
-                        i + 1,
-                        SourceSlice {
-                            from: fst_half_to + 1,
-                            to: snd_half_to,
-                        },
-                    );
-                    let fst_half_size = self.slices[i].size();
-                    let snd_half_size = self.slices[i + 1].size();
-                    assert!(
-                        old_size == fst_half_size + snd_half_size,
-                        "old: {}, fst: {}, snd: {}",
-                        old_size,
-                        fst_half_size,
-                        snd_half_size
-                    );
-                    write_at_index = i + 1;
-                    break;
-                }
-                curr_offset = next_offset;
-            }
-            self.slices.insert(
-                write_at_index,
-                SourceSlice {
-                    from: src_offset,
-                    to: src_offset + size - 1,
-                },
-            );
-            let truncate_from_index = write_at_index + 1;
-            let mut truncate_size = size;
-            for i in truncate_from_index..self.slices.len() {
-                let slice = &mut self.slices[i];
-                let can_truncate_size = slice.size();
-                if can_truncate_size > truncate_size {
-                    slice.from += truncate_size;
-                    break;
-                }
-                // make slice size zero
-                slice.from = 100000;
-                slice.to = 0;
-                truncate_size -= can_truncate_size;
-            }
-            self.slices.retain(|s| s.size() > 0);
+        if write_offset > old_size {
+            return Err(ContentError::BadOffset(write_offset, old_size));
         }
+        let size = data.size();
+        let (mut head, tail) = self.split_at(write_offset);
+        let (_, rest) = tail.split_at(size);
+        head.append(data);
+        head.append(rest);
+        *self = head;
         let new_size = self.size();
         let expected_size = max(write_offset + size, old_size);
         assert!(
@@ -147,9 +177,6 @@ impl Content {
             expected_size,
             self.slices
         );
-        for s in self.slices.iter() {
-            assert!(s.size() > 0, "{:?}", self.slices);
-        }
         Ok(())
     }
 
@@ -158,47 +185,129 @@ impl Content {
         if offset > self.size() {
             return Err(ContentError::BadOffset(offset, self.size()));
         }
-        let mut content = Content::new();
-        let mut current_offset = 0;
-        let mut read_size = 0;
-        let mut reading = false;
-        let mut end_of_file = true;
-        for s in self.slices.iter() {
-            if reading {
-                if read_size >= size {
-                    end_of_file = false;
-                    break;
-                }
-                let mut slice_read_size = s.size();
-                if read_size + slice_read_size > size {
-                    slice_read_size = size - read_size;
-                }
-                content.write_back(s.from, slice_read_size);
-                read_size += slice_read_size;
-            } else {
-                let next_offset = current_offset + s.size();
-                if next_offset > offset {
-                    reading = true;
-                    let read_from = offset - current_offset;
-                    let mut slice_read_size = s.size() - read_from;
-                    if slice_read_size > size {
-                        slice_read_size = size;
-                    }
-                    content.write_back(s.from + read_from, slice_read_size);
-                    read_size += slice_read_size;
-                    continue;
-                }
-                current_offset = next_offset;
+        let (_, tail) = self.split_at(offset);
+        let (content, _) = tail.split_at(size);
+        Ok(content)
+    }
+
+    /// Shrinks content to the specified size or extends it with a hole, similar to `truncate`.
+    pub fn truncate(&mut self, size: u64) {
+        let old_size = self.size();
+        if size <= old_size {
+            let (head, _) = self.split_at(size);
+            *self = head;
+        } else {
+            self.push_back(Slice::Hole(size - old_size));
+        }
+    }
+
+    /// Replaces slices in range of the specified offset and size (limited by content size).
+    fn map_range(&mut self, offset: u64, size: u64, f: impl Fn(Slice) -> Slice) {
+        let (mut head, tail) = self.split_at(offset);
+        let (range, rest) = tail.split_at(size);
+        for slice in range.slices {
+            head.push_back(f(slice));
+        }
+        head.append(rest);
+        *self = head;
+    }
+
+    /// Extends content with zeros up to the end of range, gap before range becomes a hole.
+    fn extend_zero(&mut self, offset: u64, size: u64) {
+        let old_size = self.size();
+        let end = offset + size;
+        if end > old_size {
+            if offset > old_size {
+                self.truncate(offset);
             }
+            self.push_back(Slice::Zero(end - max(offset, old_size)));
         }
-        assert!(
-            content.size() == read_size || end_of_file && content.size() <= read_size,
-            "read: {}, want: {}, end: {}",
-            content.size(),
-            read_size,
-            end_of_file,
-        );
-        Ok(content)
+    }
+
+    /// Allocates range, similar to `fallocate` with default mode.
+    /// Holes in range become zeros, content is extended unless `keep_size` is set.
+    pub fn allocate(&mut self, offset: u64, size: u64, keep_size: bool) {
+        self.map_range(offset, size, |slice| match slice {
+            Slice::Hole(size) => Slice::Zero(size),
+            slice => slice,
+        });
+        if !keep_size {
+            self.extend_zero(offset, size);
+        }
+    }
+
+    /// Replaces range with zeros, similar to `FALLOC_FL_ZERO_RANGE`.
+    /// Content is extended unless `keep_size` is set.
+    pub fn zero_range(&mut self, offset: u64, size: u64, keep_size: bool) {
+        self.map_range(offset, size, |slice| Slice::Zero(slice.size()));
+        if !keep_size {
+            self.extend_zero(offset, size);
+        }
+    }
+
+    /// Deallocates range, similar to `FALLOC_FL_PUNCH_HOLE`. Size is not changed.
+    pub fn punch_hole(&mut self, offset: u64, size: u64) {
+        self.map_range(offset, size, |slice| Slice::Hole(slice.size()));
+    }
+
+    /// Removes range and shifts the rest, similar to `FALLOC_FL_COLLAPSE_RANGE`.
+    /// Range must end before the end of content.
+    pub fn collapse_range(&mut self, offset: u64, size: u64) -> Result<(), ContentError> {
+        let old_size = self.size();
+        if offset + size >= old_size {
+            return Err(ContentError::BadRange(offset, size, old_size));
+        }
+        let (mut head, tail) = self.split_at(offset);
+        let (_, rest) = tail.split_at(size);
+        head.append(rest);
+        *self = head;
+        Ok(())
+    }
+
+    /// Inserts hole at offset and shifts the rest, similar to `FALLOC_FL_INSERT_RANGE`.
+    /// Offset must be less than content size.
+    pub fn insert_range(&mut self, offset: u64, size: u64) -> Result<(), ContentError> {
+        let old_size = self.size();
+        if offset >= old_size {
+            return Err(ContentError::BadOffset(offset, old_size));
+        }
+        let (mut head, tail) = self.split_at(offset);
+        head.push_back(Slice::Hole(size));
+        head.append(tail);
+        *self = head;
+        Ok(())
+    }
+
+    /// Finds the smallest offset of data not less than `offset`, similar to `SEEK_DATA`.
+    /// Zeros are considered as holes, as filesystems report unwritten ranges this way.
+    pub fn seek_data(&self, offset: u64) -> Option<u64> {
+        let mut curr_offset = 0;
+        for slice in self.slices.iter() {
+            let next_offset = curr_offset + slice.size();
+            if matches!(slice, Slice::Data(_)) && next_offset > offset {
+                return Some(max(curr_offset, offset));
+            }
+            curr_offset = next_offset;
+        }
+        None
+    }
+
+    /// Finds the smallest offset of hole not less than `offset`, similar to `SEEK_HOLE`.
+    /// There is an implicit hole at the end of file, zeros are also considered as holes.
+    pub fn seek_hole(&self, offset: u64) -> Option<u64> {
+        let size = self.size();
+        if offset >= size {
+            return None;
+        }
+        let mut curr_offset = 0;
+        for slice in self.slices.iter() {
+            let next_offset = curr_offset + slice.size();
+            if matches!(slice, Slice::Hole(_) | Slice::Zero(_)) && next_offset > offset {
+                return Some(max(curr_offset, offset));
+            }
+            curr_offset = next_offset;
+        }
+        Some(size)
     }
 
     pub fn size(&self) -> u64 {
@@ -210,7 +319,7 @@ impl Content {
 mod tests {
     use crate::abstract_fs::content::ContentError;
 
-    use super::Content;
+    use super::{Content, Slice, SourceSlice};
 
     #[test]
     fn test_read_empty() {
@@ -318,4 +427,206 @@ mod tests {
         expected.write_back(512, 100000);
         assert_eq!(expected, content)
     }
+
+    #[test]
+    fn test_truncate_shrink() {
+        let mut content = Content::new();
+        content.write_back(42, 100);
+        content.write_back(1, 2);
+        content.truncate(50);
+        let mut expected = Content::new();
+        expected.write_back(42, 50);
+        assert_eq!(expected, content);
+        content.truncate(0);
+        assert_eq!(Content::new(), content);
+    }
+
+    #[test]
+    fn test_truncate_extend() {
+        let mut content = Content::new();
+        content.write_back(42, 100);
+        content.truncate(150);
+        content.truncate(200);
+        assert_eq!(200, content.size());
+        assert_eq!(
+            vec![
+                Slice::Data(SourceSlice {
+                    from: 42,
+                    to: 42 + 100 - 1
+                }),
+                Slice::Hole(100)
+            ],
+            content.slices()
+        );
+    }
+
+    #[test]
+    fn test_write_content() {
+        let mut content = Content::new();
+        content.write_back(42, 100);
+        let mut data = Content::new();
+        data.truncate(10);
+        data.write_back(13, 20);
+        content.write_content(90, data).unwrap();
+        assert_eq!(
+            vec![
+                Slice::Data(SourceSlice {
+                    from: 42,
+                    to: 42 + 90 - 1
+                }),
+                Slice::Hole(10),
+                Slice::Data(SourceSlice {
+                    from: 13,
+                    to: 13 + 20 - 1
+                })
+            ],
+            content.slices()
+        );
+    }
+
+    #[test]
+    fn test_write_into_hole() {
+        let mut content = Content::new();
+        content.truncate(100);
+        content.write(13, 10, 20).unwrap();
+        assert_eq!(
+            vec![
+                Slice::Hole(10),
+                Slice::Data(SourceSlice {
+                    from: 13,
+                    to: 13 + 20 - 1
+                }),
+                Slice::Hole(70)
+            ],
+            content.slices()
+        );
+        assert_eq!(
+            vec![
+                Slice::Data(SourceSlice {
+                    from: 13 + 15,
+                    to: 13 + 20 - 1
+                }),
+                Slice::Hole(5)
+            ],
+            content.read(25, 10).unwrap().slices()
+        );
+    }
+
+    #[test]
+    fn test_seek_data_hole() {
+        let mut content = Content::new();
+        content.truncate(10);
+        content.write_back(42, 10);
+        content.truncate(30);
+        assert_eq!(Some(10), content.seek_data(0));
+        assert_eq!(Some(15), content.seek_data(15));
+        assert_eq!(None, content.seek_data(20));
+        assert_eq!(Some(0), content.seek_hole(0));
+        assert_eq!(Some(20), content.seek_hole(10));
+        assert_eq!(Some(25), content.seek_hole(25));
+        assert_eq!(None, content.seek_hole(30));
+    }
+
+    #[test]
+    fn test_seek_hole_end() {
+        let mut content = Content::new();
+        content.write_back(42, 10);
+        assert_eq!(Some(10), content.seek_hole(5));
+        assert_eq!(None, content.seek_hole(10));
+        assert_eq!(None, Content::new().seek_data(0));
+    }
+
+    #[test]
+    fn test_allocate() {
+        let mut content = Content::new();
+        content.write_back(42, 10);
+        content.truncate(20);
+        content.allocate(10, 5, false);
+        assert_eq!(
+            vec![
+                Slice::Data(SourceSlice {
+                    from: 42,
+                    to: 42 + 10 - 1
+                }),
+                Slice::Zero(5),
+                Slice::Hole(5)
+            ],
+            content.slices()
+        );
+        content.allocate(30, 10, true);
+        assert_eq!(20, content.size());
+        content.allocate(30, 10, false);
+        assert_eq!(
+            vec![
+                Slice::Data(SourceSlice {
+                    from: 42,
+                    to: 42 + 10 - 1
+                }),
+                Slice::Zero(5),
+                Slice::Hole(15),
+                Slice::Zero(10)
+            ],
+            content.slices()
+        );
+    }
+
+    #[test]
+    fn test_punch_hole_zero_range() {
+        let mut content = Content::new();
+        content.write_back(42, 30);
+        content.punch_hole(10, 10);
+        content.zero_range(15, 10, false);
+        content.punch_hole(25, 100);
+        content.zero_range(40, 10, true);
+        assert_eq!(
+            vec![
+                Slice::Data(SourceSlice {
+                    from: 42,
+                    to: 42 + 10 - 1
+                }),
+                Slice::Hole(5),
+                Slice::Zero(10),
+                Slice::Hole(5)
+            ],
+            content.slices()
+        );
+        content.zero_range(20, 20, false);
+        assert_eq!(40, content.size());
+        assert_eq!(None, content.seek_data(10));
+        assert_eq!(Some(10), content.seek_hole(0));
+    }
+
+    #[test]
+    fn test_collapse_insert_range() {
+        let mut content = Content::new();
+        content.write_back(42, 30);
+        assert_eq!(
+            Err(ContentError::BadRange(20, 10, 30)),
+            content.collapse_range(20, 10)
+        );
+        assert_eq!(
+            Err(ContentError::BadOffset(30, 30)),
+            content.insert_range(30, 10)
+        );
+        content.collapse_range(10, 10).unwrap();
+        content.insert_range(5, 10).unwrap();
+        assert_eq!(
+            vec![
+                Slice::Data(SourceSlice {
+                    from: 42,
+                    to: 42 + 5 - 1
+                }),
+                Slice::Hole(10),
+                Slice::Data(SourceSlice {
+                    from: 42 + 5,
+                    to: 42 + 10 - 1
+                }),
+                Slice::Data(SourceSlice {
+                    from: 42 + 20,
+                    to: 42 + 30 - 1
+                }),
+            ],
+            content.slices()
+        );
+    }
 }
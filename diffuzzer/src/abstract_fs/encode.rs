@@ -2,65 +2,172 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use std::cmp::max;
+use std::{cmp::max, collections::HashMap, fmt::Display};
 
-use super::{flags::Mode, node::FileDescriptorIndex, operation::Operation, workload::Workload};
+use super::{
+    flags::{
+        AtFlags, FallocateMode, Mode, MsyncFlags, OpenFlags, RenameFlags, SyncFileRangeFlags,
+        TimeSpec, XattrFlags,
+    },
+    node::{DirDescriptorIndex, DirFd, FileDescriptorIndex, MappingIndex},
+    operation::Operation,
+    workload::Workload,
+};
 
 /// Generates name of variable for the descriptor.
 fn descriptor_to_var(des: &FileDescriptorIndex) -> String {
     format!("fd_{}", des.0)
 }
 
+/// Generates name of variable for the directory descriptor.
+fn dir_descriptor_to_var(des: &DirDescriptorIndex) -> String {
+    format!("dfd_{}", des.0)
+}
+
+/// Generates name of variable for the mapping.
+fn mapping_to_var(map: &MappingIndex) -> String {
+    format!("map_{}", map.0)
+}
+
 impl Workload {
     /// Generates C code from workload, that can be run after building with executor.
     pub fn encode_c(&self) -> String {
         let mut result = String::new();
         result.push_str("#include \"executor.h\"\n");
         let mut descriptors_n = 0;
+        let mut dir_descriptors_n = 0;
+        // Length is required to sync and unmap the whole mapping.
+        let mut mapping_lens: HashMap<MappingIndex, u64> = HashMap::new();
         for op in self.ops.iter() {
-            if let Operation::Open { path: _, des } = op {
-                descriptors_n = max(descriptors_n, des.0 + 1);
+            // New descriptors are introduced only by `open`, `openat` and `dup`.
+            match op {
+                Operation::Open {
+                    path: _,
+                    flags: _,
+                    mode: _,
+                    des,
+                } => {
+                    descriptors_n = max(descriptors_n, des.0 + 1);
+                }
+                Operation::OpenAt {
+                    dir: _,
+                    path: _,
+                    flags: _,
+                    mode: _,
+                    des,
+                } => {
+                    descriptors_n = max(descriptors_n, des.0 + 1);
+                }
+                Operation::OpenDir {
+                    dir: _,
+                    path: _,
+                    flags: _,
+                    des,
+                } => {
+                    dir_descriptors_n = max(dir_descriptors_n, des.0 + 1);
+                }
+                Operation::Dup {
+                    old_des: _,
+                    new_des,
+                } => {
+                    descriptors_n = max(descriptors_n, new_des.0 + 1);
+                }
+                Operation::MMap {
+                    des: _,
+                    offset: _,
+                    len,
+                    map,
+                } => {
+                    mapping_lens.insert(*map, *len);
+                }
+                _ => {}
             }
         }
         if descriptors_n > 0 {
             let descriptors_vars: Vec<String> =
                 (0..descriptors_n).map(|it| format!("fd_{}", it)).collect();
-            result.push_str(format!("\nint {};\n\n", descriptors_vars.join(", ")).as_str());
+            result.push_str(format!("\nint {};\n", descriptors_vars.join(", ")).as_str());
         } else {
-            result.push_str("\n// no descriptors\n\n");
+            result.push_str("\n// no descriptors\n");
+        }
+        if dir_descriptors_n > 0 {
+            let dir_descriptors_vars: Vec<String> = (0..dir_descriptors_n)
+                .map(|it| dir_descriptor_to_var(&DirDescriptorIndex(it)))
+                .collect();
+            result.push_str(format!("int {};\n", dir_descriptors_vars.join(", ")).as_str());
         }
+        if !mapping_lens.is_empty() {
+            let mut mappings: Vec<&MappingIndex> = mapping_lens.keys().collect();
+            mappings.sort();
+            let mappings_vars: Vec<String> = mappings
+                .into_iter()
+                .map(|it| format!("*{}", mapping_to_var(it)))
+                .collect();
+            result.push_str(format!("char {};\n", mappings_vars.join(", ")).as_str());
+        }
+        result.push('\n');
         result.push_str("void test_workload()\n");
         result.push_str("{\n");
         for op in &self.ops {
             match op {
                 Operation::Create { path, mode } => {
                     result.push_str(
-                        format!("do_create(\"{}\", {});\n", path, encode_mode(mode).as_str())
-                            .as_str(),
+                        format!(
+                            "do_create({}, {});\n",
+                            encode_str(path),
+                            encode_mode(mode).as_str()
+                        )
+                        .as_str(),
                     );
                 }
                 Operation::MkDir { path, mode } => {
                     result.push_str(
-                        format!("do_mkdir(\"{}\", {});\n", path, encode_mode(mode).as_str())
-                            .as_str(),
+                        format!(
+                            "do_mkdir({}, {});\n",
+                            encode_str(path),
+                            encode_mode(mode).as_str()
+                        )
+                        .as_str(),
                     );
                 }
                 Operation::Remove { path } => {
-                    result.push_str(format!("do_remove(\"{}\");\n", path).as_str());
+                    result.push_str(format!("do_remove({});\n", encode_str(path)).as_str());
                 }
                 Operation::Hardlink { old_path, new_path } => {
                     result.push_str(
-                        format!("do_hardlink(\"{}\", \"{}\");\n", old_path, new_path).as_str(),
+                        format!(
+                            "do_hardlink({}, {});\n",
+                            encode_str(old_path),
+                            encode_str(new_path)
+                        )
+                        .as_str(),
                     );
                 }
                 Operation::Rename { old_path, new_path } => {
                     result.push_str(
-                        format!("do_rename(\"{}\", \"{}\");\n", old_path, new_path).as_str(),
+                        format!(
+                            "do_rename({}, {});\n",
+                            encode_str(old_path),
+                            encode_str(new_path)
+                        )
+                        .as_str(),
                     );
                 }
-                Operation::Open { path, des } => {
+                Operation::Open {
+                    path,
+                    flags,
+                    mode,
+                    des,
+                } => {
                     result.push_str(
-                        format!("{} = do_open(\"{}\");\n", descriptor_to_var(des), path).as_str(),
+                        format!(
+                            "{} = do_open({}, {}, {});\n",
+                            descriptor_to_var(des),
+                            encode_str(path),
+                            encode_open_flags(flags),
+                            encode_mode(mode)
+                        )
+                        .as_str(),
                     );
                 }
                 Operation::Close { des } => {
@@ -89,11 +196,605 @@ impl Workload {
                 Operation::FSync { des } => {
                     result.push_str(format!("do_fsync({});\n", descriptor_to_var(des)).as_str());
                 }
+                Operation::FDataSync { des } => {
+                    result
+                        .push_str(format!("do_fdatasync({});\n", descriptor_to_var(des)).as_str());
+                }
+                Operation::Sync => {
+                    result.push_str("do_sync();\n");
+                }
+                Operation::SyncFs { des } => {
+                    result.push_str(format!("do_syncfs({});\n", descriptor_to_var(des)).as_str());
+                }
+                Operation::SyncFileRange {
+                    des,
+                    offset,
+                    nbytes,
+                    flags,
+                } => {
+                    result.push_str(
+                        format!(
+                            "do_sync_file_range({}, {}, {}, {});\n",
+                            descriptor_to_var(des),
+                            offset,
+                            nbytes,
+                            encode_sync_file_range_flags(flags)
+                        )
+                        .as_str(),
+                    );
+                }
                 Operation::Symlink { target, linkpath } => {
                     result.push_str(
-                        format!("do_symlink(\"{}\", \"{}\");\n", target, linkpath).as_str(),
+                        format!(
+                            "do_symlink({}, {});\n",
+                            encode_str(target),
+                            encode_str(linkpath)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::Truncate { path, size } => {
+                    result.push_str(
+                        format!("do_truncate({}, {});\n", encode_str(path), size).as_str(),
+                    );
+                }
+                Operation::FTruncate { des, size } => {
+                    result.push_str(
+                        format!("do_ftruncate({}, {});\n", descriptor_to_var(des), size).as_str(),
+                    );
+                }
+                Operation::LSeek {
+                    des,
+                    offset,
+                    whence,
+                } => {
+                    result.push_str(
+                        format!(
+                            "do_lseek({}, {}, {});\n",
+                            descriptor_to_var(des),
+                            offset,
+                            whence
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::PRead { des, size, offset } => {
+                    result.push_str(
+                        format!(
+                            "do_pread({}, {}, {});\n",
+                            descriptor_to_var(des),
+                            size,
+                            offset
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::PWrite {
+                    des,
+                    src_offset,
+                    size,
+                    offset,
+                } => {
+                    result.push_str(
+                        format!(
+                            "do_pwrite({}, {}, {}, {});\n",
+                            descriptor_to_var(des),
+                            src_offset,
+                            size,
+                            offset
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::Dup { old_des, new_des } => {
+                    result.push_str(
+                        format!(
+                            "{} = do_dup({});\n",
+                            descriptor_to_var(new_des),
+                            descriptor_to_var(old_des)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::Dup2 { old_des, new_des } => {
+                    result.push_str(
+                        format!(
+                            "do_dup2({}, {});\n",
+                            descriptor_to_var(old_des),
+                            descriptor_to_var(new_des)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::SetXattr {
+                    path,
+                    name,
+                    src_offset,
+                    size,
+                    flags,
+                } => {
+                    result.push_str(
+                        format!(
+                            "do_setxattr({}, {}, {}, {}, {});\n",
+                            encode_str(path),
+                            encode_str(name),
+                            src_offset,
+                            size,
+                            encode_xattr_flags(flags)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::GetXattr { path, name, size } => {
+                    result.push_str(
+                        format!(
+                            "do_getxattr({}, {}, {});\n",
+                            encode_str(path),
+                            encode_str(name),
+                            size
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::ListXattr { path, size } => {
+                    result.push_str(
+                        format!("do_listxattr({}, {});\n", encode_str(path), size).as_str(),
+                    );
+                }
+                Operation::RemoveXattr { path, name } => {
+                    result.push_str(
+                        format!(
+                            "do_removexattr({}, {});\n",
+                            encode_str(path),
+                            encode_str(name)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::Dup3 {
+                    old_des,
+                    new_des,
+                    flags,
+                } => {
+                    result.push_str(
+                        format!(
+                            "do_dup3({}, {}, {});\n",
+                            descriptor_to_var(old_des),
+                            descriptor_to_var(new_des),
+                            encode_open_flags(flags)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::Chmod { path, mode } => {
+                    result.push_str(
+                        format!("do_chmod({}, {});\n", encode_str(path), encode_mode(mode))
+                            .as_str(),
+                    );
+                }
+                Operation::FChmod { des, mode } => {
+                    result.push_str(
+                        format!(
+                            "do_fchmod({}, {});\n",
+                            descriptor_to_var(des),
+                            encode_mode(mode)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::Chown { path, uid, gid } => {
+                    result.push_str(
+                        format!(
+                            "do_chown({}, {}, {});\n",
+                            encode_str(path),
+                            encode_id(uid),
+                            encode_id(gid)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::FChown { des, uid, gid } => {
+                    result.push_str(
+                        format!(
+                            "do_fchown({}, {}, {});\n",
+                            descriptor_to_var(des),
+                            encode_id(uid),
+                            encode_id(gid)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::UTimeNsAt { path, atime, mtime } => {
+                    result.push_str(
+                        format!(
+                            "do_utimensat({}, {}, {});\n",
+                            encode_str(path),
+                            encode_timespec(atime),
+                            encode_timespec(mtime)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::FUTimeNs { des, atime, mtime } => {
+                    result.push_str(
+                        format!(
+                            "do_futimens({}, {}, {});\n",
+                            descriptor_to_var(des),
+                            encode_timespec(atime),
+                            encode_timespec(mtime)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::FAllocate {
+                    des,
+                    mode,
+                    offset,
+                    len,
+                } => {
+                    result.push_str(
+                        format!(
+                            "do_fallocate({}, {}, {}, {});\n",
+                            descriptor_to_var(des),
+                            encode_fallocate_mode(mode),
+                            offset,
+                            len
+                        )
+                        .as_str(),
                     );
                 }
+                Operation::MMap {
+                    des,
+                    offset,
+                    len,
+                    map,
+                } => {
+                    result.push_str(
+                        format!(
+                            "{} = do_mmap({}, {}, {});\n",
+                            mapping_to_var(map),
+                            descriptor_to_var(des),
+                            len,
+                            offset
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::MWrite {
+                    map,
+                    src_offset,
+                    size,
+                    offset,
+                } => {
+                    result.push_str(
+                        format!(
+                            "do_mwrite({}, {}, {}, {});\n",
+                            mapping_to_var(map),
+                            src_offset,
+                            size,
+                            offset
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::MSync { map, flags } => {
+                    result.push_str(
+                        format!(
+                            "do_msync({}, {}, {});\n",
+                            mapping_to_var(map),
+                            mapping_lens[map],
+                            encode_msync_flags(flags)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::MUnmap { map } => {
+                    result.push_str(
+                        format!(
+                            "do_munmap({}, {});\n",
+                            mapping_to_var(map),
+                            mapping_lens[map]
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::RenameAt2 {
+                    old_path,
+                    new_path,
+                    flags,
+                } => {
+                    result.push_str(
+                        format!(
+                            "do_renameat2({}, {}, {});\n",
+                            encode_str(old_path),
+                            encode_str(new_path),
+                            encode_rename_flags(flags)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::MkNod {
+                    path,
+                    mode,
+                    file_type,
+                } => {
+                    let mode = match encode_mode(mode).as_str() {
+                        "0" => file_type.to_string(),
+                        mode => format!("{} | {}", file_type, mode),
+                    };
+                    result
+                        .push_str(format!("do_mknod({}, {});\n", encode_str(path), mode).as_str());
+                }
+                Operation::ReadDir { path } => {
+                    result.push_str(format!("do_readdir({});\n", encode_str(path)).as_str());
+                }
+                Operation::Stat { path } => {
+                    result.push_str(format!("do_stat({});\n", encode_str(path)).as_str());
+                }
+                Operation::LStat { path } => {
+                    result.push_str(format!("do_lstat({});\n", encode_str(path)).as_str());
+                }
+                Operation::FStat { des } => {
+                    result.push_str(format!("do_fstat({});\n", descriptor_to_var(des)).as_str());
+                }
+                Operation::ReadLink { path } => {
+                    result.push_str(format!("do_readlink({});\n", encode_str(path)).as_str());
+                }
+                Operation::CopyFileRange {
+                    des_in,
+                    offset_in,
+                    des_out,
+                    offset_out,
+                    len,
+                } => {
+                    result.push_str(
+                        format!(
+                            "do_copy_file_range({}, {}, {}, {}, {});\n",
+                            descriptor_to_var(des_in),
+                            encode_offset(offset_in),
+                            descriptor_to_var(des_out),
+                            encode_offset(offset_out),
+                            len
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::SendFile {
+                    des_out,
+                    des_in,
+                    offset,
+                    count,
+                } => {
+                    result.push_str(
+                        format!(
+                            "do_sendfile({}, {}, {}, {});\n",
+                            descriptor_to_var(des_out),
+                            descriptor_to_var(des_in),
+                            encode_offset(offset),
+                            count
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::Splice {
+                    des_in,
+                    offset_in,
+                    des_out,
+                    offset_out,
+                    len,
+                } => {
+                    result.push_str(
+                        format!(
+                            "do_splice({}, {}, {}, {}, {});\n",
+                            descriptor_to_var(des_in),
+                            encode_offset(offset_in),
+                            descriptor_to_var(des_out),
+                            encode_offset(offset_out),
+                            len
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::FIClone { des_in, des_out } => {
+                    result.push_str(
+                        format!(
+                            "do_ficlone({}, {});\n",
+                            descriptor_to_var(des_out),
+                            descriptor_to_var(des_in)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::FICloneRange {
+                    des_in,
+                    offset_in,
+                    len,
+                    des_out,
+                    offset_out,
+                } => {
+                    result.push_str(
+                        format!(
+                            "do_ficlonerange({}, {}, {}, {}, {});\n",
+                            descriptor_to_var(des_out),
+                            descriptor_to_var(des_in),
+                            offset_in,
+                            len,
+                            offset_out
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::FIDedupeRange {
+                    des_in,
+                    offset_in,
+                    len,
+                    des_out,
+                    offset_out,
+                } => {
+                    result.push_str(
+                        format!(
+                            "do_fideduperange({}, {}, {}, {}, {});\n",
+                            descriptor_to_var(des_in),
+                            offset_in,
+                            len,
+                            descriptor_to_var(des_out),
+                            offset_out
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::Flock { des, op } => {
+                    result.push_str(
+                        format!("do_flock({}, {});\n", descriptor_to_var(des), op).as_str(),
+                    );
+                }
+                Operation::SetLk {
+                    des,
+                    lock_type,
+                    start,
+                    len,
+                } => {
+                    result.push_str(
+                        format!(
+                            "do_setlk({}, {}, {}, {});\n",
+                            descriptor_to_var(des),
+                            lock_type,
+                            start,
+                            len
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::OfdSetLk {
+                    des,
+                    lock_type,
+                    start,
+                    len,
+                } => {
+                    result.push_str(
+                        format!(
+                            "do_ofd_setlk({}, {}, {}, {});\n",
+                            descriptor_to_var(des),
+                            lock_type,
+                            start,
+                            len
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::OpenDir {
+                    dir,
+                    path,
+                    flags,
+                    des,
+                } => {
+                    result.push_str(
+                        format!(
+                            "{} = do_opendir({}, {}, {});\n",
+                            dir_descriptor_to_var(des),
+                            encode_dirfd(dir),
+                            encode_str(path),
+                            encode_open_flags(flags)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::CloseDir { des } => {
+                    result
+                        .push_str(format!("do_close({});\n", dir_descriptor_to_var(des)).as_str());
+                }
+                Operation::ChDir { path } => {
+                    result.push_str(format!("do_chdir({});\n", encode_str(path)).as_str());
+                }
+                Operation::FChDir { des } => {
+                    result
+                        .push_str(format!("do_fchdir({});\n", dir_descriptor_to_var(des)).as_str());
+                }
+                Operation::MkDirAt { dir, path, mode } => {
+                    result.push_str(
+                        format!(
+                            "do_mkdirat({}, {}, {});\n",
+                            encode_dirfd(dir),
+                            encode_str(path),
+                            encode_mode(mode)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::OpenAt {
+                    dir,
+                    path,
+                    flags,
+                    mode,
+                    des,
+                } => {
+                    result.push_str(
+                        format!(
+                            "{} = do_openat({}, {}, {}, {});\n",
+                            descriptor_to_var(des),
+                            encode_dirfd(dir),
+                            encode_str(path),
+                            encode_open_flags(flags),
+                            encode_mode(mode)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::UnlinkAt { dir, path, flags } => {
+                    result.push_str(
+                        format!(
+                            "do_unlinkat({}, {}, {});\n",
+                            encode_dirfd(dir),
+                            encode_str(path),
+                            encode_at_flags(flags)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::RenameAt {
+                    old_dir,
+                    old_path,
+                    new_dir,
+                    new_path,
+                } => {
+                    result.push_str(
+                        format!(
+                            "do_renameat({}, {}, {}, {});\n",
+                            encode_dirfd(old_dir),
+                            encode_str(old_path),
+                            encode_dirfd(new_dir),
+                            encode_str(new_path)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::FStatAt { dir, path, flags } => {
+                    result.push_str(
+                        format!(
+                            "do_fstatat({}, {}, {});\n",
+                            encode_dirfd(dir),
+                            encode_str(path),
+                            encode_at_flags(flags)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::FLink { des, path } => {
+                    result.push_str(
+                        format!(
+                            "do_flink({}, {});\n",
+                            descriptor_to_var(des),
+                            encode_str(path)
+                        )
+                        .as_str(),
+                    );
+                }
+                Operation::Umask { mask } => {
+                    result.push_str(format!("do_umask({});\n", encode_mode(mask)).as_str());
+                }
+                Operation::SetCasefold { path } => {
+                    result.push_str(format!("do_set_casefold({});\n", encode_str(path)).as_str());
+                }
             }
         }
         result.push('}');
@@ -101,6 +802,24 @@ impl Workload {
     }
 }
 
+/// String literal, bytes except printable ASCII are escaped as octal (always three digits,
+/// so the following characters are not consumed).
+fn encode_str(value: impl Display) -> String {
+    let mut result = String::from("\"");
+    for byte in value.to_string().bytes() {
+        match byte {
+            b'"' | b'\\' => {
+                result.push('\\');
+                result.push(byte as char);
+            }
+            0x20..=0x7e => result.push(byte as char),
+            _ => result.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    result.push('"');
+    result
+}
+
 fn encode_mode(mode: &Mode) -> String {
     if mode.is_empty() {
         0.to_string()
@@ -110,9 +829,110 @@ fn encode_mode(mode: &Mode) -> String {
     }
 }
 
+/// Id that is left unchanged is encoded as `-1`.
+fn encode_id(id: &Option<u32>) -> String {
+    match id {
+        Some(id) => id.to_string(),
+        None => (-1).to_string(),
+    }
+}
+
+/// Offset of descriptor is used if explicit offset is `-1`.
+fn encode_offset(offset: &Option<u64>) -> String {
+    match offset {
+        Some(offset) => offset.to_string(),
+        None => (-1).to_string(),
+    }
+}
+
+/// Encoded as seconds and nanoseconds.
+fn encode_timespec(time: &TimeSpec) -> String {
+    match time {
+        TimeSpec::Now => "0, UTIME_NOW".to_owned(),
+        TimeSpec::Omit => "0, UTIME_OMIT".to_owned(),
+        TimeSpec::Seconds(seconds) => format!("{}, 0", seconds),
+    }
+}
+
+fn encode_xattr_flags(flags: &XattrFlags) -> String {
+    if flags.is_empty() {
+        0.to_string()
+    } else {
+        let flags_str: Vec<String> = flags.iter().map(|f| f.to_string()).collect();
+        flags_str.join(" | ")
+    }
+}
+
+fn encode_fallocate_mode(mode: &FallocateMode) -> String {
+    if mode.is_empty() {
+        0.to_string()
+    } else {
+        let mode_str: Vec<String> = mode.iter().map(|f| f.to_string()).collect();
+        mode_str.join(" | ")
+    }
+}
+
+fn encode_msync_flags(flags: &MsyncFlags) -> String {
+    if flags.is_empty() {
+        0.to_string()
+    } else {
+        let flags_str: Vec<String> = flags.iter().map(|f| f.to_string()).collect();
+        flags_str.join(" | ")
+    }
+}
+
+fn encode_sync_file_range_flags(flags: &SyncFileRangeFlags) -> String {
+    if flags.is_empty() {
+        0.to_string()
+    } else {
+        let flags_str: Vec<String> = flags.iter().map(|f| f.to_string()).collect();
+        flags_str.join(" | ")
+    }
+}
+
+fn encode_dirfd(dir: &DirFd) -> String {
+    match dir {
+        DirFd::Cwd => "AT_FDCWD".to_owned(),
+        DirFd::Des(des) => dir_descriptor_to_var(des),
+    }
+}
+
+fn encode_at_flags(flags: &AtFlags) -> String {
+    if flags.is_empty() {
+        0.to_string()
+    } else {
+        let flags_str: Vec<String> = flags.iter().map(|f| f.to_string()).collect();
+        flags_str.join(" | ")
+    }
+}
+
+fn encode_rename_flags(flags: &RenameFlags) -> String {
+    if flags.is_empty() {
+        0.to_string()
+    } else {
+        let flags_str: Vec<String> = flags.iter().map(|f| f.to_string()).collect();
+        flags_str.join(" | ")
+    }
+}
+
+fn encode_open_flags(flags: &OpenFlags) -> String {
+    if flags.is_empty() {
+        0.to_string()
+    } else {
+        let flags_str: Vec<String> = flags.iter().map(|f| f.to_string()).collect();
+        flags_str.join(" | ")
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::abstract_fs::{flags::ModeFlag, node::FileDescriptorIndex};
+    use crate::abstract_fs::{
+        flags::{
+            AtFlag, FallocateFlag, FileType, FlockOperation, LockType, ModeFlag, MsyncFlag,
+            OpenFlag, RenameFlag, SeekWhence, SyncFileRangeFlag, TimeSpec, XattrFlag,
+        },
+        node::{DirDescriptorIndex, DirFd, FileDescriptorIndex, MappingIndex},
+    };
 
     use super::*;
 
@@ -137,23 +957,85 @@ void test_workload()
         let expected = r#"
 #include "executor.h"
 
-int fd_0, fd_1;
+int fd_0, fd_1, fd_2, fd_3, fd_4;
+int dfd_0;
+char *map_0;
 
 void test_workload()
 {
 do_mkdir("/foo", 0);
 do_create("/foo/bar", S_IRWXU | S_IRWXG | S_IROTH | S_IXOTH);
-fd_0 = do_open("/foo/bar");
+fd_0 = do_open("/foo/bar", O_RDWR, 0);
 do_write(fd_0, 999, 1024);
+do_lseek(fd_0, -24, SEEK_END);
+do_pwrite(fd_0, 13, 100, 2048);
+do_pread(fd_0, 100, 512);
+map_0 = do_mmap(fd_0, 8192, 0);
+do_mwrite(map_0, 13, 100, 4000);
+do_msync(map_0, 8192, MS_SYNC | MS_INVALIDATE);
 do_close(fd_0);
 do_hardlink("/foo/bar", "/baz");
-fd_1 = do_open("/baz");
+fd_1 = do_open("/baz", O_RDWR | O_APPEND | O_SYNC, 0);
 do_read(fd_1, 1024);
+fd_2 = do_dup(fd_1);
+do_dup2(fd_2, fd_0);
+do_dup3(fd_1, fd_2, O_CLOEXEC);
 do_fsync(fd_1);
+do_fdatasync(fd_1);
+do_sync();
+do_syncfs(fd_1);
+do_sync_file_range(fd_1, 0, 0, SYNC_FILE_RANGE_WAIT_BEFORE | SYNC_FILE_RANGE_WRITE | SYNC_FILE_RANGE_WAIT_AFTER);
+do_sync_file_range(fd_1, 4096, 100, 0);
+do_ftruncate(fd_1, 100);
+do_fallocate(fd_1, FALLOC_FL_PUNCH_HOLE | FALLOC_FL_KEEP_SIZE, 0, 4096);
+do_fchmod(fd_1, S_IRUSR | S_IWUSR);
+do_fchown(fd_1, -1, 1000);
+do_futimens(fd_1, 0, UTIME_OMIT, 1000000000, 0);
 do_close(fd_1);
+do_truncate("/baz", 4096);
+fd_3 = do_open("/", O_RDWR | O_TMPFILE, S_IRUSR);
+do_flink(fd_3, "/tmp");
+do_fstat(fd_3);
+do_copy_file_range(fd_2, 0, fd_3, -1, 100);
+do_sendfile(fd_3, fd_2, -1, 100);
+do_splice(fd_2, -1, fd_3, 4096, 1024);
+do_ficlone(fd_3, fd_2);
+do_ficlonerange(fd_3, fd_2, 4096, 0, 8192);
+do_fideduperange(fd_2, 0, 4096, fd_3, 4096);
+do_flock(fd_2, LOCK_EX);
+do_setlk(fd_3, F_WRLCK, 100, 0);
+do_ofd_setlk(fd_2, F_RDLCK, 0, 4096);
+do_mknod("/fifo", S_IFIFO);
+do_mknod("/null", S_IFCHR | S_IRUSR | S_IWUSR);
+do_readdir("/");
+do_stat("/null");
 do_rename("/baz", "/gaz");
+do_renameat2("/gaz", "/foo", RENAME_EXCHANGE);
 do_symlink("/foo", "/moo");
+do_lstat("/moo");
+do_symlink("../moo/./gaz", "/foo/zoo");
+do_readlink("/foo/zoo");
+do_setxattr("/moo", "user.a", 13, 100, XATTR_CREATE);
+do_getxattr("/foo", "user.a", 0);
+do_listxattr("/foo", 1024);
+do_removexattr("/foo", "user.a");
+do_chmod("/moo", S_IRWXU | S_ISVTX);
+do_chown("/foo", 65534, -1);
+do_utimensat("/moo", 0, UTIME_NOW, 0, UTIME_OMIT);
+do_munmap(map_0, 8192);
+dfd_0 = do_opendir(AT_FDCWD, "foo", O_DIRECTORY | O_PATH);
+do_fchdir(dfd_0);
+do_chdir("..");
+do_mkdirat(dfd_0, "d", 0);
+fd_4 = do_openat(dfd_0, "d/f", O_RDWR | O_CREAT, 0);
+do_renameat(dfd_0, "d/f", AT_FDCWD, "/f");
+do_unlinkat(dfd_0, "d", AT_REMOVEDIR);
 do_remove("/foo");
+do_fstatat(dfd_0, "", AT_EMPTY_PATH);
+do_close(dfd_0);
+do_umask(S_IWGRP | S_IRWXO);
+do_mkdir("/\"a\\b\012\303\251 1", 0);
+do_set_casefold("/foo");
 }
 "#
         .trim();
@@ -175,6 +1057,8 @@ do_remove("/foo");
                 },
                 Operation::Open {
                     path: "/foo/bar".into(),
+                    flags: vec![OpenFlag::O_RDWR],
+                    mode: vec![],
                     des: FileDescriptorIndex(0),
                 },
                 Operation::Write {
@@ -182,6 +1066,38 @@ do_remove("/foo");
                     src_offset: 999,
                     size: 1024,
                 },
+                Operation::LSeek {
+                    des: FileDescriptorIndex(0),
+                    offset: -24,
+                    whence: SeekWhence::SEEK_END,
+                },
+                Operation::PWrite {
+                    des: FileDescriptorIndex(0),
+                    src_offset: 13,
+                    size: 100,
+                    offset: 2048,
+                },
+                Operation::PRead {
+                    des: FileDescriptorIndex(0),
+                    size: 100,
+                    offset: 512,
+                },
+                Operation::MMap {
+                    des: FileDescriptorIndex(0),
+                    offset: 0,
+                    len: 8192,
+                    map: MappingIndex(0),
+                },
+                Operation::MWrite {
+                    map: MappingIndex(0),
+                    src_offset: 13,
+                    size: 100,
+                    offset: 4000,
+                },
+                Operation::MSync {
+                    map: MappingIndex(0),
+                    flags: vec![MsyncFlag::MS_SYNC, MsyncFlag::MS_INVALIDATE],
+                },
                 Operation::Close {
                     des: FileDescriptorIndex(0),
                 },
@@ -191,29 +1107,282 @@ do_remove("/foo");
                 },
                 Operation::Open {
                     path: "/baz".into(),
+                    flags: vec![OpenFlag::O_RDWR, OpenFlag::O_APPEND, OpenFlag::O_SYNC],
+                    mode: vec![],
                     des: FileDescriptorIndex(1),
                 },
                 Operation::Read {
                     des: FileDescriptorIndex(1),
                     size: 1024,
                 },
+                Operation::Dup {
+                    old_des: FileDescriptorIndex(1),
+                    new_des: FileDescriptorIndex(2),
+                },
+                Operation::Dup2 {
+                    old_des: FileDescriptorIndex(2),
+                    new_des: FileDescriptorIndex(0),
+                },
+                Operation::Dup3 {
+                    old_des: FileDescriptorIndex(1),
+                    new_des: FileDescriptorIndex(2),
+                    flags: vec![OpenFlag::O_CLOEXEC],
+                },
                 Operation::FSync {
                     des: FileDescriptorIndex(1),
                 },
+                Operation::FDataSync {
+                    des: FileDescriptorIndex(1),
+                },
+                Operation::Sync,
+                Operation::SyncFs {
+                    des: FileDescriptorIndex(1),
+                },
+                Operation::SyncFileRange {
+                    des: FileDescriptorIndex(1),
+                    offset: 0,
+                    nbytes: 0,
+                    flags: vec![
+                        SyncFileRangeFlag::SYNC_FILE_RANGE_WAIT_BEFORE,
+                        SyncFileRangeFlag::SYNC_FILE_RANGE_WRITE,
+                        SyncFileRangeFlag::SYNC_FILE_RANGE_WAIT_AFTER,
+                    ],
+                },
+                Operation::SyncFileRange {
+                    des: FileDescriptorIndex(1),
+                    offset: 4096,
+                    nbytes: 100,
+                    flags: vec![],
+                },
+                Operation::FTruncate {
+                    des: FileDescriptorIndex(1),
+                    size: 100,
+                },
+                Operation::FAllocate {
+                    des: FileDescriptorIndex(1),
+                    mode: vec![
+                        FallocateFlag::FALLOC_FL_PUNCH_HOLE,
+                        FallocateFlag::FALLOC_FL_KEEP_SIZE,
+                    ],
+                    offset: 0,
+                    len: 4096,
+                },
+                Operation::FChmod {
+                    des: FileDescriptorIndex(1),
+                    mode: vec![ModeFlag::S_IRUSR, ModeFlag::S_IWUSR],
+                },
+                Operation::FChown {
+                    des: FileDescriptorIndex(1),
+                    uid: None,
+                    gid: Some(1000),
+                },
+                Operation::FUTimeNs {
+                    des: FileDescriptorIndex(1),
+                    atime: TimeSpec::Omit,
+                    mtime: TimeSpec::Seconds(1000000000),
+                },
                 Operation::Close {
                     des: FileDescriptorIndex(1),
                 },
+                Operation::Truncate {
+                    path: "/baz".into(),
+                    size: 4096,
+                },
+                Operation::Open {
+                    path: "/".into(),
+                    flags: vec![OpenFlag::O_RDWR, OpenFlag::O_TMPFILE],
+                    mode: vec![ModeFlag::S_IRUSR],
+                    des: FileDescriptorIndex(3),
+                },
+                Operation::FLink {
+                    des: FileDescriptorIndex(3),
+                    path: "/tmp".into(),
+                },
+                Operation::FStat {
+                    des: FileDescriptorIndex(3),
+                },
+                Operation::CopyFileRange {
+                    des_in: FileDescriptorIndex(2),
+                    offset_in: Some(0),
+                    des_out: FileDescriptorIndex(3),
+                    offset_out: None,
+                    len: 100,
+                },
+                Operation::SendFile {
+                    des_out: FileDescriptorIndex(3),
+                    des_in: FileDescriptorIndex(2),
+                    offset: None,
+                    count: 100,
+                },
+                Operation::Splice {
+                    des_in: FileDescriptorIndex(2),
+                    offset_in: None,
+                    des_out: FileDescriptorIndex(3),
+                    offset_out: Some(4096),
+                    len: 1024,
+                },
+                Operation::FIClone {
+                    des_in: FileDescriptorIndex(2),
+                    des_out: FileDescriptorIndex(3),
+                },
+                Operation::FICloneRange {
+                    des_in: FileDescriptorIndex(2),
+                    offset_in: 4096,
+                    len: 0,
+                    des_out: FileDescriptorIndex(3),
+                    offset_out: 8192,
+                },
+                Operation::FIDedupeRange {
+                    des_in: FileDescriptorIndex(2),
+                    offset_in: 0,
+                    len: 4096,
+                    des_out: FileDescriptorIndex(3),
+                    offset_out: 4096,
+                },
+                Operation::Flock {
+                    des: FileDescriptorIndex(2),
+                    op: FlockOperation::LOCK_EX,
+                },
+                Operation::SetLk {
+                    des: FileDescriptorIndex(3),
+                    lock_type: LockType::F_WRLCK,
+                    start: 100,
+                    len: 0,
+                },
+                Operation::OfdSetLk {
+                    des: FileDescriptorIndex(2),
+                    lock_type: LockType::F_RDLCK,
+                    start: 0,
+                    len: 4096,
+                },
+                Operation::MkNod {
+                    path: "/fifo".into(),
+                    mode: vec![],
+                    file_type: FileType::S_IFIFO,
+                },
+                Operation::MkNod {
+                    path: "/null".into(),
+                    mode: vec![ModeFlag::S_IRUSR, ModeFlag::S_IWUSR],
+                    file_type: FileType::S_IFCHR,
+                },
+                Operation::ReadDir { path: "/".into() },
+                Operation::Stat {
+                    path: "/null".into(),
+                },
                 Operation::Rename {
                     old_path: "/baz".into(),
                     new_path: "/gaz".into(),
                 },
+                Operation::RenameAt2 {
+                    old_path: "/gaz".into(),
+                    new_path: "/foo".into(),
+                    flags: vec![RenameFlag::RENAME_EXCHANGE],
+                },
                 Operation::Symlink {
                     target: "/foo".into(),
                     linkpath: "/moo".into(),
                 },
+                Operation::LStat {
+                    path: "/moo".into(),
+                },
+                Operation::Symlink {
+                    target: "../moo/./gaz".into(),
+                    linkpath: "/foo/zoo".into(),
+                },
+                Operation::ReadLink {
+                    path: "/foo/zoo".into(),
+                },
+                Operation::SetXattr {
+                    path: "/moo".into(),
+                    name: "user.a".into(),
+                    src_offset: 13,
+                    size: 100,
+                    flags: vec![XattrFlag::XATTR_CREATE],
+                },
+                Operation::GetXattr {
+                    path: "/foo".into(),
+                    name: "user.a".into(),
+                    size: 0,
+                },
+                Operation::ListXattr {
+                    path: "/foo".into(),
+                    size: 1024,
+                },
+                Operation::RemoveXattr {
+                    path: "/foo".into(),
+                    name: "user.a".into(),
+                },
+                Operation::Chmod {
+                    path: "/moo".into(),
+                    mode: vec![ModeFlag::S_IRWXU, ModeFlag::S_ISVTX],
+                },
+                Operation::Chown {
+                    path: "/foo".into(),
+                    uid: Some(65534),
+                    gid: None,
+                },
+                Operation::UTimeNsAt {
+                    path: "/moo".into(),
+                    atime: TimeSpec::Now,
+                    mtime: TimeSpec::Omit,
+                },
+                Operation::MUnmap {
+                    map: MappingIndex(0),
+                },
+                Operation::OpenDir {
+                    dir: DirFd::Cwd,
+                    path: "foo".into(),
+                    flags: vec![OpenFlag::O_DIRECTORY, OpenFlag::O_PATH],
+                    des: DirDescriptorIndex(0),
+                },
+                Operation::FChDir {
+                    des: DirDescriptorIndex(0),
+                },
+                Operation::ChDir { path: "..".into() },
+                Operation::MkDirAt {
+                    dir: DirFd::Des(DirDescriptorIndex(0)),
+                    path: "d".into(),
+                    mode: vec![],
+                },
+                Operation::OpenAt {
+                    dir: DirFd::Des(DirDescriptorIndex(0)),
+                    path: "d/f".into(),
+                    flags: vec![OpenFlag::O_RDWR, OpenFlag::O_CREAT],
+                    mode: vec![],
+                    des: FileDescriptorIndex(4),
+                },
+                Operation::RenameAt {
+                    old_dir: DirFd::Des(DirDescriptorIndex(0)),
+                    old_path: "d/f".into(),
+                    new_dir: DirFd::Cwd,
+                    new_path: "/f".into(),
+                },
+                Operation::UnlinkAt {
+                    dir: DirFd::Des(DirDescriptorIndex(0)),
+                    path: "d".into(),
+                    flags: vec![AtFlag::AT_REMOVEDIR],
+                },
                 Operation::Remove {
                     path: "/foo".into(),
                 },
+                Operation::FStatAt {
+                    dir: DirFd::Des(DirDescriptorIndex(0)),
+                    path: "".into(),
+                    flags: vec![AtFlag::AT_EMPTY_PATH],
+                },
+                Operation::CloseDir {
+                    des: DirDescriptorIndex(0),
+                },
+                Operation::Umask {
+                    mask: vec![ModeFlag::S_IWGRP, ModeFlag::S_IRWXO],
+                },
+                Operation::MkDir {
+                    path: "/\"a\\b\n\u{e9} 1".into(),
+                    mode: vec![],
+                },
+                Operation::SetCasefold {
+                    path: "/foo".into(),
+                },
             ],
         }
         .encode_c();
@@ -13,7 +13,7 @@ use serde::{Deserialize, Serialize};
 /// Flags for `open(path, flags, mode)` syscall.
 ///
 /// Applications *shall* specify __exactly one__ of the __first 5__ values.
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
 #[allow(nonstandard_style)]
 pub enum OpenFlag {
     /// Open for execute only (non-directory files).
@@ -75,9 +75,45 @@ pub enum OpenFlag {
     /// Its effect on other file types is implementation-defined.
     /// The result of using `O_TRUNC` without either `O_RDWR` or `O_WRONLY` is undefined.
     O_TRUNC,
+    /// __LINUX__: Create an unnamed regular file in the directory named by path.
+    /// It can be linked into the filesystem later, unless `O_EXCL` is set. Must be used with `O_RDWR` or `O_WRONLY`.
+    O_TMPFILE,
+    /// __LINUX__: Obtain a descriptor that only indicates a location in the filesystem tree,
+    /// it can be used as directory descriptor of `*at` operations and with `fchdir`.
+    O_PATH,
 
     O_TTY_INIT,
 }
+
+impl Display for OpenFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OpenFlag::O_EXEC => write!(f, "O_EXEC"),
+            OpenFlag::O_RDONLY => write!(f, "O_RDONLY"),
+            OpenFlag::O_RDWR => write!(f, "O_RDWR"),
+            OpenFlag::O_SEARCH => write!(f, "O_SEARCH"),
+            OpenFlag::O_WRONLY => write!(f, "O_WRONLY"),
+            OpenFlag::O_APPEND => write!(f, "O_APPEND"),
+            OpenFlag::O_CLOEXEC => write!(f, "O_CLOEXEC"),
+            OpenFlag::O_CLOFORK => write!(f, "O_CLOFORK"),
+            OpenFlag::O_CREAT => write!(f, "O_CREAT"),
+            OpenFlag::O_DIRECTORY => write!(f, "O_DIRECTORY"),
+            OpenFlag::O_DSYNC => write!(f, "O_DSYNC"),
+            OpenFlag::O_EXCL => write!(f, "O_EXCL"),
+            OpenFlag::O_NOCTTY => write!(f, "O_NOCTTY"),
+            OpenFlag::O_NOFOLLOW => write!(f, "O_NOFOLLOW"),
+            OpenFlag::O_NONBLOCK => write!(f, "O_NONBLOCK"),
+            OpenFlag::O_RSYNC => write!(f, "O_RSYNC"),
+            OpenFlag::O_SYNC => write!(f, "O_SYNC"),
+            OpenFlag::O_TRUNC => write!(f, "O_TRUNC"),
+            OpenFlag::O_TMPFILE => write!(f, "O_TMPFILE"),
+            OpenFlag::O_PATH => write!(f, "O_PATH"),
+            OpenFlag::O_TTY_INIT => write!(f, "O_TTY_INIT"),
+        }
+    }
+}
+
+pub type OpenFlags = Vec<OpenFlag>;
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
 #[allow(nonstandard_style)]
 pub enum ModeFlag {
@@ -137,3 +173,285 @@ impl Display for ModeFlag {
 }
 
 pub type Mode = Vec<ModeFlag>;
+
+/// Numeric value of mode, as passed to syscalls.
+pub fn mode_bits(mode: &Mode) -> u32 {
+    mode.iter().fold(0, |bits, flag| bits | *flag as u32)
+}
+
+/// File type bits of `st_mode`.
+pub const S_IFMT: u32 = 0o170000;
+pub const S_IFLNK: u32 = 0o120000;
+pub const S_IFREG: u32 = 0o100000;
+pub const S_IFDIR: u32 = 0o040000;
+
+/// File type bits of `mode` argument for `mknod(path, mode, dev)` syscall.
+/// Regular files and directories are created with `creat` and `mkdir` instead.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[allow(nonstandard_style)]
+pub enum FileType {
+    /// FIFO special file (named pipe).
+    S_IFIFO = 0o10000,
+    /// Socket, not bound to any address.
+    S_IFSOCK = 0o140000,
+    /// Character special file, device number is fixed by executor.
+    S_IFCHR = 0o20000,
+    /// Block special file, device number is fixed by executor.
+    S_IFBLK = 0o60000,
+}
+
+impl Display for FileType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileType::S_IFIFO => write!(f, "S_IFIFO"),
+            FileType::S_IFSOCK => write!(f, "S_IFSOCK"),
+            FileType::S_IFCHR => write!(f, "S_IFCHR"),
+            FileType::S_IFBLK => write!(f, "S_IFBLK"),
+        }
+    }
+}
+
+/// Values of `whence` argument for `lseek(fd, offset, whence)` syscall.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[allow(nonstandard_style)]
+pub enum SeekWhence {
+    /// The file offset shall be set to `offset` bytes.
+    SEEK_SET,
+    /// The file offset shall be set to its current location plus `offset`.
+    SEEK_CUR,
+    /// The file offset shall be set to the size of the file plus `offset`.
+    SEEK_END,
+    /// The file offset shall be set to the smallest location of a byte not within a hole and not less than `offset`.
+    /// If no such byte exists, `lseek()` shall fail and set errno to `ENXIO`.
+    SEEK_DATA,
+    /// The file offset shall be set to the smallest location of a byte within a hole and not less than `offset`,
+    /// except that if `offset` falls beyond the last byte not within a hole, the file offset may be set to the file size instead.
+    /// If `offset` is greater than or equal to the file size, `lseek()` shall fail and set errno to `ENXIO`.
+    SEEK_HOLE,
+}
+
+impl Display for SeekWhence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SeekWhence::SEEK_SET => write!(f, "SEEK_SET"),
+            SeekWhence::SEEK_CUR => write!(f, "SEEK_CUR"),
+            SeekWhence::SEEK_END => write!(f, "SEEK_END"),
+            SeekWhence::SEEK_DATA => write!(f, "SEEK_DATA"),
+            SeekWhence::SEEK_HOLE => write!(f, "SEEK_HOLE"),
+        }
+    }
+}
+
+/// Values of `flags` argument for `setxattr(path, name, value, size, flags)` syscall.
+/// By default, the extended attribute will be created if it does not exist, or the value will be replaced if it already exists.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[allow(nonstandard_style)]
+pub enum XattrFlag {
+    /// Perform a pure create, which fails with `EEXIST` if the named attribute exists already.
+    XATTR_CREATE,
+    /// Perform a pure replace operation, which fails with `ENODATA` if the named attribute does not already exist.
+    XATTR_REPLACE,
+}
+
+impl Display for XattrFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            XattrFlag::XATTR_CREATE => write!(f, "XATTR_CREATE"),
+            XattrFlag::XATTR_REPLACE => write!(f, "XATTR_REPLACE"),
+        }
+    }
+}
+
+pub type XattrFlags = Vec<XattrFlag>;
+
+/// Values of `mode` argument for `fallocate(fd, mode, offset, len)` syscall (Linux specific).
+/// By default, disk space is allocated for the range and file is extended if needed.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[allow(nonstandard_style)]
+pub enum FallocateFlag {
+    /// File size is not changed, even if range is beyond the end of file.
+    FALLOC_FL_KEEP_SIZE,
+    /// Deallocate range, it reads as zeros. Must be combined with `FALLOC_FL_KEEP_SIZE`.
+    FALLOC_FL_PUNCH_HOLE,
+    /// Zero range, it is allocated but reads as zeros. Can be combined with `FALLOC_FL_KEEP_SIZE`.
+    FALLOC_FL_ZERO_RANGE,
+    /// Remove range without leaving a hole, data after it is shifted. Can't be combined with other flags.
+    FALLOC_FL_COLLAPSE_RANGE,
+    /// Insert hole at offset, data after it is shifted. Can't be combined with other flags.
+    FALLOC_FL_INSERT_RANGE,
+}
+
+impl Display for FallocateFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FallocateFlag::FALLOC_FL_KEEP_SIZE => write!(f, "FALLOC_FL_KEEP_SIZE"),
+            FallocateFlag::FALLOC_FL_PUNCH_HOLE => write!(f, "FALLOC_FL_PUNCH_HOLE"),
+            FallocateFlag::FALLOC_FL_ZERO_RANGE => write!(f, "FALLOC_FL_ZERO_RANGE"),
+            FallocateFlag::FALLOC_FL_COLLAPSE_RANGE => write!(f, "FALLOC_FL_COLLAPSE_RANGE"),
+            FallocateFlag::FALLOC_FL_INSERT_RANGE => write!(f, "FALLOC_FL_INSERT_RANGE"),
+        }
+    }
+}
+
+pub type FallocateMode = Vec<FallocateFlag>;
+
+/// Value of timestamp for `utimensat(dirfd, path, times, flags)` and `futimens(fd, times)` syscalls.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum TimeSpec {
+    /// The timestamp shall be set to the current time (`UTIME_NOW`).
+    Now,
+    /// The timestamp shall not be changed (`UTIME_OMIT`).
+    Omit,
+    /// The timestamp shall be set to the number of seconds since the Epoch.
+    Seconds(i64),
+}
+
+/// Values of `flags` argument for `msync(addr, length, flags)` syscall.
+/// Exactly one of `MS_ASYNC` and `MS_SYNC` must be specified.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[allow(nonstandard_style)]
+pub enum MsyncFlag {
+    /// Schedule an update, but return immediately.
+    MS_ASYNC,
+    /// Request an update and wait for it to complete.
+    MS_SYNC,
+    /// Invalidate other mappings of the same file.
+    MS_INVALIDATE,
+}
+
+impl Display for MsyncFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MsyncFlag::MS_ASYNC => write!(f, "MS_ASYNC"),
+            MsyncFlag::MS_SYNC => write!(f, "MS_SYNC"),
+            MsyncFlag::MS_INVALIDATE => write!(f, "MS_INVALIDATE"),
+        }
+    }
+}
+
+pub type MsyncFlags = Vec<MsyncFlag>;
+
+/// Values of `flags` argument for `sync_file_range(fd, offset, nbytes, flags)` syscall (Linux specific).
+/// Without flags, it does nothing.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[allow(nonstandard_style)]
+pub enum SyncFileRangeFlag {
+    /// Wait for write-out of pages that are already submitted.
+    SYNC_FILE_RANGE_WAIT_BEFORE,
+    /// Start write-out of dirty pages, but don't wait for it.
+    SYNC_FILE_RANGE_WRITE,
+    /// Wait for write-out after it is started.
+    SYNC_FILE_RANGE_WAIT_AFTER,
+}
+
+impl Display for SyncFileRangeFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncFileRangeFlag::SYNC_FILE_RANGE_WAIT_BEFORE => {
+                write!(f, "SYNC_FILE_RANGE_WAIT_BEFORE")
+            }
+            SyncFileRangeFlag::SYNC_FILE_RANGE_WRITE => write!(f, "SYNC_FILE_RANGE_WRITE"),
+            SyncFileRangeFlag::SYNC_FILE_RANGE_WAIT_AFTER => {
+                write!(f, "SYNC_FILE_RANGE_WAIT_AFTER")
+            }
+        }
+    }
+}
+
+pub type SyncFileRangeFlags = Vec<SyncFileRangeFlag>;
+
+/// Values of `flags` argument for `renameat2(olddirfd, oldpath, newdirfd, newpath, flags)` syscall (Linux specific).
+/// By default, behaves the same as `rename`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[allow(nonstandard_style)]
+pub enum RenameFlag {
+    /// Don't overwrite new path, fails with `EEXIST` if it exists. Can't be combined with `RENAME_EXCHANGE`.
+    RENAME_NOREPLACE,
+    /// Atomically exchange old path and new path, both must exist.
+    RENAME_EXCHANGE,
+    /// Create "whiteout" object at the source of the rename (used by overlay filesystems).
+    /// Requires `CAP_MKNOD` and can't be combined with `RENAME_EXCHANGE`.
+    RENAME_WHITEOUT,
+}
+
+impl Display for RenameFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenameFlag::RENAME_NOREPLACE => write!(f, "RENAME_NOREPLACE"),
+            RenameFlag::RENAME_EXCHANGE => write!(f, "RENAME_EXCHANGE"),
+            RenameFlag::RENAME_WHITEOUT => write!(f, "RENAME_WHITEOUT"),
+        }
+    }
+}
+
+pub type RenameFlags = Vec<RenameFlag>;
+
+/// Values of `flags` argument for `*at` syscalls (e.g. `unlinkat(dirfd, path, flags)`).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[allow(nonstandard_style)]
+pub enum AtFlag {
+    /// Remove directory instead of file (`unlinkat`), directory must be empty.
+    AT_REMOVEDIR,
+    /// Don't follow symbolic link in the last component of path (`fstatat`).
+    AT_SYMLINK_NOFOLLOW,
+    /// Operate on the directory descriptor itself if path is empty (`fstatat`).
+    AT_EMPTY_PATH,
+}
+
+impl Display for AtFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AtFlag::AT_REMOVEDIR => write!(f, "AT_REMOVEDIR"),
+            AtFlag::AT_SYMLINK_NOFOLLOW => write!(f, "AT_SYMLINK_NOFOLLOW"),
+            AtFlag::AT_EMPTY_PATH => write!(f, "AT_EMPTY_PATH"),
+        }
+    }
+}
+
+pub type AtFlags = Vec<AtFlag>;
+
+/// Values of `operation` argument for `flock(fd, operation)` syscall.
+/// Executor always adds `LOCK_NB`, so conflicting lock is reported instead of blocking.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[allow(nonstandard_style)]
+pub enum FlockOperation {
+    /// Place a shared lock. More than one open file description may hold a shared lock for a given file.
+    LOCK_SH,
+    /// Place an exclusive lock. Only one open file description may hold an exclusive lock for a given file.
+    LOCK_EX,
+    /// Remove an existing lock held by this open file description.
+    LOCK_UN,
+}
+
+impl Display for FlockOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FlockOperation::LOCK_SH => write!(f, "LOCK_SH"),
+            FlockOperation::LOCK_EX => write!(f, "LOCK_EX"),
+            FlockOperation::LOCK_UN => write!(f, "LOCK_UN"),
+        }
+    }
+}
+
+/// Values of `l_type` field of `struct flock` for `fcntl(fd, F_SETLK, lock)`
+/// and `fcntl(fd, F_OFD_SETLK, lock)` syscalls.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[allow(nonstandard_style)]
+pub enum LockType {
+    /// Place a read (shared) lock, descriptor must be open for reading.
+    F_RDLCK,
+    /// Place a write (exclusive) lock, descriptor must be open for writing.
+    F_WRLCK,
+    /// Remove locks of the owner in the range.
+    F_UNLCK,
+}
+
+impl Display for LockType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockType::F_RDLCK => write!(f, "F_RDLCK"),
+            LockType::F_WRLCK => write!(f, "F_WRLCK"),
+            LockType::F_UNLCK => write!(f, "F_UNLCK"),
+        }
+    }
+}
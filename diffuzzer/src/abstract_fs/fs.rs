@@ -2,16 +2,23 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 
 use thiserror::Error;
 
 use super::{
     content::{Content, ContentError},
-    flags::Mode,
+    flags::{
+        AtFlag, AtFlags, FallocateFlag, FallocateMode, FileType, FlockOperation, LockType, Mode,
+        ModeFlag, MsyncFlag, MsyncFlags, OpenFlag, OpenFlags, RenameFlag, RenameFlags, S_IFDIR,
+        S_IFLNK, S_IFREG, SeekWhence, SyncFileRangeFlags, TimeSpec, XattrFlag, XattrFlags,
+        mode_bits,
+    },
     node::{
-        Dir, DirIndex, File, FileDescriptor, FileDescriptorIndex, FileIndex, Node, Symlink,
-        SymlinkIndex,
+        Dir, DirDescriptor, DirDescriptorIndex, DirFd, DirIndex, File, FileDescription,
+        FileDescriptionIndex, FileDescriptor, FileDescriptorIndex, FileIndex, FileLock, LockOwner,
+        Mapping, MappingIndex, Metadata, Node, RecordLock, Special, SpecialIndex, Stat, Symlink,
+        SymlinkIndex, Timestamp, Xattrs,
     },
     operation::Operation,
     pathname::{Name, PathName},
@@ -36,16 +43,24 @@ pub enum FsError {
     InvalidPath(PathName),
     #[error("directory '{0}' is not empty")]
     DirNotEmpty(PathName),
+    #[error("'{0}' is a dir")]
+    IsADir(PathName),
     #[error("bad descriptor '{0}' ({1} created)")]
     BadDescriptor(FileDescriptorIndex, usize),
     #[error("descriptor '{0}' was already closed")]
     DescriptorWasClosed(FileDescriptorIndex),
-    #[error("file at '{0}' was already opened")]
-    FileAlreadyOpened(PathName),
+    #[error("number of closed descriptor '{0}' is taken by another descriptor")]
+    DescriptorNumberTaken(FileDescriptorIndex),
     #[error("tried to rename '{0}' into subdirectory of itself '{1}'")]
     RenameToSubdirectoryError(PathName, PathName),
     #[error("loop exists in symbolic links encountered during path resolution")]
     LoopExists(PathName),
+    #[error("'{0}' resolves outside of workspace")]
+    OutsideWorkspace(PathName),
+    #[error("'{0}' is not a symbolic link")]
+    NotASymlink(PathName),
+    #[error("name or path '{0}' is too long")]
+    NameTooLong(PathName),
     /// Linux `link(2)` man
     ///
     /// POSIX.1-2001 says that link() should dereference oldpath if it is
@@ -59,10 +74,100 @@ pub enum FsError {
     /// it is a symbolic link.
     #[error("hardlink to symlink is forbidden")]
     HardlinkToSymlinkForbidden(PathName),
+    #[error("resulting offset '{0}' is negative")]
+    NegativeOffset(i64),
+    #[error("no data or hole found at or after offset '{0}'")]
+    NothingToSeek(i64),
+    #[error("invalid flags for opening '{0}'")]
+    InvalidOpenFlags(PathName),
+    #[error("'{0}' is a symbolic link, but O_NOFOLLOW is set")]
+    SymlinkNotFollowed(PathName),
+    #[error("descriptor '{0}' is not open for reading")]
+    NotOpenForReading(FileDescriptorIndex),
+    #[error("descriptor '{0}' is not open for writing")]
+    NotOpenForWriting(FileDescriptorIndex),
+    #[error("descriptors '{0}' and '{1}' are the same")]
+    SameDescriptors(FileDescriptorIndex, FileDescriptorIndex),
+    #[error("invalid flags for duplicating descriptor '{0}'")]
+    InvalidDupFlags(FileDescriptorIndex),
+    #[error("unsupported or invalid extended attribute name '{0}'")]
+    InvalidXattrName(String),
+    #[error("extended attribute value size '{0}' is too big")]
+    XattrValueTooBig(u64),
+    #[error("extended attribute '{1}' of '{0}' not found")]
+    XattrNotFound(PathName, String),
+    #[error("extended attribute '{1}' of '{0}' already exists")]
+    XattrAlreadyExists(PathName, String),
+    #[error("buffer of size '{1}' is too small for extended attributes of '{0}'")]
+    XattrBufferTooSmall(PathName, u64),
+    #[error("invalid fallocate mode '{0:?}'")]
+    InvalidFallocateMode(FallocateMode),
+    #[error("invalid fallocate range at offset '{0}' of length '{1}'")]
+    InvalidFallocateRange(u64, u64),
+    #[error("descriptor '{0}' can't be mapped for reading and writing")]
+    NotMappable(FileDescriptorIndex),
+    #[error("invalid mapping range at offset '{0}' of length '{1}'")]
+    InvalidMappingRange(u64, u64),
+    #[error("bad mapping '{0}' ({1} created)")]
+    BadMapping(MappingIndex, usize),
+    #[error("mapping '{0}' was already unmapped")]
+    MappingWasUnmapped(MappingIndex),
+    #[error("access at offset '{1}' of size '{2}' is outside of mapping '{0}'")]
+    OutOfMapping(MappingIndex, u64, u64),
+    #[error("access at offset '{1}' of size '{2}' to mapping '{0}' crosses the end of file")]
+    MappingCrossesEof(MappingIndex, u64, u64),
+    #[error("invalid msync flags '{0:?}'")]
+    InvalidMsyncFlags(MsyncFlags),
+    #[error("invalid rename flags '{0:?}'")]
+    InvalidRenameFlags(RenameFlags),
+    #[error("file of descriptor '{0}' has no links and can't be linked")]
+    NotLinkable(FileDescriptorIndex),
+    #[error("descriptor '{0}' is opened with O_APPEND")]
+    AppendOnly(FileDescriptorIndex),
+    #[error("ranges of descriptors '{0}' and '{1}' overlap in the same file")]
+    OverlappingCopy(FileDescriptorIndex, FileDescriptorIndex),
+    #[error("ranges of descriptors '{0}' and '{1}' are not aligned to block size")]
+    UnalignedRemap(FileDescriptorIndex, FileDescriptorIndex),
+    #[error("range of descriptor '{0}' is out of file")]
+    RemapOutOfRange(FileDescriptorIndex),
+    #[error("invalid lock range at offset '{0}' of length '{1}'")]
+    InvalidLockRange(u64, u64),
+    #[error("bad directory descriptor '{0}' ({1} created)")]
+    BadDirDescriptor(DirDescriptorIndex, usize),
+    #[error("directory descriptor '{0}' was already closed")]
+    DirDescriptorWasClosed(DirDescriptorIndex),
+    #[error("relative path '{0}' can't be resolved from removed directory")]
+    RemovedDir(PathName),
+    #[error("invalid *at flags '{0:?}'")]
+    InvalidAtFlags(AtFlags),
+    #[error("permission to access '{0}' is denied")]
+    AccessDenied(PathName),
+    #[error("operation on '{0}' is not permitted")]
+    NotPermitted(PathName),
+    #[error("permission to access file of descriptor '{0}' is denied")]
+    DescriptorAccessDenied(FileDescriptorIndex),
+    #[error("operation on descriptor '{0}' is not permitted")]
+    DescriptorNotPermitted(FileDescriptorIndex),
+    #[error("permission to access directory of descriptor '{0}' is denied")]
+    DirDescriptorAccessDenied(DirDescriptorIndex),
     #[error(transparent)]
     ContentError(#[from] ContentError),
 }
 
+impl FsError {
+    /// Operation is denied by permission checks (`EACCES` or `EPERM`).
+    pub fn is_denied(&self) -> bool {
+        matches!(
+            self,
+            FsError::AccessDenied(_)
+                | FsError::NotPermitted(_)
+                | FsError::DescriptorAccessDenied(_)
+                | FsError::DescriptorNotPermitted(_)
+                | FsError::DirDescriptorAccessDenied(_)
+        )
+    }
+}
+
 /// Abstract model of filesystem that approximates filesystem functions.
 ///
 /// All file nodes are stored as vectors and can be accessed using indicies (similar to inodes).
@@ -71,7 +176,21 @@ pub struct AbstractFS {
     pub dirs: Vec<Dir>,
     pub files: Vec<File>,
     pub symlinks: Vec<Symlink>,
+    pub specials: Vec<Special>,
     pub descriptors: Vec<FileDescriptor>,
+    pub descriptions: Vec<FileDescription>,
+    pub mappings: Vec<Mapping>,
+    pub dir_descriptors: Vec<DirDescriptor>,
+    /// Relative paths are resolved from it, can be removed.
+    pub cwd: DirIndex,
+    /// Lock table of `flock` locks.
+    pub file_locks: Vec<FileLock>,
+    /// Lock table of `fcntl` byte-range locks (POSIX and OFD).
+    pub record_locks: Vec<RecordLock>,
+    /// User that runs the test, owns new nodes.
+    pub credentials: Credentials,
+    /// File mode creation mask, changed by `umask`.
+    pub umask: u32,
     /// Every succesful operation is recorded and can be replayed from scratch.
     pub recording: Workload,
 }
@@ -85,108 +204,265 @@ pub struct AliveNodes {
     pub files_symlinked: Vec<(FileIndex, PathName)>,
     /// Includes files targeted by symlinks, but can also include symlinks referencing directories and etc.
     pub symlinks: Vec<PathName>,
+    /// FIFOs, sockets and device nodes (symlinks are not followed).
+    pub specials: Vec<(SpecialIndex, PathName)>,
 }
 
 const MAX_SYMLINK_FOLLOW: u8 = 2;
 
+/// Namespaces of extended attributes that are modeled.
+pub const XATTR_NAMESPACES: &[&str] = &["user.", "trusted."];
+/// Max size of extended attribute value on Linux.
+const XATTR_SIZE_MAX: u64 = 65536;
+
+/// File mode creation mask, executor sets the same value before running the test.
+pub const UMASK: u32 = 0o022;
+/// Block size of filesystem, ranges of `FALLOC_FL_COLLAPSE_RANGE`, `FALLOC_FL_INSERT_RANGE`
+/// and remapping (clone and dedupe) are aligned to it.
+pub const BLOCK_SIZE: u64 = 4096;
+/// Page size, offsets of mappings are aligned to it.
+pub const PAGE_SIZE: u64 = 4096;
+/// Uid and gid of root.
+const ROOT_ID: u32 = 0;
+
+/// Max length of file name in bytes.
+pub const NAME_MAX: usize = 255;
+/// Max length of path in bytes including the terminating null byte.
+pub const PATH_MAX: usize = 4096;
+/// Max length of workspace path, executor prefixes absolute paths with it.
+pub const WORKSPACE_MAX: usize = 128;
+/// Max length of symbolic link target including the terminating null byte (limit of XFS).
+const SYMLINK_MAX: usize = 1024;
+
+/// Access modes of permission checks, similar to `MAY_*` in Linux.
+const MAY_EXEC: u32 = 0o1;
+const MAY_WRITE: u32 = 0o2;
+const MAY_READ: u32 = 0o4;
+
+/// User and group that the test is executed with, supplementary groups are dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Credentials {
+    pub uid: u32,
+    pub gid: u32,
+}
+
+impl Credentials {
+    pub const ROOT: Credentials = Credentials {
+        uid: ROOT_ID,
+        gid: ROOT_ID,
+    };
+
+    /// Root bypasses all permission checks.
+    pub fn is_root(&self) -> bool {
+        self.uid == ROOT_ID
+    }
+}
+
 impl AbstractFS {
+    #[allow(dead_code)]
     pub fn new() -> Self {
+        AbstractFS::with_credentials(Credentials::ROOT)
+    }
+
+    /// Model of filesystem for the test that is executed by the user,
+    /// workspace is owned by the user.
+    pub fn with_credentials(credentials: Credentials) -> Self {
         AbstractFS {
             dirs: vec![Dir {
                 children: HashMap::new(),
+                xattrs: BTreeMap::new(),
+                // Workspace is created by executor with `0775` mode.
+                meta: Metadata {
+                    mode: 0o775 & !UMASK,
+                    uid: credentials.uid,
+                    gid: credentials.gid,
+                    atime: Timestamp::Now(0),
+                    mtime: Timestamp::Now(0),
+                    ctime: Timestamp::Now(0),
+                },
+                casefold: false,
             }],
             files: vec![],
             descriptors: vec![],
+            descriptions: vec![],
+            mappings: vec![],
+            dir_descriptors: vec![],
+            cwd: AbstractFS::root_index(),
+            file_locks: vec![],
+            record_locks: vec![],
             symlinks: vec![],
+            specials: vec![],
+            credentials,
+            umask: UMASK,
             recording: Workload::new(),
         }
     }
 
     /// Removes node, similar to `unlink` (for files) and `rmdir` (for dirs).
     pub fn remove(&mut self, path: PathName) -> Result<()> {
+        if let Ok((_, Node::Dir(idx))) = self.resolve_node(path.clone(), false) {
+            self.check_remove_tree(&idx, &path)?;
+        }
+        self.remove_node(path.clone())?;
+        self.recording.push(Operation::Remove { path });
+        Ok(())
+    }
+
+    fn remove_node(&mut self, path: PathName) -> Result<()> {
         if path.is_root() {
             return Err(FsError::RootRemovalForbidden);
         }
+        self.check_search(&path, false)?;
         let (parent_path, name) = path.split();
         let (_, parent_idx) = self.resolve_dir(parent_path.to_owned())?;
-        let parent = self.dir_mut(&parent_idx);
-        if parent.children.remove(&name).is_none() {
-            return Err(FsError::NotFound(path));
-        }
-        self.recording
-            .push(Operation::Remove { path: path.clone() });
+        let node = match self.dir(&parent_idx).lookup(&name) {
+            Some(node) => node.clone(),
+            None => return Err(FsError::NotFound(path)),
+        };
+        self.check_delete(&parent_idx, &node, &path)?;
+        self.dir_mut(&parent_idx).unlink(&name);
+        self.update_mtime(&Node::Dir(parent_idx), self.now());
+        self.update_removed_ctime(&node);
         Ok(())
     }
 
     /// Creates an empty directory, similar to `mkdir`.
     pub fn mkdir(&mut self, path: PathName, mode: Mode) -> Result<DirIndex> {
+        let dir_idx = self.make_dir(path.clone(), &mode)?;
+        self.recording.push(Operation::MkDir { path, mode });
+        Ok(dir_idx)
+    }
+
+    fn make_dir(&mut self, path: PathName, mode: &Mode) -> Result<DirIndex> {
+        self.check_search(&path, false)?;
         let (parent_path, name) = path.split();
         let (_, parent) = self.resolve_dir(parent_path.to_owned())?;
         if self.name_exists(&parent, &name) {
             return Err(FsError::NameAlreadyExists(path));
         }
+        self.check_dir_write(&parent, &path)?;
         let dir = Dir {
             children: HashMap::new(),
+            xattrs: BTreeMap::new(),
+            meta: self.new_metadata(&parent, mode_bits(mode) & 0o1777, true),
+            casefold: self.dir(&parent).casefold,
         };
         let dir_idx = DirIndex(self.dirs.len());
         self.dirs.push(dir);
         self.dir_mut(&parent)
             .children
             .insert(name, Node::Dir(dir_idx));
-        self.recording.push(Operation::MkDir { path, mode });
+        self.update_mtime(&Node::Dir(parent), self.now());
         Ok(dir_idx)
     }
 
     /// Creates an empty file, similar to `creat` but without open file descriptor.
     pub fn create(&mut self, path: PathName, mode: Mode) -> Result<FileIndex> {
+        self.check_search(&path, false)?;
         let (parent_path, name) = path.split();
         let (_, parent) = self.resolve_dir(parent_path.to_owned())?;
         if self.name_exists(&parent, &name) {
             return Err(FsError::NameAlreadyExists(path));
         }
+        self.check_dir_write(&parent, &path)?;
         let file = File {
-            descriptor: None,
+            descriptors: BTreeSet::new(),
             content: Content::new(),
+            xattrs: BTreeMap::new(),
+            meta: self.new_metadata(&parent, mode_bits(&mode) & 0o7777, false),
+            linkable: false,
         };
         let file_idx = FileIndex(self.files.len());
         self.files.push(file);
         self.dir_mut(&parent)
             .children
             .insert(name.clone(), Node::File(file_idx));
+        self.update_mtime(&Node::Dir(parent), self.now());
         self.recording.push(Operation::Create { path, mode });
         Ok(file_idx)
     }
 
-    /// Creates a "hard" link from one file to another, similar to `link`.
+    /// Creates a "hard" link from one file (or special file) to another, similar to `link`.
     /// Both files refer to the same node (in the file tree) but with different names.
-    pub fn hardlink(&mut self, old_path: PathName, new_path: PathName) -> Result<FileIndex> {
+    ///
+    /// Unprivileged user can only link own files, which is stricter than `protected_hardlinks` sysctl.
+    pub fn hardlink(&mut self, old_path: PathName, new_path: PathName) -> Result<Node> {
+        self.check_search(&old_path, false)?;
+        self.check_search(&new_path, false)?;
         let (parent_path, name) = old_path.split();
         let (_, parent) = self.resolve_dir(parent_path.to_owned())?;
-        if let Some(Node::Symlink(_)) = self.dir(&parent).children.get(&name) {
+        if let Some(Node::Symlink(_)) = self.dir(&parent).lookup(&name) {
             return Err(FsError::HardlinkToSymlinkForbidden(old_path));
         }
 
-        let (_, old_file) = self.resolve_file(old_path.clone())?;
+        let old_node = match self.resolve_node(old_path.clone(), true)? {
+            (_, node @ (Node::File(_) | Node::Special(_))) => node,
+            _ => return Err(FsError::NotAFile(old_path)),
+        };
+        if !self.is_owner(&old_node) {
+            return Err(FsError::NotPermitted(old_path));
+        }
         let (parent_path, name) = new_path.split();
         let (_, parent) = self.resolve_dir(parent_path.to_owned())?;
         if self.name_exists(&parent, &name) {
             return Err(FsError::NameAlreadyExists(new_path));
         }
+        self.check_dir_write(&parent, &new_path)?;
         let parent_dir = self.dir_mut(&parent);
-        parent_dir
-            .children
-            .insert(name.clone(), Node::File(old_file.to_owned()));
+        parent_dir.children.insert(name.clone(), old_node.clone());
+        self.update_mtime(&Node::Dir(parent), self.now());
+        self.update_ctime(&old_node, self.now());
         self.recording
             .push(Operation::Hardlink { old_path, new_path });
-        Ok(old_file.to_owned())
+        Ok(old_node)
+    }
+
+    /// Creates a link to the file using the file descriptor, similar to `linkat` with `AT_EMPTY_PATH`.
+    /// Unnamed file (see [`File::linkable`]) can be linked only once, removed file can't be linked.
+    /// Only root can link by descriptor (`CAP_DAC_READ_SEARCH` is required by older kernels).
+    pub fn flink(&mut self, des_idx: FileDescriptorIndex, path: PathName) -> Result<FileIndex> {
+        self.open_descriptor(&des_idx)?;
+        if !self.credentials.is_root() {
+            return Err(FsError::DescriptorNotPermitted(des_idx));
+        }
+        AbstractFS::check_length(&path)?;
+        let file_idx = self.description(&des_idx)?.file;
+        let named = self.alive().files.iter().any(|(idx, _)| *idx == file_idx);
+        if !named && !self.file(&file_idx).linkable {
+            return Err(FsError::NotLinkable(des_idx));
+        }
+        let (parent_path, name) = path.split();
+        let (_, parent) = self.resolve_dir(parent_path.to_owned())?;
+        if self.name_exists(&parent, &name) {
+            return Err(FsError::NameAlreadyExists(path));
+        }
+        self.dir_mut(&parent)
+            .children
+            .insert(name, Node::File(file_idx));
+        self.file_mut(&file_idx).linkable = false;
+        self.update_mtime(&Node::Dir(parent), self.now());
+        self.update_ctime(&Node::File(file_idx), self.now());
+        self.recording.push(Operation::FLink { des: des_idx, path });
+        Ok(file_idx)
     }
 
     pub fn symlink(&mut self, target: PathName, linkpath: PathName) -> Result<SymlinkIndex> {
+        // Absolute target is prefixed with workspace.
+        let prefix = if target.is_absolute() {
+            WORKSPACE_MAX
+        } else {
+            0
+        };
+        if prefix + target.len() >= SYMLINK_MAX {
+            return Err(FsError::NameTooLong(target));
+        }
+        self.check_search(&linkpath, false)?;
         let (parent_path, name) = linkpath.split();
         let (_, parent) = self.resolve_dir(parent_path.to_owned())?;
         if self.name_exists(&parent, &name) {
             return Err(FsError::NameAlreadyExists(linkpath));
         }
+        self.check_dir_write(&parent, &linkpath)?;
         let symlink = Symlink {
             target: target.clone(),
         };
@@ -195,892 +471,5387 @@ impl AbstractFS {
         self.dir_mut(&parent)
             .children
             .insert(name.clone(), Node::Symlink(sym_idx));
+        self.update_mtime(&Node::Dir(parent), self.now());
         self.recording.push(Operation::Symlink { target, linkpath });
         Ok(sym_idx)
     }
 
-    /// Renames a file, moving it between directories if required, similar to `rename`.
-    pub fn rename(&mut self, old_path: PathName, new_path: PathName) -> Result<Node> {
-        if let Ok((_, dir_idx)) = self.resolve_dir(new_path.clone()) {
-            if !self.dir(&dir_idx).children.is_empty() {
-                return Err(FsError::DirNotEmpty(new_path));
-            }
+    /// Creates a special file (FIFO, socket or device node), similar to `mknod`.
+    /// Device nodes can only be created by root (`CAP_MKNOD`).
+    pub fn mknod(
+        &mut self,
+        path: PathName,
+        mode: Mode,
+        file_type: FileType,
+    ) -> Result<SpecialIndex> {
+        self.check_search(&path, false)?;
+        let (parent_path, name) = path.split();
+        let (_, parent) = self.resolve_dir(parent_path.to_owned())?;
+        if self.name_exists(&parent, &name) {
+            return Err(FsError::NameAlreadyExists(path));
+        }
+        self.check_dir_write(&parent, &path)?;
+        let device = matches!(file_type, FileType::S_IFCHR | FileType::S_IFBLK);
+        if device && !self.credentials.is_root() {
+            return Err(FsError::NotPermitted(path));
+        }
+        let special = Special {
+            file_type,
+            meta: self.new_metadata(&parent, mode_bits(&mode) & 0o7777, false),
+        };
+        let special_idx = SpecialIndex(self.specials.len());
+        self.specials.push(special);
+        self.dir_mut(&parent)
+            .children
+            .insert(name, Node::Special(special_idx));
+        self.update_mtime(&Node::Dir(parent), self.now());
+        self.recording.push(Operation::MkNod {
+            path,
+            mode,
+            file_type,
+        });
+        Ok(special_idx)
+    }
+
+    /// Lists entries of the directory (without `.` and `..`), similar to `getdents`.
+    /// Symbolic links are followed.
+    /// Directory is read with search permission, because executor may stat the entries.
+    pub fn readdir(&mut self, path: PathName) -> Result<Vec<(Name, Node)>> {
+        let entries = self.dir_entries(path.clone())?;
+        self.check_search(&path, true)?;
+        let (_, idx) = self.resolve_dir(path.clone())?;
+        self.check_permission(&Node::Dir(idx), MAY_READ | MAY_EXEC, &path)?;
+        self.recording.push(Operation::ReadDir { path });
+        Ok(entries)
+    }
+
+    /// Returns target of the symbolic link, similar to `readlink`.
+    pub fn readlink(&mut self, path: PathName) -> Result<PathName> {
+        let target = self.link_target(path.clone())?;
+        self.check_search(&path, false)?;
+        self.recording.push(Operation::ReadLink { path });
+        Ok(target)
+    }
+
+    pub fn link_target(&self, path: PathName) -> Result<PathName> {
+        match self.resolve_node(path.clone(), false)? {
+            (_, Node::Symlink(idx)) => Ok(self.sym(&idx).target.clone()),
+            _ => Err(FsError::NotASymlink(path)),
         }
+    }
 
-        let (_, node) = self.resolve_node(old_path.clone(), false)?;
-        let (parent_path, name) = new_path.split();
-        let (old_dirs, parent) = self.resolve_dir(parent_path.to_owned())?;
+    /// Returns metadata of the node, similar to `stat`.
+    pub fn stat(&mut self, path: PathName) -> Result<Stat> {
+        let stat = self.stat_path(path.clone(), true)?;
+        self.check_search(&path, true)?;
+        self.recording.push(Operation::Stat { path });
+        Ok(stat)
+    }
+
+    /// Returns metadata of the node without following symbolic link, similar to `lstat`.
+    pub fn lstat(&mut self, path: PathName) -> Result<Stat> {
+        let stat = self.stat_path(path.clone(), false)?;
+        self.check_search(&path, false)?;
+        self.recording.push(Operation::LStat { path });
+        Ok(stat)
+    }
+
+    /// Returns metadata of the file, similar to `fstat`.
+    pub fn fstat(&mut self, des_idx: FileDescriptorIndex) -> Result<Stat> {
+        let stat = self.stat_descriptor(&des_idx)?;
+        self.recording.push(Operation::FStat { des: des_idx });
+        Ok(stat)
+    }
+
+    pub fn stat_path(&self, path: PathName, follow_symlinks: bool) -> Result<Stat> {
+        let (_, node) = self.resolve_node(path, follow_symlinks)?;
+        Ok(self.node_stat(&node))
+    }
+
+    pub fn stat_descriptor(&self, des_idx: &FileDescriptorIndex) -> Result<Stat> {
+        self.open_descriptor(des_idx)?;
+        let file_idx = self.description(des_idx)?.file;
+        Ok(self.node_stat(&Node::File(file_idx)))
+    }
 
-        if let Node::Dir(old_idx) = node {
-            if old_dirs.contains(&old_idx) || parent == old_idx {
-                return Err(FsError::RenameToSubdirectoryError(old_path, new_path));
+    fn node_stat(&self, node: &Node) -> Stat {
+        match node {
+            Node::File(idx) => {
+                let file = self.file(idx);
+                Stat {
+                    file_type: S_IFREG,
+                    mode: file.meta.mode,
+                    nlink: self.link_count(node),
+                    size: file.content.size(),
+                }
+            }
+            Node::Dir(idx) => Stat {
+                file_type: S_IFDIR,
+                mode: self.dir(idx).meta.mode,
+                nlink: 0,
+                size: 0,
+            },
+            // Permissions of symbolic links are not used by Linux and are always `0777`.
+            Node::Symlink(idx) => Stat {
+                file_type: S_IFLNK,
+                mode: 0o777,
+                nlink: self.link_count(node),
+                size: self.sym(idx).target.to_string().len() as u64,
+            },
+            Node::Special(idx) => {
+                let special = self.special(idx);
+                Stat {
+                    file_type: special.file_type as u32,
+                    mode: special.meta.mode,
+                    nlink: self.link_count(node),
+                    size: 0,
+                }
             }
         }
+    }
 
-        let parent_dir = self.dir_mut(&parent);
-        parent_dir.children.insert(name.clone(), node.clone());
+    /// Number of names that refer to the node, removed or unnamed file has no links.
+    fn link_count(&self, node: &Node) -> u64 {
+        let mut count = 0;
+        let mut queue = VecDeque::from([AbstractFS::root_index()]);
+        while let Some(idx) = queue.pop_front() {
+            for child in self.dir(&idx).children.values() {
+                if child == node {
+                    count += 1;
+                }
+                if let Node::Dir(child_idx) = child {
+                    queue.push_back(*child_idx);
+                }
+            }
+        }
+        count
+    }
 
-        let (parent_path, name) = old_path.split();
-        let (_, parent) = self.resolve_dir(parent_path.to_owned())?;
-        let parent_dir = self.dir_mut(&parent);
-        parent_dir.children.remove(&name);
+    /// Returns entries of the directory sorted by name, symbolic links are followed.
+    pub fn dir_entries(&self, path: PathName) -> Result<Vec<(Name, Node)>> {
+        let (_, idx) = self.resolve_dir(path)?;
+        let mut entries: Vec<(Name, Node)> = self
+            .dir(&idx)
+            .children
+            .iter()
+            .map(|(name, node)| (name.clone(), node.clone()))
+            .collect();
+        entries.sort();
+        Ok(entries)
+    }
 
+    /// Renames a file, moving it between directories if required, similar to `rename`.
+    pub fn rename(&mut self, old_path: PathName, new_path: PathName) -> Result<Node> {
+        let node = self.move_node(old_path.clone(), new_path.clone(), false)?;
         self.recording
             .push(Operation::Rename { old_path, new_path });
         Ok(node)
     }
 
+    /// Renames a file with flags, similar to `renameat2`.
+    /// With `RENAME_WHITEOUT` the old name is replaced by whiteout (character device 0:0),
+    /// which can be created by unprivileged user too.
+    pub fn renameat2(
+        &mut self,
+        old_path: PathName,
+        new_path: PathName,
+        flags: RenameFlags,
+    ) -> Result<()> {
+        let noreplace = flags.contains(&RenameFlag::RENAME_NOREPLACE);
+        let exchange = flags.contains(&RenameFlag::RENAME_EXCHANGE);
+        let whiteout = flags.contains(&RenameFlag::RENAME_WHITEOUT);
+        if exchange && (noreplace || whiteout) {
+            return Err(FsError::InvalidRenameFlags(flags));
+        }
+        if exchange {
+            self.exchange_nodes(old_path.clone(), new_path.clone())?;
+        } else {
+            if noreplace {
+                self.resolve_node(old_path.clone(), false)?;
+                let (parent_path, name) = new_path.split();
+                let (_, parent) = self.resolve_dir(parent_path.to_owned())?;
+                if self.name_exists(&parent, &name) {
+                    return Err(FsError::NameAlreadyExists(new_path));
+                }
+            }
+            self.move_node(old_path.clone(), new_path.clone(), whiteout)?;
+        }
+        self.recording.push(Operation::RenameAt2 {
+            old_path,
+            new_path,
+            flags,
+        });
+        Ok(())
+    }
+
+    /// Creates whiteout in place of the moved node.
+    fn leave_whiteout(&mut self, parent: DirIndex, name: Name) {
+        let special_idx = SpecialIndex(self.specials.len());
+        self.specials.push(Special {
+            file_type: FileType::S_IFCHR,
+            meta: self.new_metadata(&parent, 0, false),
+        });
+        self.dir_mut(&parent)
+            .children
+            .insert(name, Node::Special(special_idx));
+    }
+
+    /// Moves node to the new path, existing node at the new path is replaced
+    /// if types are compatible (non-directory by non-directory, empty directory by directory).
+    /// Whiteout is left at the old path only if node is moved.
+    fn move_node(
+        &mut self,
+        old_path: PathName,
+        new_path: PathName,
+        whiteout: bool,
+    ) -> Result<Node> {
+        if old_path.is_root() {
+            return Err(FsError::InvalidPath(old_path));
+        }
+        if new_path.is_root() {
+            return Err(FsError::InvalidPath(new_path));
+        }
+        self.check_search(&old_path, false)?;
+        self.check_search(&new_path, false)?;
+        let (_, node) = self.resolve_node(old_path.clone(), false)?;
+        let (old_parent_path, old_name) = old_path.split();
+        let (_, old_parent) = self.resolve_dir(old_parent_path.to_owned())?;
+        let (new_parent_path, new_name) = new_path.split();
+        let (new_dirs, new_parent) = self.resolve_dir(new_parent_path.to_owned())?;
+        let replaced = self.dir(&new_parent).lookup(&new_name).cloned();
+        // Linux does nothing if both paths refer to the same node (e.g. hardlinks).
+        if replaced.as_ref() == Some(&node) {
+            return Ok(node);
+        }
+        if let Node::Dir(idx) = node
+            && (new_dirs.contains(&idx) || new_parent == idx)
+        {
+            return Err(FsError::RenameToSubdirectoryError(old_path, new_path));
+        }
+        match (&node, &replaced) {
+            (Node::Dir(_), Some(Node::Dir(idx))) if !self.dir(idx).children.is_empty() => {
+                return Err(FsError::DirNotEmpty(new_path));
+            }
+            (Node::Dir(_), Some(replaced)) if !matches!(replaced, Node::Dir(_)) => {
+                return Err(FsError::NotADir(new_path));
+            }
+            (node, Some(Node::Dir(_))) if !matches!(node, Node::Dir(_)) => {
+                return Err(FsError::IsADir(new_path));
+            }
+            _ => {}
+        }
+        self.check_delete(&old_parent, &node, &old_path)?;
+        match &replaced {
+            Some(replaced) => self.check_delete(&new_parent, replaced, &new_path)?,
+            None => self.check_dir_write(&new_parent, &new_path)?,
+        }
+        // Entry `..` of directory is updated if it is moved to another parent.
+        if matches!(node, Node::Dir(_)) && old_parent != new_parent {
+            self.check_permission(&node, MAY_WRITE, &old_path)?;
+        }
+
+        self.dir_mut(&old_parent).unlink(&old_name);
+        self.dir_mut(&new_parent).link(new_name, node.clone());
+        if whiteout {
+            self.leave_whiteout(old_parent, old_name);
+        }
+
+        self.update_mtime(&Node::Dir(old_parent), self.now());
+        self.update_mtime(&Node::Dir(new_parent), self.now());
+        // POSIX allows to either update status change timestamp of renamed node or not.
+        self.update_ctime(&node, self.unspecified());
+        if let Some(replaced) = replaced {
+            self.update_removed_ctime(&replaced);
+        }
+        Ok(node)
+    }
+
+    /// Atomically exchanges nodes, nodes can be of different types.
+    fn exchange_nodes(&mut self, old_path: PathName, new_path: PathName) -> Result<()> {
+        if old_path.is_root() {
+            return Err(FsError::InvalidPath(old_path));
+        }
+        if new_path.is_root() {
+            return Err(FsError::InvalidPath(new_path));
+        }
+        self.check_search(&old_path, false)?;
+        self.check_search(&new_path, false)?;
+        let (_, old_node) = self.resolve_node(old_path.clone(), false)?;
+        let (_, new_node) = self.resolve_node(new_path.clone(), false)?;
+        let (old_parent_path, old_name) = old_path.split();
+        let (old_dirs, old_parent) = self.resolve_dir(old_parent_path.to_owned())?;
+        let (new_parent_path, new_name) = new_path.split();
+        let (new_dirs, new_parent) = self.resolve_dir(new_parent_path.to_owned())?;
+        // Linux does nothing if both paths refer to the same node.
+        if old_node == new_node {
+            return Ok(());
+        }
+        if let Node::Dir(idx) = old_node
+            && (new_dirs.contains(&idx) || new_parent == idx)
+        {
+            return Err(FsError::RenameToSubdirectoryError(old_path, new_path));
+        }
+        if let Node::Dir(idx) = new_node
+            && (old_dirs.contains(&idx) || old_parent == idx)
+        {
+            return Err(FsError::RenameToSubdirectoryError(new_path, old_path));
+        }
+        self.check_delete(&old_parent, &old_node, &old_path)?;
+        self.check_delete(&new_parent, &new_node, &new_path)?;
+        if old_parent != new_parent {
+            if matches!(old_node, Node::Dir(_)) {
+                self.check_permission(&old_node, MAY_WRITE, &old_path)?;
+            }
+            if matches!(new_node, Node::Dir(_)) {
+                self.check_permission(&new_node, MAY_WRITE, &new_path)?;
+            }
+        }
+        self.dir_mut(&old_parent).link(old_name, new_node.clone());
+        self.dir_mut(&new_parent).link(new_name, old_node.clone());
+        self.update_mtime(&Node::Dir(old_parent), self.now());
+        self.update_mtime(&Node::Dir(new_parent), self.now());
+        self.update_ctime(&old_node, self.unspecified());
+        self.update_ctime(&new_node, self.unspecified());
+        Ok(())
+    }
+
     /// Opens a file and returns the file descriptor, similar to `open`.
-    ///
-    /// TODO: flags
-    pub fn open(&mut self, path: PathName) -> Result<FileDescriptorIndex> {
+    /// File is created with `O_CREAT`, descriptors of directories are opened by `open_dir`.
+    pub fn open(
+        &mut self,
+        path: PathName,
+        flags: OpenFlags,
+        mode: Mode,
+    ) -> Result<FileDescriptorIndex> {
+        self.try_open(path, flags, mode)?
+    }
+
+    /// Same as `open`, but expected failures (`O_NOFOLLOW` on a symlink and `O_DIRECTORY`
+    /// on a file) are returned as the inner error. Such operation is still recorded,
+    /// so that error paths of `open` (`ELOOP` and `ENOTDIR`) are tested too.
+    pub fn try_open(
+        &mut self,
+        path: PathName,
+        flags: OpenFlags,
+        mode: Mode,
+    ) -> Result<Result<FileDescriptorIndex>> {
+        let result = self.open_file(path.clone(), &flags, &mode)?;
+        self.recording.push(Operation::Open {
+            path,
+            flags,
+            mode,
+            des: self.recorded_descriptor(&result),
+        });
+        Ok(result)
+    }
+
+    /// Descriptor of the recorded `open`, failed one gets the index
+    /// that the next opened descriptor will take.
+    fn recorded_descriptor(&self, result: &Result<FileDescriptorIndex>) -> FileDescriptorIndex {
+        match result {
+            Ok(des) => *des,
+            Err(_) => FileDescriptorIndex(self.descriptors.len()),
+        }
+    }
+
+    /// Outer error rejects the operation, inner one is the expected failure of `open`.
+    fn open_file(
+        &mut self,
+        path: PathName,
+        flags: &OpenFlags,
+        mode: &Mode,
+    ) -> Result<Result<FileDescriptorIndex>> {
+        let access_modes = flags
+            .iter()
+            .filter(|f| {
+                matches!(
+                    f,
+                    OpenFlag::O_RDONLY | OpenFlag::O_WRONLY | OpenFlag::O_RDWR
+                )
+            })
+            .count();
+        // Flags that are not available on Linux are rejected,
+        // `O_PATH` is only used for descriptors of directories.
+        let unsupported = flags.iter().any(|f| {
+            matches!(
+                f,
+                OpenFlag::O_EXEC
+                    | OpenFlag::O_SEARCH
+                    | OpenFlag::O_CLOFORK
+                    | OpenFlag::O_TTY_INIT
+                    | OpenFlag::O_PATH
+            )
+        });
+        if access_modes > 1 || unsupported {
+            return Err(FsError::InvalidOpenFlags(path));
+        }
+        let tmpfile = flags.contains(&OpenFlag::O_TMPFILE);
+        // `O_TMPFILE` requires write access and can't be combined with `O_CREAT` or `O_DIRECTORY`.
+        if tmpfile
+            && (!(flags.contains(&OpenFlag::O_WRONLY) || flags.contains(&OpenFlag::O_RDWR))
+                || flags.contains(&OpenFlag::O_CREAT)
+                || flags.contains(&OpenFlag::O_DIRECTORY))
+        {
+            return Err(FsError::InvalidOpenFlags(path));
+        }
         let des = FileDescriptorIndex(self.descriptors.len());
-        let (_, file_idx) = self.resolve_file(path.clone())?;
+        let file_idx = if tmpfile {
+            self.create_tmpfile(path, flags, mode)?
+        } else {
+            match self.open_named(path, flags, mode)? {
+                Ok(file_idx) => file_idx,
+                Err(err) => return Ok(Err(err)),
+            }
+        };
+        let number = self.free_number();
         let file = self.file_mut(&file_idx);
-        if file.descriptor.is_some() {
-            return Err(FsError::FileAlreadyOpened(path));
+        file.descriptors.insert(des);
+        // Linux truncates file even if it is opened with `O_RDONLY`.
+        if flags.contains(&OpenFlag::O_TRUNC) {
+            file.content.truncate(0);
+            self.update_mtime(&Node::File(file_idx), self.now());
         }
-        file.descriptor = Some(des);
         self.descriptors.push(FileDescriptor {
+            description: FileDescriptionIndex(self.descriptions.len()),
+            number,
+        });
+        self.descriptions.push(FileDescription {
             file: file_idx,
             offset: 0,
+            flags: flags.clone(),
         });
-        self.recording.push(Operation::Open { path, des });
-        Ok(des)
+        Ok(Ok(des))
+    }
+
+    /// Finds or creates (with `O_CREAT`) a file at the path.
+    /// Fails with the inner error if the file can't be opened because of `O_NOFOLLOW` or `O_DIRECTORY`.
+    fn open_named(
+        &mut self,
+        path: PathName,
+        flags: &OpenFlags,
+        mode: &Mode,
+    ) -> Result<Result<FileIndex>> {
+        self.check_search(&path, !flags.contains(&OpenFlag::O_NOFOLLOW))?;
+        let (parent_path, name) = path.split();
+        let (_, parent) = self.resolve_dir(parent_path.to_owned())?;
+        let file_idx = match self.dir(&parent).lookup(&name) {
+            None if flags.contains(&OpenFlag::O_CREAT) => {
+                if flags.contains(&OpenFlag::O_DIRECTORY) {
+                    return Err(FsError::InvalidOpenFlags(path));
+                }
+                self.check_dir_write(&parent, &path)?;
+                let file_idx = FileIndex(self.files.len());
+                self.files.push(File {
+                    descriptors: BTreeSet::new(),
+                    content: Content::new(),
+                    xattrs: BTreeMap::new(),
+                    meta: self.new_metadata(&parent, mode_bits(mode) & 0o7777, false),
+                    linkable: false,
+                });
+                self.dir_mut(&parent)
+                    .children
+                    .insert(name, Node::File(file_idx));
+                self.update_mtime(&Node::Dir(parent), self.now());
+                file_idx
+            }
+            None => return Err(FsError::NotFound(path)),
+            Some(_) if flags.contains(&OpenFlag::O_CREAT) && flags.contains(&OpenFlag::O_EXCL) => {
+                return Err(FsError::NameAlreadyExists(path));
+            }
+            Some(Node::Symlink(_)) if flags.contains(&OpenFlag::O_NOFOLLOW) => {
+                // Linux checks `O_DIRECTORY` before refusing to open a symlink.
+                if flags.contains(&OpenFlag::O_DIRECTORY) {
+                    return Ok(Err(FsError::NotADir(path)));
+                }
+                return Ok(Err(FsError::SymlinkNotFollowed(path)));
+            }
+            Some(_) => match self.resolve_node(path.clone(), true)? {
+                (dirs, Node::File(file_idx)) if !flags.contains(&OpenFlag::O_DIRECTORY) => {
+                    self.check_open(file_idx, dirs.last().unwrap(), flags, &path)?;
+                    file_idx
+                }
+                (_, Node::File(_)) => return Ok(Err(FsError::NotADir(path))),
+                // Directories are opened with `open_dir`.
+                _ => return Err(FsError::NotAFile(path)),
+            },
+        };
+        Ok(Ok(file_idx))
+    }
+
+    /// Creates an unnamed file in the directory at the path (`O_TMPFILE`).
+    fn create_tmpfile(
+        &mut self,
+        path: PathName,
+        flags: &OpenFlags,
+        mode: &Mode,
+    ) -> Result<FileIndex> {
+        let follow_symlinks = !flags.contains(&OpenFlag::O_NOFOLLOW);
+        let dir = match self.resolve_node(path.clone(), follow_symlinks)? {
+            (_, Node::Dir(idx)) => idx,
+            _ => return Err(FsError::NotADir(path)),
+        };
+        self.check_search(&path, follow_symlinks)?;
+        self.check_dir_write(&dir, &path)?;
+        let file_idx = FileIndex(self.files.len());
+        self.files.push(File {
+            descriptors: BTreeSet::new(),
+            content: Content::new(),
+            xattrs: BTreeMap::new(),
+            meta: self.new_metadata(&dir, mode_bits(mode) & 0o7777, false),
+            linkable: !flags.contains(&OpenFlag::O_EXCL),
+        });
+        Ok(file_idx)
     }
 
     /// Closes a file using the file descriptor, similar to `close`.
     pub fn close(&mut self, des_idx: FileDescriptorIndex) -> Result<()> {
-        let des = self.descriptor(&des_idx)?.clone();
+        let des = self.description(&des_idx)?.clone();
         let file = self.file_mut(&des.file);
-        if file.descriptor != Some(des_idx) {
+        if !file.descriptors.contains(&des_idx) {
             return Err(FsError::DescriptorWasClosed(des_idx));
         }
-        file.descriptor = None;
+        file.descriptors.remove(&des_idx);
+        let description = self.descriptor(&des_idx)?.description;
+        self.release_locks(des.file, description);
         self.recording.push(Operation::Close { des: des_idx });
         Ok(())
     }
 
-    /// Reads content of file using the file descriptor of specified size, similar to `read`.
-    /// Read position is managed by descriptor.
-    pub fn read(&mut self, des_idx: FileDescriptorIndex, size: u64) -> Result<Content> {
-        let des = self.descriptor(&des_idx)?.clone();
-        let offset = des.offset;
-        let file = self.file_mut(&des.file);
-        if file.descriptor != Some(des_idx) {
-            return Err(FsError::DescriptorWasClosed(des_idx));
-        }
-        let content = file.content.read(offset, size)?;
-        let file_size = file.content.size();
-        let des = self.descriptor_mut(&des_idx)?;
-        des.offset += content.size();
-        assert!(
-            des.offset <= file_size,
-            "offset: {}, size: {}",
-            des.offset,
-            file_size
-        );
-        self.recording.push(Operation::Read { des: des_idx, size });
-        Ok(content)
+    /// Duplicates the file descriptor, similar to `dup`.
+    /// Both descriptors share the same offset and flags.
+    pub fn dup(&mut self, old_des: FileDescriptorIndex) -> Result<FileDescriptorIndex> {
+        let description = self.open_descriptor(&old_des)?.description;
+        let file_idx = self.description(&old_des)?.file;
+        let new_des = FileDescriptorIndex(self.descriptors.len());
+        let number = self.free_number();
+        self.file_mut(&file_idx).descriptors.insert(new_des);
+        self.descriptors.push(FileDescriptor {
+            description,
+            number,
+        });
+        self.recording.push(Operation::Dup { old_des, new_des });
+        Ok(new_des)
     }
 
-    /// Writes slice of "source" data using the file descriptor, similar to `write`.
-    /// Write position is managed by descriptor.
-    pub fn write(
+    /// Makes descriptor refer to the same open file description as other descriptor, similar to `dup2`.
+    ///
+    /// Closed descriptor is opened again, unless its number was taken by another descriptor.
+    pub fn dup2(
         &mut self,
-        des_idx: FileDescriptorIndex,
-        src_offset: u64,
-        size: u64,
+        old_des: FileDescriptorIndex,
+        new_des: FileDescriptorIndex,
     ) -> Result<()> {
-        let des = self.descriptor(&des_idx)?.clone();
-        let file = self.file_mut(&des.file);
-        if file.descriptor != Some(des_idx) {
-            return Err(FsError::DescriptorWasClosed(des_idx));
-        }
-        let offset = des.offset;
-        file.content.write(src_offset, offset, size)?;
-        let file_size = file.content.size();
-        let des = self.descriptor_mut(&des_idx)?;
-        des.offset += size;
-        assert!(
-            des.offset <= file_size,
-            "offset: {}, size: {}",
-            des.offset,
-            file_size
-        );
-        self.recording.push(Operation::Write {
-            des: des_idx,
-            src_offset,
-            size,
-        });
+        self.reassign_descriptor(old_des, new_des)?;
+        self.recording.push(Operation::Dup2 { old_des, new_des });
         Ok(())
     }
 
-    /// No-op, sync file state with storage device, similar to `fsync`.
-    pub fn fsync(&mut self, des_idx: FileDescriptorIndex) -> Result<()> {
-        let des = self.descriptor(&des_idx)?.clone();
-        let file = self.file_mut(&des.file);
-        if file.descriptor != Some(des_idx) {
-            return Err(FsError::DescriptorWasClosed(des_idx));
+    /// Same as `dup2`, but descriptors must differ, similar to `dup3`.
+    /// Only `O_CLOEXEC` flag is allowed.
+    pub fn dup3(
+        &mut self,
+        old_des: FileDescriptorIndex,
+        new_des: FileDescriptorIndex,
+        flags: OpenFlags,
+    ) -> Result<()> {
+        if flags.iter().any(|f| *f != OpenFlag::O_CLOEXEC) {
+            return Err(FsError::InvalidDupFlags(old_des));
         }
-        self.recording.push(Operation::FSync { des: des_idx });
+        if old_des == new_des {
+            return Err(FsError::SameDescriptors(old_des, new_des));
+        }
+        self.reassign_descriptor(old_des, new_des)?;
+        self.recording.push(Operation::Dup3 {
+            old_des,
+            new_des,
+            flags,
+        });
         Ok(())
     }
 
-    /// Replay operations from workload. Does not reset the state.
-    pub fn replay(&mut self, workload: &Workload) -> Result<()> {
-        for op in &workload.ops {
-            match op {
-                Operation::MkDir { path, mode } => {
-                    self.mkdir(path.clone(), mode.clone())?;
-                }
-                Operation::Create { path, mode } => {
-                    self.create(path.clone(), mode.clone())?;
-                }
-                Operation::Remove { path } => self.remove(path.clone())?,
-                Operation::Hardlink { old_path, new_path } => {
-                    self.hardlink(old_path.clone(), new_path.clone())?;
-                }
-                Operation::Rename { old_path, new_path } => {
-                    self.rename(old_path.clone(), new_path.clone())?;
-                }
-                Operation::Open { path, des: _ } => {
-                    self.open(path.clone())?;
-                }
-                Operation::Close { des } => {
-                    self.close(*des)?;
-                }
-                Operation::Read { des, size } => {
-                    self.read(*des, *size)?;
-                }
-                Operation::Write {
-                    des,
-                    src_offset,
-                    size,
-                } => {
-                    self.write(*des, *src_offset, *size)?;
-                }
-                Operation::FSync { des } => {
-                    self.fsync(*des)?;
-                }
-                Operation::Symlink { target, linkpath } => {
-                    self.symlink(target.clone(), linkpath.clone())?;
-                }
-            };
+    fn reassign_descriptor(
+        &mut self,
+        old_des: FileDescriptorIndex,
+        new_des: FileDescriptorIndex,
+    ) -> Result<()> {
+        let description = self.open_descriptor(&old_des)?.description;
+        if old_des == new_des {
+            return Ok(());
+        }
+        let number = self.descriptor(&new_des)?.number;
+        if self.open_descriptor(&new_des).is_err() {
+            if self.taken_numbers().contains(&number) {
+                return Err(FsError::DescriptorNumberTaken(new_des));
+            }
+            self.descriptor_mut(&new_des)?.description = description;
+            let file_idx = self.description(&new_des)?.file;
+            self.file_mut(&file_idx).descriptors.insert(new_des);
+            return Ok(());
         }
+        let closed_file_idx = self.description(&new_des)?.file;
+        let closed_description = self.descriptor(&new_des)?.description;
+        self.file_mut(&closed_file_idx).descriptors.remove(&new_des);
+        self.descriptor_mut(&new_des)?.description = description;
+        let file_idx = self.description(&new_des)?.file;
+        self.file_mut(&file_idx).descriptors.insert(new_des);
+        self.release_locks(closed_file_idx, closed_description);
         Ok(())
     }
 
-    fn name_exists(&self, idx: &DirIndex, name: &Name) -> bool {
-        self.dir(idx).children.contains_key(name)
-    }
-
-    fn dir(&self, idx: &DirIndex) -> &Dir {
-        self.dirs.get(idx.0).unwrap()
+    /// Opens descriptor of the directory, similar to `openat` with `O_DIRECTORY`.
+    /// Only `O_RDONLY`, `O_PATH` and `O_CLOEXEC` flags are allowed in addition.
+    pub fn open_dir(
+        &mut self,
+        dir: DirFd,
+        path: PathName,
+        flags: OpenFlags,
+    ) -> Result<DirDescriptorIndex> {
+        let allowed = flags.iter().all(|f| {
+            matches!(
+                f,
+                OpenFlag::O_RDONLY | OpenFlag::O_DIRECTORY | OpenFlag::O_PATH | OpenFlag::O_CLOEXEC
+            )
+        });
+        if !allowed || !flags.contains(&OpenFlag::O_DIRECTORY) {
+            return Err(FsError::InvalidOpenFlags(path));
+        }
+        let abs_path = self.at_path(&dir, &path)?;
+        let dir_idx = self.resolve_dir(abs_path.clone())?.1;
+        self.check_search(&abs_path, true)?;
+        if !flags.contains(&OpenFlag::O_PATH) {
+            self.check_permission(&Node::Dir(dir_idx), MAY_READ, &abs_path)?;
+        }
+        let des = DirDescriptorIndex(self.dir_descriptors.len());
+        let number = self.free_number();
+        self.dir_descriptors.push(DirDescriptor {
+            dir: dir_idx,
+            closed: false,
+            number,
+        });
+        self.recording.push(Operation::OpenDir {
+            dir,
+            path,
+            flags,
+            des,
+        });
+        Ok(des)
     }
 
-    fn dir_mut(&mut self, idx: &DirIndex) -> &mut Dir {
-        self.dirs.get_mut(idx.0).unwrap()
+    /// Closes the directory descriptor, similar to `close`.
+    pub fn close_dir(&mut self, des: DirDescriptorIndex) -> Result<()> {
+        self.dir_descriptor(&des)?;
+        self.dir_descriptors[des.0].closed = true;
+        self.recording.push(Operation::CloseDir { des });
+        Ok(())
     }
 
-    pub fn file(&self, idx: &FileIndex) -> &File {
-        self.files.get(idx.0).unwrap()
+    /// Changes current working directory, similar to `chdir`.
+    pub fn chdir(&mut self, path: PathName) -> Result<()> {
+        let abs_path = self.at_path(&DirFd::Cwd, &path)?;
+        let dir_idx = self.resolve_dir(abs_path.clone())?.1;
+        self.check_search(&abs_path, true)?;
+        self.check_permission(&Node::Dir(dir_idx), MAY_EXEC, &abs_path)?;
+        self.cwd = dir_idx;
+        self.recording.push(Operation::ChDir { path });
+        Ok(())
     }
 
-    fn file_mut(&mut self, idx: &FileIndex) -> &mut File {
-        self.files.get_mut(idx.0).unwrap()
+    /// Changes current working directory to the directory of descriptor, similar to `fchdir`.
+    /// Directory can be already removed.
+    pub fn fchdir(&mut self, des: DirDescriptorIndex) -> Result<()> {
+        let dir_idx = self.dir_descriptor(&des)?.dir;
+        if !self.permitted(&Node::Dir(dir_idx), MAY_EXEC) {
+            return Err(FsError::DirDescriptorAccessDenied(des));
+        }
+        self.cwd = dir_idx;
+        self.recording.push(Operation::FChDir { des });
+        Ok(())
     }
 
-    fn sym(&self, idx: &SymlinkIndex) -> &Symlink {
-        self.symlinks.get(idx.0).unwrap()
+    /// Same as `mkdir`, but relative path is resolved from the directory, similar to `mkdirat`.
+    pub fn mkdirat(&mut self, dir: DirFd, path: PathName, mode: Mode) -> Result<DirIndex> {
+        let dir_idx = self.make_dir(self.at_path(&dir, &path)?, &mode)?;
+        self.recording.push(Operation::MkDirAt { dir, path, mode });
+        Ok(dir_idx)
     }
 
-    #[allow(dead_code)]
-    fn root(&self) -> &Dir {
-        self.dirs.first().unwrap()
+    /// Same as `open`, but relative path is resolved from the directory, similar to `openat`.
+    pub fn openat(
+        &mut self,
+        dir: DirFd,
+        path: PathName,
+        flags: OpenFlags,
+        mode: Mode,
+    ) -> Result<FileDescriptorIndex> {
+        self.try_openat(dir, path, flags, mode)?
     }
 
-    fn descriptor(&self, idx: &FileDescriptorIndex) -> Result<&FileDescriptor> {
-        self.descriptors
-            .get(idx.0)
-            .ok_or(FsError::BadDescriptor(*idx, self.descriptors.len()))
+    /// Same as `openat`, but expected failures are returned as the inner error (see `try_open`).
+    pub fn try_openat(
+        &mut self,
+        dir: DirFd,
+        path: PathName,
+        flags: OpenFlags,
+        mode: Mode,
+    ) -> Result<Result<FileDescriptorIndex>> {
+        let result = self.open_file(self.at_path(&dir, &path)?, &flags, &mode)?;
+        self.recording.push(Operation::OpenAt {
+            dir,
+            path,
+            flags,
+            mode,
+            des: self.recorded_descriptor(&result),
+        });
+        Ok(result)
     }
 
-    fn descriptor_mut(&mut self, idx: &FileDescriptorIndex) -> Result<&mut FileDescriptor> {
-        let len = self.descriptors.len();
-        self.descriptors
-            .get_mut(idx.0)
-            .ok_or(FsError::BadDescriptor(*idx, len))
+    /// Removes file or empty directory (with `AT_REMOVEDIR`), similar to `unlinkat`.
+    pub fn unlinkat(&mut self, dir: DirFd, path: PathName, flags: AtFlags) -> Result<()> {
+        if flags.iter().any(|f| *f != AtFlag::AT_REMOVEDIR) {
+            return Err(FsError::InvalidAtFlags(flags));
+        }
+        let abs_path = self.at_path(&dir, &path)?;
+        let (_, node) = self.resolve_node(abs_path.clone(), false)?;
+        match node {
+            Node::Dir(idx)
+                if flags.contains(&AtFlag::AT_REMOVEDIR) && !self.dir(&idx).children.is_empty() =>
+            {
+                return Err(FsError::DirNotEmpty(path));
+            }
+            Node::Dir(_) if flags.contains(&AtFlag::AT_REMOVEDIR) => {}
+            Node::Dir(_) => return Err(FsError::IsADir(path)),
+            _ if flags.contains(&AtFlag::AT_REMOVEDIR) => return Err(FsError::NotADir(path)),
+            _ => {}
+        }
+        self.remove_node(abs_path)?;
+        self.recording
+            .push(Operation::UnlinkAt { dir, path, flags });
+        Ok(())
     }
 
-    pub fn resolve_node(
-        &self,
-        path: PathName,
-        follow_symlinks: bool,
-    ) -> Result<(Vec<DirIndex>, Node)> {
-        self.resolve_node_rec(path, follow_symlinks, vec![])
+    /// Same as `rename`, but relative paths are resolved from the directories, similar to `renameat`.
+    pub fn renameat(
+        &mut self,
+        old_dir: DirFd,
+        old_path: PathName,
+        new_dir: DirFd,
+        new_path: PathName,
+    ) -> Result<Node> {
+        let node = self.move_node(
+            self.at_path(&old_dir, &old_path)?,
+            self.at_path(&new_dir, &new_path)?,
+            false,
+        )?;
+        self.recording.push(Operation::RenameAt {
+            old_dir,
+            old_path,
+            new_dir,
+            new_path,
+        });
+        Ok(node)
     }
 
-    pub fn resolve_node_rec(
-        &self,
-        path: PathName,
-        follow_symlinks: bool,
-        mut visited_symlinks: Vec<SymlinkIndex>,
-    ) -> Result<(Vec<DirIndex>, Node)> {
-        if !path.is_valid() {
-            return Err(FsError::InvalidPath(path));
+    /// Returns metadata of the node, similar to `fstatat`.
+    pub fn fstatat(&mut self, dir: DirFd, path: PathName, flags: AtFlags) -> Result<Stat> {
+        let stat = self.stat_at(&dir, &path, &flags)?;
+        if !path.is_empty() {
+            let follow_symlinks = !flags.contains(&AtFlag::AT_SYMLINK_NOFOLLOW);
+            self.check_search(&self.at_path(&dir, &path)?, follow_symlinks)?;
         }
-        let mut dirs = vec![];
-        let segments = path.segments();
-        let mut last = Node::Dir(AbstractFS::root_index());
-        let mut path = String::new();
-        for segment in &segments {
-            path.push('/');
-            path.push_str(segment);
-            let dir = match last {
-                Node::Dir(idx) => {
-                    dirs.push(idx);
-                    self.dir(&idx)
-                }
-                Node::Symlink(idx) => {
-                    let target = self.sym(&idx).target.clone();
-                    let (mut rec_dirs, idx) = self.resolve_dir(target)?;
-                    dirs.append(&mut rec_dirs);
-                    dirs.push(idx);
-                    self.dir(&idx)
-                }
-                _ => return Err(FsError::NotADir(path.into())),
-            };
-            last = dir
-                .children
-                .get(segment.to_owned())
-                .ok_or(FsError::NotFound(path.clone().into()))?
-                .clone();
+        self.recording.push(Operation::FStatAt { dir, path, flags });
+        Ok(stat)
+    }
+
+    /// Empty path refers to the directory itself if `AT_EMPTY_PATH` is set,
+    /// symbolic link is not followed with `AT_SYMLINK_NOFOLLOW`.
+    pub fn stat_at(&self, dir: &DirFd, path: &PathName, flags: &AtFlags) -> Result<Stat> {
+        if flags.contains(&AtFlag::AT_REMOVEDIR) {
+            return Err(FsError::InvalidAtFlags(flags.clone()));
         }
-        match last {
-            Node::Symlink(idx) if follow_symlinks => {
-                if visited_symlinks.contains(&idx) {
-                    return Err(FsError::LoopExists(path.into()));
-                }
-                let target = self.sym(&idx).target.clone();
-                visited_symlinks.push(idx);
-                let (mut rec_dirs, last) =
-                    self.resolve_node_rec(target, follow_symlinks, visited_symlinks)?;
-                dirs.append(&mut rec_dirs);
-                Ok((dirs, last))
+        if path.is_empty() {
+            if !flags.contains(&AtFlag::AT_EMPTY_PATH) {
+                return Err(FsError::NotFound(path.clone()));
             }
-            _ => Ok((dirs, last)),
+            return Ok(self.node_stat(&Node::Dir(self.dirfd_dir(dir)?)));
         }
+        self.stat_path(
+            self.at_path(dir, path)?,
+            !flags.contains(&AtFlag::AT_SYMLINK_NOFOLLOW),
+        )
     }
 
-    pub fn resolve_file(&self, path: PathName) -> Result<(Vec<DirIndex>, FileIndex)> {
-        match self.resolve_node(path.clone(), true)? {
-            (dirs, Node::File(idx)) => Ok((dirs, idx)),
-            _ => Err(FsError::NotAFile(path)),
+    /// Returns absolute path for the path of `*at` operation.
+    /// Relative path is resolved from the current path of directory, which must not be removed.
+    pub fn at_path(&self, dir: &DirFd, path: &PathName) -> Result<PathName> {
+        if path.is_absolute() {
+            return Ok(path.clone());
         }
+        if path.is_empty() {
+            return Err(FsError::NotFound(path.clone()));
+        }
+        let dir_path = self
+            .dir_path(&self.dirfd_dir(dir)?)
+            .ok_or_else(|| FsError::RemovedDir(path.clone()))?;
+        Ok(dir_path.join(path.to_string()))
     }
 
-    pub fn resolve_dir(&self, path: PathName) -> Result<(Vec<DirIndex>, DirIndex)> {
-        match self.resolve_node(path.clone(), true)? {
-            (dirs, Node::Dir(idx)) => Ok((dirs, idx)),
-            _ => Err(FsError::NotADir(path)),
+    /// Directory of the descriptor or current working directory.
+    pub fn dirfd_dir(&self, dir: &DirFd) -> Result<DirIndex> {
+        match dir {
+            DirFd::Cwd => Ok(self.cwd),
+            DirFd::Des(des) => Ok(self.dir_descriptor(des)?.dir),
         }
     }
 
-    pub fn root_index() -> DirIndex {
-        DirIndex(0)
+    /// Path of the directory without symbolic links, `None` if it was removed.
+    pub fn dir_path(&self, idx: &DirIndex) -> Option<PathName> {
+        let mut queue = VecDeque::from([("/".into(), AbstractFS::root_index())]);
+        while let Some((path, dir_idx)) = queue.pop_front() {
+            if dir_idx == *idx {
+                return Some(path);
+            }
+            for (name, node) in self.dir(&dir_idx).children.iter() {
+                if let Node::Dir(child) = node {
+                    queue.push_back((path.join(name.clone()), *child));
+                }
+            }
+        }
+        None
     }
 
-    /// Get nodes that are considired "alive" (accessible from root)
-    pub fn alive(&self) -> AliveNodes {
-        let root = AbstractFS::root_index();
-        let mut alive = AliveNodes {
-            dirs: vec![],
-            files: vec![],
-            files_symlinked: vec![],
-            symlinks: vec![],
+    /// Reads content of file using the file descriptor of specified size, similar to `read`.
+    /// Read position is managed by descriptor.
+    pub fn read(&mut self, des_idx: FileDescriptorIndex, size: u64) -> Result<Content> {
+        let des = self.description(&des_idx)?.clone();
+        let offset = des.offset;
+        let file = self.file_mut(&des.file);
+        if !file.descriptors.contains(&des_idx) {
+            return Err(FsError::DescriptorWasClosed(des_idx));
+        }
+        if !des.readable() {
+            return Err(FsError::NotOpenForReading(des_idx));
+        }
+        // Offset can be past the end of file (e.g. after `truncate`), nothing is read then.
+        let content = if offset < file.content.size() {
+            file.content.read(offset, size)?
+        } else {
+            Content::new()
         };
-        let mut queue = VecDeque::new();
-        queue.push_back(("/".into(), root));
-        alive.dirs.push((Self::root_index(), "/".into()));
+        let des = self.description_mut(&des_idx)?;
+        des.offset += content.size();
+        self.recording.push(Operation::Read { des: des_idx, size });
+        Ok(content)
+    }
 
-        // Because symbolic links can loop back, we follow them only a few times.
-        for _ in 1..=MAX_SYMLINK_FOLLOW {
-            let follow_queue = self.alive_follow_once(&mut alive, queue);
-            queue = follow_queue;
+    /// Writes slice of "source" data using the file descriptor, similar to `write`.
+    /// Write position is managed by descriptor.
+    pub fn write(
+        &mut self,
+        des_idx: FileDescriptorIndex,
+        src_offset: u64,
+        size: u64,
+    ) -> Result<()> {
+        let des = self.description(&des_idx)?.clone();
+        let file = self.file_mut(&des.file);
+        if !file.descriptors.contains(&des_idx) {
+            return Err(FsError::DescriptorWasClosed(des_idx));
+        }
+        if !des.writable() {
+            return Err(FsError::NotOpenForWriting(des_idx));
+        }
+        // Linux does nothing if there is no data to write (even with `O_APPEND`).
+        if size > 0 {
+            // With `O_APPEND` offset is moved to the end of file before each write.
+            let offset = if des.append() {
+                file.content.size()
+            } else {
+                des.offset
+            };
+            if offset > file.content.size() {
+                // Writing past the end of file leaves a hole.
+                file.content.truncate(offset);
+            }
+            file.content.write(src_offset, offset, size)?;
+            let file_size = file.content.size();
+            self.update_mtime(&Node::File(des.file), self.now());
+            let des = self.description_mut(&des_idx)?;
+            des.offset = offset + size;
+            assert!(
+                des.offset <= file_size,
+                "offset: {}, size: {}",
+                des.offset,
+                file_size
+            );
         }
+        self.recording.push(Operation::Write {
+            des: des_idx,
+            src_offset,
+            size,
+        });
+        Ok(())
+    }
 
-        alive.dirs.sort();
-        alive.files.sort();
-        alive.files_symlinked.sort();
-        alive.symlinks.sort();
-        alive
+    /// Shrinks or extends file to the specified size, similar to `truncate`.
+    /// Extended part reads as zeros.
+    pub fn truncate(&mut self, path: PathName, size: u64) -> Result<()> {
+        let (_, file_idx) = self.resolve_file(path.clone())?;
+        self.check_search(&path, true)?;
+        self.check_permission(&Node::File(file_idx), MAY_WRITE, &path)?;
+        // POSIX only requires to update timestamps if size is changed, but some filesystems always do.
+        let time = if self.file(&file_idx).content.size() != size {
+            self.now()
+        } else {
+            self.unspecified()
+        };
+        self.file_mut(&file_idx).content.truncate(size);
+        self.update_mtime(&Node::File(file_idx), time);
+        self.recording.push(Operation::Truncate { path, size });
+        Ok(())
     }
 
-    /// Breadth-first search, follow symbolic links once
-    fn alive_follow_once(
-        &self,
-        alive: &mut AliveNodes,
-        mut queue: VecDeque<(PathName, DirIndex)>,
-    ) -> VecDeque<(PathName, DirIndex)> {
-        let mut follow_next = VecDeque::new();
-        while let Some((dir_path, idx)) = queue.pop_front() {
-            let dir = self.dir(&idx);
-            for (child_name, node) in dir.children.iter() {
-                match node {
-                    Node::Dir(idx) => {
-                        let path = dir_path.join(child_name.to_owned());
-                        queue.push_back((path.clone(), *idx));
-                        alive.dirs.push((idx.clone(), path.clone()));
-                    }
-                    Node::File(idx) => {
-                        alive
-                            .files
-                            .push((*idx, dir_path.join(child_name.to_owned())));
-                    }
-                    Node::Symlink(idx) => {
-                        alive.symlinks.push(dir_path.join(child_name.to_owned()));
-                        let follow_path = self.sym(&idx).target.clone();
-                        match self.resolve_node(follow_path, true) {
-                            Ok((_, Node::File(idx))) => {
-                                alive
-                                    .files_symlinked
-                                    .push((idx, dir_path.join(child_name.to_owned())));
-                            }
-                            Ok((_, Node::Dir(idx))) => {
-                                let path = dir_path.join(child_name.to_owned());
-                                follow_next.push_back((path.clone(), idx));
-                                alive.dirs.push((idx, path.clone()));
-                            }
-                            _ => {}
-                        }
-                    }
+    /// Shrinks or extends file using the file descriptor, similar to `ftruncate`.
+    /// Descriptor offset is not changed.
+    pub fn ftruncate(&mut self, des_idx: FileDescriptorIndex, size: u64) -> Result<()> {
+        let des = self.description(&des_idx)?.clone();
+        let file = self.file_mut(&des.file);
+        if !file.descriptors.contains(&des_idx) {
+            return Err(FsError::DescriptorWasClosed(des_idx));
+        }
+        if !des.writable() {
+            return Err(FsError::NotOpenForWriting(des_idx));
+        }
+        file.content.truncate(size);
+        // Unlike `truncate`, Linux always updates timestamps.
+        self.update_mtime(&Node::File(des.file), self.now());
+        self.recording
+            .push(Operation::FTruncate { des: des_idx, size });
+        Ok(())
+    }
+
+    /// Manipulates allocated space of file using the file descriptor, similar to `fallocate`.
+    /// Descriptor offset is not changed.
+    pub fn fallocate(
+        &mut self,
+        des_idx: FileDescriptorIndex,
+        mode: FallocateMode,
+        offset: u64,
+        len: u64,
+    ) -> Result<()> {
+        let des = self.description(&des_idx)?.clone();
+        let file = self.file_mut(&des.file);
+        if !file.descriptors.contains(&des_idx) {
+            return Err(FsError::DescriptorWasClosed(des_idx));
+        }
+        if !des.writable() {
+            return Err(FsError::NotOpenForWriting(des_idx));
+        }
+        if len == 0 {
+            return Err(FsError::InvalidFallocateRange(offset, len));
+        }
+        let has = |flag| mode.contains(&flag);
+        let keep_size = has(FallocateFlag::FALLOC_FL_KEEP_SIZE);
+        let content = &mut file.content;
+        match (
+            has(FallocateFlag::FALLOC_FL_PUNCH_HOLE),
+            has(FallocateFlag::FALLOC_FL_ZERO_RANGE),
+            has(FallocateFlag::FALLOC_FL_COLLAPSE_RANGE),
+            has(FallocateFlag::FALLOC_FL_INSERT_RANGE),
+        ) {
+            (false, false, false, false) => content.allocate(offset, len, keep_size),
+            (true, false, false, false) if keep_size => content.punch_hole(offset, len),
+            (false, true, false, false) => content.zero_range(offset, len, keep_size),
+            (false, false, collapse, insert) if collapse != insert && !keep_size => {
+                if !offset.is_multiple_of(BLOCK_SIZE) || !len.is_multiple_of(BLOCK_SIZE) {
+                    return Err(FsError::InvalidFallocateRange(offset, len));
+                }
+                if collapse {
+                    content.collapse_range(offset, len)?;
+                } else {
+                    content.insert_range(offset, len)?;
                 }
             }
+            _ => return Err(FsError::InvalidFallocateMode(mode)),
         }
-        follow_next
+        // Filesystems differ in updating timestamps, when size is not changed.
+        self.update_mtime(&Node::File(des.file), self.unspecified());
+        self.recording.push(Operation::FAllocate {
+            des: des_idx,
+            mode,
+            offset,
+            len,
+        });
+        Ok(())
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::abstract_fs::content::SourceSlice;
 
-    use super::*;
-
-    #[test]
-    fn test_init_root() {
-        let fs = AbstractFS::new();
-        assert_eq!(
-            AliveNodes {
-                dirs: vec![(AbstractFS::root_index(), "/".into())],
-                files: vec![],
-                files_symlinked: vec![],
-                symlinks: vec![],
-            },
-            fs.alive()
-        )
+    /// Repositions offset of the file descriptor, similar to `lseek`.
+    /// Returns the resulting offset.
+    pub fn lseek(
+        &mut self,
+        des_idx: FileDescriptorIndex,
+        offset: i64,
+        whence: SeekWhence,
+    ) -> Result<u64> {
+        let des = self.description(&des_idx)?.clone();
+        let file = self.file(&des.file);
+        if !file.descriptors.contains(&des_idx) {
+            return Err(FsError::DescriptorWasClosed(des_idx));
+        }
+        let content = &file.content;
+        let new_offset = match whence {
+            SeekWhence::SEEK_SET => offset,
+            SeekWhence::SEEK_CUR => des.offset as i64 + offset,
+            SeekWhence::SEEK_END => content.size() as i64 + offset,
+            SeekWhence::SEEK_DATA | SeekWhence::SEEK_HOLE if offset < 0 => {
+                return Err(FsError::NothingToSeek(offset));
+            }
+            SeekWhence::SEEK_DATA => content
+                .seek_data(offset as u64)
+                .ok_or(FsError::NothingToSeek(offset))? as i64,
+            SeekWhence::SEEK_HOLE => content
+                .seek_hole(offset as u64)
+                .ok_or(FsError::NothingToSeek(offset))? as i64,
+        };
+        if new_offset < 0 {
+            return Err(FsError::NegativeOffset(new_offset));
+        }
+        let des = self.description_mut(&des_idx)?;
+        des.offset = new_offset as u64;
+        self.recording.push(Operation::LSeek {
+            des: des_idx,
+            offset,
+            whence,
+        });
+        Ok(new_offset as u64)
     }
 
-    #[test]
-    fn test_remove_root() {
-        let mut fs = AbstractFS::new();
-        assert_eq!(Err(FsError::RootRemovalForbidden), fs.remove("/".into()));
+    /// Reads content of file at the specified offset, similar to `pread`.
+    /// Descriptor offset is not changed.
+    pub fn pread(
+        &mut self,
+        des_idx: FileDescriptorIndex,
+        size: u64,
+        offset: u64,
+    ) -> Result<Content> {
+        let des = self.description(&des_idx)?.clone();
+        let file = self.file(&des.file);
+        if !file.descriptors.contains(&des_idx) {
+            return Err(FsError::DescriptorWasClosed(des_idx));
+        }
+        if !des.readable() {
+            return Err(FsError::NotOpenForReading(des_idx));
+        }
+        let content = if offset < file.content.size() {
+            file.content.read(offset, size)?
+        } else {
+            Content::new()
+        };
+        self.recording.push(Operation::PRead {
+            des: des_idx,
+            size,
+            offset,
+        });
+        Ok(content)
     }
 
-    #[test]
-    fn test_mkdir() {
-        let mut fs = AbstractFS::new();
-        let foo = fs.mkdir("/foobar".into(), vec![]).unwrap();
-        assert_eq!(Node::Dir(foo), *fs.root().children.get("foobar").unwrap());
-        assert_eq!(
+    /// Writes slice of "source" data at the specified offset, similar to `pwrite`.
+    /// Descriptor offset is not changed.
+    pub fn pwrite(
+        &mut self,
+        des_idx: FileDescriptorIndex,
+        src_offset: u64,
+        size: u64,
+        offset: u64,
+    ) -> Result<()> {
+        let des = self.description(&des_idx)?.clone();
+        let file = self.file_mut(&des.file);
+        if !file.descriptors.contains(&des_idx) {
+            return Err(FsError::DescriptorWasClosed(des_idx));
+        }
+        if !des.writable() {
+            return Err(FsError::NotOpenForWriting(des_idx));
+        }
+        // Linux appends data regardless of offset if file is opened with `O_APPEND`.
+        let write_offset = if des.append() {
+            file.content.size()
+        } else {
+            offset
+        };
+        // Linux does nothing if there is no data to write, even if offset is past the end of file.
+        if size > 0 {
+            if write_offset > file.content.size() {
+                file.content.truncate(write_offset);
+            }
+            file.content.write(src_offset, write_offset, size)?;
+            self.update_mtime(&Node::File(des.file), self.now());
+        }
+        self.recording.push(Operation::PWrite {
+            des: des_idx,
+            src_offset,
+            size,
+            offset,
+        });
+        Ok(())
+    }
+
+    /// Copies data between files, similar to `copy_file_range`.
+    /// Returns number of copied bytes, copying stops at the end of input file.
+    pub fn copy_file_range(
+        &mut self,
+        des_in: FileDescriptorIndex,
+        offset_in: Option<u64>,
+        des_out: FileDescriptorIndex,
+        offset_out: Option<u64>,
+        len: u64,
+    ) -> Result<u64> {
+        let copied = self.copy_range(des_in, offset_in, des_out, offset_out, len, false)?;
+        self.recording.push(Operation::CopyFileRange {
+            des_in,
+            offset_in,
+            des_out,
+            offset_out,
+            len,
+        });
+        Ok(copied)
+    }
+
+    /// Copies data to the offset of output descriptor, similar to `sendfile`.
+    pub fn sendfile(
+        &mut self,
+        des_out: FileDescriptorIndex,
+        des_in: FileDescriptorIndex,
+        offset: Option<u64>,
+        count: u64,
+    ) -> Result<u64> {
+        let copied = self.copy_range(des_in, offset, des_out, None, count, true)?;
+        self.recording.push(Operation::SendFile {
+            des_out,
+            des_in,
+            offset,
+            count,
+        });
+        Ok(copied)
+    }
+
+    /// Moves data between files through pipe, similar to `splice` from file to pipe
+    /// and from pipe to file.
+    pub fn splice(
+        &mut self,
+        des_in: FileDescriptorIndex,
+        offset_in: Option<u64>,
+        des_out: FileDescriptorIndex,
+        offset_out: Option<u64>,
+        len: u64,
+    ) -> Result<u64> {
+        let copied = self.copy_range(des_in, offset_in, des_out, offset_out, len, true)?;
+        self.recording.push(Operation::Splice {
+            des_in,
+            offset_in,
+            des_out,
+            offset_out,
+            len,
+        });
+        Ok(copied)
+    }
+
+    /// Copies slices of input file content, writing past the end of output file leaves a hole.
+    /// Offset of descriptor is used (and moved) if explicit offset is not specified.
+    fn copy_range(
+        &mut self,
+        des_in: FileDescriptorIndex,
+        offset_in: Option<u64>,
+        des_out: FileDescriptorIndex,
+        offset_out: Option<u64>,
+        len: u64,
+        through_pipe: bool,
+    ) -> Result<u64> {
+        self.open_descriptor(&des_in)?;
+        self.open_descriptor(&des_out)?;
+        let description_in = self.description(&des_in)?.clone();
+        let description_out = self.description(&des_out)?.clone();
+        if !description_in.readable() {
+            return Err(FsError::NotOpenForReading(des_in));
+        }
+        if !description_out.writable() {
+            return Err(FsError::NotOpenForWriting(des_out));
+        }
+        if description_out.append() {
+            return Err(FsError::AppendOnly(des_out));
+        }
+        let pos_in = offset_in.unwrap_or(description_in.offset);
+        let pos_out = offset_out.unwrap_or(description_out.offset);
+        let content = &self.file(&description_in.file).content;
+        let copied = len.min(content.size().saturating_sub(pos_in));
+        if description_in.file == description_out.file && copied > 0 {
+            // Linux forbids it for `copy_file_range`, with pipe the result depends on its capacity.
+            let overlap = pos_in < pos_out + copied && pos_out < pos_in + copied;
+            // Unlike `copy_file_range`, data is moved through pipe in chunks
+            // and reading past the old end of file gets the data just written.
+            let chase = through_pipe && pos_out >= pos_in && copied < len;
+            if overlap || chase {
+                return Err(FsError::OverlappingCopy(des_in, des_out));
+            }
+        }
+        if copied > 0 {
+            self.copy_content(
+                description_in.file,
+                pos_in,
+                description_out.file,
+                pos_out,
+                copied,
+            )?;
+        }
+        if offset_in.is_none() {
+            self.description_mut(&des_in)?.offset = pos_in + copied;
+        }
+        if offset_out.is_none() {
+            self.description_mut(&des_out)?.offset = pos_out + copied;
+        }
+        Ok(copied)
+    }
+
+    /// Writing past the end of output file leaves a hole.
+    fn copy_content(
+        &mut self,
+        file_in: FileIndex,
+        pos_in: u64,
+        file_out: FileIndex,
+        pos_out: u64,
+        count: u64,
+    ) -> Result<()> {
+        let data = self.file(&file_in).content.read(pos_in, count)?;
+        let file = self.file_mut(&file_out);
+        if pos_out > file.content.size() {
+            file.content.truncate(pos_out);
+        }
+        file.content.write_content(pos_out, data)?;
+        self.update_mtime(&Node::File(file_out), self.now());
+        Ok(())
+    }
+
+    /// Shares data of whole input file, similar to `ioctl(FICLONE)`.
+    pub fn ficlone(
+        &mut self,
+        des_in: FileDescriptorIndex,
+        des_out: FileDescriptorIndex,
+    ) -> Result<()> {
+        self.clone_range(des_in, 0, 0, des_out, 0)?;
+        self.recording.push(Operation::FIClone { des_in, des_out });
+        Ok(())
+    }
+
+    /// Shares data of file range, similar to `ioctl(FICLONERANGE)`.
+    /// Zero length means the end of input file.
+    pub fn ficlonerange(
+        &mut self,
+        des_in: FileDescriptorIndex,
+        offset_in: u64,
+        len: u64,
+        des_out: FileDescriptorIndex,
+        offset_out: u64,
+    ) -> Result<()> {
+        self.clone_range(des_in, offset_in, len, des_out, offset_out)?;
+        self.recording.push(Operation::FICloneRange {
+            des_in,
+            offset_in,
+            len,
+            des_out,
+            offset_out,
+        });
+        Ok(())
+    }
+
+    /// Shares data of identical file ranges, similar to `ioctl(FIDEDUPERANGE)`.
+    /// Content is not changed, so only the ranges are checked.
+    pub fn fideduperange(
+        &mut self,
+        des_in: FileDescriptorIndex,
+        offset_in: u64,
+        len: u64,
+        des_out: FileDescriptorIndex,
+        offset_out: u64,
+    ) -> Result<()> {
+        self.remap_count(des_in, offset_in, len, des_out, offset_out, true)?;
+        self.recording.push(Operation::FIDedupeRange {
+            des_in,
+            offset_in,
+            len,
+            des_out,
+            offset_out,
+        });
+        Ok(())
+    }
+
+    fn clone_range(
+        &mut self,
+        des_in: FileDescriptorIndex,
+        offset_in: u64,
+        len: u64,
+        des_out: FileDescriptorIndex,
+        offset_out: u64,
+    ) -> Result<()> {
+        let count = self.remap_count(des_in, offset_in, len, des_out, offset_out, false)?;
+        if count > 0 {
+            let file_in = self.description(&des_in)?.file;
+            let file_out = self.description(&des_out)?.file;
+            self.copy_content(file_in, offset_in, file_out, offset_out, count)?;
+        }
+        Ok(())
+    }
+
+    /// Checks ranges of clone or dedupe and returns number of bytes to remap.
+    ///
+    /// Filesystems can have different block size, so ranges are rejected
+    /// if the result depends on it (e.g. length is rounded down).
+    pub fn remap_count(
+        &self,
+        des_in: FileDescriptorIndex,
+        offset_in: u64,
+        len: u64,
+        des_out: FileDescriptorIndex,
+        offset_out: u64,
+        dedupe: bool,
+    ) -> Result<u64> {
+        self.open_descriptor(&des_in)?;
+        self.open_descriptor(&des_out)?;
+        let description_in = self.description(&des_in)?;
+        let description_out = self.description(&des_out)?;
+        if !description_in.readable() {
+            return Err(FsError::NotOpenForReading(des_in));
+        }
+        if !description_out.writable() {
+            return Err(FsError::NotOpenForWriting(des_out));
+        }
+        if description_out.append() {
+            return Err(FsError::AppendOnly(des_out));
+        }
+        if !offset_in.is_multiple_of(BLOCK_SIZE) || !offset_out.is_multiple_of(BLOCK_SIZE) {
+            return Err(FsError::UnalignedRemap(des_in, des_out));
+        }
+        let size_in = self.file(&description_in.file).content.size();
+        let size_out = self.file(&description_out.file).content.size();
+        let len = if len == 0 {
+            // Zero length dedupe does nothing, clone goes to the end of input file.
+            if dedupe || offset_in == size_in {
+                return Ok(0);
+            }
+            size_in.saturating_sub(offset_in)
+        } else {
+            len
+        };
+        // Linux shortens neither clone nor dedupe to the end of file.
+        if offset_in + len > size_in || offset_in >= size_in {
+            return Err(FsError::RemapOutOfRange(des_in));
+        }
+        if dedupe && (offset_out + len > size_out || offset_out >= size_out) {
+            return Err(FsError::RemapOutOfRange(des_out));
+        }
+        // Only the last block of input file can be partial,
+        // but it can't be placed in the middle of output file.
+        let to_end = offset_in + len == size_in;
+        if !len.is_multiple_of(BLOCK_SIZE) && (!to_end || offset_out + len < size_out) {
+            return Err(FsError::UnalignedRemap(des_in, des_out));
+        }
+        let blocks_len = if to_end {
+            size_in.next_multiple_of(BLOCK_SIZE) - offset_in
+        } else {
+            len
+        };
+        if description_in.file == description_out.file
+            && offset_out + blocks_len > offset_in
+            && offset_out < offset_in + blocks_len
+        {
+            return Err(FsError::OverlappingCopy(des_in, des_out));
+        }
+        Ok(len)
+    }
+
+    /// No-op, sync file state with storage device, similar to `fsync`.
+    pub fn fsync(&mut self, des_idx: FileDescriptorIndex) -> Result<()> {
+        let des = self.description(&des_idx)?.clone();
+        let file = self.file_mut(&des.file);
+        if !file.descriptors.contains(&des_idx) {
+            return Err(FsError::DescriptorWasClosed(des_idx));
+        }
+        self.recording.push(Operation::FSync { des: des_idx });
+        Ok(())
+    }
+
+    /// No-op, sync file data with storage device, similar to `fdatasync`.
+    pub fn fdatasync(&mut self, des_idx: FileDescriptorIndex) -> Result<()> {
+        self.open_descriptor(&des_idx)?;
+        self.recording.push(Operation::FDataSync { des: des_idx });
+        Ok(())
+    }
+
+    /// No-op, sync all filesystems with storage devices, similar to `sync`.
+    pub fn sync(&mut self) {
+        self.recording.push(Operation::Sync);
+    }
+
+    /// No-op, sync filesystem of the descriptor with storage device, similar to `syncfs`.
+    pub fn syncfs(&mut self, des_idx: FileDescriptorIndex) -> Result<()> {
+        self.open_descriptor(&des_idx)?;
+        self.recording.push(Operation::SyncFs { des: des_idx });
+        Ok(())
+    }
+
+    /// No-op, sync file range with storage device, similar to `sync_file_range`.
+    /// Range is not checked against the size of file.
+    pub fn sync_file_range(
+        &mut self,
+        des_idx: FileDescriptorIndex,
+        offset: u64,
+        nbytes: u64,
+        flags: SyncFileRangeFlags,
+    ) -> Result<()> {
+        self.open_descriptor(&des_idx)?;
+        self.recording.push(Operation::SyncFileRange {
+            des: des_idx,
+            offset,
+            nbytes,
+            flags,
+        });
+        Ok(())
+    }
+
+    /// Applies or removes lock of the open file description, similar to `flock` with `LOCK_NB`.
+    /// Returns `false` if lock conflicts with lock of another open file description.
+    ///
+    /// Conversion of the held lock is not atomic: it is removed first and not restored on conflict.
+    pub fn flock(&mut self, des_idx: FileDescriptorIndex, op: FlockOperation) -> Result<bool> {
+        let conflict = self.flock_conflicts(&des_idx, op)?;
+        let description = self.descriptor(&des_idx)?.description;
+        let file = self.description(&des_idx)?.file;
+        let exclusive = op == FlockOperation::LOCK_EX;
+        let held = self
+            .file_locks
+            .iter()
+            .position(|lock| lock.description == description);
+        let unchanged = op != FlockOperation::LOCK_UN
+            && held.is_some_and(|i| self.file_locks[i].exclusive == exclusive);
+        if !unchanged {
+            if let Some(i) = held {
+                self.file_locks.remove(i);
+            }
+            if op != FlockOperation::LOCK_UN && !conflict {
+                self.file_locks.push(FileLock {
+                    file,
+                    description,
+                    exclusive,
+                });
+            }
+        }
+        self.recording.push(Operation::Flock { des: des_idx, op });
+        Ok(!conflict)
+    }
+
+    /// Checks if `flock` would conflict with lock of another open file description of the same file.
+    pub fn flock_conflicts(
+        &self,
+        des_idx: &FileDescriptorIndex,
+        op: FlockOperation,
+    ) -> Result<bool> {
+        let description = self.open_descriptor(des_idx)?.description;
+        let file = self.description(des_idx)?.file;
+        let exclusive = match op {
+            FlockOperation::LOCK_SH => false,
+            FlockOperation::LOCK_EX => true,
+            FlockOperation::LOCK_UN => return Ok(false),
+        };
+        Ok(self.file_locks.iter().any(|lock| {
+            lock.file == file && lock.description != description && (exclusive || lock.exclusive)
+        }))
+    }
+
+    /// Places or removes POSIX record lock, similar to `fcntl` with `F_SETLK`.
+    /// Returns `false` if lock conflicts with OFD lock (locks of the same process never conflict).
+    pub fn setlk(
+        &mut self,
+        des_idx: FileDescriptorIndex,
+        lock_type: LockType,
+        start: u64,
+        len: u64,
+    ) -> Result<bool> {
+        let acquired = self.set_record_lock(&des_idx, false, lock_type, start, len)?;
+        self.recording.push(Operation::SetLk {
+            des: des_idx,
+            lock_type,
+            start,
+            len,
+        });
+        Ok(acquired)
+    }
+
+    /// Places or removes open file description lock, similar to `fcntl` with `F_OFD_SETLK`.
+    /// Returns `false` if lock conflicts with POSIX lock or lock of another open file description.
+    pub fn ofd_setlk(
+        &mut self,
+        des_idx: FileDescriptorIndex,
+        lock_type: LockType,
+        start: u64,
+        len: u64,
+    ) -> Result<bool> {
+        let acquired = self.set_record_lock(&des_idx, true, lock_type, start, len)?;
+        self.recording.push(Operation::OfdSetLk {
+            des: des_idx,
+            lock_type,
+            start,
+            len,
+        });
+        Ok(acquired)
+    }
+
+    /// Checks if byte-range lock would conflict with lock of another owner.
+    pub fn record_lock_conflicts(
+        &self,
+        des_idx: &FileDescriptorIndex,
+        ofd: bool,
+        lock_type: LockType,
+        start: u64,
+        len: u64,
+    ) -> Result<bool> {
+        let (file, owner, end) = self.record_lock_target(des_idx, ofd, lock_type, start, len)?;
+        let exclusive = match lock_type {
+            LockType::F_RDLCK => false,
+            LockType::F_WRLCK => true,
+            LockType::F_UNLCK => return Ok(false),
+        };
+        Ok(self.record_locks.iter().any(|lock| {
+            lock.file == file
+                && lock.owner != owner
+                && lock.overlaps(start, end)
+                && (exclusive || lock.exclusive)
+        }))
+    }
+
+    /// Replaces locks of the owner in the range, parts of old locks outside of it are kept.
+    fn set_record_lock(
+        &mut self,
+        des_idx: &FileDescriptorIndex,
+        ofd: bool,
+        lock_type: LockType,
+        start: u64,
+        len: u64,
+    ) -> Result<bool> {
+        if self.record_lock_conflicts(des_idx, ofd, lock_type, start, len)? {
+            return Ok(false);
+        }
+        let (file, owner, end) = self.record_lock_target(des_idx, ofd, lock_type, start, len)?;
+        let mut locks = vec![];
+        for lock in std::mem::take(&mut self.record_locks) {
+            if lock.file != file || lock.owner != owner || !lock.overlaps(start, end) {
+                locks.push(lock);
+                continue;
+            }
+            if lock.start < start {
+                locks.push(RecordLock {
+                    end: Some(start),
+                    ..lock.clone()
+                });
+            }
+            if let Some(end) = end
+                && lock.end.is_none_or(|e| e > end)
+            {
+                locks.push(RecordLock { start: end, ..lock });
+            }
+        }
+        if lock_type != LockType::F_UNLCK {
+            locks.push(RecordLock {
+                file,
+                owner,
+                exclusive: lock_type == LockType::F_WRLCK,
+                start,
+                end,
+            });
+        }
+        self.record_locks = locks;
+        Ok(true)
+    }
+
+    /// Returns file, owner and end of the lock range.
+    /// Descriptor must be open for reading to place read lock and for writing to place write lock.
+    fn record_lock_target(
+        &self,
+        des_idx: &FileDescriptorIndex,
+        ofd: bool,
+        lock_type: LockType,
+        start: u64,
+        len: u64,
+    ) -> Result<(FileIndex, LockOwner, Option<u64>)> {
+        let description = self.open_descriptor(des_idx)?.description;
+        let des = self.description(des_idx)?;
+        // Last byte of the range must fit into `off_t`.
+        if start > i64::MAX as u64 || len > i64::MAX as u64 - start + 1 {
+            return Err(FsError::InvalidLockRange(start, len));
+        }
+        match lock_type {
+            LockType::F_RDLCK if !des.readable() => {
+                return Err(FsError::NotOpenForReading(*des_idx));
+            }
+            LockType::F_WRLCK if !des.writable() => {
+                return Err(FsError::NotOpenForWriting(*des_idx));
+            }
+            _ => {}
+        }
+        let owner = if ofd {
+            LockOwner::Description(description)
+        } else {
+            LockOwner::Process
+        };
+        let end = if len == 0 { None } else { Some(start + len) };
+        Ok((des.file, owner, end))
+    }
+
+    /// Releases locks after the descriptor of the open file description is closed.
+    /// POSIX record locks of the file are released when any descriptor of it is closed.
+    fn release_locks(&mut self, file: FileIndex, description: FileDescriptionIndex) {
+        self.record_locks
+            .retain(|lock| lock.file != file || lock.owner != LockOwner::Process);
+        self.release_description_locks(description);
+    }
+
+    /// Releases locks of the open file description if it is no longer referenced
+    /// by descriptors or mappings.
+    fn release_description_locks(&mut self, description: FileDescriptionIndex) {
+        let file = self.descriptions[description.0].file;
+        let referenced = self
+            .file(&file)
+            .descriptors
+            .iter()
+            .any(|des| self.descriptors[des.0].description == description)
+            || self
+                .mappings
+                .iter()
+                .any(|map| !map.unmapped && map.description == description);
+        if referenced {
+            return;
+        }
+        self.file_locks
+            .retain(|lock| lock.description != description);
+        self.record_locks
+            .retain(|lock| lock.owner != LockOwner::Description(description));
+    }
+
+    /// Maps file region into memory for reading and writing, similar to `mmap` with `MAP_SHARED`.
+    /// Region can extend beyond the end of file.
+    pub fn mmap(
+        &mut self,
+        des_idx: FileDescriptorIndex,
+        offset: u64,
+        len: u64,
+    ) -> Result<MappingIndex> {
+        let des = self.description(&des_idx)?.clone();
+        let file = self.file(&des.file);
+        if !file.descriptors.contains(&des_idx) {
+            return Err(FsError::DescriptorWasClosed(des_idx));
+        }
+        // Shared writable mapping requires `O_RDWR` and is not permitted with `O_APPEND`.
+        if !des.readable() || !des.writable() || des.append() {
+            return Err(FsError::NotMappable(des_idx));
+        }
+        if len == 0 || !offset.is_multiple_of(PAGE_SIZE) {
+            return Err(FsError::InvalidMappingRange(offset, len));
+        }
+        let map_idx = MappingIndex(self.mappings.len());
+        self.mappings.push(Mapping {
+            file: des.file,
+            description: self.descriptor(&des_idx)?.description,
+            offset,
+            len,
+            unmapped: false,
+        });
+        self.recording.push(Operation::MMap {
+            des: des_idx,
+            offset,
+            len,
+            map: map_idx,
+        });
+        Ok(map_idx)
+    }
+
+    /// Copies slice of "source" data into the mapping at the specified offset.
+    /// Returns `false` if access is beyond the end of file (process receives `SIGBUS`),
+    /// access to the last partial page of file is undefined.
+    pub fn mwrite(
+        &mut self,
+        map_idx: MappingIndex,
+        src_offset: u64,
+        size: u64,
+        offset: u64,
+    ) -> Result<bool> {
+        let map = self.mapping(&map_idx)?.clone();
+        if offset + size > map.len {
+            return Err(FsError::OutOfMapping(map_idx, offset, size));
+        }
+        let file_offset = map.offset + offset;
+        let file_size = self.file(&map.file).content.size();
+        let mapped = if size == 0 {
+            true
+        } else if file_offset + size <= file_size {
+            self.file_mut(&map.file)
+                .content
+                .write(src_offset, file_offset, size)?;
+            // Timestamps are updated on page fault, so repeated writes to the same page may not update them.
+            self.update_mtime(&Node::File(map.file), self.unspecified());
+            true
+        } else if file_offset >= file_size.div_ceil(PAGE_SIZE) * PAGE_SIZE {
+            false
+        } else {
+            return Err(FsError::MappingCrossesEof(map_idx, offset, size));
+        };
+        self.recording.push(Operation::MWrite {
+            map: map_idx,
+            src_offset,
+            size,
+            offset,
+        });
+        Ok(mapped)
+    }
+
+    /// No-op, sync the whole mapping with storage device, similar to `msync`.
+    pub fn msync(&mut self, map_idx: MappingIndex, flags: MsyncFlags) -> Result<()> {
+        self.mapping(&map_idx)?;
+        if flags.contains(&MsyncFlag::MS_ASYNC) == flags.contains(&MsyncFlag::MS_SYNC) {
+            return Err(FsError::InvalidMsyncFlags(flags));
+        }
+        self.recording.push(Operation::MSync {
+            map: map_idx,
+            flags,
+        });
+        Ok(())
+    }
+
+    /// Removes the whole mapping, similar to `munmap`.
+    pub fn munmap(&mut self, map_idx: MappingIndex) -> Result<()> {
+        let description = self.mapping(&map_idx)?.description;
+        self.mappings[map_idx.0].unmapped = true;
+        self.release_description_locks(description);
+        self.recording.push(Operation::MUnmap { map: map_idx });
+        Ok(())
+    }
+
+    /// Sets the value of extended attribute, similar to `setxattr`.
+    /// Value is a slice of "source" data.
+    pub fn setxattr(
+        &mut self,
+        path: PathName,
+        name: String,
+        src_offset: u64,
+        size: u64,
+        flags: XattrFlags,
+    ) -> Result<()> {
+        if !XATTR_NAMESPACES
+            .iter()
+            .any(|ns| name.len() > ns.len() && name.starts_with(ns))
+        {
+            return Err(FsError::InvalidXattrName(name));
+        }
+        if size > XATTR_SIZE_MAX {
+            return Err(FsError::XattrValueTooBig(size));
+        }
+        self.check_xattr_access(&path, &name, MAY_WRITE)?;
+        let mut value = Content::new();
+        value.write_back(src_offset, size);
+        let xattrs = self.resolve_xattrs_mut(path.clone())?;
+        let exists = xattrs.contains_key(&name);
+        if flags.contains(&XattrFlag::XATTR_CREATE) && exists {
+            return Err(FsError::XattrAlreadyExists(path, name));
+        }
+        if flags.contains(&XattrFlag::XATTR_REPLACE) && !exists {
+            return Err(FsError::XattrNotFound(path, name));
+        }
+        xattrs.insert(name.clone(), value);
+        let (_, node) = self.resolve_node(path.clone(), true)?;
+        self.update_ctime(&node, self.now());
+        self.recording.push(Operation::SetXattr {
+            path,
+            name,
+            src_offset,
+            size,
+            flags,
+        });
+        Ok(())
+    }
+
+    /// Returns the value of extended attribute, similar to `getxattr`.
+    /// Zero size can be used to get the size of value.
+    pub fn getxattr(&mut self, path: PathName, name: String, size: u64) -> Result<Content> {
+        self.check_xattr_access(&path, &name, MAY_READ)?;
+        let xattrs = self.resolve_xattrs_mut(path.clone())?;
+        let value = match xattrs.get(&name) {
+            Some(value) => value.clone(),
+            None => return Err(FsError::XattrNotFound(path, name)),
+        };
+        if size != 0 && size < value.size() {
+            return Err(FsError::XattrBufferTooSmall(path, size));
+        }
+        self.recording
+            .push(Operation::GetXattr { path, name, size });
+        Ok(value)
+    }
+
+    /// Returns names of extended attributes, similar to `listxattr`.
+    /// Zero size can be used to get the size of list.
+    pub fn listxattr(&mut self, path: PathName, size: u64) -> Result<Vec<String>> {
+        self.check_search(&path, true)?;
+        let xattrs = self.resolve_xattrs_mut(path.clone())?;
+        let names: Vec<String> = xattrs.keys().cloned().collect();
+        // Names are null-terminated in the list.
+        let list_size: u64 = names.iter().map(|name| name.len() as u64 + 1).sum();
+        if size != 0 && size < list_size {
+            return Err(FsError::XattrBufferTooSmall(path, size));
+        }
+        self.recording.push(Operation::ListXattr { path, size });
+        Ok(names)
+    }
+
+    /// Removes extended attribute, similar to `removexattr`.
+    pub fn removexattr(&mut self, path: PathName, name: String) -> Result<()> {
+        self.check_xattr_access(&path, &name, MAY_WRITE)?;
+        let xattrs = self.resolve_xattrs_mut(path.clone())?;
+        if xattrs.remove(&name).is_none() {
+            return Err(FsError::XattrNotFound(path, name));
+        }
+        let (_, node) = self.resolve_node(path.clone(), true)?;
+        self.update_ctime(&node, self.now());
+        self.recording.push(Operation::RemoveXattr { path, name });
+        Ok(())
+    }
+
+    /// Changes permission bits of file or directory, similar to `chmod`.
+    /// Symbolic links are followed.
+    pub fn chmod(&mut self, path: PathName, mode: Mode) -> Result<()> {
+        self.check_search(&path, true)?;
+        let (_, node) = self.resolve_node(path.clone(), true)?;
+        let bits = self
+            .chmod_bits(&node, mode_bits(&mode) & 0o7777)
+            .ok_or_else(|| FsError::NotPermitted(path.clone()))?;
+        let meta = self.resolve_metadata_mut(path.clone())?;
+        meta.mode = bits;
+        self.update_ctime(&node, self.now());
+        self.recording.push(Operation::Chmod { path, mode });
+        Ok(())
+    }
+
+    /// Changes permission bits of file using the file descriptor, similar to `fchmod`.
+    pub fn fchmod(&mut self, des_idx: FileDescriptorIndex, mode: Mode) -> Result<()> {
+        self.open_descriptor(&des_idx)?;
+        let file_idx = self.description(&des_idx)?.file;
+        let bits = self
+            .chmod_bits(&Node::File(file_idx), mode_bits(&mode) & 0o7777)
+            .ok_or(FsError::DescriptorNotPermitted(des_idx))?;
+        self.file_mut(&file_idx).meta.mode = bits;
+        self.update_ctime(&Node::File(file_idx), self.now());
+        self.recording
+            .push(Operation::FChmod { des: des_idx, mode });
+        Ok(())
+    }
+
+    /// Changes owner and group of file or directory, similar to `chown`.
+    /// `None` leaves the id unchanged (`-1` is passed). Symbolic links are followed.
+    pub fn chown(&mut self, path: PathName, uid: Option<u32>, gid: Option<u32>) -> Result<()> {
+        self.check_search(&path, true)?;
+        let (_, node) = self.resolve_node(path.clone(), true)?;
+        if !self.chown_permitted(&node, uid, gid) {
+            return Err(FsError::NotPermitted(path));
+        }
+        let is_dir = matches!(node, Node::Dir(_));
+        let meta = self.resolve_metadata_mut(path.clone())?;
+        AbstractFS::change_owner(meta, uid, gid, is_dir);
+        self.update_ctime(&node, self.now());
+        self.recording.push(Operation::Chown { path, uid, gid });
+        Ok(())
+    }
+
+    /// Changes owner and group of file using the file descriptor, similar to `fchown`.
+    pub fn fchown(
+        &mut self,
+        des_idx: FileDescriptorIndex,
+        uid: Option<u32>,
+        gid: Option<u32>,
+    ) -> Result<()> {
+        self.open_descriptor(&des_idx)?;
+        let file_idx = self.description(&des_idx)?.file;
+        if !self.chown_permitted(&Node::File(file_idx), uid, gid) {
+            return Err(FsError::DescriptorNotPermitted(des_idx));
+        }
+        AbstractFS::change_owner(&mut self.file_mut(&file_idx).meta, uid, gid, false);
+        self.update_ctime(&Node::File(file_idx), self.now());
+        self.recording.push(Operation::FChown {
+            des: des_idx,
+            uid,
+            gid,
+        });
+        Ok(())
+    }
+
+    /// Changes access and modification timestamps of file or directory, similar to `utimensat`.
+    /// Symbolic links are followed.
+    pub fn utimensat(&mut self, path: PathName, atime: TimeSpec, mtime: TimeSpec) -> Result<()> {
+        // Linux does not even resolve the path if both timestamps are omitted.
+        if atime != TimeSpec::Omit || mtime != TimeSpec::Omit {
+            let (_, node) = self.resolve_node(path.clone(), true)?;
+            self.check_search(&path, true)?;
+            self.check_times(&node, atime, mtime, &path)?;
+            self.set_times(&node, atime, mtime);
+        }
+        self.recording
+            .push(Operation::UTimeNsAt { path, atime, mtime });
+        Ok(())
+    }
+
+    /// Changes access and modification timestamps of file using the file descriptor, similar to `futimens`.
+    pub fn futimens(
+        &mut self,
+        des_idx: FileDescriptorIndex,
+        atime: TimeSpec,
+        mtime: TimeSpec,
+    ) -> Result<()> {
+        self.open_descriptor(&des_idx)?;
+        let file_idx = self.description(&des_idx)?.file;
+        self.check_times(&Node::File(file_idx), atime, mtime, &PathName::from(""))
+            .map_err(|err| match err {
+                FsError::AccessDenied(_) => FsError::DescriptorAccessDenied(des_idx),
+                _ => FsError::DescriptorNotPermitted(des_idx),
+            })?;
+        self.set_times(&Node::File(file_idx), atime, mtime);
+        self.recording.push(Operation::FUTimeNs {
+            des: des_idx,
+            atime,
+            mtime,
+        });
+        Ok(())
+    }
+
+    /// Sets file mode creation mask, similar to `umask`.
+    pub fn umask(&mut self, mask: Mode) {
+        self.umask = mode_bits(&mask) & 0o777;
+        self.recording.push(Operation::Umask { mask });
+    }
+
+    /// Sets casefold attribute of the directory, similar to `FS_IOC_SETFLAGS` with `FS_CASEFOLD_FL`.
+    /// Attribute of non-empty directory can't be changed, workspace is never empty
+    /// (e.g. `lost+found` of ext4), so it is rejected.
+    pub fn set_casefold(&mut self, path: PathName) -> Result<()> {
+        self.check_search(&path, true)?;
+        let idx = match self.resolve_node(path.clone(), true)? {
+            (_, Node::Dir(idx)) if idx == AbstractFS::root_index() => {
+                return Err(FsError::InvalidPath(path));
+            }
+            (_, Node::Dir(idx)) => idx,
+            _ => return Err(FsError::NotADir(path)),
+        };
+        // Executor opens the directory for reading.
+        self.check_permission(&Node::Dir(idx), MAY_READ, &path)?;
+        if !self.is_owner(&Node::Dir(idx)) {
+            return Err(FsError::NotPermitted(path));
+        }
+        let dir = self.dir_mut(&idx);
+        if !dir.casefold && !dir.children.is_empty() {
+            return Err(FsError::DirNotEmpty(path));
+        }
+        dir.casefold = true;
+        self.update_ctime(&Node::Dir(idx), self.now());
+        self.recording.push(Operation::SetCasefold { path });
+        Ok(())
+    }
+
+    /// Directory at the path is casefolded, names in it are case-insensitive.
+    pub fn casefolded(&self, path: &PathName) -> bool {
+        self.resolve_dir(path.clone())
+            .is_ok_and(|(_, idx)| self.dir(&idx).casefold)
+    }
+
+    /// Link count of removed file is changed, directories are removed recursively.
+    fn update_removed_ctime(&mut self, node: &Node) {
+        if let Node::Dir(idx) = node {
+            let children: Vec<Node> = self.dir(idx).children.values().cloned().collect();
+            for child in children.iter() {
+                self.update_removed_ctime(child);
+            }
+        }
+        self.update_ctime(node, self.now());
+    }
+
+    /// Status change timestamp is also updated, unless both timestamps are omitted.
+    fn set_times(&mut self, node: &Node, atime: TimeSpec, mtime: TimeSpec) {
+        if atime == TimeSpec::Omit && mtime == TimeSpec::Omit {
+            return;
+        }
+        let now = self.now();
+        let Some(meta) = self.metadata_mut(node) else {
+            return;
+        };
+        let set = |timestamp: Timestamp, spec: TimeSpec| match spec {
+            TimeSpec::Now => now,
+            TimeSpec::Omit => timestamp,
+            TimeSpec::Seconds(seconds) => Timestamp::Seconds(seconds),
+        };
+        meta.atime = set(meta.atime, atime);
+        meta.mtime = set(meta.mtime, mtime);
+        meta.ctime = now;
+    }
+
+    /// Logical time of the current operation, zero is the time before the first operation.
+    pub fn tick(&self) -> usize {
+        self.recording.ops.len() + 1
+    }
+
+    fn now(&self) -> Timestamp {
+        Timestamp::Now(self.tick())
+    }
+
+    /// Operation may or may not update timestamp.
+    fn unspecified(&self) -> Timestamp {
+        Timestamp::Unspecified(self.tick())
+    }
+
+    /// Marks data modification (also status change), similar to `file_update_time` in Linux.
+    fn update_mtime(&mut self, node: &Node, time: Timestamp) {
+        if let Some(meta) = self.metadata_mut(node) {
+            meta.mtime = time;
+            meta.ctime = time;
+        }
+    }
+
+    /// Marks status change (e.g. mode, owner or link count).
+    fn update_ctime(&mut self, node: &Node, time: Timestamp) {
+        if let Some(meta) = self.metadata_mut(node) {
+            meta.ctime = time;
+        }
+    }
+
+    /// Metadata of symbolic links is not modeled.
+    fn metadata(&self, node: &Node) -> Option<&Metadata> {
+        match node {
+            Node::File(idx) => Some(&self.file(idx).meta),
+            Node::Dir(idx) => Some(&self.dir(idx).meta),
+            Node::Symlink(_) => None,
+            Node::Special(idx) => Some(&self.special(idx).meta),
+        }
+    }
+
+    fn metadata_mut(&mut self, node: &Node) -> Option<&mut Metadata> {
+        match node {
+            Node::File(idx) => Some(&mut self.file_mut(idx).meta),
+            Node::Dir(idx) => Some(&mut self.dir_mut(idx).meta),
+            Node::Symlink(_) => None,
+            Node::Special(idx) => Some(&mut self.special_mut(idx).meta),
+        }
+    }
+
+    /// Linux clears `S_ISUID` and group-executable `S_ISGID` of non-directories on ownership change,
+    /// even if the ids are left unchanged.
+    fn change_owner(meta: &mut Metadata, uid: Option<u32>, gid: Option<u32>, is_dir: bool) {
+        if let Some(uid) = uid {
+            meta.uid = uid;
+        }
+        if let Some(gid) = gid {
+            meta.gid = gid;
+        }
+        if !is_dir {
+            meta.mode &= !(ModeFlag::S_ISUID as u32);
+            let sgid = ModeFlag::S_ISGID as u32 | ModeFlag::S_IXGRP as u32;
+            if meta.mode & sgid == sgid {
+                meta.mode &= !(ModeFlag::S_ISGID as u32);
+            }
+        }
+    }
+
+    /// Metadata of a new node in the directory, similar to `inode_init_owner` in Linux.
+    /// Group is inherited from directory with `S_ISGID`, new directories also inherit the bit.
+    fn new_metadata(&self, parent: &DirIndex, mode: u32, is_dir: bool) -> Metadata {
+        let parent = self.dir(parent).meta;
+        let now = self.now();
+        let mut meta = Metadata {
+            mode: mode & !self.umask,
+            uid: self.credentials.uid,
+            gid: self.credentials.gid,
+            atime: now,
+            mtime: now,
+            ctime: now,
+        };
+        if parent.mode & ModeFlag::S_ISGID as u32 != 0 {
+            meta.gid = parent.gid;
+            if is_dir {
+                meta.mode |= ModeFlag::S_ISGID as u32;
+            }
+        }
+        meta
+    }
+
+    /// Checks if the user has permission to access the node, similar to `generic_permission` in Linux.
+    /// Only permission bits of the class the user belongs to (owner, group or others) are checked.
+    fn permitted(&self, node: &Node, mask: u32) -> bool {
+        if self.credentials.is_root() {
+            return true;
+        }
+        let Some(meta) = self.metadata(node) else {
+            return true;
+        };
+        let bits = if meta.uid == self.credentials.uid {
+            meta.mode >> 6
+        } else if meta.gid == self.credentials.gid {
+            meta.mode >> 3
+        } else {
+            meta.mode
+        };
+        bits & mask == mask
+    }
+
+    fn check_permission(&self, node: &Node, mask: u32, path: &PathName) -> Result<()> {
+        if self.permitted(node, mask) {
+            Ok(())
+        } else {
+            Err(FsError::AccessDenied(path.clone()))
+        }
+    }
+
+    /// User owns the node or is root, symbolic links are always created by the user.
+    fn is_owner(&self, node: &Node) -> bool {
+        self.credentials.is_root()
+            || self
+                .metadata(node)
+                .is_none_or(|meta| meta.uid == self.credentials.uid)
+    }
+
+    /// Path must not be too long and every directory that is looked up in
+    /// while resolving the path must be searchable.
+    fn check_search(&self, path: &PathName, follow_symlinks: bool) -> Result<()> {
+        AbstractFS::check_length(path)?;
+        if self.credentials.is_root() {
+            return Ok(());
+        }
+        let mut searched = vec![];
+        // Errors of resolution are reported by operation, directories before the failed lookup
+        // are still searched.
+        let _ = self.resolve_node_rec(
+            path.clone(),
+            follow_symlinks,
+            vec![],
+            AbstractFS::root_index(),
+            vec![],
+            &mut searched,
+        );
+        if searched
+            .iter()
+            .all(|idx| self.permitted(&Node::Dir(*idx), MAY_EXEC))
+        {
+            Ok(())
+        } else {
+            Err(FsError::AccessDenied(path.clone()))
+        }
+    }
+
+    /// Paths are limited by `PATH_MAX` (absolute paths are prefixed with workspace),
+    /// names by `NAME_MAX`, both in bytes.
+    fn check_length(path: &PathName) -> Result<()> {
+        let prefix = if path.is_absolute() { WORKSPACE_MAX } else { 0 };
+        if prefix + path.len() >= PATH_MAX
+            || path
+                .segments()
+                .iter()
+                .any(|segment| segment.len() > NAME_MAX)
+        {
+            return Err(FsError::NameTooLong(path.clone()));
+        }
+        Ok(())
+    }
+
+    /// Entries are added to or removed from the directory with write and search permission.
+    fn check_dir_write(&self, dir: &DirIndex, path: &PathName) -> Result<()> {
+        self.check_permission(&Node::Dir(*dir), MAY_WRITE | MAY_EXEC, path)
+    }
+
+    /// Checks if the node can be removed from (or replaced in) the directory, similar to `may_delete` in Linux.
+    /// Entries of directory with `S_ISVTX` can only be removed by the owner of entry or directory.
+    fn check_delete(&self, dir: &DirIndex, node: &Node, path: &PathName) -> Result<()> {
+        self.check_dir_write(dir, path)?;
+        let sticky = self.dir(dir).meta.mode & ModeFlag::S_ISVTX as u32 != 0;
+        if sticky && !self.is_owner(node) && !self.is_owner(&Node::Dir(*dir)) {
+            return Err(FsError::NotPermitted(path.clone()));
+        }
+        Ok(())
+    }
+
+    /// Executor removes directory recursively, reading every subdirectory and removing its entries.
+    fn check_remove_tree(&self, dir: &DirIndex, path: &PathName) -> Result<()> {
+        self.check_permission(&Node::Dir(*dir), MAY_READ | MAY_WRITE | MAY_EXEC, path)?;
+        for (name, child) in self.dir(dir).children.iter() {
+            let child_path = path.join(name.clone());
+            self.check_delete(dir, child, &child_path)?;
+            if let Node::Dir(idx) = child {
+                self.check_remove_tree(idx, &child_path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks access mode of existing file that is opened, similar to `may_open` in Linux.
+    /// Files of others in directory with `S_ISVTX` can't be opened with `O_CREAT`,
+    /// which is stricter than `protected_regular` sysctl.
+    fn check_open(
+        &self,
+        file_idx: FileIndex,
+        parent: &DirIndex,
+        flags: &OpenFlags,
+        path: &PathName,
+    ) -> Result<()> {
+        let node = Node::File(file_idx);
+        let mut mask = 0;
+        if !flags.contains(&OpenFlag::O_WRONLY) {
+            mask |= MAY_READ;
+        }
+        if flags.contains(&OpenFlag::O_WRONLY)
+            || flags.contains(&OpenFlag::O_RDWR)
+            || flags.contains(&OpenFlag::O_TRUNC)
+        {
+            mask |= MAY_WRITE;
+        }
+        self.check_permission(&node, mask, path)?;
+        let sticky = self.dir(parent).meta.mode & ModeFlag::S_ISVTX as u32 != 0;
+        if flags.contains(&OpenFlag::O_CREAT)
+            && sticky
+            && !self.is_owner(&node)
+            && !self.is_owner(&Node::Dir(*parent))
+        {
+            return Err(FsError::AccessDenied(path.clone()));
+        }
+        Ok(())
+    }
+
+    /// Checks access to extended attribute, similar to `xattr_permission` in Linux.
+    /// Trusted attributes can only be changed by root (`CAP_SYS_ADMIN`),
+    /// user attributes of directory with `S_ISVTX` can only be changed by the owner.
+    fn check_xattr_access(&self, path: &PathName, name: &str, mask: u32) -> Result<()> {
+        self.check_search(path, true)?;
+        if self.credentials.is_root() {
+            return Ok(());
+        }
+        let (_, node) = self.resolve_node(path.clone(), true)?;
+        if mask & MAY_WRITE != 0 {
+            if name.starts_with("trusted.") {
+                return Err(FsError::NotPermitted(path.clone()));
+            }
+            if let Node::Dir(idx) = node
+                && self.dir(&idx).meta.mode & ModeFlag::S_ISVTX as u32 != 0
+                && !self.is_owner(&node)
+            {
+                return Err(FsError::NotPermitted(path.clone()));
+            }
+        }
+        self.check_permission(&node, mask, path)
+    }
+
+    /// Only owner can change permission bits, `S_ISGID` is cleared if the user is not
+    /// a member of the group, similar to `setattr_prepare` in Linux. `None` if not permitted.
+    fn chmod_bits(&self, node: &Node, mode: u32) -> Option<u32> {
+        if self.credentials.is_root() {
+            return Some(mode);
+        }
+        let meta = self.metadata(node)?;
+        if meta.uid != self.credentials.uid {
+            None
+        } else if meta.gid != self.credentials.gid {
+            Some(mode & !(ModeFlag::S_ISGID as u32))
+        } else {
+            Some(mode)
+        }
+    }
+
+    /// Without `CAP_CHOWN` only owner can change the group and only to its own group,
+    /// owner itself can't be changed, similar to `chown_ok` and `chgrp_ok` in Linux.
+    /// Clearing of set-user-ID and set-group-ID bits is also restricted to the owner.
+    fn chown_permitted(&self, node: &Node, uid: Option<u32>, gid: Option<u32>) -> bool {
+        if self.credentials.is_root() {
+            return true;
+        }
+        let Some(meta) = self.metadata(node) else {
+            return false;
+        };
+        let owner = meta.uid == self.credentials.uid;
+        let sgid = ModeFlag::S_ISGID as u32 | ModeFlag::S_IXGRP as u32;
+        let clears_mode = !matches!(node, Node::Dir(_))
+            && (meta.mode & ModeFlag::S_ISUID as u32 != 0 || meta.mode & sgid == sgid);
+        uid.is_none_or(|uid| owner && uid == meta.uid)
+            && gid.is_none_or(|gid| owner && (gid == meta.gid || gid == self.credentials.gid))
+            && (owner || !clears_mode)
+    }
+
+    /// Setting both timestamps to the current time requires write permission, unless the user
+    /// is the owner. Other changes are restricted to the owner, similar to `vfs_utimes` in Linux.
+    fn check_times(
+        &self,
+        node: &Node,
+        atime: TimeSpec,
+        mtime: TimeSpec,
+        path: &PathName,
+    ) -> Result<()> {
+        if self.is_owner(node) || (atime == TimeSpec::Omit && mtime == TimeSpec::Omit) {
+            return Ok(());
+        }
+        if atime == TimeSpec::Now && mtime == TimeSpec::Now {
+            self.check_permission(node, MAY_WRITE, path)
+        } else {
+            Err(FsError::NotPermitted(path.clone()))
+        }
+    }
+
+    /// Replay operations from workload. Does not reset the state.
+    /// Expected failures of `open` (see `try_open`) are replayed too.
+    pub fn replay(&mut self, workload: &Workload) -> Result<()> {
+        for op in &workload.ops {
+            self.replay_op(op)?;
+        }
+        Ok(())
+    }
+
+    /// Replay single operation, returns the expected failure of `open` (see `try_open`).
+    pub fn replay_op(&mut self, op: &Operation) -> Result<Option<FsError>> {
+        match op {
+            Operation::MkDir { path, mode } => {
+                self.mkdir(path.clone(), mode.clone())?;
+            }
+            Operation::Create { path, mode } => {
+                self.create(path.clone(), mode.clone())?;
+            }
+            Operation::Remove { path } => self.remove(path.clone())?,
+            Operation::Hardlink { old_path, new_path } => {
+                self.hardlink(old_path.clone(), new_path.clone())?;
+            }
+            Operation::Rename { old_path, new_path } => {
+                self.rename(old_path.clone(), new_path.clone())?;
+            }
+            Operation::Open {
+                path,
+                flags,
+                mode,
+                des: _,
+            } => {
+                return Ok(self
+                    .try_open(path.clone(), flags.clone(), mode.clone())?
+                    .err());
+            }
+            Operation::Close { des } => {
+                self.close(*des)?;
+            }
+            Operation::Dup {
+                old_des,
+                new_des: _,
+            } => {
+                self.dup(*old_des)?;
+            }
+            Operation::Dup2 { old_des, new_des } => {
+                self.dup2(*old_des, *new_des)?;
+            }
+            Operation::Dup3 {
+                old_des,
+                new_des,
+                flags,
+            } => {
+                self.dup3(*old_des, *new_des, flags.clone())?;
+            }
+            Operation::SetXattr {
+                path,
+                name,
+                src_offset,
+                size,
+                flags,
+            } => {
+                self.setxattr(
+                    path.clone(),
+                    name.clone(),
+                    *src_offset,
+                    *size,
+                    flags.clone(),
+                )?;
+            }
+            Operation::GetXattr { path, name, size } => {
+                self.getxattr(path.clone(), name.clone(), *size)?;
+            }
+            Operation::ListXattr { path, size } => {
+                self.listxattr(path.clone(), *size)?;
+            }
+            Operation::RemoveXattr { path, name } => {
+                self.removexattr(path.clone(), name.clone())?;
+            }
+            Operation::Chmod { path, mode } => {
+                self.chmod(path.clone(), mode.clone())?;
+            }
+            Operation::FChmod { des, mode } => {
+                self.fchmod(*des, mode.clone())?;
+            }
+            Operation::Chown { path, uid, gid } => {
+                self.chown(path.clone(), *uid, *gid)?;
+            }
+            Operation::FChown { des, uid, gid } => {
+                self.fchown(*des, *uid, *gid)?;
+            }
+            Operation::UTimeNsAt { path, atime, mtime } => {
+                self.utimensat(path.clone(), *atime, *mtime)?;
+            }
+            Operation::FUTimeNs { des, atime, mtime } => {
+                self.futimens(*des, *atime, *mtime)?;
+            }
+            Operation::FAllocate {
+                des,
+                mode,
+                offset,
+                len,
+            } => {
+                self.fallocate(*des, mode.clone(), *offset, *len)?;
+            }
+            Operation::MMap {
+                des,
+                offset,
+                len,
+                map: _,
+            } => {
+                self.mmap(*des, *offset, *len)?;
+            }
+            Operation::MWrite {
+                map,
+                src_offset,
+                size,
+                offset,
+            } => {
+                self.mwrite(*map, *src_offset, *size, *offset)?;
+            }
+            Operation::MSync { map, flags } => {
+                self.msync(*map, flags.clone())?;
+            }
+            Operation::MUnmap { map } => {
+                self.munmap(*map)?;
+            }
+            Operation::RenameAt2 {
+                old_path,
+                new_path,
+                flags,
+            } => {
+                self.renameat2(old_path.clone(), new_path.clone(), flags.clone())?;
+            }
+            Operation::FLink { des, path } => {
+                self.flink(*des, path.clone())?;
+            }
+            Operation::MkNod {
+                path,
+                mode,
+                file_type,
+            } => {
+                self.mknod(path.clone(), mode.clone(), *file_type)?;
+            }
+            Operation::ReadDir { path } => {
+                self.readdir(path.clone())?;
+            }
+            Operation::Stat { path } => {
+                self.stat(path.clone())?;
+            }
+            Operation::LStat { path } => {
+                self.lstat(path.clone())?;
+            }
+            Operation::FStat { des } => {
+                self.fstat(*des)?;
+            }
+            Operation::ReadLink { path } => {
+                self.readlink(path.clone())?;
+            }
+            Operation::CopyFileRange {
+                des_in,
+                offset_in,
+                des_out,
+                offset_out,
+                len,
+            } => {
+                self.copy_file_range(*des_in, *offset_in, *des_out, *offset_out, *len)?;
+            }
+            Operation::SendFile {
+                des_out,
+                des_in,
+                offset,
+                count,
+            } => {
+                self.sendfile(*des_out, *des_in, *offset, *count)?;
+            }
+            Operation::Splice {
+                des_in,
+                offset_in,
+                des_out,
+                offset_out,
+                len,
+            } => {
+                self.splice(*des_in, *offset_in, *des_out, *offset_out, *len)?;
+            }
+            Operation::FIClone { des_in, des_out } => {
+                self.ficlone(*des_in, *des_out)?;
+            }
+            Operation::FICloneRange {
+                des_in,
+                offset_in,
+                len,
+                des_out,
+                offset_out,
+            } => {
+                self.ficlonerange(*des_in, *offset_in, *len, *des_out, *offset_out)?;
+            }
+            Operation::FIDedupeRange {
+                des_in,
+                offset_in,
+                len,
+                des_out,
+                offset_out,
+            } => {
+                self.fideduperange(*des_in, *offset_in, *len, *des_out, *offset_out)?;
+            }
+            Operation::Flock { des, op } => {
+                self.flock(*des, *op)?;
+            }
+            Operation::OpenDir {
+                dir,
+                path,
+                flags,
+                des: _,
+            } => {
+                self.open_dir(*dir, path.clone(), flags.clone())?;
+            }
+            Operation::CloseDir { des } => {
+                self.close_dir(*des)?;
+            }
+            Operation::ChDir { path } => {
+                self.chdir(path.clone())?;
+            }
+            Operation::FChDir { des } => {
+                self.fchdir(*des)?;
+            }
+            Operation::MkDirAt { dir, path, mode } => {
+                self.mkdirat(*dir, path.clone(), mode.clone())?;
+            }
+            Operation::OpenAt {
+                dir,
+                path,
+                flags,
+                mode,
+                des: _,
+            } => {
+                return Ok(self
+                    .try_openat(*dir, path.clone(), flags.clone(), mode.clone())?
+                    .err());
+            }
+            Operation::UnlinkAt { dir, path, flags } => {
+                self.unlinkat(*dir, path.clone(), flags.clone())?;
+            }
+            Operation::RenameAt {
+                old_dir,
+                old_path,
+                new_dir,
+                new_path,
+            } => {
+                self.renameat(*old_dir, old_path.clone(), *new_dir, new_path.clone())?;
+            }
+            Operation::FStatAt { dir, path, flags } => {
+                self.fstatat(*dir, path.clone(), flags.clone())?;
+            }
+            Operation::Umask { mask } => self.umask(mask.clone()),
+            Operation::SetCasefold { path } => {
+                self.set_casefold(path.clone())?;
+            }
+            Operation::SetLk {
+                des,
+                lock_type,
+                start,
+                len,
+            } => {
+                self.setlk(*des, *lock_type, *start, *len)?;
+            }
+            Operation::OfdSetLk {
+                des,
+                lock_type,
+                start,
+                len,
+            } => {
+                self.ofd_setlk(*des, *lock_type, *start, *len)?;
+            }
+            Operation::Read { des, size } => {
+                self.read(*des, *size)?;
+            }
+            Operation::Write {
+                des,
+                src_offset,
+                size,
+            } => {
+                self.write(*des, *src_offset, *size)?;
+            }
+            Operation::FSync { des } => {
+                self.fsync(*des)?;
+            }
+            Operation::FDataSync { des } => {
+                self.fdatasync(*des)?;
+            }
+            Operation::Sync => {
+                self.sync();
+            }
+            Operation::SyncFs { des } => {
+                self.syncfs(*des)?;
+            }
+            Operation::SyncFileRange {
+                des,
+                offset,
+                nbytes,
+                flags,
+            } => {
+                self.sync_file_range(*des, *offset, *nbytes, flags.clone())?;
+            }
+            Operation::Symlink { target, linkpath } => {
+                self.symlink(target.clone(), linkpath.clone())?;
+            }
+            Operation::Truncate { path, size } => {
+                self.truncate(path.clone(), *size)?;
+            }
+            Operation::FTruncate { des, size } => {
+                self.ftruncate(*des, *size)?;
+            }
+            Operation::LSeek {
+                des,
+                offset,
+                whence,
+            } => {
+                self.lseek(*des, *offset, *whence)?;
+            }
+            Operation::PRead { des, size, offset } => {
+                self.pread(*des, *size, *offset)?;
+            }
+            Operation::PWrite {
+                des,
+                src_offset,
+                size,
+                offset,
+            } => {
+                self.pwrite(*des, *src_offset, *size, *offset)?;
+            }
+        };
+        Ok(None)
+    }
+
+    fn name_exists(&self, idx: &DirIndex, name: &Name) -> bool {
+        self.dir(idx).lookup(name).is_some()
+    }
+
+    pub fn dir(&self, idx: &DirIndex) -> &Dir {
+        self.dirs.get(idx.0).unwrap()
+    }
+
+    fn dir_mut(&mut self, idx: &DirIndex) -> &mut Dir {
+        self.dirs.get_mut(idx.0).unwrap()
+    }
+
+    pub fn file(&self, idx: &FileIndex) -> &File {
+        self.files.get(idx.0).unwrap()
+    }
+
+    fn file_mut(&mut self, idx: &FileIndex) -> &mut File {
+        self.files.get_mut(idx.0).unwrap()
+    }
+
+    fn sym(&self, idx: &SymlinkIndex) -> &Symlink {
+        self.symlinks.get(idx.0).unwrap()
+    }
+
+    pub fn special(&self, idx: &SpecialIndex) -> &Special {
+        self.specials.get(idx.0).unwrap()
+    }
+
+    fn special_mut(&mut self, idx: &SpecialIndex) -> &mut Special {
+        self.specials.get_mut(idx.0).unwrap()
+    }
+
+    #[allow(dead_code)]
+    fn root(&self) -> &Dir {
+        self.dirs.first().unwrap()
+    }
+
+    /// Returns directory descriptor only if it was not closed.
+    fn dir_descriptor(&self, idx: &DirDescriptorIndex) -> Result<&DirDescriptor> {
+        let des = self
+            .dir_descriptors
+            .get(idx.0)
+            .ok_or(FsError::BadDirDescriptor(*idx, self.dir_descriptors.len()))?;
+        if des.closed {
+            return Err(FsError::DirDescriptorWasClosed(*idx));
+        }
+        Ok(des)
+    }
+
+    fn descriptor(&self, idx: &FileDescriptorIndex) -> Result<&FileDescriptor> {
+        self.descriptors
+            .get(idx.0)
+            .ok_or(FsError::BadDescriptor(*idx, self.descriptors.len()))
+    }
+
+    fn descriptor_mut(&mut self, idx: &FileDescriptorIndex) -> Result<&mut FileDescriptor> {
+        let len = self.descriptors.len();
+        self.descriptors
+            .get_mut(idx.0)
+            .ok_or(FsError::BadDescriptor(*idx, len))
+    }
+
+    /// Numbers of open file and directory descriptors.
+    pub fn taken_numbers(&self) -> BTreeSet<usize> {
+        let files = (0..self.descriptors.len())
+            .map(FileDescriptorIndex)
+            .filter_map(|des| self.open_descriptor(&des).ok())
+            .map(|des| des.number);
+        let dirs = self
+            .dir_descriptors
+            .iter()
+            .filter(|des| !des.closed)
+            .map(|des| des.number);
+        files.chain(dirs).collect()
+    }
+
+    /// Number of new descriptor, Linux allocates the lowest one that is not taken.
+    fn free_number(&self) -> usize {
+        let taken = self.taken_numbers();
+        (0..).find(|number| !taken.contains(number)).unwrap()
+    }
+
+    /// Returns descriptor only if it was not closed.
+    fn open_descriptor(&self, idx: &FileDescriptorIndex) -> Result<&FileDescriptor> {
+        let file = self.file(&self.description(idx)?.file);
+        if !file.descriptors.contains(idx) {
+            return Err(FsError::DescriptorWasClosed(*idx));
+        }
+        self.descriptor(idx)
+    }
+
+    /// Returns mapping only if it was not unmapped.
+    fn mapping(&self, idx: &MappingIndex) -> Result<&Mapping> {
+        let map = self
+            .mappings
+            .get(idx.0)
+            .ok_or(FsError::BadMapping(*idx, self.mappings.len()))?;
+        if map.unmapped {
+            return Err(FsError::MappingWasUnmapped(*idx));
+        }
+        Ok(map)
+    }
+
+    /// Returns open file description that the descriptor refers to.
+    pub fn description(&self, idx: &FileDescriptorIndex) -> Result<&FileDescription> {
+        let description = self.descriptor(idx)?.description;
+        Ok(self.descriptions.get(description.0).unwrap())
+    }
+
+    fn description_mut(&mut self, idx: &FileDescriptorIndex) -> Result<&mut FileDescription> {
+        let description = self.descriptor(idx)?.description;
+        Ok(self.descriptions.get_mut(description.0).unwrap())
+    }
+
+    /// Resolves absolute path, returns the node and its ancestor directories
+    /// (starting from root, symbolic links in path are resolved).
+    pub fn resolve_node(
+        &self,
+        path: PathName,
+        follow_symlinks: bool,
+    ) -> Result<(Vec<DirIndex>, Node)> {
+        if !path.is_valid() {
+            return Err(FsError::InvalidPath(path));
+        }
+        self.resolve_node_rec(
+            path,
+            follow_symlinks,
+            vec![],
+            AbstractFS::root_index(),
+            vec![],
+            &mut vec![],
+        )
+    }
+
+    /// Resolves path relative to the directory with the ancestors,
+    /// absolute paths are resolved from root.
+    /// Directories that are looked up in are collected to `searched`.
+    fn resolve_node_rec(
+        &self,
+        path: PathName,
+        follow_symlinks: bool,
+        mut dirs: Vec<DirIndex>,
+        start: DirIndex,
+        visited_symlinks: Vec<SymlinkIndex>,
+        searched: &mut Vec<DirIndex>,
+    ) -> Result<(Vec<DirIndex>, Node)> {
+        let mut last = Node::Dir(start);
+        if path.is_absolute() {
+            if !path.is_valid() {
+                return Err(FsError::InvalidPath(path));
+            }
+            dirs.clear();
+            last = Node::Dir(AbstractFS::root_index());
+        }
+        let segments = path.segments();
+        let mut path = String::new();
+        for segment in &segments {
+            path.push('/');
+            path.push_str(segment);
+            let idx = match last {
+                Node::Dir(idx) => idx,
+                Node::Symlink(idx) => {
+                    match self.follow_symlink(
+                        idx,
+                        path.clone(),
+                        dirs,
+                        visited_symlinks.clone(),
+                        searched,
+                    )? {
+                        (rec_dirs, Node::Dir(idx)) => {
+                            dirs = rec_dirs;
+                            idx
+                        }
+                        _ => return Err(FsError::NotADir(self.sym(&idx).target.clone())),
+                    }
+                }
+                _ => return Err(FsError::NotADir(path.into())),
+            };
+            searched.push(idx);
+            last = match *segment {
+                "." => Node::Dir(idx),
+                // Workspace is not the root of real filesystem, so nodes outside are not known.
+                ".." => Node::Dir(
+                    dirs.pop()
+                        .ok_or_else(|| FsError::OutsideWorkspace(path.clone().into()))?,
+                ),
+                _ => {
+                    dirs.push(idx);
+                    self.dir(&idx)
+                        .lookup(segment)
+                        .ok_or(FsError::NotFound(path.clone().into()))?
+                        .clone()
+                }
+            };
+        }
+        match last {
+            Node::Symlink(idx) if follow_symlinks => {
+                self.follow_symlink(idx, path, dirs, visited_symlinks, searched)
+            }
+            _ => Ok((dirs, last)),
+        }
+    }
+
+    /// Resolves target of symbolic link, relative target is resolved
+    /// from the directory containing the link (last of the ancestors).
+    fn follow_symlink(
+        &self,
+        idx: SymlinkIndex,
+        path: String,
+        mut dirs: Vec<DirIndex>,
+        mut visited_symlinks: Vec<SymlinkIndex>,
+        searched: &mut Vec<DirIndex>,
+    ) -> Result<(Vec<DirIndex>, Node)> {
+        // Only symlinks that are being resolved in the current chain form a loop.
+        if visited_symlinks.contains(&idx) {
+            return Err(FsError::LoopExists(path.into()));
+        }
+        visited_symlinks.push(idx);
+        let target = self.sym(&idx).target.clone();
+        let parent = dirs.pop().unwrap();
+        self.resolve_node_rec(target, true, dirs, parent, visited_symlinks, searched)
+    }
+
+    pub fn resolve_file(&self, path: PathName) -> Result<(Vec<DirIndex>, FileIndex)> {
+        match self.resolve_node(path.clone(), true)? {
+            (dirs, Node::File(idx)) => Ok((dirs, idx)),
+            _ => Err(FsError::NotAFile(path)),
+        }
+    }
+
+    pub fn resolve_dir(&self, path: PathName) -> Result<(Vec<DirIndex>, DirIndex)> {
+        match self.resolve_node(path.clone(), true)? {
+            (dirs, Node::Dir(idx)) => Ok((dirs, idx)),
+            _ => Err(FsError::NotADir(path)),
+        }
+    }
+
+    /// Returns extended attributes of file or directory, symbolic links are followed.
+    /// Extended attributes of special files are not modeled.
+    pub fn resolve_xattrs(&self, path: PathName) -> Result<&Xattrs> {
+        match self.resolve_node(path.clone(), true)? {
+            (_, Node::File(idx)) => Ok(&self.file(&idx).xattrs),
+            (_, Node::Dir(idx)) => Ok(&self.dir(&idx).xattrs),
+            (_, Node::Symlink(_)) => Err(FsError::NotFound(path)),
+            (_, Node::Special(_)) => Err(FsError::NotAFile(path)),
+        }
+    }
+
+    fn resolve_xattrs_mut(&mut self, path: PathName) -> Result<&mut Xattrs> {
+        match self.resolve_node(path.clone(), true)? {
+            (_, Node::File(idx)) => Ok(&mut self.file_mut(&idx).xattrs),
+            (_, Node::Dir(idx)) => Ok(&mut self.dir_mut(&idx).xattrs),
+            (_, Node::Symlink(_)) => Err(FsError::NotFound(path)),
+            (_, Node::Special(_)) => Err(FsError::NotAFile(path)),
+        }
+    }
+
+    /// Returns metadata of file, directory or special file, symbolic links are followed.
+    pub fn resolve_metadata(&self, path: PathName) -> Result<Metadata> {
+        match self.resolve_node(path.clone(), true)? {
+            (_, Node::File(idx)) => Ok(self.file(&idx).meta),
+            (_, Node::Dir(idx)) => Ok(self.dir(&idx).meta),
+            (_, Node::Symlink(_)) => Err(FsError::NotFound(path)),
+            (_, Node::Special(idx)) => Ok(self.special(&idx).meta),
+        }
+    }
+
+    fn resolve_metadata_mut(&mut self, path: PathName) -> Result<&mut Metadata> {
+        match self.resolve_node(path.clone(), true)? {
+            (_, Node::File(idx)) => Ok(&mut self.file_mut(&idx).meta),
+            (_, Node::Dir(idx)) => Ok(&mut self.dir_mut(&idx).meta),
+            (_, Node::Symlink(_)) => Err(FsError::NotFound(path)),
+            (_, Node::Special(idx)) => Ok(&mut self.special_mut(&idx).meta),
+        }
+    }
+
+    pub fn root_index() -> DirIndex {
+        DirIndex(0)
+    }
+
+    /// Get nodes that are considired "alive" (accessible from root)
+    pub fn alive(&self) -> AliveNodes {
+        let root = AbstractFS::root_index();
+        let mut alive = AliveNodes {
+            dirs: vec![],
+            files: vec![],
+            files_symlinked: vec![],
+            symlinks: vec![],
+            specials: vec![],
+        };
+        let mut queue = VecDeque::new();
+        queue.push_back(("/".into(), root));
+        alive.dirs.push((Self::root_index(), "/".into()));
+
+        // Because symbolic links can loop back, we follow them only a few times.
+        for _ in 1..=MAX_SYMLINK_FOLLOW {
+            let follow_queue = self.alive_follow_once(&mut alive, queue);
+            queue = follow_queue;
+        }
+
+        alive.dirs.sort();
+        alive.files.sort();
+        alive.files_symlinked.sort();
+        alive.symlinks.sort();
+        alive.specials.sort();
+        alive
+    }
+
+    /// Breadth-first search, follow symbolic links once
+    fn alive_follow_once(
+        &self,
+        alive: &mut AliveNodes,
+        mut queue: VecDeque<(PathName, DirIndex)>,
+    ) -> VecDeque<(PathName, DirIndex)> {
+        let mut follow_next = VecDeque::new();
+        while let Some((dir_path, idx)) = queue.pop_front() {
+            let dir = self.dir(&idx);
+            for (child_name, node) in dir.children.iter() {
+                match node {
+                    Node::Dir(idx) => {
+                        let path = dir_path.join(child_name.to_owned());
+                        queue.push_back((path.clone(), *idx));
+                        alive.dirs.push((idx.clone(), path.clone()));
+                    }
+                    Node::File(idx) => {
+                        alive
+                            .files
+                            .push((*idx, dir_path.join(child_name.to_owned())));
+                    }
+                    Node::Symlink(_) => {
+                        alive.symlinks.push(dir_path.join(child_name.to_owned()));
+                        let follow_path = dir_path.join(child_name.to_owned());
+                        match self.resolve_node(follow_path, true) {
+                            Ok((_, Node::File(idx))) => {
+                                alive
+                                    .files_symlinked
+                                    .push((idx, dir_path.join(child_name.to_owned())));
+                            }
+                            Ok((_, Node::Dir(idx))) => {
+                                let path = dir_path.join(child_name.to_owned());
+                                follow_next.push_back((path.clone(), idx));
+                                alive.dirs.push((idx, path.clone()));
+                            }
+                            _ => {}
+                        }
+                    }
+                    Node::Special(idx) => {
+                        alive
+                            .specials
+                            .push((*idx, dir_path.join(child_name.to_owned())));
+                    }
+                }
+            }
+        }
+        follow_next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::abstract_fs::{
+        content::{Slice, SourceSlice},
+        flags::{
+            FallocateFlag, FileType, ModeFlag, MsyncFlag, RenameFlag, SyncFileRangeFlag, TimeSpec,
+            XattrFlag,
+        },
+        node::Timestamp,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_init_root() {
+        let fs = AbstractFS::new();
+        assert_eq!(
+            AliveNodes {
+                dirs: vec![(AbstractFS::root_index(), "/".into())],
+                files: vec![],
+                files_symlinked: vec![],
+                symlinks: vec![],
+                specials: vec![],
+            },
+            fs.alive()
+        )
+    }
+
+    #[test]
+    fn test_remove_root() {
+        let mut fs = AbstractFS::new();
+        assert_eq!(Err(FsError::RootRemovalForbidden), fs.remove("/".into()));
+    }
+
+    #[test]
+    fn test_mkdir() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.mkdir("/foobar".into(), vec![]).unwrap();
+        assert_eq!(Node::Dir(foo), *fs.root().children.get("foobar").unwrap());
+        assert_eq!(
+            Workload {
+                ops: vec![Operation::MkDir {
+                    path: "/foobar".into(),
+                    mode: vec![],
+                }],
+            },
+            fs.recording
+        );
+        assert_eq!(
+            AliveNodes {
+                dirs: vec![
+                    (AbstractFS::root_index(), "/".into()),
+                    (foo, "/foobar".into())
+                ],
+                files: vec![],
+                files_symlinked: vec![],
+                symlinks: vec![],
+                specials: vec![],
+            },
+            fs.alive()
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_mkdir_name_exists() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foobar".into(), vec![]).unwrap();
+        assert_eq!(
+            Err(FsError::NameAlreadyExists("/foobar".into())),
+            fs.mkdir("/foobar".into(), vec![])
+        );
+    }
+
+    #[test]
+    fn test_create() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foobar".into(), vec![]).unwrap();
+        assert_eq!(Node::File(foo), *fs.root().children.get("foobar").unwrap());
+        assert_eq!(
+            AliveNodes {
+                dirs: vec![(AbstractFS::root_index(), "/".into())],
+                files: vec![(foo, "/foobar".into())],
+                files_symlinked: vec![],
+                symlinks: vec![],
+                specials: vec![],
+            },
+            fs.alive()
+        );
+        assert_eq!(
+            Workload {
+                ops: vec![Operation::Create {
+                    path: "/foobar".into(),
+                    mode: vec![],
+                }]
+            },
+            fs.recording
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_create_name_exists() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foobar".into(), vec![]).unwrap();
+        assert_eq!(
+            Err(FsError::NameAlreadyExists("/foobar".into())),
+            fs.create("/foobar".into(), vec![])
+        );
+    }
+
+    #[test]
+    fn test_remove_file() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foobar".into(), vec![]).unwrap();
+        let boo = fs.create("/boo".into(), vec![]).unwrap();
+
+        assert_eq!(
+            AliveNodes {
+                dirs: vec![(AbstractFS::root_index(), "/".into())],
+                files: vec![(foo, "/foobar".into()), (boo, "/boo".into())],
+                files_symlinked: vec![],
+                symlinks: vec![],
+                specials: vec![],
+            },
+            fs.alive()
+        );
+
+        fs.remove("/foobar".into()).unwrap();
+
+        assert_eq!(1, fs.root().children.len());
+        assert_eq!(Node::File(boo), *fs.root().children.get("boo").unwrap());
+        assert_eq!(
+            AliveNodes {
+                dirs: vec![(AbstractFS::root_index(), "/".into())],
+                files: vec![(boo, "/boo".into())],
+                files_symlinked: vec![],
+                symlinks: vec![],
+                specials: vec![],
+            },
+            fs.alive()
+        );
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::Create {
+                        path: "/foobar".into(),
+                        mode: vec![],
+                    },
+                    Operation::Create {
+                        path: "/boo".into(),
+                        mode: vec![],
+                    },
+                    Operation::Remove {
+                        path: "/foobar".into(),
+                    }
+                ],
+            },
+            fs.recording
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_hardlink() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let bar = fs.mkdir("/bar".into(), vec![]).unwrap();
+        let boo = fs.hardlink("/foo".into(), "/bar/boo".into()).unwrap();
+
+        assert_eq!(Node::File(foo), boo);
+        assert_eq!(
+            AliveNodes {
+                dirs: vec![(AbstractFS::root_index(), "/".into()), (bar, "/bar".into())],
+                files: vec![(foo, "/bar/boo".into()), (foo, "/foo".into())],
+                files_symlinked: vec![],
+                symlinks: vec![],
+                specials: vec![],
+            },
+            fs.alive()
+        );
+
+        let root = fs.root();
+        let bar_dir = fs.dir(&bar);
+        assert_eq!(2, root.children.len());
+        assert_eq!(1, bar_dir.children.len());
+        assert_eq!(
+            root.children.get("foo").unwrap(),
+            bar_dir.children.get("boo").unwrap()
+        );
+
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::Create {
+                        path: "/foo".into(),
+                        mode: vec![],
+                    },
+                    Operation::MkDir {
+                        path: "/bar".into(),
+                        mode: vec![],
+                    },
+                    Operation::Hardlink {
+                        old_path: "/foo".into(),
+                        new_path: "/bar/boo".into(),
+                    }
+                ],
+            },
+            fs.recording
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_remove_hardlink() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        fs.hardlink("/foo".into(), "/bar".into()).unwrap();
+        fs.remove("/bar".into()).unwrap();
+
+        assert_eq!(
+            AliveNodes {
+                dirs: vec![(AbstractFS::root_index(), "/".into())],
+                files: vec![(foo, "/foo".into())],
+                files_symlinked: vec![],
+                symlinks: vec![],
+                specials: vec![],
+            },
+            fs.alive()
+        );
+
+        assert_eq!(1, fs.root().children.len());
+
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::Create {
+                        path: "/foo".into(),
+                        mode: vec![],
+                    },
+                    Operation::Hardlink {
+                        old_path: "/foo".into(),
+                        new_path: "/bar".into(),
+                    },
+                    Operation::Remove {
+                        path: "/bar".into(),
+                    }
+                ],
+            },
+            fs.recording
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_remove_hardlink_dir() {
+        let mut fs = AbstractFS::new();
+        let root = AbstractFS::root_index();
+        let zero = fs.create("/0".into(), vec![]).unwrap();
+        let one = fs.mkdir("/1".into(), vec![]).unwrap();
+        let two = fs.mkdir("/1/2".into(), vec![]).unwrap();
+        fs.hardlink("/0".into(), "/1/2/3".into()).unwrap();
+        assert_eq!(
+            Ok((vec![root, one, two], zero)),
+            fs.resolve_file("/1/2/3".into())
+        );
+        fs.remove("/1".into()).unwrap();
+        assert_eq!(
+            Err(FsError::NotFound("/1".into())),
+            fs.resolve_file("/1/2/3".into())
+        );
+        assert_eq!(Ok((vec![root], zero)), fs.resolve_file("/0".into()));
+    }
+
+    #[test]
+    fn test_hardlink_name_exists() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        fs.create("/bar".into(), vec![]).unwrap();
+        assert_eq!(
+            Err(FsError::NameAlreadyExists("/foo".into())),
+            fs.hardlink("/bar".into(), "/foo".into())
+        );
+    }
+
+    #[test]
+    fn test_remove_dir() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.mkdir("/foobar".into(), vec![]).unwrap();
+        let boo = fs.mkdir("/boo".into(), vec![]).unwrap();
+
+        assert_eq!(
+            AliveNodes {
+                dirs: vec![
+                    (AbstractFS::root_index(), "/".into()),
+                    (foo, "/foobar".into()),
+                    (boo, "/boo".into()),
+                ],
+                files: vec![],
+                files_symlinked: vec![],
+                symlinks: vec![],
+                specials: vec![],
+            },
+            fs.alive()
+        );
+
+        fs.remove("/foobar".into()).unwrap();
+
+        assert_eq!(1, fs.root().children.len());
+        assert_eq!(Node::Dir(boo), *fs.root().children.get("boo").unwrap());
+        assert_eq!(
+            AliveNodes {
+                dirs: vec![(AbstractFS::root_index(), "/".into()), (boo, "/boo".into())],
+                files: vec![],
+                files_symlinked: vec![],
+                symlinks: vec![],
+                specials: vec![],
+            },
+            fs.alive()
+        );
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::MkDir {
+                        path: "/foobar".into(),
+                        mode: vec![],
+                    },
+                    Operation::MkDir {
+                        path: "/boo".into(),
+                        mode: vec![],
+                    },
+                    Operation::Remove {
+                        path: "/foobar".into(),
+                    }
+                ],
+            },
+            fs.recording
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_remove_twice() {
+        let mut fs = AbstractFS::new();
+        fs.create("/0".into(), vec![]).unwrap();
+        fs.remove("/0".into()).unwrap();
+        assert_eq!(Err(FsError::NotFound("/0".into())), fs.remove("/0".into()))
+    }
+
+    #[test]
+    fn test_rename_file() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        fs.rename("/foo".into(), "/bar".into()).unwrap();
+        assert_eq!(
+            AliveNodes {
+                dirs: vec![(AbstractFS::root_index(), "/".into())],
+                files: vec![(foo, "/bar".into())],
+                files_symlinked: vec![],
+                symlinks: vec![],
+                specials: vec![],
+            },
+            fs.alive()
+        );
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::Create {
+                        path: "/foo".into(),
+                        mode: vec![]
+                    },
+                    Operation::Rename {
+                        old_path: "/foo".into(),
+                        new_path: "/bar".into(),
+                    }
+                ]
+            },
+            fs.recording
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_rename_dir() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.rename("/foo".into(), "/bar".into()).unwrap();
+        assert_eq!(
+            AliveNodes {
+                dirs: vec![(AbstractFS::root_index(), "/".into()), (foo, "/bar".into())],
+                files: vec![],
+                files_symlinked: vec![],
+                symlinks: vec![],
+                specials: vec![],
+            },
+            fs.alive()
+        );
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::MkDir {
+                        path: "/foo".into(),
+                        mode: vec![]
+                    },
+                    Operation::Rename {
+                        old_path: "/foo".into(),
+                        new_path: "/bar".into(),
+                    }
+                ]
+            },
+            fs.recording
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_rename_dir_non_empty() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.mkdir("/bar".into(), vec![]).unwrap();
+        fs.create("/bar/baz".into(), vec![]).unwrap();
+        assert_eq!(
+            Err(FsError::DirNotEmpty("/bar".into())),
+            fs.rename("/foo".into(), "/bar".into())
+        );
+        fs.remove("/bar/baz".into()).unwrap();
+        fs.rename("/foo".into(), "/bar".into()).unwrap();
+    }
+
+    #[test]
+    fn test_rename_overwrite() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.mkdir("/bar".into(), vec![]).unwrap();
+        let baz = fs.create("/baz".into(), vec![]).unwrap();
+        let boo = fs.create("/boo".into(), vec![]).unwrap();
+        fs.hardlink("/boo".into(), "/foo/boo".into()).unwrap();
+        let des = fs
+            .open("/boo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        assert_eq!(
+            Err(FsError::IsADir("/bar".into())),
+            fs.rename("/baz".into(), "/bar".into())
+        );
+        assert_eq!(
+            Err(FsError::NotADir("/baz".into())),
+            fs.rename("/bar".into(), "/baz".into())
+        );
+        assert_eq!(
+            Err(FsError::DirNotEmpty("/foo".into())),
+            fs.rename("/bar".into(), "/foo".into())
+        );
+        // Hardlinks of the same file are left unchanged.
+        fs.rename("/boo".into(), "/foo/boo".into()).unwrap();
+        fs.rename("/baz".into(), "/baz".into()).unwrap();
+        fs.rename("/baz".into(), "/boo".into()).unwrap();
+        fs.rename("/foo".into(), "/bar".into()).unwrap();
+        assert_eq!(
+            AliveNodes {
+                dirs: vec![
+                    (AbstractFS::root_index(), "/".into()),
+                    (DirIndex(1), "/bar".into())
+                ],
+                files: vec![(baz, "/boo".into()), (boo, "/bar/boo".into())],
+                files_symlinked: vec![],
+                symlinks: vec![],
+                specials: vec![],
+            },
+            fs.alive()
+        );
+        // Descriptor still refers to the replaced file.
+        fs.write(des, 0, 100).unwrap();
+        assert_eq!(100, fs.file(&boo).content.size());
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_rename_replace_symlink_in_path() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        fs.symlink("/".into(), "/bar".into()).unwrap();
+        fs.rename("/bar/foo".into(), "/bar".into()).unwrap();
+        assert_eq!(
+            AliveNodes {
+                dirs: vec![(AbstractFS::root_index(), "/".into())],
+                files: vec![(foo, "/bar".into())],
+                files_symlinked: vec![],
+                symlinks: vec![],
+                specials: vec![],
+            },
+            fs.alive()
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_rename_to_subdirectory() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/0".into(), vec![]).unwrap();
+        assert_eq!(
+            Err(FsError::RenameToSubdirectoryError(
+                "/0".into(),
+                "/0/1".into()
+            )),
+            fs.rename("/0".into(), "/0/1".into())
+        );
+    }
+
+    #[test]
+    fn test_rename_to_subdirectory_symlink() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/0".into(), vec![]).unwrap();
+        fs.symlink("/0".into(), "/symlink".into()).unwrap();
+        assert_eq!(
+            Err(FsError::RenameToSubdirectoryError(
+                "/0".into(),
+                "/symlink/1".into()
+            )),
+            fs.rename("/0".into(), "/symlink/1".into())
+        );
+    }
+
+    #[test]
+    fn test_open_close_file() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        let file = fs.file(&foo);
+        assert_eq!(BTreeSet::from([des]), file.descriptors);
+        fs.close(des).unwrap();
+        let file = fs.file(&foo);
+        assert!(file.descriptors.is_empty());
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::Create {
+                        path: "/foo".into(),
+                        mode: vec![]
+                    },
+                    Operation::Open {
+                        path: "/foo".into(),
+                        flags: vec![OpenFlag::O_RDWR],
+                        mode: vec![],
+                        des
+                    },
+                    Operation::Close { des }
+                ]
+            },
+            fs.recording
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_close_bad_descriptor() {
+        let mut fs = AbstractFS::new();
+        let des = FileDescriptorIndex(0);
+        assert_eq!(Err(FsError::BadDescriptor(des, 0)), fs.close(des));
+    }
+
+    #[test]
+    fn test_close_twice() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.close(des).unwrap();
+        assert_eq!(Err(FsError::DescriptorWasClosed(des)), fs.close(des));
+    }
+
+    #[test]
+    fn test_open_twice() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        fs.hardlink("/foo".into(), "/bar".into()).unwrap();
+        let des_1 = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        let des_2 = fs
+            .open("/bar".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        assert_eq!(BTreeSet::from([des_1, des_2]), fs.file(&foo).descriptors);
+        fs.write(des_1, 0, 100).unwrap();
+        assert_eq!(100, fs.lseek(des_1, 0, SeekWhence::SEEK_CUR).unwrap());
+        assert_eq!(0, fs.lseek(des_2, 0, SeekWhence::SEEK_CUR).unwrap());
+        assert_eq!(fs.pread(des_1, 10, 0).unwrap(), fs.read(des_2, 10).unwrap());
+        fs.lseek(des_2, 20, SeekWhence::SEEK_SET).unwrap();
+        fs.write(des_2, 42, 10).unwrap();
+        fs.lseek(des_1, 20, SeekWhence::SEEK_SET).unwrap();
+        assert_eq!(
+            fs.pread(des_2, 10, 20).unwrap(),
+            fs.read(des_1, 10).unwrap()
+        );
+        fs.close(des_1).unwrap();
+        assert_eq!(BTreeSet::from([des_2]), fs.file(&foo).descriptors);
+        assert_eq!(Err(FsError::DescriptorWasClosed(des_1)), fs.read(des_1, 10));
+        fs.read(des_2, 10).unwrap();
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_open_create() {
+        let mut fs = AbstractFS::new();
+        let des = fs
+            .open(
+                "/foo".into(),
+                vec![OpenFlag::O_WRONLY, OpenFlag::O_CREAT, OpenFlag::O_EXCL],
+                vec![ModeFlag::S_IRWXU],
+            )
+            .unwrap();
+        assert_eq!(
+            AliveNodes {
+                dirs: vec![(AbstractFS::root_index(), "/".into())],
+                files: vec![(FileIndex(0), "/foo".into())],
+                files_symlinked: vec![],
+                symlinks: vec![],
+                specials: vec![],
+            },
+            fs.alive()
+        );
+        fs.close(des).unwrap();
+        fs.open(
+            "/foo".into(),
+            vec![OpenFlag::O_RDWR, OpenFlag::O_CREAT],
+            vec![],
+        )
+        .unwrap();
+        assert_eq!(1, fs.files.len());
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_open_excl() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        fs.symlink("/bar".into(), "/baz".into()).unwrap();
+        let flags = vec![OpenFlag::O_RDWR, OpenFlag::O_CREAT, OpenFlag::O_EXCL];
+        assert_eq!(
+            Err(FsError::NameAlreadyExists("/foo".into())),
+            fs.open("/foo".into(), flags.clone(), vec![])
+        );
+        assert_eq!(
+            Err(FsError::NameAlreadyExists("/baz".into())),
+            fs.open("/baz".into(), flags, vec![])
+        );
+    }
+
+    #[test]
+    fn test_open_not_found() {
+        let mut fs = AbstractFS::new();
+        assert_eq!(
+            Err(FsError::NotFound("/foo".into())),
+            fs.open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+        );
+    }
+
+    #[test]
+    fn test_open_nofollow() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        fs.symlink("/foo".into(), "/bar".into()).unwrap();
+        assert_eq!(
+            Err(FsError::SymlinkNotFollowed("/bar".into())),
+            fs.open(
+                "/bar".into(),
+                vec![OpenFlag::O_RDWR, OpenFlag::O_NOFOLLOW],
+                vec![]
+            )
+        );
+        assert_eq!(
+            Err(FsError::NotADir("/bar".into())),
+            fs.open(
+                "/bar".into(),
+                vec![
+                    OpenFlag::O_RDONLY,
+                    OpenFlag::O_DIRECTORY,
+                    OpenFlag::O_NOFOLLOW
+                ],
+                vec![]
+            )
+        );
+        assert_eq!(
+            Ok(FileDescriptorIndex(0)),
+            fs.open(
+                "/foo".into(),
+                vec![OpenFlag::O_RDWR, OpenFlag::O_NOFOLLOW],
+                vec![],
+            )
+        );
+        assert_eq!(
+            Operation::Open {
+                path: "/bar".into(),
+                flags: vec![OpenFlag::O_RDWR, OpenFlag::O_NOFOLLOW],
+                mode: vec![],
+                des: FileDescriptorIndex(0),
+            },
+            fs.recording.ops[2]
+        );
+        assert_eq!(5, fs.recording.ops.len());
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_open_directory() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        fs.mkdir("/bar".into(), vec![]).unwrap();
+        assert_eq!(
+            Err(FsError::NotADir("/foo".into())),
+            fs.open(
+                "/foo".into(),
+                vec![OpenFlag::O_RDONLY, OpenFlag::O_DIRECTORY],
+                vec![]
+            )
+        );
+        assert_eq!(
+            Err(FsError::NotAFile("/bar".into())),
+            fs.open("/bar".into(), vec![OpenFlag::O_RDONLY], vec![])
+        );
+        assert_eq!(
+            Err(FsError::NotADir("/foo".into())),
+            fs.openat(
+                DirFd::Cwd,
+                "foo".into(),
+                vec![OpenFlag::O_RDONLY, OpenFlag::O_DIRECTORY],
+                vec![]
+            )
+        );
+        assert_eq!(4, fs.recording.ops.len());
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_replay_not_a_dir() {
+        let open = Operation::Open {
+            path: "/foo".into(),
+            flags: vec![OpenFlag::O_RDONLY, OpenFlag::O_DIRECTORY],
+            mode: vec![],
+            des: FileDescriptorIndex(0),
+        };
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        assert_eq!(
+            Ok(Some(FsError::NotADir("/foo".into()))),
+            fs.replay_op(&open)
+        );
+        let workload = Workload {
+            ops: vec![
+                Operation::Create {
+                    path: "/foo".into(),
+                    mode: vec![],
+                },
+                open,
+                Operation::UnlinkAt {
+                    dir: DirFd::Cwd,
+                    path: "foo".into(),
+                    flags: vec![AtFlag::AT_REMOVEDIR],
+                },
+            ],
+        };
+        let mut fs = AbstractFS::new();
+        assert_eq!(Err(FsError::NotADir("foo".into())), fs.replay(&workload));
+        assert_eq!(workload.ops[..2], fs.recording.ops);
+    }
+
+    #[test]
+    fn test_open_invalid_flags() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        assert_eq!(
+            Err(FsError::InvalidOpenFlags("/foo".into())),
+            fs.open(
+                "/foo".into(),
+                vec![OpenFlag::O_RDONLY, OpenFlag::O_RDWR],
+                vec![]
+            )
+        );
+        assert_eq!(
+            Err(FsError::InvalidOpenFlags("/foo".into())),
+            fs.open("/foo".into(), vec![OpenFlag::O_EXEC], vec![])
+        );
+    }
+
+    #[test]
+    fn test_open_trunc() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.write(des, 0, 100).unwrap();
+        fs.close(des).unwrap();
+        let des = fs
+            .open(
+                "/foo".into(),
+                vec![OpenFlag::O_RDWR, OpenFlag::O_TRUNC],
+                vec![],
+            )
+            .unwrap();
+        assert_eq!(0, fs.read(des, 100).unwrap().size());
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_open_append() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open(
+                "/foo".into(),
+                vec![OpenFlag::O_RDWR, OpenFlag::O_APPEND],
+                vec![],
+            )
+            .unwrap();
+        fs.write(des, 0, 100).unwrap();
+        fs.lseek(des, 10, SeekWhence::SEEK_SET).unwrap();
+        fs.write(des, 0, 10).unwrap();
+        assert_eq!(110, fs.lseek(des, 0, SeekWhence::SEEK_CUR).unwrap());
+        fs.pwrite(des, 0, 10, 0).unwrap();
+        assert_eq!(120, fs.lseek(des, 0, SeekWhence::SEEK_END).unwrap());
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_open_access_mode() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDONLY], vec![])
+            .unwrap();
+        assert_eq!(Err(FsError::NotOpenForWriting(des)), fs.write(des, 0, 10));
+        assert_eq!(
+            Err(FsError::NotOpenForWriting(des)),
+            fs.pwrite(des, 0, 10, 0)
+        );
+        assert_eq!(Err(FsError::NotOpenForWriting(des)), fs.ftruncate(des, 10));
+        fs.close(des).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_WRONLY], vec![])
+            .unwrap();
+        assert_eq!(Err(FsError::NotOpenForReading(des)), fs.read(des, 10));
+        assert_eq!(Err(FsError::NotOpenForReading(des)), fs.pread(des, 10, 0));
+        fs.write(des, 0, 10).unwrap();
+    }
+
+    #[test]
+    fn test_dup() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        let dup_des = fs.dup(des).unwrap();
+        assert_eq!(BTreeSet::from([des, dup_des]), fs.file(&foo).descriptors);
+        fs.write(des, 0, 100).unwrap();
+        assert_eq!(100, fs.lseek(dup_des, 0, SeekWhence::SEEK_CUR).unwrap());
+        fs.rename("/foo".into(), "/bar".into()).unwrap();
+        fs.close(des).unwrap();
+        assert_eq!(Err(FsError::DescriptorWasClosed(des)), fs.dup(des));
+        fs.lseek(dup_des, 10, SeekWhence::SEEK_SET).unwrap();
+        fs.write(dup_des, 0, 10).unwrap();
+        assert_eq!(20, fs.lseek(dup_des, 0, SeekWhence::SEEK_CUR).unwrap());
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::Create {
+                        path: "/foo".into(),
+                        mode: vec![]
+                    },
+                    Operation::Open {
+                        path: "/foo".into(),
+                        flags: vec![OpenFlag::O_RDWR],
+                        mode: vec![],
+                        des
+                    },
+                    Operation::Dup {
+                        old_des: des,
+                        new_des: dup_des
+                    },
+                    Operation::Write {
+                        des,
+                        src_offset: 0,
+                        size: 100
+                    },
+                    Operation::LSeek {
+                        des: dup_des,
+                        offset: 0,
+                        whence: SeekWhence::SEEK_CUR
+                    },
+                    Operation::Rename {
+                        old_path: "/foo".into(),
+                        new_path: "/bar".into()
+                    },
+                    Operation::Close { des },
+                    Operation::LSeek {
+                        des: dup_des,
+                        offset: 10,
+                        whence: SeekWhence::SEEK_SET
+                    },
+                    Operation::Write {
+                        des: dup_des,
+                        src_offset: 0,
+                        size: 10
+                    },
+                    Operation::LSeek {
+                        des: dup_des,
+                        offset: 0,
+                        whence: SeekWhence::SEEK_CUR
+                    },
+                ]
+            },
+            fs.recording
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_dup_shared_flags() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDONLY], vec![])
+            .unwrap();
+        let dup_des = fs.dup(des).unwrap();
+        assert_eq!(
+            Err(FsError::NotOpenForWriting(dup_des)),
+            fs.write(dup_des, 0, 10)
+        );
+    }
+
+    #[test]
+    fn test_dup2() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let bar = fs.create("/bar".into(), vec![]).unwrap();
+        let foo_des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        let bar_des = fs
+            .open("/bar".into(), vec![OpenFlag::O_RDONLY], vec![])
+            .unwrap();
+        fs.dup2(foo_des, foo_des).unwrap();
+        fs.dup2(foo_des, bar_des).unwrap();
+        assert_eq!(
+            BTreeSet::from([foo_des, bar_des]),
+            fs.file(&foo).descriptors
+        );
+        assert!(fs.file(&bar).descriptors.is_empty());
+        fs.write(bar_des, 0, 100).unwrap();
+        assert_eq!(100, fs.lseek(foo_des, 0, SeekWhence::SEEK_CUR).unwrap());
+        fs.close(bar_des).unwrap();
+        fs.dup2(foo_des, bar_des).unwrap();
+        fs.write(bar_des, 0, 100).unwrap();
+        assert_eq!(200, fs.lseek(foo_des, 0, SeekWhence::SEEK_CUR).unwrap());
+        fs.close(foo_des).unwrap();
+        let baz_des = fs
+            .open("/bar".into(), vec![OpenFlag::O_RDONLY], vec![])
+            .unwrap();
+        assert_eq!(
+            Err(FsError::DescriptorNumberTaken(foo_des)),
+            fs.dup2(bar_des, foo_des)
+        );
+        fs.close(baz_des).unwrap();
+        fs.dup2(bar_des, foo_des).unwrap();
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_dup3() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des_1 = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        let des_2 = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        assert_eq!(
+            Err(FsError::SameDescriptors(des_1, des_1)),
+            fs.dup3(des_1, des_1, vec![])
+        );
+        assert_eq!(
+            Err(FsError::InvalidDupFlags(des_1)),
+            fs.dup3(des_1, des_2, vec![OpenFlag::O_APPEND])
+        );
+        fs.dup3(des_1, des_2, vec![OpenFlag::O_CLOEXEC]).unwrap();
+        fs.write(des_1, 0, 100).unwrap();
+        assert_eq!(100, fs.lseek(des_2, 0, SeekWhence::SEEK_CUR).unwrap());
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_read_bad_descriptor() {
+        let mut fs = AbstractFS::new();
+        let des = FileDescriptorIndex(0);
+        assert_eq!(Err(FsError::BadDescriptor(des, 0)), fs.read(des, 0));
+    }
+
+    #[test]
+    fn test_read_closed() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.close(des).unwrap();
+        assert_eq!(Err(FsError::DescriptorWasClosed(des)), fs.read(des, 0));
+    }
+
+    #[test]
+    fn test_read_empty() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        let content = fs.read(des, 1024).unwrap();
+        fs.close(des).unwrap();
+
+        assert_eq!(Content::new(), content);
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::Create {
+                        path: "/foo".into(),
+                        mode: vec![]
+                    },
+                    Operation::Open {
+                        path: "/foo".into(),
+                        flags: vec![OpenFlag::O_RDWR],
+                        mode: vec![],
+                        des
+                    },
+                    Operation::Read { des, size: 1024 },
+                    Operation::Close { des },
+                ]
+            },
+            fs.recording
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_write_bad_descriptor() {
+        let mut fs = AbstractFS::new();
+        let des = FileDescriptorIndex(0);
+        assert_eq!(Err(FsError::BadDescriptor(des, 0)), fs.write(des, 0, 0));
+    }
+
+    #[test]
+    fn test_write_closed() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.close(des).unwrap();
+        assert_eq!(Err(FsError::DescriptorWasClosed(des)), fs.write(des, 0, 0));
+    }
+
+    #[test]
+    fn test_write() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.write(des, 999, 1024).unwrap();
+        fs.close(des).unwrap();
+
+        assert_eq!(
+            vec![Slice::Data(SourceSlice {
+                from: 999,
+                to: 999 + 1024 - 1
+            })],
+            fs.file(&foo).content.slices()
+        );
+
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::Create {
+                        path: "/foo".into(),
+                        mode: vec![]
+                    },
+                    Operation::Open {
+                        path: "/foo".into(),
+                        flags: vec![OpenFlag::O_RDWR],
+                        mode: vec![],
+                        des
+                    },
+                    Operation::Write {
+                        des,
+                        src_offset: 999,
+                        size: 1024
+                    },
+                    Operation::Close { des },
+                ]
+            },
+            fs.recording
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_write_empty() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.write(des, 0, 10).unwrap();
+        fs.lseek(des, 100, SeekWhence::SEEK_SET).unwrap();
+        fs.write(des, 0, 0).unwrap();
+        fs.pwrite(des, 0, 0, 5000).unwrap();
+        assert_eq!(10, fs.file(&foo).content.size());
+        let des_append = fs
+            .open(
+                "/foo".into(),
+                vec![OpenFlag::O_RDWR, OpenFlag::O_APPEND],
+                vec![],
+            )
+            .unwrap();
+        fs.lseek(des_append, 3, SeekWhence::SEEK_SET).unwrap();
+        fs.write(des_append, 0, 0).unwrap();
+        assert_eq!(3, fs.description(&des_append).unwrap().offset);
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_copy_file_range() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let bar = fs.create("/bar".into(), vec![]).unwrap();
+        let des_foo = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.write(des_foo, 0, 100).unwrap();
+        let des_bar = fs
+            .open("/bar".into(), vec![OpenFlag::O_WRONLY], vec![])
+            .unwrap();
+        assert_eq!(
+            30,
+            fs.copy_file_range(des_foo, Some(70), des_bar, Some(10), 1000)
+                .unwrap()
+        );
+        assert_eq!(100, fs.description(&des_foo).unwrap().offset);
+        assert_eq!(0, fs.description(&des_bar).unwrap().offset);
+        assert_eq!(
+            0,
+            fs.copy_file_range(des_foo, None, des_bar, None, 1000)
+                .unwrap()
+        );
+        fs.lseek(des_foo, 20, SeekWhence::SEEK_SET).unwrap();
+        assert_eq!(
+            5,
+            fs.copy_file_range(des_foo, None, des_bar, None, 5).unwrap()
+        );
+        assert_eq!(25, fs.description(&des_foo).unwrap().offset);
+        assert_eq!(5, fs.description(&des_bar).unwrap().offset);
+        assert_eq!(
+            vec![
+                Slice::Data(SourceSlice { from: 20, to: 24 }),
+                Slice::Hole(5),
+                Slice::Data(SourceSlice { from: 70, to: 99 }),
+            ],
+            fs.file(&bar).content.slices()
+        );
+        assert_eq!(100, fs.file(&foo).content.size());
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_copy_errors() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des_rdwr = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.write(des_rdwr, 0, 100).unwrap();
+        let des_append = fs
+            .open(
+                "/foo".into(),
+                vec![OpenFlag::O_WRONLY, OpenFlag::O_APPEND],
+                vec![],
+            )
+            .unwrap();
+        assert_eq!(
+            Err(FsError::AppendOnly(des_append)),
+            fs.sendfile(des_append, des_rdwr, Some(0), 10)
+        );
+        assert_eq!(
+            Err(FsError::NotOpenForReading(des_append)),
+            fs.splice(des_append, None, des_rdwr, None, 10)
+        );
+        assert_eq!(
+            Err(FsError::OverlappingCopy(des_rdwr, des_rdwr)),
+            fs.copy_file_range(des_rdwr, Some(0), des_rdwr, Some(50), 51)
+        );
+        assert_eq!(
+            50,
+            fs.splice(des_rdwr, Some(0), des_rdwr, Some(50), 50)
+                .unwrap()
+        );
+        assert_eq!(
+            Err(FsError::OverlappingCopy(des_rdwr, des_rdwr)),
+            fs.sendfile(des_rdwr, des_rdwr, Some(0), 101)
+        );
+        assert_eq!(100, fs.sendfile(des_rdwr, des_rdwr, Some(0), 100).unwrap());
+        assert_eq!(200, fs.description(&des_rdwr).unwrap().offset);
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_ficlone() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let bar = fs.create("/bar".into(), vec![]).unwrap();
+        let des_foo = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        let des_bar = fs
+            .open("/bar".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.write(des_foo, 0, BLOCK_SIZE + 100).unwrap();
+        fs.write(des_bar, 13, 100).unwrap();
+        fs.ficlone(des_foo, des_bar).unwrap();
+        assert_eq!(fs.file(&foo).content, fs.file(&bar).content);
+        assert_eq!(100, fs.description(&des_bar).unwrap().offset);
+        assert_eq!(
+            Err(FsError::OverlappingCopy(des_foo, des_foo)),
+            fs.ficlone(des_foo, des_foo)
+        );
+        fs.ftruncate(des_bar, 2 * BLOCK_SIZE).unwrap();
+        assert_eq!(
+            Err(FsError::UnalignedRemap(des_foo, des_bar)),
+            fs.ficlone(des_foo, des_bar)
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_ficlonerange() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.write(des, 0, 2 * BLOCK_SIZE + 1).unwrap();
+        assert_eq!(
+            Err(FsError::UnalignedRemap(des, des)),
+            fs.ficlonerange(des, 1, BLOCK_SIZE, des, 4 * BLOCK_SIZE)
+        );
+        assert_eq!(
+            Err(FsError::UnalignedRemap(des, des)),
+            fs.ficlonerange(des, 0, 100, des, 4 * BLOCK_SIZE)
+        );
+        assert_eq!(
+            Err(FsError::RemapOutOfRange(des)),
+            fs.ficlonerange(des, 0, 4 * BLOCK_SIZE, des, 4 * BLOCK_SIZE)
+        );
+        assert_eq!(
+            Err(FsError::OverlappingCopy(des, des)),
+            fs.ficlonerange(des, 0, 0, des, 2 * BLOCK_SIZE)
+        );
+        fs.ficlonerange(des, 0, BLOCK_SIZE, des, BLOCK_SIZE)
+            .unwrap();
+        fs.ficlonerange(des, BLOCK_SIZE, 0, des, 4 * BLOCK_SIZE)
+            .unwrap();
+        assert_eq!(
+            vec![
+                Slice::Data(SourceSlice {
+                    from: 0,
+                    to: BLOCK_SIZE - 1
+                }),
+                Slice::Data(SourceSlice {
+                    from: 0,
+                    to: BLOCK_SIZE - 1
+                }),
+                Slice::Data(SourceSlice {
+                    from: 2 * BLOCK_SIZE,
+                    to: 2 * BLOCK_SIZE
+                }),
+                Slice::Hole(2 * BLOCK_SIZE - 1),
+                Slice::Data(SourceSlice {
+                    from: 0,
+                    to: BLOCK_SIZE - 1
+                }),
+                Slice::Data(SourceSlice {
+                    from: 2 * BLOCK_SIZE,
+                    to: 2 * BLOCK_SIZE
+                }),
+            ],
+            fs.file(&foo).content.slices()
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_fideduperange() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.write(des, 0, 2 * BLOCK_SIZE).unwrap();
+        let content = fs.file(&foo).content.clone();
+        fs.fideduperange(des, 0, BLOCK_SIZE, des, BLOCK_SIZE)
+            .unwrap();
+        assert_eq!(content, fs.file(&foo).content);
+        assert_eq!(
+            Err(FsError::RemapOutOfRange(des)),
+            fs.fideduperange(des, 0, BLOCK_SIZE, des, 2 * BLOCK_SIZE)
+        );
+        let des_append = fs
+            .open(
+                "/foo".into(),
+                vec![OpenFlag::O_RDWR, OpenFlag::O_APPEND],
+                vec![],
+            )
+            .unwrap();
+        assert_eq!(
+            Err(FsError::AppendOnly(des_append)),
+            fs.fideduperange(des, 0, BLOCK_SIZE, des_append, BLOCK_SIZE)
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_write_rewrite() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let des_1 = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.write(des_1, 13, 100).unwrap();
+        fs.close(des_1).unwrap();
+        let des_2 = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.write(des_2, 42, 55).unwrap();
+        fs.close(des_2).unwrap();
+
+        assert_eq!(
+            vec![
+                Slice::Data(SourceSlice {
+                    from: 42,
+                    to: 42 + 55 - 1
+                }),
+                Slice::Data(SourceSlice {
+                    from: 13 + 55,
+                    to: 13 + 100 - 1
+                })
+            ],
+            fs.file(&foo).content.slices()
+        );
+
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::Create {
+                        path: "/foo".into(),
+                        mode: vec![]
+                    },
+                    Operation::Open {
+                        path: "/foo".into(),
+                        flags: vec![OpenFlag::O_RDWR],
+                        mode: vec![],
+                        des: des_1
+                    },
+                    Operation::Write {
+                        des: des_1,
+                        src_offset: 13,
+                        size: 100
+                    },
+                    Operation::Close { des: des_1 },
+                    Operation::Open {
+                        path: "/foo".into(),
+                        flags: vec![OpenFlag::O_RDWR],
+                        mode: vec![],
+                        des: des_2
+                    },
+                    Operation::Write {
+                        des: des_2,
+                        src_offset: 42,
+                        size: 55
+                    },
+                    Operation::Close { des: des_2 },
+                ]
+            },
+            fs.recording
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_read() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des_write = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.write(des_write, 13, 100).unwrap();
+        fs.write(des_write, 42, 55).unwrap();
+        fs.close(des_write).unwrap();
+        let des_read = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        assert_eq!(Vec::<Slice>::new(), fs.read(des_read, 0).unwrap().slices());
+        assert_eq!(
+            vec![Slice::Data(SourceSlice {
+                from: 13,
+                to: 13 + 10 - 1
+            })],
+            fs.read(des_read, 10).unwrap().slices()
+        );
+        assert_eq!(
+            vec![Slice::Data(SourceSlice {
+                from: (13 + 10),
+                to: (13 + 10) + 10 - 1
+            })],
+            fs.read(des_read, 10).unwrap().slices()
+        );
+        assert_eq!(
+            vec![
+                Slice::Data(SourceSlice {
+                    from: (13 + 20),
+                    to: 13 + 100 - 1
+                }),
+                Slice::Data(SourceSlice {
+                    from: 42,
+                    to: 42 + 55 - 1
+                }),
+            ],
+            fs.read(des_read, 1024).unwrap().slices()
+        );
+        fs.close(des_read).unwrap();
+
+        assert_eq!(
             Workload {
-                ops: vec![Operation::MkDir {
-                    path: "/foobar".into(),
-                    mode: vec![],
-                }],
+                ops: vec![
+                    Operation::Create {
+                        path: "/foo".into(),
+                        mode: vec![]
+                    },
+                    Operation::Open {
+                        path: "/foo".into(),
+                        flags: vec![OpenFlag::O_RDWR],
+                        mode: vec![],
+                        des: des_write
+                    },
+                    Operation::Write {
+                        des: des_write,
+                        src_offset: 13,
+                        size: 100
+                    },
+                    Operation::Write {
+                        des: des_write,
+                        src_offset: 42,
+                        size: 55
+                    },
+                    Operation::Close { des: des_write },
+                    Operation::Open {
+                        path: "/foo".into(),
+                        flags: vec![OpenFlag::O_RDWR],
+                        mode: vec![],
+                        des: des_read
+                    },
+                    Operation::Read {
+                        des: des_read,
+                        size: 0
+                    },
+                    Operation::Read {
+                        des: des_read,
+                        size: 10
+                    },
+                    Operation::Read {
+                        des: des_read,
+                        size: 10
+                    },
+                    Operation::Read {
+                        des: des_read,
+                        size: 1024
+                    },
+                    Operation::Close { des: des_read },
+                ]
+            },
+            fs.recording
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_fsync_bad_descriptor() {
+        let mut fs = AbstractFS::new();
+        let des = FileDescriptorIndex(0);
+        assert_eq!(Err(FsError::BadDescriptor(des, 0)), fs.fsync(des));
+    }
+
+    #[test]
+    fn test_fsync_closed() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.close(des).unwrap();
+        assert_eq!(Err(FsError::DescriptorWasClosed(des)), fs.fsync(des));
+    }
+
+    #[test]
+    fn test_fsync() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.fsync(des).unwrap();
+        fs.close(des).unwrap();
+
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::Create {
+                        path: "/foo".into(),
+                        mode: vec![]
+                    },
+                    Operation::Open {
+                        path: "/foo".into(),
+                        flags: vec![OpenFlag::O_RDWR],
+                        mode: vec![],
+                        des
+                    },
+                    Operation::FSync { des },
+                    Operation::Close { des },
+                ]
+            },
+            fs.recording
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_sync_family() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDONLY], vec![])
+            .unwrap();
+        fs.fdatasync(des).unwrap();
+        fs.sync();
+        fs.syncfs(des).unwrap();
+        fs.sync_file_range(des, 4096, 0, vec![SyncFileRangeFlag::SYNC_FILE_RANGE_WRITE])
+            .unwrap();
+        fs.close(des).unwrap();
+        assert_eq!(Err(FsError::DescriptorWasClosed(des)), fs.fdatasync(des));
+        assert_eq!(Err(FsError::DescriptorWasClosed(des)), fs.syncfs(des));
+        assert_eq!(
+            Err(FsError::DescriptorWasClosed(des)),
+            fs.sync_file_range(des, 0, 0, vec![])
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_flock() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let fst = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDONLY], vec![])
+            .unwrap();
+        let dup = fs.dup(fst).unwrap();
+        let snd = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDONLY], vec![])
+            .unwrap();
+        assert_eq!(Ok(true), fs.flock(fst, FlockOperation::LOCK_SH));
+        assert_eq!(Ok(true), fs.flock(snd, FlockOperation::LOCK_SH));
+        // Failed conversion drops the shared lock.
+        assert_eq!(Ok(false), fs.flock(snd, FlockOperation::LOCK_EX));
+        assert_eq!(Ok(true), fs.flock(dup, FlockOperation::LOCK_EX));
+        assert_eq!(Ok(false), fs.flock(snd, FlockOperation::LOCK_SH));
+        // Lock follows the file and is released with the last descriptor of description.
+        fs.rename("/foo".into(), "/bar".into()).unwrap();
+        fs.close(fst).unwrap();
+        assert_eq!(Ok(true), fs.flock_conflicts(&snd, FlockOperation::LOCK_SH));
+        fs.close(dup).unwrap();
+        assert_eq!(Ok(true), fs.flock(snd, FlockOperation::LOCK_EX));
+        assert_eq!(Ok(true), fs.flock(snd, FlockOperation::LOCK_UN));
+        assert!(fs.file_locks.is_empty());
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_flock_mapping() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let fst = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        let snd = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        assert_eq!(Ok(true), fs.flock(fst, FlockOperation::LOCK_EX));
+        let map = fs.mmap(fst, 0, PAGE_SIZE).unwrap();
+        fs.close(fst).unwrap();
+        assert_eq!(Ok(false), fs.flock(snd, FlockOperation::LOCK_SH));
+        fs.munmap(map).unwrap();
+        assert_eq!(Ok(true), fs.flock(snd, FlockOperation::LOCK_SH));
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_record_locks() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let fst = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        let snd = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDONLY], vec![])
+            .unwrap();
+        assert_eq!(
+            Err(FsError::NotOpenForWriting(snd)),
+            fs.setlk(snd, LockType::F_WRLCK, 0, 0)
+        );
+        assert_eq!(
+            Err(FsError::InvalidLockRange(i64::MAX as u64, 2)),
+            fs.setlk(fst, LockType::F_RDLCK, i64::MAX as u64, 2)
+        );
+        // POSIX locks of the same process never conflict.
+        assert_eq!(Ok(true), fs.setlk(fst, LockType::F_WRLCK, 0, 0));
+        assert_eq!(Ok(true), fs.setlk(snd, LockType::F_RDLCK, 100, 100));
+        assert_eq!(Ok(true), fs.setlk(fst, LockType::F_UNLCK, 150, 0));
+        assert_eq!(Ok(false), fs.ofd_setlk(snd, LockType::F_RDLCK, 0, 200));
+        assert_eq!(Ok(true), fs.ofd_setlk(snd, LockType::F_RDLCK, 150, 100));
+        assert_eq!(Ok(false), fs.setlk(fst, LockType::F_WRLCK, 200, 0));
+        // OFD locks of different descriptions conflict.
+        assert_eq!(Ok(true), fs.ofd_setlk(fst, LockType::F_RDLCK, 200, 0));
+        assert_eq!(Ok(false), fs.ofd_setlk(fst, LockType::F_WRLCK, 0, 0));
+        // Closing any descriptor releases POSIX locks of the file.
+        let other = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDONLY], vec![])
+            .unwrap();
+        fs.close(other).unwrap();
+        assert_eq!(Ok(true), fs.ofd_setlk(snd, LockType::F_RDLCK, 0, 0));
+        fs.close(snd).unwrap();
+        assert_eq!(Ok(true), fs.ofd_setlk(fst, LockType::F_WRLCK, 0, 0));
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_symlink() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.mkdir("/foo".into(), vec![]).unwrap();
+        let bar = fs.create("/foo/bar".into(), vec![]).unwrap();
+        fs.symlink("/foo".into(), "/baz".into()).unwrap();
+        assert_eq!(
+            AliveNodes {
+                dirs: vec![
+                    (AbstractFS::root_index(), "/".into()),
+                    (foo, "/baz".into()),
+                    (foo, "/foo".into())
+                ],
+                files: vec![(bar, "/baz/bar".into()), (bar, "/foo/bar".into())],
+                files_symlinked: vec![],
+                symlinks: vec!["/baz".into()],
+                specials: vec![],
+            },
+            fs.alive()
+        );
+        assert_eq!(
+            Workload {
+                ops: vec![
+                    Operation::MkDir {
+                        path: "/foo".into(),
+                        mode: vec![]
+                    },
+                    Operation::Create {
+                        path: "/foo/bar".into(),
+                        mode: vec![]
+                    },
+                    Operation::Symlink {
+                        target: "/foo".into(),
+                        linkpath: "/baz".into(),
+                    }
+                ]
             },
             fs.recording
         );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_symlink_name_exists() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        fs.create("/bar".into(), vec![]).unwrap();
+        assert_eq!(
+            Err(FsError::NameAlreadyExists("/bar".into())),
+            fs.symlink("/foo".into(), "/bar".into()),
+        );
+    }
+
+    #[test]
+    fn test_symlink_recursion() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.symlink("/foo".into(), "/foo/bar".into()).unwrap();
         assert_eq!(
             AliveNodes {
                 dirs: vec![
                     (AbstractFS::root_index(), "/".into()),
-                    (foo, "/foobar".into())
+                    (foo, "/foo".into()),
+                    (foo, "/foo/bar".into()),
+                    (foo, "/foo/bar/bar".into()),
+                    // (foo, "/foo/bar/bar/bar".into())
+                ],
+                files: vec![],
+                files_symlinked: vec![],
+                symlinks: vec![
+                    "/foo/bar".into(),
+                    "/foo/bar/bar".into(),
+                    // "/foo/bar/bar/bar".into(),
                 ],
-                files: vec![],
-                files_symlinked: vec![],
-                symlinks: vec![],
+                specials: vec![],
             },
             fs.alive()
         );
-        test_replay(fs.recording);
     }
 
     #[test]
-    fn test_mkdir_name_exists() {
+    fn test_symlink_loop_in_path() {
         let mut fs = AbstractFS::new();
-        fs.mkdir("/foobar".into(), vec![]).unwrap();
+        fs.symlink("/foo/bar".into(), "/foo".into()).unwrap();
         assert_eq!(
-            Err(FsError::NameAlreadyExists("/foobar".into())),
-            fs.mkdir("/foobar".into(), vec![])
+            Err(FsError::LoopExists("/foo/bar".into())),
+            fs.create("/foo/baz".into(), vec![])
         );
     }
 
     #[test]
-    fn test_create() {
+    fn test_symlink_to_symlink() {
         let mut fs = AbstractFS::new();
-        let foo = fs.create("/foobar".into(), vec![]).unwrap();
-        assert_eq!(Node::File(foo), *fs.root().children.get("foobar").unwrap());
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        fs.symlink("/foo".into(), "/bar".into()).unwrap();
+        fs.symlink("/bar".into(), "/boo".into()).unwrap();
         assert_eq!(
             AliveNodes {
-                dirs: vec![(AbstractFS::root_index(), "/".into())],
-                files: vec![(foo, "/foobar".into())],
-                files_symlinked: vec![],
-                symlinks: vec![],
+                dirs: vec![(AbstractFS::root_index(), "/".into()),],
+                files: vec![(foo, "/foo".into()),],
+                files_symlinked: vec![(foo, "/bar".into()), (foo, "/boo".into())],
+                symlinks: vec!["/bar".into(), "/boo".into()],
+                specials: vec![],
             },
             fs.alive()
         );
-        assert_eq!(
-            Workload {
-                ops: vec![Operation::Create {
-                    path: "/foobar".into(),
-                    mode: vec![],
-                }]
-            },
-            fs.recording
-        );
-        test_replay(fs.recording);
-    }
-
-    #[test]
-    fn test_create_name_exists() {
-        let mut fs = AbstractFS::new();
-        fs.create("/foobar".into(), vec![]).unwrap();
-        assert_eq!(
-            Err(FsError::NameAlreadyExists("/foobar".into())),
-            fs.create("/foobar".into(), vec![])
-        );
     }
 
     #[test]
-    fn test_remove_file() {
+    fn test_symlink_to_symlink_recursive() {
         let mut fs = AbstractFS::new();
-        let foo = fs.create("/foobar".into(), vec![]).unwrap();
-        let boo = fs.create("/boo".into(), vec![]).unwrap();
-
+        fs.symlink("/foo".into(), "/bar".into()).unwrap();
+        fs.symlink("/bar".into(), "/foo".into()).unwrap();
         assert_eq!(
             AliveNodes {
-                dirs: vec![(AbstractFS::root_index(), "/".into())],
-                files: vec![(foo, "/foobar".into()), (boo, "/boo".into())],
+                dirs: vec![(AbstractFS::root_index(), "/".into()),],
+                files: vec![],
                 files_symlinked: vec![],
-                symlinks: vec![],
+                symlinks: vec!["/bar".into(), "/foo".into()],
+                specials: vec![],
             },
             fs.alive()
         );
+    }
 
-        fs.remove("/foobar".into()).unwrap();
-
-        assert_eq!(1, fs.root().children.len());
-        assert_eq!(Node::File(boo), *fs.root().children.get("boo").unwrap());
+    #[test]
+    fn test_remove_symlink() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        fs.symlink("/foo".into(), "/bar".into()).unwrap();
+        fs.remove("/bar".into()).unwrap();
         assert_eq!(
             AliveNodes {
                 dirs: vec![(AbstractFS::root_index(), "/".into())],
-                files: vec![(boo, "/boo".into())],
+                files: vec![(foo, "/foo".into())],
                 files_symlinked: vec![],
                 symlinks: vec![],
+                specials: vec![],
             },
             fs.alive()
         );
-        assert_eq!(
-            Workload {
-                ops: vec![
-                    Operation::Create {
-                        path: "/foobar".into(),
-                        mode: vec![],
-                    },
-                    Operation::Create {
-                        path: "/boo".into(),
-                        mode: vec![],
-                    },
-                    Operation::Remove {
-                        path: "/foobar".into(),
-                    }
-                ],
-            },
-            fs.recording
-        );
         test_replay(fs.recording);
     }
 
     #[test]
-    fn test_hardlink() {
+    fn test_rename_symlink() {
         let mut fs = AbstractFS::new();
         let foo = fs.create("/foo".into(), vec![]).unwrap();
-        let bar = fs.mkdir("/bar".into(), vec![]).unwrap();
-        let boo = fs.hardlink("/foo".into(), "/bar/boo".into()).unwrap();
-
-        assert_eq!(foo, boo);
+        fs.symlink("/foo".into(), "/bar".into()).unwrap();
+        fs.rename("/bar".into(), "/baz".into()).unwrap();
         assert_eq!(
             AliveNodes {
-                dirs: vec![(AbstractFS::root_index(), "/".into()), (bar, "/bar".into())],
-                files: vec![(boo, "/bar/boo".into()), (foo, "/foo".into())],
-                files_symlinked: vec![],
-                symlinks: vec![],
+                dirs: vec![(AbstractFS::root_index(), "/".into())],
+                files: vec![(foo, "/foo".into())],
+                files_symlinked: vec![(foo, "/baz".into())],
+                symlinks: vec!["/baz".into()],
+                specials: vec![],
             },
             fs.alive()
         );
-
-        let root = fs.root();
-        let bar_dir = fs.dir(&bar);
-        assert_eq!(2, root.children.len());
-        assert_eq!(1, bar_dir.children.len());
-        assert_eq!(
-            root.children.get("foo").unwrap(),
-            bar_dir.children.get("boo").unwrap()
-        );
-
-        assert_eq!(
-            Workload {
-                ops: vec![
-                    Operation::Create {
-                        path: "/foo".into(),
-                        mode: vec![],
-                    },
-                    Operation::MkDir {
-                        path: "/bar".into(),
-                        mode: vec![],
-                    },
-                    Operation::Hardlink {
-                        old_path: "/foo".into(),
-                        new_path: "/bar/boo".into(),
-                    }
-                ],
-            },
-            fs.recording
-        );
         test_replay(fs.recording);
     }
 
     #[test]
-    fn test_remove_hardlink() {
+    fn test_rename_symlink_overwrite() {
         let mut fs = AbstractFS::new();
         let foo = fs.create("/foo".into(), vec![]).unwrap();
-        fs.hardlink("/foo".into(), "/bar".into()).unwrap();
-        fs.remove("/bar".into()).unwrap();
-
+        fs.symlink("/foo".into(), "/bar".into()).unwrap();
+        fs.rename("/foo".into(), "/bar".into()).unwrap();
         assert_eq!(
             AliveNodes {
                 dirs: vec![(AbstractFS::root_index(), "/".into())],
-                files: vec![(foo, "/foo".into())],
+                files: vec![(foo, "/bar".into())],
                 files_symlinked: vec![],
                 symlinks: vec![],
+                specials: vec![],
             },
             fs.alive()
         );
-
-        assert_eq!(1, fs.root().children.len());
-
-        assert_eq!(
-            Workload {
-                ops: vec![
-                    Operation::Create {
-                        path: "/foo".into(),
-                        mode: vec![],
-                    },
-                    Operation::Hardlink {
-                        old_path: "/foo".into(),
-                        new_path: "/bar".into(),
-                    },
-                    Operation::Remove {
-                        path: "/bar".into(),
-                    }
-                ],
-            },
-            fs.recording
-        );
         test_replay(fs.recording);
     }
 
     #[test]
-    fn test_remove_hardlink_dir() {
+    fn test_open_tmpfile() {
         let mut fs = AbstractFS::new();
-        let root = AbstractFS::root_index();
-        let zero = fs.create("/0".into(), vec![]).unwrap();
-        let one = fs.mkdir("/1".into(), vec![]).unwrap();
-        let two = fs.mkdir("/1/2".into(), vec![]).unwrap();
-        fs.hardlink("/0".into(), "/1/2/3".into()).unwrap();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.create("/foo/bar".into(), vec![]).unwrap();
         assert_eq!(
-            Ok((vec![root, one, two], zero)),
-            fs.resolve_file("/1/2/3".into())
+            Err(FsError::InvalidOpenFlags("/foo".into())),
+            fs.open(
+                "/foo".into(),
+                vec![OpenFlag::O_RDONLY, OpenFlag::O_TMPFILE],
+                vec![]
+            )
         );
-        fs.remove("/1".into()).unwrap();
         assert_eq!(
-            Err(FsError::NotFound("/1".into())),
-            fs.resolve_file("/1/2/3".into())
+            Err(FsError::NotADir("/foo/bar".into())),
+            fs.open(
+                "/foo/bar".into(),
+                vec![OpenFlag::O_RDWR, OpenFlag::O_TMPFILE],
+                vec![]
+            )
         );
-        assert_eq!(Ok((vec![root], zero)), fs.resolve_file("/0".into()));
+        let des = fs
+            .open(
+                "/foo".into(),
+                vec![OpenFlag::O_RDWR, OpenFlag::O_TMPFILE],
+                vec![ModeFlag::S_IRUSR],
+            )
+            .unwrap();
+        fs.write(des, 0, 100).unwrap();
+        assert_eq!(1, fs.dir(&DirIndex(1)).children.len());
+        assert_eq!(1, fs.alive().files.len());
+        test_replay(fs.recording);
     }
 
     #[test]
-    fn test_hardlink_name_exists() {
+    fn test_flink() {
         let mut fs = AbstractFS::new();
-        fs.create("/foo".into(), vec![]).unwrap();
-        fs.create("/bar".into(), vec![]).unwrap();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open(
+                "/foo".into(),
+                vec![OpenFlag::O_WRONLY, OpenFlag::O_TMPFILE],
+                vec![],
+            )
+            .unwrap();
+        fs.write(des, 0, 100).unwrap();
+        let file = fs.flink(des, "/foo/bar".into()).unwrap();
         assert_eq!(
-            Err(FsError::NameAlreadyExists("/foo".into())),
-            fs.hardlink("/bar".into(), "/foo".into())
+            (
+                vec![AbstractFS::root_index(), DirIndex(1)],
+                Node::File(file)
+            ),
+            fs.resolve_node("/foo/bar".into(), false).unwrap()
+        );
+        assert_eq!(100, fs.file(&file).content.size());
+        fs.flink(des, "/baz".into()).unwrap();
+        assert_eq!(
+            Err(FsError::NameAlreadyExists("/baz".into())),
+            fs.flink(des, "/baz".into())
+        );
+        fs.remove("/baz".into()).unwrap();
+        fs.remove("/foo/bar".into()).unwrap();
+        assert_eq!(Err(FsError::NotLinkable(des)), fs.flink(des, "/boo".into()));
+        fs.close(des).unwrap();
+        assert_eq!(
+            Err(FsError::DescriptorWasClosed(des)),
+            fs.flink(des, "/boo".into())
         );
+        test_replay(fs.recording);
     }
 
     #[test]
-    fn test_remove_dir() {
+    fn test_flink_tmpfile_excl() {
         let mut fs = AbstractFS::new();
-        let foo = fs.mkdir("/foobar".into(), vec![]).unwrap();
-        let boo = fs.mkdir("/boo".into(), vec![]).unwrap();
+        let des = fs
+            .open(
+                "/".into(),
+                vec![OpenFlag::O_RDWR, OpenFlag::O_TMPFILE, OpenFlag::O_EXCL],
+                vec![],
+            )
+            .unwrap();
+        assert_eq!(Err(FsError::NotLinkable(des)), fs.flink(des, "/foo".into()));
+        fs.write(des, 0, 10).unwrap();
+        assert!(fs.dir(&AbstractFS::root_index()).children.is_empty());
+        test_replay(fs.recording);
+    }
 
+    #[test]
+    fn test_mknod() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.mkdir("/foo".into(), vec![]).unwrap();
+        let fifo = fs
+            .mknod(
+                "/foo/fifo".into(),
+                vec![ModeFlag::S_IRWXU, ModeFlag::S_IRWXO],
+                FileType::S_IFIFO,
+            )
+            .unwrap();
+        let chr = fs.mknod("/chr".into(), vec![], FileType::S_IFCHR).unwrap();
+        assert_eq!(
+            Err(FsError::NameAlreadyExists("/chr".into())),
+            fs.mknod("/chr".into(), vec![], FileType::S_IFSOCK)
+        );
         assert_eq!(
             AliveNodes {
-                dirs: vec![
-                    (AbstractFS::root_index(), "/".into()),
-                    (foo, "/foobar".into()),
-                    (boo, "/boo".into()),
-                ],
+                dirs: vec![(AbstractFS::root_index(), "/".into()), (foo, "/foo".into())],
                 files: vec![],
                 files_symlinked: vec![],
                 symlinks: vec![],
+                specials: vec![(fifo, "/foo/fifo".into()), (chr, "/chr".into())],
             },
             fs.alive()
         );
+        assert_eq!(FileType::S_IFIFO, fs.special(&fifo).file_type);
+        assert_eq!(0o705, fs.resolve_metadata("/foo/fifo".into()).unwrap().mode);
+        assert_eq!(
+            Err(FsError::NotAFile("/chr".into())),
+            fs.open("/chr".into(), vec![OpenFlag::O_RDONLY], vec![])
+        );
+        test_replay(fs.recording);
+    }
 
-        fs.remove("/foobar".into()).unwrap();
+    #[test]
+    fn test_readdir() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.mkdir("/foo".into(), vec![]).unwrap();
+        let zoo = fs.create("/foo/zoo".into(), vec![]).unwrap();
+        let fifo = fs
+            .mknod("/foo/bar".into(), vec![], FileType::S_IFIFO)
+            .unwrap();
+        let link = fs.symlink("/foo".into(), "/link".into()).unwrap();
+        assert_eq!(
+            Ok(vec![
+                ("bar".into(), Node::Special(fifo)),
+                ("zoo".into(), Node::File(zoo)),
+            ]),
+            fs.readdir("/link".into())
+        );
+        assert_eq!(
+            Ok(vec![
+                ("foo".into(), Node::Dir(foo)),
+                ("link".into(), Node::Symlink(link))
+            ]),
+            fs.readdir("/".into())
+        );
+        assert_eq!(
+            Err(FsError::NotADir("/foo/zoo".into())),
+            fs.readdir("/foo/zoo".into())
+        );
+        test_replay(fs.recording);
+    }
 
-        assert_eq!(1, fs.root().children.len());
-        assert_eq!(Node::Dir(boo), *fs.root().children.get("boo").unwrap());
+    #[test]
+    fn test_stat() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![ModeFlag::S_IRWXU]).unwrap();
+        fs.create("/bar".into(), vec![ModeFlag::S_IRUSR, ModeFlag::S_IWUSR])
+            .unwrap();
+        let des = fs
+            .open("/bar".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.write(des, 0, 1000).unwrap();
+        fs.hardlink("/bar".into(), "/foo/baz".into()).unwrap();
+        fs.symlink("/foo".into(), "/link".into()).unwrap();
+        let bar = Stat {
+            file_type: S_IFREG,
+            mode: 0o600,
+            nlink: 2,
+            size: 1000,
+        };
+        assert_eq!(Ok(bar), fs.stat("/bar".into()));
+        assert_eq!(Ok(bar), fs.fstat(des));
+        let foo = Stat {
+            file_type: S_IFDIR,
+            mode: 0o700,
+            nlink: 0,
+            size: 0,
+        };
+        assert_eq!(Ok(foo), fs.stat("/link".into()));
         assert_eq!(
-            AliveNodes {
-                dirs: vec![(AbstractFS::root_index(), "/".into()), (boo, "/boo".into())],
-                files: vec![],
-                files_symlinked: vec![],
-                symlinks: vec![],
-            },
-            fs.alive()
+            Ok(Stat {
+                file_type: S_IFLNK,
+                mode: 0o777,
+                nlink: 1,
+                size: 4,
+            }),
+            fs.lstat("/link".into())
         );
+        fs.remove("/bar".into()).unwrap();
+        fs.remove("/foo/baz".into()).unwrap();
+        assert_eq!(Ok(Stat { nlink: 0, ..bar }), fs.fstat(des));
         assert_eq!(
-            Workload {
-                ops: vec![
-                    Operation::MkDir {
-                        path: "/foobar".into(),
-                        mode: vec![],
-                    },
-                    Operation::MkDir {
-                        path: "/boo".into(),
-                        mode: vec![],
-                    },
-                    Operation::Remove {
-                        path: "/foobar".into(),
-                    }
-                ],
-            },
-            fs.recording
+            Err(FsError::NotFound("/bar".into())),
+            fs.stat("/bar".into())
         );
         test_replay(fs.recording);
     }
 
     #[test]
-    fn test_remove_twice() {
+    fn test_special_link_rename_remove() {
         let mut fs = AbstractFS::new();
-        fs.create("/0".into(), vec![]).unwrap();
-        fs.remove("/0".into()).unwrap();
-        assert_eq!(Err(FsError::NotFound("/0".into())), fs.remove("/0".into()))
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        let sock = fs
+            .mknod("/sock".into(), vec![], FileType::S_IFSOCK)
+            .unwrap();
+        assert_eq!(
+            Ok(Node::Special(sock)),
+            fs.hardlink("/sock".into(), "/foo/sock".into())
+        );
+        assert_eq!(
+            Err(FsError::IsADir("/foo".into())),
+            fs.rename("/sock".into(), "/foo".into())
+        );
+        assert_eq!(
+            Err(FsError::NotADir("/sock".into())),
+            fs.rename("/foo".into(), "/sock".into())
+        );
+        fs.create("/bar".into(), vec![]).unwrap();
+        fs.rename("/sock".into(), "/bar".into()).unwrap();
+        fs.remove("/foo/sock".into()).unwrap();
+        assert_eq!(vec![(sock, "/bar".into())], fs.alive().specials);
+        fs.remove("/bar".into()).unwrap();
+        assert!(fs.alive().specials.is_empty());
+        test_replay(fs.recording);
     }
 
     #[test]
-    fn test_rename_file() {
+    fn test_renameat2_noreplace() {
         let mut fs = AbstractFS::new();
         let foo = fs.create("/foo".into(), vec![]).unwrap();
-        fs.rename("/foo".into(), "/bar".into()).unwrap();
+        fs.create("/bar".into(), vec![]).unwrap();
         assert_eq!(
-            AliveNodes {
-                dirs: vec![(AbstractFS::root_index(), "/".into())],
-                files: vec![(foo, "/bar".into())],
-                files_symlinked: vec![],
-                symlinks: vec![],
-            },
-            fs.alive()
+            Err(FsError::NameAlreadyExists("/bar".into())),
+            fs.renameat2(
+                "/foo".into(),
+                "/bar".into(),
+                vec![RenameFlag::RENAME_NOREPLACE]
+            )
         );
         assert_eq!(
-            Workload {
-                ops: vec![
-                    Operation::Create {
-                        path: "/foo".into(),
-                        mode: vec![]
-                    },
-                    Operation::Rename {
-                        old_path: "/foo".into(),
-                        new_path: "/bar".into(),
-                    }
-                ]
-            },
-            fs.recording
+            Err(FsError::InvalidRenameFlags(vec![
+                RenameFlag::RENAME_NOREPLACE,
+                RenameFlag::RENAME_EXCHANGE
+            ])),
+            fs.renameat2(
+                "/foo".into(),
+                "/baz".into(),
+                vec![RenameFlag::RENAME_NOREPLACE, RenameFlag::RENAME_EXCHANGE]
+            )
+        );
+        fs.renameat2(
+            "/foo".into(),
+            "/baz".into(),
+            vec![RenameFlag::RENAME_NOREPLACE],
+        )
+        .unwrap();
+        assert_eq!(
+            (vec![AbstractFS::root_index()], Node::File(foo)),
+            fs.resolve_node("/baz".into(), false).unwrap()
         );
         test_replay(fs.recording);
     }
 
     #[test]
-    fn test_rename_dir() {
+    fn test_renameat2_whiteout() {
         let mut fs = AbstractFS::new();
         let foo = fs.mkdir("/foo".into(), vec![]).unwrap();
-        fs.rename("/foo".into(), "/bar".into()).unwrap();
+        fs.create("/bar".into(), vec![]).unwrap();
+        let whiteout = vec![RenameFlag::RENAME_WHITEOUT];
         assert_eq!(
-            AliveNodes {
-                dirs: vec![(AbstractFS::root_index(), "/".into()), (foo, "/bar".into())],
-                files: vec![],
-                files_symlinked: vec![],
-                symlinks: vec![],
-            },
-            fs.alive()
+            Err(FsError::InvalidRenameFlags(vec![
+                RenameFlag::RENAME_WHITEOUT,
+                RenameFlag::RENAME_EXCHANGE
+            ])),
+            fs.renameat2(
+                "/foo".into(),
+                "/bar".into(),
+                vec![RenameFlag::RENAME_WHITEOUT, RenameFlag::RENAME_EXCHANGE]
+            )
         );
+        fs.renameat2("/bar".into(), "/bar".into(), whiteout.clone())
+            .unwrap();
+        assert!(fs.alive().specials.is_empty());
+        fs.renameat2("/foo".into(), "/baz".into(), whiteout)
+            .unwrap();
         assert_eq!(
-            Workload {
-                ops: vec![
-                    Operation::MkDir {
-                        path: "/foo".into(),
-                        mode: vec![]
-                    },
-                    Operation::Rename {
-                        old_path: "/foo".into(),
-                        new_path: "/bar".into(),
-                    }
-                ]
-            },
-            fs.recording
+            Node::Dir(foo),
+            fs.resolve_node("/baz".into(), false).unwrap().1
         );
+        let (_, Node::Special(idx)) = fs.resolve_node("/foo".into(), false).unwrap() else {
+            panic!("whiteout is not created");
+        };
+        assert_eq!(FileType::S_IFCHR, fs.specials[idx.0].file_type);
+        assert_eq!(0, fs.specials[idx.0].meta.mode);
         test_replay(fs.recording);
     }
 
     #[test]
-    fn test_rename_dir_non_empty() {
+    fn test_renameat2_exchange() {
         let mut fs = AbstractFS::new();
-        fs.mkdir("/foo".into(), vec![]).unwrap();
-        fs.mkdir("/bar".into(), vec![]).unwrap();
-        fs.create("/bar/baz".into(), vec![]).unwrap();
+        let foo = fs.mkdir("/foo".into(), vec![]).unwrap();
+        let bar = fs.create("/foo/bar".into(), vec![]).unwrap();
+        let baz = fs.symlink("/foo".into(), "/baz".into()).unwrap();
+        let exchange = vec![RenameFlag::RENAME_EXCHANGE];
         assert_eq!(
-            Err(FsError::DirNotEmpty("/bar".into())),
-            fs.rename("/foo".into(), "/bar".into())
+            Err(FsError::NotFound("/boo".into())),
+            fs.renameat2("/foo".into(), "/boo".into(), exchange.clone())
         );
-        fs.remove("/bar/baz".into()).unwrap();
-        fs.rename("/foo".into(), "/bar".into()).unwrap();
-    }
-
-    #[test]
-    fn test_rename_to_subdirectory() {
-        let mut fs = AbstractFS::new();
-        fs.mkdir("/0".into(), vec![]).unwrap();
         assert_eq!(
             Err(FsError::RenameToSubdirectoryError(
-                "/0".into(),
-                "/0/1".into()
+                "/foo".into(),
+                "/foo/bar".into()
             )),
-            fs.rename("/0".into(), "/0/1".into())
+            fs.renameat2("/foo".into(), "/foo/bar".into(), exchange.clone())
         );
-    }
-
-    #[test]
-    fn test_rename_to_subdirectory_symlink() {
-        let mut fs = AbstractFS::new();
-        fs.mkdir("/0".into(), vec![]).unwrap();
-        fs.symlink("/0".into(), "/symlink".into()).unwrap();
         assert_eq!(
             Err(FsError::RenameToSubdirectoryError(
-                "/0".into(),
-                "/symlink/1".into()
+                "/foo".into(),
+                "/foo/bar".into()
             )),
-            fs.rename("/0".into(), "/symlink/1".into())
+            fs.renameat2("/foo/bar".into(), "/foo".into(), exchange.clone())
+        );
+        fs.renameat2("/foo/bar".into(), "/baz".into(), exchange.clone())
+            .unwrap();
+        fs.renameat2("/foo".into(), "/foo".into(), exchange.clone())
+            .unwrap();
+        assert_eq!(
+            Node::Symlink(baz),
+            fs.resolve_node("/foo/bar".into(), false).unwrap().1
+        );
+        assert_eq!(
+            Node::File(bar),
+            fs.resolve_node("/baz".into(), false).unwrap().1
         );
+        assert_eq!(
+            Node::Dir(foo),
+            fs.resolve_node("/foo".into(), false).unwrap().1
+        );
+        test_replay(fs.recording);
     }
 
     #[test]
-    fn test_open_close_file() {
+    fn test_hardlink_to_symlink() {
         let mut fs = AbstractFS::new();
-        let foo = fs.create("/foo".into(), vec![]).unwrap();
-        let des = fs.open("/foo".into()).unwrap();
-        let file = fs.file(&foo);
-        assert_eq!(Some(des), file.descriptor);
-        fs.close(des).unwrap();
-        let file = fs.file(&foo);
-        assert_eq!(None, file.descriptor);
+        fs.create("/foo".into(), vec![]).unwrap();
+        fs.symlink("/foo".into(), "/bar".into()).unwrap();
         assert_eq!(
-            Workload {
-                ops: vec![
-                    Operation::Create {
-                        path: "/foo".into(),
-                        mode: vec![]
-                    },
-                    Operation::Open {
-                        path: "/foo".into(),
-                        des
-                    },
-                    Operation::Close { des }
-                ]
-            },
-            fs.recording
+            Err(FsError::HardlinkToSymlinkForbidden("/bar".into())),
+            fs.hardlink("/bar".into(), "/boo".into())
         );
         test_replay(fs.recording);
     }
 
     #[test]
-    fn test_close_bad_descriptor() {
+    fn test_resolve_node() {
         let mut fs = AbstractFS::new();
-        let des = FileDescriptorIndex(0);
-        assert_eq!(Err(FsError::BadDescriptor(des, 0)), fs.close(des));
+        let root = AbstractFS::root_index();
+        assert_eq!(
+            (vec![], Node::Dir(root)),
+            fs.resolve_node("/".into(), true).unwrap()
+        );
+        let foo = fs.mkdir("/foo".into(), vec![]).unwrap();
+        let bar = fs.mkdir("/foo/bar".into(), vec![]).unwrap();
+        let boo = fs.create("/foo/bar/boo".into(), vec![]).unwrap();
+        assert_eq!(
+            Err(FsError::InvalidPath("".into())),
+            fs.resolve_node("".into(), true)
+        );
+        assert_eq!(
+            Err(FsError::InvalidPath("foo".into())),
+            fs.resolve_node("foo".into(), true)
+        );
+        assert_eq!(
+            Err(FsError::InvalidPath("/foo/".into())),
+            fs.resolve_node("/foo/".into(), true)
+        );
+        assert_eq!(
+            (vec![root], Node::Dir(foo)),
+            fs.resolve_node("/foo".into(), true).unwrap()
+        );
+        assert_eq!(
+            (vec![root, foo], Node::Dir(bar)),
+            fs.resolve_node("/foo/bar".into(), true).unwrap()
+        );
+        assert_eq!(
+            (vec![root, foo, bar], Node::File(boo)),
+            fs.resolve_node("/foo/bar/boo".into(), true).unwrap()
+        );
+        test_replay(fs.recording);
     }
 
     #[test]
-    fn test_close_twice() {
+    fn test_symlink_relative() {
         let mut fs = AbstractFS::new();
-        fs.create("/foo".into(), vec![]).unwrap();
-        let des = fs.open("/foo".into()).unwrap();
-        fs.close(des).unwrap();
-        assert_eq!(Err(FsError::DescriptorWasClosed(des)), fs.close(des));
+        let root = AbstractFS::root_index();
+        let foo = fs.mkdir("/foo".into(), vec![]).unwrap();
+        let bar = fs.mkdir("/foo/bar".into(), vec![]).unwrap();
+        let baz = fs.create("/foo/baz".into(), vec![]).unwrap();
+        fs.symlink("../baz".into(), "/foo/bar/up".into()).unwrap();
+        fs.symlink("foo/./bar".into(), "/down".into()).unwrap();
+        fs.symlink(".".into(), "/foo/bar/self".into()).unwrap();
+        assert_eq!(
+            (vec![root, foo], Node::File(baz)),
+            fs.resolve_node("/foo/bar/up".into(), true).unwrap()
+        );
+        assert_eq!(
+            (vec![root, foo], Node::Dir(bar)),
+            fs.resolve_node("/down/self/self".into(), true).unwrap()
+        );
+        // `..` in target is resolved from the real parent, not from the path with symlinks.
+        assert_eq!(
+            (vec![root, foo], Node::File(baz)),
+            fs.resolve_node("/down/up".into(), true).unwrap()
+        );
+        // Relative target of moved link refers to another node.
+        fs.rename("/foo/bar/up".into(), "/up".into()).unwrap();
+        assert_eq!(
+            Err(FsError::OutsideWorkspace("/..".into())),
+            fs.resolve_node("/up".into(), true)
+        );
+        test_replay(fs.recording);
     }
 
     #[test]
-    fn test_open_twice() {
+    fn test_symlink_dangling_outside() {
         let mut fs = AbstractFS::new();
-        fs.create("/foo".into(), vec![]).unwrap();
-        fs.open("/foo".into()).unwrap();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.symlink("/foo/bar".into(), "/dangling".into()).unwrap();
+        fs.symlink("../../foo".into(), "/foo/outside".into())
+            .unwrap();
+        assert_eq!(
+            Err(FsError::NotFound("/foo/bar".into())),
+            fs.create("/dangling/baz".into(), vec![])
+        );
+        assert_eq!(
+            Err(FsError::OutsideWorkspace("/../..".into())),
+            fs.mkdir("/foo/outside/baz".into(), vec![])
+        );
+        let bar = fs.create("/foo/bar".into(), vec![]).unwrap();
         assert_eq!(
-            Err(FsError::FileAlreadyOpened("/foo".into())),
-            fs.open("/foo".into())
+            Ok((vec![AbstractFS::root_index(), DirIndex(1)], Node::File(bar))),
+            fs.resolve_node("/dangling".into(), true)
         );
+        test_replay(fs.recording);
     }
 
     #[test]
-    fn test_read_bad_descriptor() {
+    fn test_readlink() {
         let mut fs = AbstractFS::new();
-        let des = FileDescriptorIndex(0);
-        assert_eq!(Err(FsError::BadDescriptor(des, 0)), fs.read(des, 0));
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.symlink("/foo".into(), "/abs".into()).unwrap();
+        fs.symlink("../abs/.".into(), "/foo/rel".into()).unwrap();
+        assert_eq!(Ok("/foo".into()), fs.readlink("/abs".into()));
+        assert_eq!(Ok("../abs/.".into()), fs.readlink("/abs/rel".into()));
+        assert_eq!(
+            Err(FsError::NotASymlink("/foo".into())),
+            fs.readlink("/foo".into())
+        );
+        assert_eq!(
+            Err(FsError::NotFound("/bar".into())),
+            fs.readlink("/bar".into())
+        );
+        test_replay(fs.recording);
     }
 
     #[test]
-    fn test_read_closed() {
+    fn test_resolve_node_symlinks() {
         let mut fs = AbstractFS::new();
-        fs.create("/foo".into(), vec![]).unwrap();
-        let des = fs.open("/foo".into()).unwrap();
-        fs.close(des).unwrap();
-        assert_eq!(Err(FsError::DescriptorWasClosed(des)), fs.read(des, 0));
+        let root = AbstractFS::root_index();
+        let foo = fs.mkdir("/foo".into(), vec![]).unwrap();
+        let bar = fs.create("/foo/bar".into(), vec![]).unwrap();
+        let foos = fs.symlink("/foo".into(), "/foos".into()).unwrap();
+        assert_eq!(
+            (vec![root], Node::Dir(foo)),
+            fs.resolve_node("/foo".into(), true).unwrap()
+        );
+        assert_eq!(
+            (vec![root], Node::Dir(foo)),
+            fs.resolve_node("/foos".into(), true).unwrap()
+        );
+        assert_eq!(
+            (vec![root], Node::Symlink(foos)),
+            fs.resolve_node("/foos".into(), false).unwrap()
+        );
+        assert_eq!(
+            (vec![root, foo], Node::File(bar)),
+            fs.resolve_node("/foo/bar".into(), true).unwrap()
+        );
+        assert_eq!(
+            (vec![root, foo], Node::File(bar)),
+            fs.resolve_node("/foos/bar".into(), true).unwrap()
+        );
+        // Always follow symlinks in dirname part of the path.
+        assert_eq!(
+            (vec![root, foo], Node::File(bar)),
+            fs.resolve_node("/foos/bar".into(), false).unwrap()
+        );
+        test_replay(fs.recording);
     }
 
     #[test]
-    fn test_read_empty() {
+    fn test_truncate() {
         let mut fs = AbstractFS::new();
-        fs.create("/foo".into(), vec![]).unwrap();
-        let des = fs.open("/foo".into()).unwrap();
-        let content = fs.read(des, 1024).unwrap();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.write(des, 13, 100).unwrap();
         fs.close(des).unwrap();
-
-        assert_eq!(Content::new(), content);
+        fs.truncate("/foo".into(), 10).unwrap();
+        assert_eq!(
+            vec![Slice::Data(SourceSlice {
+                from: 13,
+                to: 13 + 10 - 1
+            })],
+            fs.file(&foo).content.slices()
+        );
+        fs.truncate("/foo".into(), 30).unwrap();
+        assert_eq!(
+            vec![
+                Slice::Data(SourceSlice {
+                    from: 13,
+                    to: 13 + 10 - 1
+                }),
+                Slice::Hole(20)
+            ],
+            fs.file(&foo).content.slices()
+        );
         assert_eq!(
             Workload {
                 ops: vec![
@@ -1090,10 +5861,24 @@ mod tests {
                     },
                     Operation::Open {
                         path: "/foo".into(),
+                        flags: vec![OpenFlag::O_RDWR],
+                        mode: vec![],
                         des
                     },
-                    Operation::Read { des, size: 1024 },
+                    Operation::Write {
+                        des,
+                        src_offset: 13,
+                        size: 100
+                    },
                     Operation::Close { des },
+                    Operation::Truncate {
+                        path: "/foo".into(),
+                        size: 10
+                    },
+                    Operation::Truncate {
+                        path: "/foo".into(),
+                        size: 30
+                    },
                 ]
             },
             fs.recording
@@ -1102,37 +5887,69 @@ mod tests {
     }
 
     #[test]
-    fn test_write_bad_descriptor() {
+    fn test_truncate_dir() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        assert_eq!(
+            Err(FsError::NotAFile("/foo".into())),
+            fs.truncate("/foo".into(), 0)
+        );
+    }
+
+    #[test]
+    fn test_truncate_symlink() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        fs.symlink("/foo".into(), "/bar".into()).unwrap();
+        fs.truncate("/bar".into(), 42).unwrap();
+        assert_eq!(42, fs.file(&foo).content.size());
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_ftruncate_bad_descriptor() {
         let mut fs = AbstractFS::new();
         let des = FileDescriptorIndex(0);
-        assert_eq!(Err(FsError::BadDescriptor(des, 0)), fs.write(des, 0, 0));
+        assert_eq!(Err(FsError::BadDescriptor(des, 0)), fs.ftruncate(des, 0));
     }
 
     #[test]
-    fn test_write_closed() {
+    fn test_ftruncate_closed() {
         let mut fs = AbstractFS::new();
         fs.create("/foo".into(), vec![]).unwrap();
-        let des = fs.open("/foo".into()).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
         fs.close(des).unwrap();
-        assert_eq!(Err(FsError::DescriptorWasClosed(des)), fs.write(des, 0, 0));
+        assert_eq!(Err(FsError::DescriptorWasClosed(des)), fs.ftruncate(des, 0));
     }
 
     #[test]
-    fn test_write() {
+    fn test_ftruncate_offset_past_end() {
         let mut fs = AbstractFS::new();
         let foo = fs.create("/foo".into(), vec![]).unwrap();
-        let des = fs.open("/foo".into()).unwrap();
-        fs.write(des, 999, 1024).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.write(des, 13, 100).unwrap();
+        fs.ftruncate(des, 10).unwrap();
+        assert_eq!(Content::new(), fs.read(des, 1024).unwrap());
+        fs.write(des, 42, 5).unwrap();
         fs.close(des).unwrap();
-
         assert_eq!(
-            vec![SourceSlice {
-                from: 999,
-                to: 999 + 1024 - 1
-            }],
+            vec![
+                Slice::Data(SourceSlice {
+                    from: 13,
+                    to: 13 + 10 - 1
+                }),
+                Slice::Hole(90),
+                Slice::Data(SourceSlice {
+                    from: 42,
+                    to: 42 + 5 - 1
+                }),
+            ],
             fs.file(&foo).content.slices()
         );
-
         assert_eq!(
             Workload {
                 ops: vec![
@@ -1142,12 +5959,21 @@ mod tests {
                     },
                     Operation::Open {
                         path: "/foo".into(),
+                        flags: vec![OpenFlag::O_RDWR],
+                        mode: vec![],
                         des
                     },
                     Operation::Write {
                         des,
-                        src_offset: 999,
-                        size: 1024
+                        src_offset: 13,
+                        size: 100
+                    },
+                    Operation::FTruncate { des, size: 10 },
+                    Operation::Read { des, size: 1024 },
+                    Operation::Write {
+                        des,
+                        src_offset: 42,
+                        size: 5
                     },
                     Operation::Close { des },
                 ]
@@ -1158,106 +5984,111 @@ mod tests {
     }
 
     #[test]
-    fn test_write_rewrite() {
+    fn test_lseek() {
         let mut fs = AbstractFS::new();
         let foo = fs.create("/foo".into(), vec![]).unwrap();
-        let des_1 = fs.open("/foo".into()).unwrap();
-        fs.write(des_1, 13, 100).unwrap();
-        fs.close(des_1).unwrap();
-        let des_2 = fs.open("/foo".into()).unwrap();
-        fs.write(des_2, 42, 55).unwrap();
-        fs.close(des_2).unwrap();
-
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.write(des, 13, 100).unwrap();
+        assert_eq!(Ok(10), fs.lseek(des, 10, SeekWhence::SEEK_SET));
+        assert_eq!(Ok(15), fs.lseek(des, 5, SeekWhence::SEEK_CUR));
+        assert_eq!(Ok(90), fs.lseek(des, -10, SeekWhence::SEEK_END));
+        assert_eq!(Ok(110), fs.lseek(des, 10, SeekWhence::SEEK_END));
+        fs.write(des, 42, 5).unwrap();
+        fs.close(des).unwrap();
         assert_eq!(
             vec![
-                SourceSlice {
-                    from: 42,
-                    to: 42 + 55 - 1
-                },
-                SourceSlice {
-                    from: 13 + 55,
+                Slice::Data(SourceSlice {
+                    from: 13,
                     to: 13 + 100 - 1
-                }
+                }),
+                Slice::Hole(10),
+                Slice::Data(SourceSlice {
+                    from: 42,
+                    to: 42 + 5 - 1
+                }),
             ],
             fs.file(&foo).content.slices()
         );
+        test_replay(fs.recording);
+    }
 
+    #[test]
+    fn test_lseek_negative() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
         assert_eq!(
-            Workload {
-                ops: vec![
-                    Operation::Create {
-                        path: "/foo".into(),
-                        mode: vec![]
-                    },
-                    Operation::Open {
-                        path: "/foo".into(),
-                        des: des_1
-                    },
-                    Operation::Write {
-                        des: des_1,
-                        src_offset: 13,
-                        size: 100
-                    },
-                    Operation::Close { des: des_1 },
-                    Operation::Open {
-                        path: "/foo".into(),
-                        des: des_2
-                    },
-                    Operation::Write {
-                        des: des_2,
-                        src_offset: 42,
-                        size: 55
-                    },
-                    Operation::Close { des: des_2 },
-                ]
-            },
-            fs.recording
+            Err(FsError::NegativeOffset(-1)),
+            fs.lseek(des, -1, SeekWhence::SEEK_SET)
+        );
+        assert_eq!(
+            Err(FsError::NegativeOffset(-1)),
+            fs.lseek(des, -1, SeekWhence::SEEK_END)
         );
-        test_replay(fs.recording);
     }
 
     #[test]
-    fn test_read() {
+    fn test_lseek_data_hole() {
         let mut fs = AbstractFS::new();
         fs.create("/foo".into(), vec![]).unwrap();
-        let des_write = fs.open("/foo".into()).unwrap();
-        fs.write(des_write, 13, 100).unwrap();
-        fs.write(des_write, 42, 55).unwrap();
-        fs.close(des_write).unwrap();
-        let des_read = fs.open("/foo".into()).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.ftruncate(des, 100).unwrap();
+        fs.pwrite(des, 13, 10, 50).unwrap();
+        assert_eq!(Ok(50), fs.lseek(des, 0, SeekWhence::SEEK_DATA));
+        assert_eq!(Ok(60), fs.lseek(des, 55, SeekWhence::SEEK_HOLE));
         assert_eq!(
-            Vec::<SourceSlice>::new(),
-            fs.read(des_read, 0).unwrap().slices()
+            Err(FsError::NothingToSeek(60)),
+            fs.lseek(des, 60, SeekWhence::SEEK_DATA)
         );
         assert_eq!(
-            vec![SourceSlice {
-                from: 13,
-                to: 13 + 10 - 1
-            }],
-            fs.read(des_read, 10).unwrap().slices()
+            Err(FsError::NothingToSeek(100)),
+            fs.lseek(des, 100, SeekWhence::SEEK_HOLE)
         );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_pread_pwrite() {
+        let mut fs = AbstractFS::new();
+        let foo = fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.write(des, 13, 100).unwrap();
+        fs.pwrite(des, 42, 10, 20).unwrap();
         assert_eq!(
-            vec![SourceSlice {
-                from: (13 + 10),
-                to: (13 + 10) + 10 - 1
-            }],
-            fs.read(des_read, 10).unwrap().slices()
+            vec![Slice::Data(SourceSlice {
+                from: 42 + 5,
+                to: 42 + 10 - 1
+            })],
+            fs.pread(des, 5, 25).unwrap().slices()
         );
+        assert_eq!(Content::new(), fs.pread(des, 5, 1000).unwrap());
+        assert_eq!(Ok(100), fs.lseek(des, 0, SeekWhence::SEEK_CUR));
+        fs.close(des).unwrap();
         assert_eq!(
             vec![
-                SourceSlice {
-                    from: (13 + 20),
-                    to: 13 + 100 - 1
-                },
-                SourceSlice {
+                Slice::Data(SourceSlice {
+                    from: 13,
+                    to: 13 + 20 - 1
+                }),
+                Slice::Data(SourceSlice {
                     from: 42,
-                    to: 42 + 55 - 1
-                },
+                    to: 42 + 10 - 1
+                }),
+                Slice::Data(SourceSlice {
+                    from: 13 + 30,
+                    to: 13 + 100 - 1
+                }),
             ],
-            fs.read(des_read, 1024).unwrap().slices()
+            fs.file(&foo).content.slices()
         );
-        fs.close(des_read).unwrap();
-
         assert_eq!(
             Workload {
                 ops: vec![
@@ -1267,40 +6098,37 @@ mod tests {
                     },
                     Operation::Open {
                         path: "/foo".into(),
-                        des: des_write
+                        flags: vec![OpenFlag::O_RDWR],
+                        mode: vec![],
+                        des
                     },
                     Operation::Write {
-                        des: des_write,
+                        des,
                         src_offset: 13,
                         size: 100
                     },
-                    Operation::Write {
-                        des: des_write,
+                    Operation::PWrite {
+                        des,
                         src_offset: 42,
-                        size: 55
-                    },
-                    Operation::Close { des: des_write },
-                    Operation::Open {
-                        path: "/foo".into(),
-                        des: des_read
-                    },
-                    Operation::Read {
-                        des: des_read,
-                        size: 0
+                        size: 10,
+                        offset: 20
                     },
-                    Operation::Read {
-                        des: des_read,
-                        size: 10
+                    Operation::PRead {
+                        des,
+                        size: 5,
+                        offset: 25
                     },
-                    Operation::Read {
-                        des: des_read,
-                        size: 10
+                    Operation::PRead {
+                        des,
+                        size: 5,
+                        offset: 1000
                     },
-                    Operation::Read {
-                        des: des_read,
-                        size: 1024
+                    Operation::LSeek {
+                        des,
+                        offset: 0,
+                        whence: SeekWhence::SEEK_CUR
                     },
-                    Operation::Close { des: des_read },
+                    Operation::Close { des },
                 ]
             },
             fs.recording
@@ -1309,295 +6137,816 @@ mod tests {
     }
 
     #[test]
-    fn test_fsync_bad_descriptor() {
+    fn test_pwrite_closed() {
         let mut fs = AbstractFS::new();
-        let des = FileDescriptorIndex(0);
-        assert_eq!(Err(FsError::BadDescriptor(des, 0)), fs.fsync(des));
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.close(des).unwrap();
+        assert_eq!(
+            Err(FsError::DescriptorWasClosed(des)),
+            fs.pwrite(des, 0, 0, 0)
+        );
+        assert_eq!(Err(FsError::DescriptorWasClosed(des)), fs.pread(des, 0, 0));
+        assert_eq!(
+            Err(FsError::DescriptorWasClosed(des)),
+            fs.lseek(des, 0, SeekWhence::SEEK_SET)
+        );
     }
 
     #[test]
-    fn test_fsync_closed() {
+    fn test_setxattr_getxattr() {
         let mut fs = AbstractFS::new();
         fs.create("/foo".into(), vec![]).unwrap();
-        let des = fs.open("/foo".into()).unwrap();
-        fs.close(des).unwrap();
-        assert_eq!(Err(FsError::DescriptorWasClosed(des)), fs.fsync(des));
+        fs.symlink("/foo".into(), "/bar".into()).unwrap();
+        fs.setxattr("/foo".into(), "user.a".into(), 13, 100, vec![])
+            .unwrap();
+        let mut expected = Content::new();
+        expected.write_back(13, 100);
+        assert_eq!(
+            expected,
+            fs.getxattr("/bar".into(), "user.a".into(), 0).unwrap()
+        );
+        assert_eq!(
+            Err(FsError::XattrBufferTooSmall("/foo".into(), 99)),
+            fs.getxattr("/foo".into(), "user.a".into(), 99)
+        );
+        fs.setxattr("/bar".into(), "user.a".into(), 42, 10, vec![])
+            .unwrap();
+        let mut expected = Content::new();
+        expected.write_back(42, 10);
+        assert_eq!(
+            expected,
+            fs.getxattr("/foo".into(), "user.a".into(), 10).unwrap()
+        );
+        assert_eq!(
+            Err(FsError::XattrNotFound("/foo".into(), "user.b".into())),
+            fs.getxattr("/foo".into(), "user.b".into(), 0)
+        );
+        test_replay(fs.recording);
     }
 
     #[test]
-    fn test_fsync() {
+    fn test_setxattr_flags() {
         let mut fs = AbstractFS::new();
-        fs.create("/foo".into(), vec![]).unwrap();
-        let des = fs.open("/foo".into()).unwrap();
-        fs.fsync(des).unwrap();
-        fs.close(des).unwrap();
-
+        fs.mkdir("/foo".into(), vec![]).unwrap();
         assert_eq!(
-            Workload {
-                ops: vec![
-                    Operation::Create {
-                        path: "/foo".into(),
-                        mode: vec![]
-                    },
-                    Operation::Open {
-                        path: "/foo".into(),
-                        des
-                    },
-                    Operation::FSync { des },
-                    Operation::Close { des },
-                ]
-            },
-            fs.recording
+            Err(FsError::XattrNotFound("/foo".into(), "trusted.a".into())),
+            fs.setxattr(
+                "/foo".into(),
+                "trusted.a".into(),
+                0,
+                10,
+                vec![XattrFlag::XATTR_REPLACE]
+            )
+        );
+        fs.setxattr(
+            "/foo".into(),
+            "trusted.a".into(),
+            0,
+            10,
+            vec![XattrFlag::XATTR_CREATE],
+        )
+        .unwrap();
+        assert_eq!(
+            Err(FsError::XattrAlreadyExists(
+                "/foo".into(),
+                "trusted.a".into()
+            )),
+            fs.setxattr(
+                "/foo".into(),
+                "trusted.a".into(),
+                0,
+                10,
+                vec![XattrFlag::XATTR_CREATE]
+            )
+        );
+        fs.setxattr(
+            "/foo".into(),
+            "trusted.a".into(),
+            0,
+            0,
+            vec![XattrFlag::XATTR_REPLACE],
+        )
+        .unwrap();
+        assert_eq!(
+            0,
+            fs.getxattr("/foo".into(), "trusted.a".into(), 0)
+                .unwrap()
+                .size()
         );
         test_replay(fs.recording);
     }
 
     #[test]
-    fn test_symlink() {
+    fn test_setxattr_invalid() {
         let mut fs = AbstractFS::new();
-        let foo = fs.mkdir("/foo".into(), vec![]).unwrap();
-        let bar = fs.create("/foo/bar".into(), vec![]).unwrap();
-        fs.symlink("/foo".into(), "/baz".into()).unwrap();
+        fs.create("/foo".into(), vec![]).unwrap();
         assert_eq!(
-            AliveNodes {
-                dirs: vec![
-                    (AbstractFS::root_index(), "/".into()),
-                    (foo, "/baz".into()),
-                    (foo, "/foo".into())
-                ],
-                files: vec![(bar, "/baz/bar".into()), (bar, "/foo/bar".into())],
-                files_symlinked: vec![],
-                symlinks: vec!["/baz".into()],
-            },
-            fs.alive()
+            Err(FsError::InvalidXattrName("foo.a".into())),
+            fs.setxattr("/foo".into(), "foo.a".into(), 0, 10, vec![])
         );
         assert_eq!(
-            Workload {
-                ops: vec![
-                    Operation::MkDir {
-                        path: "/foo".into(),
-                        mode: vec![]
-                    },
-                    Operation::Create {
-                        path: "/foo/bar".into(),
-                        mode: vec![]
-                    },
-                    Operation::Symlink {
-                        target: "/foo".into(),
-                        linkpath: "/baz".into(),
-                    }
-                ]
-            },
-            fs.recording
+            Err(FsError::InvalidXattrName("user.".into())),
+            fs.setxattr("/foo".into(), "user.".into(), 0, 10, vec![])
+        );
+        assert_eq!(
+            Err(FsError::XattrValueTooBig(65537)),
+            fs.setxattr("/foo".into(), "user.a".into(), 0, 65537, vec![])
+        );
+        assert_eq!(
+            Err(FsError::NotFound("/bar".into())),
+            fs.setxattr("/bar".into(), "user.a".into(), 0, 10, vec![])
+        );
+    }
+
+    #[test]
+    fn test_listxattr_removexattr() {
+        let mut fs = AbstractFS::new();
+        fs.create("/foo".into(), vec![]).unwrap();
+        fs.hardlink("/foo".into(), "/bar".into()).unwrap();
+        assert_eq!(
+            Vec::<String>::new(),
+            fs.listxattr("/foo".into(), 0).unwrap()
+        );
+        fs.setxattr("/foo".into(), "user.b".into(), 0, 10, vec![])
+            .unwrap();
+        fs.setxattr("/foo".into(), "trusted.a".into(), 0, 10, vec![])
+            .unwrap();
+        assert_eq!(
+            vec!["trusted.a".to_owned(), "user.b".to_owned()],
+            fs.listxattr("/bar".into(), 17).unwrap()
+        );
+        assert_eq!(
+            Err(FsError::XattrBufferTooSmall("/bar".into(), 16)),
+            fs.listxattr("/bar".into(), 16)
+        );
+        fs.removexattr("/bar".into(), "user.b".into()).unwrap();
+        assert_eq!(
+            Err(FsError::XattrNotFound("/foo".into(), "user.b".into())),
+            fs.removexattr("/foo".into(), "user.b".into())
+        );
+        assert_eq!(
+            vec!["trusted.a".to_owned()],
+            fs.listxattr("/foo".into(), 0).unwrap()
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_create_mode() {
+        let mut fs = AbstractFS::new();
+        let mode = vec![ModeFlag::S_IRWXU, ModeFlag::S_IRWXG, ModeFlag::S_IRWXO];
+        fs.mkdir(
+            "/foo".into(),
+            vec![ModeFlag::S_IRWXU, ModeFlag::S_ISVTX, ModeFlag::S_ISUID],
+        )
+        .unwrap();
+        fs.create("/bar".into(), mode.clone()).unwrap();
+        fs.open("/boo".into(), vec![OpenFlag::O_CREAT], mode.clone())
+            .unwrap();
+        // Existing file is not affected.
+        fs.open("/bar".into(), vec![OpenFlag::O_CREAT], vec![])
+            .unwrap();
+        assert_eq!(0o1700, fs.resolve_metadata("/foo".into()).unwrap().mode);
+        assert_eq!(0o755, fs.resolve_metadata("/bar".into()).unwrap().mode);
+        assert_eq!(0o755, fs.resolve_metadata("/boo".into()).unwrap().mode);
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_chmod() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.create("/foo/bar".into(), vec![]).unwrap();
+        fs.symlink("/foo/bar".into(), "/baz".into()).unwrap();
+        fs.chmod("/foo".into(), vec![ModeFlag::S_IRWXU, ModeFlag::S_ISVTX])
+            .unwrap();
+        fs.chmod("/baz".into(), vec![ModeFlag::S_IRUSR, ModeFlag::S_ISUID])
+            .unwrap();
+        assert_eq!(0o1700, fs.resolve_metadata("/foo".into()).unwrap().mode);
+        assert_eq!(0o4400, fs.resolve_metadata("/foo/bar".into()).unwrap().mode);
+        let des = fs
+            .open("/foo/bar".into(), vec![OpenFlag::O_RDONLY], vec![])
+            .unwrap();
+        fs.fchmod(des, vec![]).unwrap();
+        assert_eq!(0, fs.resolve_metadata("/baz".into()).unwrap().mode);
+        assert_eq!(
+            Err(FsError::NotFound("/boo".into())),
+            fs.chmod("/boo".into(), vec![])
+        );
+        fs.close(des).unwrap();
+        assert_eq!(
+            Err(FsError::DescriptorWasClosed(des)),
+            fs.fchmod(des, vec![])
         );
         test_replay(fs.recording);
     }
 
     #[test]
-    fn test_symlink_name_exists() {
+    fn test_fallocate() {
         let mut fs = AbstractFS::new();
         fs.create("/foo".into(), vec![]).unwrap();
-        fs.create("/bar".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.fallocate(des, vec![], 0, 2 * BLOCK_SIZE).unwrap();
+        fs.write(des, 42, 100).unwrap();
+        fs.fallocate(
+            des,
+            vec![FallocateFlag::FALLOC_FL_KEEP_SIZE],
+            0,
+            10 * BLOCK_SIZE,
+        )
+        .unwrap();
+        fs.fallocate(
+            des,
+            vec![
+                FallocateFlag::FALLOC_FL_PUNCH_HOLE,
+                FallocateFlag::FALLOC_FL_KEEP_SIZE,
+            ],
+            50,
+            BLOCK_SIZE,
+        )
+        .unwrap();
+        fs.fallocate(
+            des,
+            vec![FallocateFlag::FALLOC_FL_INSERT_RANGE],
+            BLOCK_SIZE,
+            BLOCK_SIZE,
+        )
+        .unwrap();
+        fs.fallocate(
+            des,
+            vec![FallocateFlag::FALLOC_FL_COLLAPSE_RANGE],
+            0,
+            BLOCK_SIZE,
+        )
+        .unwrap();
+        fs.fallocate(
+            des,
+            vec![FallocateFlag::FALLOC_FL_ZERO_RANGE],
+            2 * BLOCK_SIZE - 10,
+            20,
+        )
+        .unwrap();
         assert_eq!(
-            Err(FsError::NameAlreadyExists("/bar".into())),
-            fs.symlink("/foo".into(), "/bar".into()),
+            vec![
+                Slice::Hole(BLOCK_SIZE + 50),
+                Slice::Zero(BLOCK_SIZE - 50 + 10),
+            ],
+            fs.file(&FileIndex(0)).content.slices()
+        );
+        assert_eq!(
+            Err(FsError::InvalidFallocateMode(vec![
+                FallocateFlag::FALLOC_FL_PUNCH_HOLE
+            ])),
+            fs.fallocate(des, vec![FallocateFlag::FALLOC_FL_PUNCH_HOLE], 0, 1)
+        );
+        assert_eq!(
+            Err(FsError::InvalidFallocateRange(1, BLOCK_SIZE)),
+            fs.fallocate(
+                des,
+                vec![FallocateFlag::FALLOC_FL_COLLAPSE_RANGE],
+                1,
+                BLOCK_SIZE
+            )
         );
+        assert_eq!(
+            Err(FsError::InvalidFallocateRange(0, 0)),
+            fs.fallocate(des, vec![], 0, 0)
+        );
+        test_replay(fs.recording);
     }
 
     #[test]
-    fn test_symlink_recursion() {
+    fn test_mmap() {
         let mut fs = AbstractFS::new();
-        let foo = fs.mkdir("/foo".into(), vec![]).unwrap();
-        fs.symlink("/foo".into(), "/foo/bar".into()).unwrap();
+        fs.create("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        let des_ro = fs.open("/foo".into(), vec![], vec![]).unwrap();
+        fs.ftruncate(des, 5000).unwrap();
         assert_eq!(
-            AliveNodes {
-                dirs: vec![
-                    (AbstractFS::root_index(), "/".into()),
-                    (foo, "/foo".into()),
-                    (foo, "/foo/bar".into()),
-                    (foo, "/foo/bar/bar".into()),
-                    // (foo, "/foo/bar/bar/bar".into())
-                ],
-                files: vec![],
-                files_symlinked: vec![],
-                symlinks: vec![
-                    "/foo/bar".into(),
-                    "/foo/bar/bar".into(),
-                    // "/foo/bar/bar/bar".into(),
-                ]
-            },
-            fs.alive()
+            Err(FsError::NotMappable(des_ro)),
+            fs.mmap(des_ro, 0, PAGE_SIZE)
+        );
+        assert_eq!(
+            Err(FsError::InvalidMappingRange(1, PAGE_SIZE)),
+            fs.mmap(des, 1, PAGE_SIZE)
+        );
+        assert_eq!(Err(FsError::InvalidMappingRange(0, 0)), fs.mmap(des, 0, 0));
+        let map = fs.mmap(des, 0, 3 * PAGE_SIZE).unwrap();
+        fs.close(des).unwrap();
+        assert_eq!(Ok(true), fs.mwrite(map, 10, 100, 0));
+        assert_eq!(Ok(false), fs.mwrite(map, 0, 1, 2 * PAGE_SIZE));
+        assert_eq!(
+            Err(FsError::MappingCrossesEof(map, 4950, 100)),
+            fs.mwrite(map, 0, 100, 4950)
+        );
+        assert_eq!(
+            Err(FsError::MappingCrossesEof(map, 5000, 1)),
+            fs.mwrite(map, 0, 1, 5000)
+        );
+        assert_eq!(
+            Err(FsError::OutOfMapping(map, 3 * PAGE_SIZE, 1)),
+            fs.mwrite(map, 0, 1, 3 * PAGE_SIZE)
+        );
+        assert_eq!(
+            vec![
+                Slice::Data(SourceSlice { from: 10, to: 109 }),
+                Slice::Hole(4900),
+            ],
+            fs.file(&FileIndex(0)).content.slices()
+        );
+        assert_eq!(
+            Err(FsError::InvalidMsyncFlags(vec![
+                MsyncFlag::MS_ASYNC,
+                MsyncFlag::MS_SYNC
+            ])),
+            fs.msync(map, vec![MsyncFlag::MS_ASYNC, MsyncFlag::MS_SYNC])
+        );
+        fs.msync(map, vec![MsyncFlag::MS_SYNC, MsyncFlag::MS_INVALIDATE])
+            .unwrap();
+        fs.munmap(map).unwrap();
+        assert_eq!(
+            Err(FsError::MappingWasUnmapped(map)),
+            fs.mwrite(map, 0, 0, 0)
+        );
+        assert_eq!(
+            Err(FsError::BadMapping(MappingIndex(1), 1)),
+            fs.munmap(MappingIndex(1))
         );
+        test_replay(fs.recording);
     }
 
     #[test]
-    fn test_symlink_to_symlink() {
+    fn test_timestamps() {
         let mut fs = AbstractFS::new();
-        let foo = fs.create("/foo".into(), vec![]).unwrap();
-        fs.symlink("/foo".into(), "/bar".into()).unwrap();
-        fs.symlink("/bar".into(), "/boo".into()).unwrap();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.create("/foo/bar".into(), vec![]).unwrap();
+        let des = fs
+            .open("/foo/bar".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.write(des, 0, 0).unwrap();
+        fs.write(des, 0, 5).unwrap();
+        let meta = fs.resolve_metadata("/foo/bar".into()).unwrap();
+        assert_eq!(Timestamp::Now(5), meta.mtime);
+        assert_eq!(Timestamp::Now(5), meta.ctime);
+        assert_eq!(Timestamp::Now(2), meta.atime);
+        fs.chmod("/foo/bar".into(), vec![ModeFlag::S_IRWXU])
+            .unwrap();
+        let meta = fs.resolve_metadata("/foo/bar".into()).unwrap();
+        assert_eq!(Timestamp::Now(5), meta.mtime);
+        assert_eq!(Timestamp::Now(6), meta.ctime);
+        fs.rename("/foo/bar".into(), "/baz".into()).unwrap();
         assert_eq!(
-            AliveNodes {
-                dirs: vec![(AbstractFS::root_index(), "/".into()),],
-                files: vec![(foo, "/foo".into()),],
-                files_symlinked: vec![(foo, "/bar".into()), (foo, "/boo".into())],
-                symlinks: vec!["/bar".into(), "/boo".into()]
-            },
-            fs.alive()
+            Timestamp::Now(7),
+            fs.resolve_metadata("/foo".into()).unwrap().mtime
+        );
+        assert_eq!(
+            Timestamp::Now(7),
+            fs.resolve_metadata("/".into()).unwrap().mtime
+        );
+        let meta = fs.resolve_metadata("/baz".into()).unwrap();
+        assert_eq!(Timestamp::Now(5), meta.mtime);
+        assert_eq!(Timestamp::Unspecified(7), meta.ctime);
+        fs.utimensat("/baz".into(), TimeSpec::Seconds(1000), TimeSpec::Omit)
+            .unwrap();
+        fs.futimens(des, TimeSpec::Omit, TimeSpec::Omit).unwrap();
+        fs.utimensat("/qux".into(), TimeSpec::Omit, TimeSpec::Omit)
+            .unwrap();
+        let meta = fs.resolve_metadata("/baz".into()).unwrap();
+        assert_eq!(Timestamp::Seconds(1000), meta.atime);
+        assert_eq!(Timestamp::Now(5), meta.mtime);
+        assert_eq!(Timestamp::Now(8), meta.ctime);
+        fs.truncate("/baz".into(), 5).unwrap();
+        assert_eq!(
+            Timestamp::Unspecified(11),
+            fs.resolve_metadata("/baz".into()).unwrap().mtime
+        );
+        fs.truncate("/baz".into(), 0).unwrap();
+        assert_eq!(
+            Timestamp::Now(12),
+            fs.resolve_metadata("/baz".into()).unwrap().mtime
         );
+        test_replay(fs.recording);
     }
 
     #[test]
-    fn test_symlink_to_symlink_recursive() {
+    fn test_chown() {
         let mut fs = AbstractFS::new();
-        fs.symlink("/foo".into(), "/bar".into()).unwrap();
-        fs.symlink("/bar".into(), "/foo".into()).unwrap();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.create("/bar".into(), vec![]).unwrap();
+        fs.chmod(
+            "/foo".into(),
+            vec![ModeFlag::S_IRWXU, ModeFlag::S_ISUID, ModeFlag::S_ISGID],
+        )
+        .unwrap();
+        fs.chmod(
+            "/bar".into(),
+            vec![ModeFlag::S_IRWXU, ModeFlag::S_ISUID, ModeFlag::S_ISGID],
+        )
+        .unwrap();
+        fs.chown("/foo".into(), Some(1000), None).unwrap();
+        fs.chown("/bar".into(), None, None).unwrap();
+        let foo = fs.resolve_metadata("/foo".into()).unwrap();
+        assert_eq!((0o6700, 1000, 0), (foo.mode, foo.uid, foo.gid));
+        // `S_ISGID` without `S_IXGRP` is kept.
+        let bar = fs.resolve_metadata("/bar".into()).unwrap();
+        assert_eq!((0o2700, 0, 0), (bar.mode, bar.uid, bar.gid));
+        fs.chmod(
+            "/bar".into(),
+            vec![ModeFlag::S_IRWXU, ModeFlag::S_IXGRP, ModeFlag::S_ISGID],
+        )
+        .unwrap();
+        let des = fs
+            .open("/bar".into(), vec![OpenFlag::O_RDONLY], vec![])
+            .unwrap();
+        fs.fchown(des, Some(65534), Some(1000)).unwrap();
+        let bar = fs.resolve_metadata("/bar".into()).unwrap();
+        assert_eq!((0o710, 65534, 1000), (bar.mode, bar.uid, bar.gid));
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_chown_unprivileged() {
+        let mut fs = AbstractFS::with_credentials(USER);
+        fs.create("/foo".into(), vec![ModeFlag::S_IRUSR]).unwrap();
+        fs.chown("/foo".into(), Some(1000), Some(1000)).unwrap();
         assert_eq!(
-            AliveNodes {
-                dirs: vec![(AbstractFS::root_index(), "/".into()),],
-                files: vec![],
-                files_symlinked: vec![],
-                symlinks: vec!["/bar".into(), "/foo".into()]
-            },
-            fs.alive()
+            Err(FsError::NotPermitted("/foo".into())),
+            fs.chown("/foo".into(), Some(0), None)
+        );
+        assert_eq!(
+            Err(FsError::NotPermitted("/foo".into())),
+            fs.chown("/foo".into(), None, Some(0))
+        );
+        let des = fs
+            .open("/foo".into(), vec![OpenFlag::O_RDONLY], vec![])
+            .unwrap();
+        assert_eq!(
+            Err(FsError::DescriptorNotPermitted(des)),
+            fs.fchown(des, None, Some(65534))
+        );
+        fs.fchown(des, None, None).unwrap();
+
+        fs.credentials = Credentials::ROOT;
+        fs.create("/bar".into(), vec![]).unwrap();
+        fs.chmod("/bar".into(), vec![ModeFlag::S_IRWXU, ModeFlag::S_ISUID])
+            .unwrap();
+        fs.chown("/foo".into(), Some(0), None).unwrap();
+        fs.credentials = USER;
+        // Only owner can change the group and clear set-user-ID bit.
+        fs.chown("/foo".into(), None, None).unwrap();
+        assert_eq!(
+            Err(FsError::NotPermitted("/foo".into())),
+            fs.chown("/foo".into(), None, Some(0))
         );
+        assert_eq!(
+            Err(FsError::NotPermitted("/bar".into())),
+            fs.chown("/bar".into(), None, None)
+        );
+        test_replay(fs.recording);
     }
 
     #[test]
-    fn test_remove_symlink() {
+    fn test_setgid_inheritance() {
         let mut fs = AbstractFS::new();
-        let foo = fs.create("/foo".into(), vec![]).unwrap();
-        fs.symlink("/foo".into(), "/bar".into()).unwrap();
-        fs.remove("/bar".into()).unwrap();
-        assert_eq!(
-            AliveNodes {
-                dirs: vec![(AbstractFS::root_index(), "/".into())],
-                files: vec![(foo, "/foo".into())],
-                files_symlinked: vec![],
-                symlinks: vec![],
-            },
-            fs.alive()
-        );
+        let mode = vec![ModeFlag::S_IRWXU, ModeFlag::S_IRWXG, ModeFlag::S_IRWXO];
+        fs.mkdir("/foo".into(), mode.clone()).unwrap();
+        fs.chmod("/foo".into(), vec![ModeFlag::S_IRWXU, ModeFlag::S_ISGID])
+            .unwrap();
+        fs.chown("/foo".into(), Some(1000), Some(65534)).unwrap();
+        let des = fs
+            .open("/foo/bar".into(), vec![OpenFlag::O_CREAT], mode.clone())
+            .unwrap();
+        fs.mkdir("/foo/dir".into(), mode.clone()).unwrap();
+        fs.fchmod(des, vec![ModeFlag::S_IRUSR, ModeFlag::S_ISVTX])
+            .unwrap();
+        fs.fchown(des, None, Some(1000)).unwrap();
+        let bar = fs.resolve_metadata("/foo/bar".into()).unwrap();
+        assert_eq!((0o1400, 0, 1000), (bar.mode, bar.uid, bar.gid));
+        let dir = fs.resolve_metadata("/foo/dir".into()).unwrap();
+        assert_eq!((0o2755, 0, 65534), (dir.mode, dir.uid, dir.gid));
         test_replay(fs.recording);
     }
 
     #[test]
-    fn test_rename_symlink() {
+    fn test_dir_descriptor_rename() {
         let mut fs = AbstractFS::new();
-        let foo = fs.create("/foo".into(), vec![]).unwrap();
-        fs.symlink("/foo".into(), "/bar".into()).unwrap();
-        fs.rename("/bar".into(), "/baz".into()).unwrap();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open_dir(
+                DirFd::Cwd,
+                "foo".into(),
+                vec![OpenFlag::O_DIRECTORY, OpenFlag::O_PATH],
+            )
+            .unwrap();
+        fs.rename("/foo".into(), "/bar".into()).unwrap();
+        fs.mkdirat(DirFd::Des(des), "baz".into(), vec![]).unwrap();
+        let file = fs
+            .openat(
+                DirFd::Des(des),
+                "baz/../f".into(),
+                vec![OpenFlag::O_CREAT],
+                vec![],
+            )
+            .unwrap();
         assert_eq!(
-            AliveNodes {
-                dirs: vec![(AbstractFS::root_index(), "/".into())],
-                files: vec![(foo, "/foo".into())],
-                files_symlinked: vec![(foo, "/baz".into())],
-                symlinks: vec!["/baz".into()],
-            },
-            fs.alive()
+            Ok(()),
+            fs.renameat(DirFd::Des(des), "f".into(), DirFd::Cwd, "g".into())
+                .map(|_| ())
+        );
+        assert_eq!(
+            Ok(Node::File(fs.description(&file).unwrap().file)),
+            fs.resolve_node("/g".into(), false).map(|(_, node)| node)
+        );
+        assert_eq!(
+            Some("/bar/baz".into()),
+            fs.dir_path(&fs.resolve_dir("/bar/baz".into()).unwrap().1)
+        );
+        assert_eq!(
+            Err(FsError::InvalidOpenFlags("/bar".into())),
+            fs.open_dir(DirFd::Cwd, "/bar".into(), vec![OpenFlag::O_PATH])
+        );
+        fs.close_dir(des).unwrap();
+        assert_eq!(
+            Err(FsError::DirDescriptorWasClosed(des)),
+            fs.mkdirat(DirFd::Des(des), "d".into(), vec![])
         );
         test_replay(fs.recording);
     }
 
     #[test]
-    fn test_rename_symlink_overwrite() {
+    fn test_dir_descriptor_remove() {
         let mut fs = AbstractFS::new();
-        let foo = fs.create("/foo".into(), vec![]).unwrap();
-        fs.symlink("/foo".into(), "/bar".into()).unwrap();
-        fs.rename("/foo".into(), "/bar".into()).unwrap();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        let des = fs
+            .open_dir(DirFd::Cwd, "/foo".into(), vec![OpenFlag::O_DIRECTORY])
+            .unwrap();
+        fs.chdir("foo".into()).unwrap();
+        fs.unlinkat(DirFd::Cwd, "/foo".into(), vec![AtFlag::AT_REMOVEDIR])
+            .unwrap();
         assert_eq!(
-            AliveNodes {
-                dirs: vec![(AbstractFS::root_index(), "/".into())],
-                files: vec![(foo, "/bar".into())],
-                files_symlinked: vec![],
-                symlinks: vec![],
-            },
-            fs.alive()
+            Err(FsError::RemovedDir("bar".into())),
+            fs.mkdirat(DirFd::Cwd, "bar".into(), vec![])
+        );
+        assert!(
+            fs.fstatat(DirFd::Des(des), "".into(), vec![AtFlag::AT_EMPTY_PATH])
+                .is_ok()
+        );
+        assert_eq!(
+            Err(FsError::NotFound("".into())),
+            fs.fstatat(DirFd::Des(des), "".into(), vec![])
+        );
+        fs.chdir("/".into()).unwrap();
+        fs.mkdirat(DirFd::Cwd, "bar".into(), vec![]).unwrap();
+        fs.fchdir(des).unwrap();
+        assert_eq!(
+            Err(FsError::RemovedDir("baz".into())),
+            fs.openat(DirFd::Cwd, "baz".into(), vec![OpenFlag::O_CREAT], vec![])
         );
         test_replay(fs.recording);
     }
 
     #[test]
-    fn test_hardlink_to_symlink() {
+    fn test_unlinkat() {
         let mut fs = AbstractFS::new();
-        fs.create("/foo".into(), vec![]).unwrap();
-        fs.symlink("/foo".into(), "/bar".into()).unwrap();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.create("/foo/bar".into(), vec![]).unwrap();
+        let des = fs
+            .open_dir(DirFd::Cwd, "/foo".into(), vec![OpenFlag::O_DIRECTORY])
+            .unwrap();
         assert_eq!(
-            Err(FsError::HardlinkToSymlinkForbidden("/bar".into())),
-            fs.hardlink("/bar".into(), "/boo".into())
+            Err(FsError::IsADir("/foo".into())),
+            fs.unlinkat(DirFd::Cwd, "/foo".into(), vec![])
+        );
+        assert_eq!(
+            Err(FsError::DirNotEmpty("foo".into())),
+            fs.unlinkat(DirFd::Cwd, "foo".into(), vec![AtFlag::AT_REMOVEDIR])
+        );
+        assert_eq!(
+            Err(FsError::NotADir("bar".into())),
+            fs.unlinkat(DirFd::Des(des), "bar".into(), vec![AtFlag::AT_REMOVEDIR])
+        );
+        assert_eq!(
+            Err(FsError::InvalidAtFlags(vec![AtFlag::AT_EMPTY_PATH])),
+            fs.unlinkat(DirFd::Des(des), "bar".into(), vec![AtFlag::AT_EMPTY_PATH])
+        );
+        fs.unlinkat(DirFd::Des(des), "bar".into(), vec![]).unwrap();
+        fs.unlinkat(DirFd::Cwd, "foo".into(), vec![AtFlag::AT_REMOVEDIR])
+            .unwrap();
+        assert_eq!(
+            Err(FsError::BadDirDescriptor(DirDescriptorIndex(1), 1)),
+            fs.fstatat(
+                DirFd::Des(DirDescriptorIndex(1)),
+                "".into(),
+                vec![AtFlag::AT_EMPTY_PATH]
+            )
         );
         test_replay(fs.recording);
     }
 
+    const USER: Credentials = Credentials {
+        uid: 1000,
+        gid: 1000,
+    };
+
     #[test]
-    fn test_resolve_node() {
-        let mut fs = AbstractFS::new();
-        let root = AbstractFS::root_index();
+    fn test_permissions() {
+        let mut fs = AbstractFS::with_credentials(USER);
+        fs.mkdir("/foo".into(), vec![ModeFlag::S_IRWXU]).unwrap();
+        fs.create("/foo/bar".into(), vec![ModeFlag::S_IRUSR])
+            .unwrap();
+        // Only root can give node to another user.
+        fs.credentials = Credentials::ROOT;
+        fs.chown("/foo".into(), Some(0), Some(0)).unwrap();
+        fs.credentials = USER;
         assert_eq!(
-            (vec![], Node::Dir(root)),
-            fs.resolve_node("/".into(), true).unwrap()
+            Err(FsError::AccessDenied("/foo/baz".into())),
+            fs.create("/foo/baz".into(), vec![])
         );
-        let foo = fs.mkdir("/foo".into(), vec![]).unwrap();
-        let bar = fs.mkdir("/foo/bar".into(), vec![]).unwrap();
-        let boo = fs.create("/foo/bar/boo".into(), vec![]).unwrap();
         assert_eq!(
-            Err(FsError::InvalidPath("".into())),
-            fs.resolve_node("".into(), true)
+            Err(FsError::AccessDenied("/foo/bar".into())),
+            fs.stat("/foo/bar".into()).map(|_| ())
         );
         assert_eq!(
-            Err(FsError::InvalidPath("foo".into())),
-            fs.resolve_node("foo".into(), true)
+            Err(FsError::AccessDenied("/foo".into())),
+            fs.readdir("/foo".into()).map(|_| ())
         );
         assert_eq!(
-            Err(FsError::InvalidPath("/foo/".into())),
-            fs.resolve_node("/foo/".into(), true)
+            Err(FsError::NotPermitted("/foo".into())),
+            fs.chmod("/foo".into(), vec![ModeFlag::S_IRWXU])
         );
+        fs.utimensat("/foo".into(), TimeSpec::Omit, TimeSpec::Omit)
+            .unwrap();
         assert_eq!(
-            (vec![root], Node::Dir(foo)),
-            fs.resolve_node("/foo".into(), true).unwrap()
+            Err(FsError::AccessDenied("/foo".into())),
+            fs.utimensat("/foo".into(), TimeSpec::Now, TimeSpec::Now)
         );
         assert_eq!(
-            (vec![root, foo], Node::Dir(bar)),
-            fs.resolve_node("/foo/bar".into(), true).unwrap()
+            Err(FsError::NotPermitted("/foo".into())),
+            fs.utimensat("/foo".into(), TimeSpec::Now, TimeSpec::Omit)
         );
         assert_eq!(
-            (vec![root, foo, bar], Node::File(boo)),
-            fs.resolve_node("/foo/bar/boo".into(), true).unwrap()
+            Err(FsError::NotPermitted("/foo".into())),
+            fs.chown("/foo".into(), Some(1000), None)
+        );
+        fs.credentials = Credentials::ROOT;
+        fs.chown("/foo".into(), Some(1000), None).unwrap();
+        fs.credentials = USER;
+        assert_eq!(
+            Err(FsError::AccessDenied("/foo/bar".into())),
+            fs.open("/foo/bar".into(), vec![OpenFlag::O_RDWR], vec![])
+                .map(|_| ())
+        );
+        fs.open("/foo/bar".into(), vec![OpenFlag::O_RDONLY], vec![])
+            .unwrap();
+        assert_eq!(
+            Err(FsError::NotPermitted("/baz".into())),
+            fs.mknod("/baz".into(), vec![], FileType::S_IFCHR)
+                .map(|_| ())
+        );
+        assert_eq!(
+            Err(FsError::NotPermitted("/foo".into())),
+            fs.setxattr("/foo".into(), "trusted.foo".into(), 0, 1, vec![])
         );
         test_replay(fs.recording);
     }
 
     #[test]
-    fn test_resolve_node_symlinks() {
+    fn test_sticky_dir() {
+        let mut fs = AbstractFS::with_credentials(USER);
+        fs.umask(vec![]);
+        let mode = vec![
+            ModeFlag::S_IRWXU,
+            ModeFlag::S_IRWXG,
+            ModeFlag::S_IRWXO,
+            ModeFlag::S_ISVTX,
+        ];
+        fs.mkdir("/tmp".into(), mode).unwrap();
+        fs.create("/tmp/foo".into(), vec![]).unwrap();
+        fs.create("/tmp/bar".into(), vec![]).unwrap();
+        fs.credentials = Credentials::ROOT;
+        fs.chown("/tmp/foo".into(), Some(0), Some(0)).unwrap();
+        fs.chown("/tmp".into(), Some(0), Some(0)).unwrap();
+        fs.credentials = USER;
+        assert_eq!(
+            Err(FsError::NotPermitted("/tmp/foo".into())),
+            fs.remove("/tmp/foo".into())
+        );
+        assert_eq!(
+            Err(FsError::NotPermitted("/tmp/foo".into())),
+            fs.rename("/tmp/bar".into(), "/tmp/foo".into()).map(|_| ())
+        );
+        fs.rename("/tmp/bar".into(), "/tmp/baz".into()).unwrap();
+        fs.remove("/tmp/baz".into()).unwrap();
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_umask_chmod_unprivileged() {
+        let mut fs = AbstractFS::with_credentials(USER);
+        fs.umask(vec![ModeFlag::S_IRWXG, ModeFlag::S_IRWXO]);
+        fs.mkdir("/foo".into(), vec![ModeFlag::S_IRWXU, ModeFlag::S_IRWXG])
+            .unwrap();
+        fs.chmod("/foo".into(), vec![ModeFlag::S_IRWXU, ModeFlag::S_ISGID])
+            .unwrap();
+        let foo = fs.resolve_metadata("/foo".into()).unwrap();
+        assert_eq!((0o2700, 1000, 1000), (foo.mode, foo.uid, foo.gid));
+        let des = fs
+            .open("/bar".into(), vec![OpenFlag::O_CREAT], vec![])
+            .unwrap();
+        assert_eq!(
+            Err(FsError::DescriptorNotPermitted(des)),
+            fs.flink(des, "/baz".into()).map(|_| ())
+        );
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_root_permissions() {
         let mut fs = AbstractFS::new();
-        let root = AbstractFS::root_index();
-        let foo = fs.mkdir("/foo".into(), vec![]).unwrap();
-        let bar = fs.create("/foo/bar".into(), vec![]).unwrap();
-        let foos = fs.symlink("/foo".into(), "/foos".into()).unwrap();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.chown("/foo".into(), Some(1000), Some(1000)).unwrap();
+        fs.create("/foo/bar".into(), vec![]).unwrap();
+        fs.chmod("/foo".into(), vec![ModeFlag::S_ISVTX]).unwrap();
+        fs.open("/foo/bar".into(), vec![OpenFlag::O_RDWR], vec![])
+            .unwrap();
+        fs.remove("/foo".into()).unwrap();
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_name_too_long() {
+        let mut fs = AbstractFS::new();
+        let long: PathName = format!("/{}", "x".repeat(NAME_MAX + 1)).into();
         assert_eq!(
-            (vec![root], Node::Dir(foo)),
-            fs.resolve_node("/foo".into(), true).unwrap()
+            Err(FsError::NameTooLong(long.clone())),
+            fs.create(long, vec![])
         );
+        let multibyte: PathName = format!("/{}", "\u{e9}".repeat(NAME_MAX / 2 + 1)).into();
         assert_eq!(
-            (vec![root, root], Node::Dir(foo)),
-            fs.resolve_node("/foos".into(), true).unwrap()
+            Err(FsError::NameTooLong(multibyte.clone())),
+            fs.mkdir(multibyte, vec![])
         );
+        let mut path = PathName::from("/");
+        while WORKSPACE_MAX + path.len() + NAME_MAX + 1 < PATH_MAX {
+            path = path.join("x".repeat(NAME_MAX));
+            fs.mkdir(path.clone(), vec![]).unwrap();
+        }
+        let deep = path.join("x".repeat(NAME_MAX));
         assert_eq!(
-            (vec![root], Node::Symlink(foos)),
-            fs.resolve_node("/foos".into(), false).unwrap()
+            Err(FsError::NameTooLong(deep.clone())),
+            fs.create(deep, vec![])
         );
+        let target: PathName = "x".repeat(SYMLINK_MAX).into();
         assert_eq!(
-            (vec![root, foo], Node::File(bar)),
-            fs.resolve_node("/foo/bar".into(), true).unwrap()
+            Err(FsError::NameTooLong(target.clone())),
+            fs.symlink(target, "/foo".into())
         );
         assert_eq!(
-            (vec![root, root, foo], Node::File(bar)),
-            fs.resolve_node("/foos/bar".into(), true).unwrap()
+            Err(FsError::NotFound("/foo".into())),
+            fs.remove("/foo".into())
         );
-        // Always follow symlinks in dirname part of the path.
+        test_replay(fs.recording);
+    }
+
+    #[test]
+    fn test_casefold() {
+        let mut fs = AbstractFS::new();
+        fs.mkdir("/foo".into(), vec![]).unwrap();
+        fs.set_casefold("/foo".into()).unwrap();
+        fs.mkdir("/foo/Bar".into(), vec![]).unwrap();
+        assert!(fs.casefolded(&"/foo/bar".into()));
+        fs.create("/foo/bar/\u{c5}".into(), vec![]).unwrap();
         assert_eq!(
-            (vec![root, root, foo], Node::File(bar)),
-            fs.resolve_node("/foos/bar".into(), false).unwrap()
+            Err(FsError::NameAlreadyExists("/foo/BAR".into())),
+            fs.mkdir("/foo/BAR".into(), vec![])
+        );
+        fs.create("/foo/baz".into(), vec![]).unwrap();
+        fs.rename("/foo/BAZ".into(), "/foo/bAR/a\u{30a}".into())
+            .unwrap();
+        let (_, bar) = fs.resolve_dir("/foo/BAR".into()).unwrap();
+        let names: Vec<&Name> = fs.dir(&bar).children.keys().collect();
+        assert_eq!(vec!["\u{c5}"], names);
+        fs.set_casefold("/foo/BAR".into()).unwrap();
+        assert_eq!(
+            Err(FsError::InvalidPath("/".into())),
+            fs.set_casefold("/".into())
+        );
+        fs.mkdir("/Baz".into(), vec![]).unwrap();
+        fs.create("/Baz/qux".into(), vec![]).unwrap();
+        assert_eq!(
+            Err(FsError::NotFound("/baz".into())),
+            fs.set_casefold("/baz".into())
+        );
+        assert_eq!(
+            Err(FsError::DirNotEmpty("/Baz".into())),
+            fs.set_casefold("/Baz".into())
         );
         test_replay(fs.recording);
     }
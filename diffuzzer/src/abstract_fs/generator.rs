@@ -2,14 +2,20 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use rand::{seq::IndexedRandom, Rng};
+use std::{collections::BTreeSet, iter};
+
+use rand::{Rng, seq::IndexedRandom};
 
 use super::{
-    flags::ModeFlag,
-    fs::{AbstractFS, FsError},
-    node::FileDescriptorIndex,
+    flags::{
+        AtFlag, AtFlags, FallocateFlag, FileType, FlockOperation, LockType, Mode, ModeFlag,
+        MsyncFlag, OpenFlag, RenameFlag, SeekWhence, SyncFileRangeFlag, TimeSpec, XattrFlag,
+    },
+    fs::{AbstractFS, AliveNodes, BLOCK_SIZE, Credentials, FsError, PAGE_SIZE, XATTR_NAMESPACES},
+    name::{NameGenerator, NameWeights, variant},
+    node::{DirDescriptorIndex, DirFd, FileDescriptorIndex, MappingIndex, Node},
     operation::{OperationKind, OperationWeights},
-    pathname::{Name, PathName},
+    pathname::PathName,
     workload::Workload,
 };
 
@@ -33,17 +39,195 @@ fn random_interesting_unsigned(rng: &mut impl Rng) -> u64 {
     *INTERESTING_UNSIGNED.choose(rng).unwrap()
 }
 
-/// Generates new random test workload of specified size.
-pub fn generate_new(rng: &mut impl Rng, size: usize, weights: &OperationWeights) -> Workload {
-    let mut fs = AbstractFS::new();
-    let mut name_idx: usize = 0;
-    let mut gen_name = || {
-        let name = name_idx.to_string();
-        name_idx += 1;
-        name
+/// Explicit offset or offset of descriptor.
+fn random_offset(rng: &mut impl Rng) -> Option<u64> {
+    if rng.random_bool(0.5) {
+        Some(random_interesting_unsigned(rng))
+    } else {
+        None
+    }
+}
+
+fn descriptor_file_size(fs: &AbstractFS, des: FileDescriptorIndex) -> u64 {
+    fs.file(&fs.description(&des).unwrap().file).content.size()
+}
+
+/// Offset aligned to block size, not greater than the limit.
+fn random_block_offset(rng: &mut impl Rng, limit: u64) -> u64 {
+    rng.random_range(0..=limit / BLOCK_SIZE) * BLOCK_SIZE
+}
+
+/// Zero (the end of file), length to the end of file or some number of blocks.
+fn random_remap_len(rng: &mut impl Rng, offset: u64, size: u64) -> u64 {
+    match rng.random_range(0..3) {
+        0 => 0,
+        1 => size.saturating_sub(offset),
+        _ => rng.random_range(1..=16) * BLOCK_SIZE,
+    }
+}
+
+/// Directory and path for `*at` operation, absolute path is used sometimes
+/// (directory is ignored then) or if there is no directory to resolve path from.
+fn random_at_path(
+    rng: &mut impl Rng,
+    bases: &[(DirFd, PathName)],
+    path: &PathName,
+) -> (DirFd, PathName) {
+    let Some((dir, base)) = bases.choose(rng) else {
+        return (DirFd::Cwd, path.clone());
     };
+    let relative = relative_path(base, path);
+    // Last component must name the node itself, not its parent.
+    let last = relative.segments().last().copied();
+    if rng.random_bool(0.1) || matches!(last, Some(".") | Some("..")) {
+        (*dir, path.clone())
+    } else {
+        (*dir, relative)
+    }
+}
+
+/// Names in casefolded directories are replaced by their case variants with some probability,
+/// variant refers to the same entry.
+fn vary_case(rng: &mut impl Rng, fs: &AbstractFS, mut alive: AliveNodes) -> AliveNodes {
+    if !alive.dirs.iter().any(|(idx, _)| fs.dir(idx).casefold) {
+        return alive;
+    }
+    let mut vary = |path: &mut PathName| {
+        let mut varied = PathName::from("/");
+        for segment in path.segments() {
+            let name = match variant(segment) {
+                Some(name) if rng.random_bool(0.5) && fs.casefolded(&varied) => name,
+                _ => segment.to_owned(),
+            };
+            varied = varied.join(name);
+        }
+        *path = varied;
+    };
+    alive.dirs.iter_mut().for_each(|(_, path)| vary(path));
+    alive.files.iter_mut().for_each(|(_, path)| vary(path));
+    alive
+        .files_symlinked
+        .iter_mut()
+        .for_each(|(_, path)| vary(path));
+    alive.symlinks.iter_mut().for_each(&mut vary);
+    alive.specials.iter_mut().for_each(|(_, path)| vary(path));
+    alive
+}
+
+/// Starts and lengths of byte-range locks, small pool makes ranges overlap
+/// (zero length means the end of file).
+const LOCK_OFFSETS: &[u64] = &[0, 100, 4096];
+
+/// Small pool of names keeps total size of extended attributes
+/// within the limits of filesystems (e.g. one block in ext4).
+const XATTR_NAMES: &[&str] = &["a", "b", "c", "d"];
+const XATTR_VALUE_MAX_SIZE: u64 = 256;
+
+fn random_xattr_name(rng: &mut impl Rng) -> String {
+    format!(
+        "{}{}",
+        XATTR_NAMESPACES.choose(rng).unwrap(),
+        XATTR_NAMES.choose(rng).unwrap()
+    )
+}
+
+/// Permission bits that can be set by `chmod`.
+/// `S_ISUID` and `S_ISGID` are omitted, because Linux clears them on write and truncate.
+const CHMOD_FLAGS: &[ModeFlag] = &[
+    ModeFlag::S_IRUSR,
+    ModeFlag::S_IWUSR,
+    ModeFlag::S_IXUSR,
+    ModeFlag::S_IRGRP,
+    ModeFlag::S_IWGRP,
+    ModeFlag::S_IXGRP,
+    ModeFlag::S_IROTH,
+    ModeFlag::S_IWOTH,
+    ModeFlag::S_IXOTH,
+    ModeFlag::S_ISVTX,
+];
+
+fn random_umask(rng: &mut impl Rng) -> Mode {
+    CHMOD_FLAGS
+        .iter()
+        .filter(|flag| **flag != ModeFlag::S_ISVTX && rng.random_bool(0.3))
+        .cloned()
+        .collect()
+}
+
+/// Operations on paths can be denied to unprivileged user or rejected because of too long names,
+/// such operations are not recorded. Expected failures of `open` are recorded (see `try_open`).
+trait Permitted {
+    /// Panics if the operation failed for any other reason.
+    fn permitted(self);
+    /// Operation either succeeded or was rejected, so retrying it is pointless.
+    fn done(&self) -> bool;
+}
+
+fn is_rejected(err: &FsError) -> bool {
+    err.is_denied() || matches!(err, FsError::NameTooLong(_))
+}
+
+impl<T> Permitted for Result<T, FsError> {
+    fn permitted(self) {
+        if let Err(err) = self
+            && !is_rejected(&err)
+        {
+            panic!("operation failed: {}", err);
+        }
+    }
+
+    fn done(&self) -> bool {
+        match self {
+            Ok(_) => true,
+            Err(err) => is_rejected(err),
+        }
+    }
+}
+
+/// Root, regular user and `nobody`.
+const IDS: &[u32] = &[0, 1000, 65534];
+
+fn random_chmod_mode(rng: &mut impl Rng) -> Mode {
+    CHMOD_FLAGS
+        .iter()
+        .filter(|_| rng.random_bool(0.5))
+        .cloned()
+        .collect()
+}
+
+/// `None` leaves the id unchanged.
+fn random_id(rng: &mut impl Rng) -> Option<u32> {
+    if rng.random_bool(0.2) {
+        None
+    } else {
+        Some(*IDS.choose(rng).unwrap())
+    }
+}
+
+/// Seconds since the Epoch that fit into timestamps of most filesystems (1980-2038)
+/// and are even (FAT has 2 seconds granularity).
+const SECONDS: &[i64] = &[315532800, 1000000000, 1234567890, 2000000000];
+
+fn random_timespec(rng: &mut impl Rng) -> TimeSpec {
+    match rng.random_range(0..5) {
+        0 | 1 => TimeSpec::Now,
+        2 => TimeSpec::Omit,
+        _ => TimeSpec::Seconds(*SECONDS.choose(rng).unwrap()),
+    }
+}
+
+/// Generates new random test workload of specified size.
+pub fn generate_new(
+    rng: &mut impl Rng,
+    size: usize,
+    weights: &OperationWeights,
+    name_weights: &NameWeights,
+    credentials: Credentials,
+) -> Workload {
+    let mut fs = AbstractFS::with_credentials(credentials);
+    let mut names = NameGenerator::new(name_weights.clone());
     for _ in 0..size {
-        append_one(rng, &mut fs, weights, &mut gen_name);
+        append_one(rng, &mut fs, weights, &mut names);
     }
     fs.recording
 }
@@ -53,7 +237,7 @@ pub fn append_one(
     rng: &mut impl Rng,
     fs: &mut AbstractFS,
     weights: &OperationWeights,
-    mut gen_name: impl FnMut() -> Name,
+    names: &mut NameGenerator,
 ) {
     let mode = vec![
         ModeFlag::S_IRWXU,
@@ -61,7 +245,7 @@ pub fn append_one(
         ModeFlag::S_IROTH,
         ModeFlag::S_IXOTH,
     ];
-    let alive = fs.alive();
+    let alive = vary_case(rng, fs, fs.alive());
     let alive_dirs_except_root: Vec<PathName> = alive
         .dirs
         .iter()
@@ -69,99 +253,380 @@ pub fn append_one(
         .map(|(_, path)| path)
         .cloned()
         .collect();
-    let alive_closed_files: Vec<PathName> = [alive.files.clone(), alive.files_symlinked.clone()]
+    let alive_specials: Vec<PathName> = alive
+        .specials
+        .iter()
+        .map(|(_, path)| path)
+        .cloned()
+        .collect();
+    let alive_files: Vec<PathName> = [alive.files.clone(), alive.files_symlinked.clone()]
         .concat()
         .iter()
-        .filter(|(idx, _)| fs.file(idx).descriptor.is_none())
         .map(|(_, path)| path)
         .cloned()
         .collect();
-    let alive_open_files: Vec<FileDescriptorIndex> = alive
+    // Descriptors remain valid after the file is removed or if it was never named.
+    let alive_open_files: Vec<FileDescriptorIndex> = fs
         .files
         .iter()
-        .filter_map(|(idx, _)| fs.file(idx).descriptor)
+        .flat_map(|file| file.descriptors.iter().copied())
+        .collect::<BTreeSet<FileDescriptorIndex>>()
+        .into_iter()
+        .collect();
+    // Closed descriptor can be opened again by `dup2`, unless its number was taken.
+    let taken_numbers = fs.taken_numbers();
+    let alive_dup_targets: Vec<FileDescriptorIndex> = fs
+        .descriptors
+        .iter()
+        .enumerate()
+        .map(|(idx, des)| (FileDescriptorIndex(idx), des))
+        .filter(|(idx, des)| alive_open_files.contains(idx) || !taken_numbers.contains(&des.number))
+        .map(|(idx, _)| idx)
+        .collect();
+    let alive_readable: Vec<FileDescriptorIndex> = alive_open_files
+        .iter()
+        .filter(|des| fs.description(des).unwrap().readable())
+        .cloned()
+        .collect();
+    let alive_writable: Vec<FileDescriptorIndex> = alive_open_files
+        .iter()
+        .filter(|des| fs.description(des).unwrap().writable())
+        .cloned()
+        .collect();
+    let alive_mappable: Vec<FileDescriptorIndex> = alive_open_files
+        .iter()
+        .filter(|des| {
+            let des = fs.description(des).unwrap();
+            des.readable() && des.writable() && !des.append()
+        })
+        .cloned()
+        .collect();
+    // Output of copying can't be opened with `O_APPEND`.
+    let alive_copy_writable: Vec<FileDescriptorIndex> = alive_writable
+        .iter()
+        .filter(|des| !fs.description(des).unwrap().append())
+        .cloned()
+        .collect();
+    // Whole file can't be cloned into the same file or into the middle of another file.
+    let alive_clonable: Vec<(FileDescriptorIndex, FileDescriptorIndex)> = alive_readable
+        .iter()
+        .flat_map(|des_in| {
+            alive_copy_writable
+                .iter()
+                .map(|des_out| (*des_in, *des_out))
+        })
+        .filter(|(des_in, des_out)| fs.remap_count(*des_in, 0, 0, *des_out, 0, false).is_ok())
+        .collect();
+    // Mappings remain valid after the file is removed.
+    let alive_mappings: Vec<MappingIndex> = fs
+        .mappings
+        .iter()
+        .enumerate()
+        .filter(|(_, map)| !map.unmapped)
+        .map(|(idx, _)| MappingIndex(idx))
+        .collect();
+    let alive_linkable: Vec<FileDescriptorIndex> = alive_open_files
+        .iter()
+        .filter(|des| {
+            let file_idx = fs.description(des).unwrap().file;
+            fs.file(&file_idx).linkable || alive.files.iter().any(|(idx, _)| *idx == file_idx)
+        })
+        .cloned()
+        .collect();
+    let alive_dirs_and_files: Vec<PathName> = alive
+        .dirs
+        .iter()
+        .map(|(_, path)| path.clone())
+        .chain(alive_files.iter().cloned())
+        .collect();
+    let alive_xattrs: Vec<(PathName, String)> = alive_dirs_and_files
+        .iter()
+        .flat_map(|path| {
+            fs.resolve_xattrs(path.clone())
+                .unwrap()
+                .keys()
+                .map(|name| (path.clone(), name.clone()))
+        })
+        .collect();
+    let alive_open_dirs: Vec<DirDescriptorIndex> = fs
+        .dir_descriptors
+        .iter()
+        .enumerate()
+        .filter(|(_, des)| !des.closed)
+        .map(|(idx, _)| DirDescriptorIndex(idx))
+        .collect();
+    // Relative paths can't be resolved from removed directories.
+    let alive_bases: Vec<(DirFd, PathName)> = iter::once(DirFd::Cwd)
+        .chain(alive_open_dirs.iter().map(|des| DirFd::Des(*des)))
+        .filter_map(|dir| {
+            fs.dir_path(&fs.dirfd_dir(&dir).unwrap())
+                .map(|path| (dir, path))
+        })
+        .collect();
+    let alive_empty_dirs: Vec<PathName> = alive_dirs_except_root
+        .iter()
+        .filter(|path| {
+            matches!(fs.resolve_node((*path).clone(), false),
+                Ok((_, Node::Dir(idx))) if fs.dir(&idx).children.is_empty())
+        })
+        .cloned()
         .collect();
     let mut ops = weights.clone();
-    if alive_dirs_except_root.is_empty() && alive.files.is_empty() {
-        ops.weights.retain(|(op, _)| *op != OperationKind::Remove);
+    if alive_dirs_except_root.is_empty() && alive.files.is_empty() && alive_specials.is_empty() {
+        ops.weights.retain(|(op, _)| {
+            !matches!(
+                op,
+                OperationKind::Remove
+                    | OperationKind::Rename
+                    | OperationKind::RenameAt2
+                    | OperationKind::RenameWhiteout
+                    | OperationKind::RenameAt
+            )
+        });
     }
-    if alive.files.is_empty() {
+    if alive.files.is_empty() && alive_specials.is_empty() {
         ops.weights.retain(|(op, _)| *op != OperationKind::Hardlink);
     }
-    if alive_dirs_except_root.is_empty() && alive.files.is_empty() {
-        ops.weights.retain(|(op, _)| *op != OperationKind::Rename);
-    }
-    if alive_closed_files.is_empty() {
-        ops.weights.retain(|(op, _)| *op != OperationKind::Open);
+    if alive_empty_dirs.is_empty() && alive.files.is_empty() && alive_specials.is_empty() {
+        ops.weights.retain(|(op, _)| *op != OperationKind::UnlinkAt);
     }
     if alive_open_files.is_empty() {
-        ops.weights.retain(|(op, _)| *op != OperationKind::Close);
-        ops.weights.retain(|(op, _)| *op != OperationKind::Read);
-        ops.weights.retain(|(op, _)| *op != OperationKind::Write);
-        ops.weights.retain(|(op, _)| *op != OperationKind::FSync);
+        ops.weights.retain(|(op, _)| {
+            !matches!(
+                op,
+                OperationKind::Close
+                    | OperationKind::FSync
+                    | OperationKind::FDataSync
+                    | OperationKind::SyncFs
+                    | OperationKind::SyncFileRange
+                    | OperationKind::LSeek
+                    | OperationKind::FStat
+                    | OperationKind::Dup
+                    | OperationKind::Dup2
+                    | OperationKind::FChmod
+                    | OperationKind::FChown
+                    | OperationKind::FUTimeNs
+                    | OperationKind::Flock
+                    | OperationKind::SetLk
+                    | OperationKind::OfdSetLk
+            )
+        });
+    }
+    if alive_open_files.is_empty() || alive_dup_targets.len() < 2 {
+        ops.weights.retain(|(op, _)| *op != OperationKind::Dup3);
+    }
+    if alive_open_dirs.is_empty() {
+        ops.weights
+            .retain(|(op, _)| !matches!(op, OperationKind::CloseDir | OperationKind::FChDir));
+    }
+    if alive.symlinks.is_empty() {
+        ops.weights.retain(|(op, _)| *op != OperationKind::ReadLink);
+    }
+    if alive_readable.is_empty() {
+        ops.weights
+            .retain(|(op, _)| !matches!(op, OperationKind::Read | OperationKind::PRead));
+    }
+    if alive_writable.is_empty() {
+        ops.weights.retain(|(op, _)| {
+            !matches!(
+                op,
+                OperationKind::Write
+                    | OperationKind::FTruncate
+                    | OperationKind::PWrite
+                    | OperationKind::FAllocate
+            )
+        });
+    }
+    if alive_readable.is_empty() || alive_copy_writable.is_empty() {
+        ops.weights.retain(|(op, _)| {
+            !matches!(
+                op,
+                OperationKind::CopyFileRange
+                    | OperationKind::SendFile
+                    | OperationKind::Splice
+                    | OperationKind::FICloneRange
+                    | OperationKind::FIDedupeRange
+            )
+        });
+    }
+    if alive_clonable.is_empty() {
+        ops.weights.retain(|(op, _)| *op != OperationKind::FIClone);
+    }
+    if alive_xattrs.is_empty() {
+        ops.weights
+            .retain(|(op, _)| !matches!(op, OperationKind::GetXattr | OperationKind::RemoveXattr));
+    }
+    if alive.files.is_empty() && alive.files_symlinked.is_empty() {
+        ops.weights.retain(|(op, _)| *op != OperationKind::Truncate);
+    }
+    if alive_mappable.is_empty() {
+        ops.weights.retain(|(op, _)| *op != OperationKind::MMap);
+    }
+    if alive_mappings.is_empty() {
+        ops.weights.retain(|(op, _)| {
+            !matches!(
+                op,
+                OperationKind::MWrite | OperationKind::MSync | OperationKind::MUnmap
+            )
+        });
+    }
+    // Casefold attribute can't be changed in non-empty directory.
+    let alive_casefoldable: Vec<PathName> = alive_dirs_except_root
+        .iter()
+        .filter(|path| fs.casefolded(path) || alive_empty_dirs.contains(path))
+        .cloned()
+        .collect();
+    if alive_casefoldable.is_empty() {
+        ops.weights
+            .retain(|(op, _)| *op != OperationKind::SetCasefold);
+    }
+    // Linking of descriptors requires `CAP_DAC_READ_SEARCH`.
+    if alive_linkable.is_empty() || !fs.credentials.is_root() {
+        ops.weights.retain(|(op, _)| *op != OperationKind::FLink);
     }
     match ops.weights.choose_weighted(rng, |item| item.1).unwrap().0 {
         OperationKind::MkDir => {
-            let path = alive.dirs.choose(rng).unwrap().to_owned().1;
-            fs.mkdir(path.join(gen_name()), mode.clone()).unwrap();
+            let path = if rng.random_bool(0.2) {
+                // Nesting of directories brings paths closer to `PATH_MAX`.
+                alive
+                    .dirs
+                    .iter()
+                    .max_by_key(|(_, path)| path.len())
+                    .unwrap()
+                    .to_owned()
+                    .1
+            } else {
+                alive.dirs.choose(rng).unwrap().to_owned().1
+            };
+            let name = names.next_in(rng, &path, fs.casefolded(&path));
+            fs.mkdir(path.join(name), mode.clone()).permitted();
         }
         OperationKind::Create => {
             let path = alive.dirs.choose(rng).unwrap().to_owned().1;
-            fs.create(path.join(gen_name()), mode.clone()).unwrap();
+            let name = names.next_in(rng, &path, fs.casefolded(&path));
+            fs.create(path.join(name), mode.clone()).permitted();
         }
         OperationKind::Remove => {
             let path = [
                 alive_dirs_except_root.clone(),
                 alive.files.iter().map(|(_, path)| path.clone()).collect(),
                 alive.symlinks.clone(),
+                alive_specials.clone(),
             ]
             .concat()
             .choose(rng)
             .unwrap()
             .to_owned();
-            fs.remove(path).unwrap();
+            fs.remove(path).permitted();
         }
         OperationKind::Hardlink => {
-            let file_path = alive.files.choose(rng).unwrap().to_owned().1;
+            let file_path = [
+                alive.files.iter().map(|(_, path)| path.clone()).collect(),
+                alive_specials.clone(),
+            ]
+            .concat()
+            .choose(rng)
+            .unwrap()
+            .to_owned();
             let dir_path = alive.dirs.choose(rng).unwrap().to_owned().1;
-            fs.hardlink(file_path, dir_path.join(gen_name())).unwrap();
+            let name = names.next_in(rng, &dir_path, fs.casefolded(&dir_path));
+            fs.hardlink(file_path, dir_path.join(name)).permitted();
         }
         OperationKind::Rename => {
-            let old_path = [
+            let paths = [
                 alive_dirs_except_root,
                 alive.files.iter().map(|(_, path)| path.clone()).collect(),
                 alive.symlinks.clone(),
+                alive_specials.clone(),
             ]
-            .concat()
-            .choose(rng)
-            .unwrap()
-            .to_owned();
+            .concat();
+            let old_path = paths.choose(rng).unwrap().to_owned();
 
-            let name = gen_name();
-            loop {
-                let new_path = alive.dirs.choose(rng).unwrap().1.to_owned();
-                if let Err(FsError::RenameToSubdirectoryError(..)) =
-                    fs.rename(old_path.clone(), new_path.join(name.clone()))
-                {
-                    continue;
+            if rng.random_bool(0.3) {
+                // Existing node can't always be replaced, renaming to itself always succeeds.
+                loop {
+                    let new_path = paths.choose(rng).unwrap().to_owned();
+                    if fs.rename(old_path.clone(), new_path).done() {
+                        break;
+                    }
+                }
+            } else {
+                let name = names.next(rng);
+                loop {
+                    let new_path = alive.dirs.choose(rng).unwrap().1.to_owned();
+                    if let Err(FsError::RenameToSubdirectoryError(..)) =
+                        fs.rename(old_path.clone(), new_path.join(name.clone()))
+                    {
+                        continue;
+                    }
+                    break;
                 }
-                break;
             }
         }
         OperationKind::Open => {
-            let path = alive_closed_files.choose(rng).unwrap().to_owned();
-            fs.open(path).unwrap();
+            let access = *[OpenFlag::O_RDONLY, OpenFlag::O_WRONLY, OpenFlag::O_RDWR]
+                .choose(rng)
+                .unwrap();
+            let mut flags = vec![access];
+            for flag in [
+                OpenFlag::O_APPEND,
+                OpenFlag::O_TRUNC,
+                OpenFlag::O_SYNC,
+                OpenFlag::O_DSYNC,
+                OpenFlag::O_NOFOLLOW,
+            ] {
+                if rng.random_bool(0.2) {
+                    flags.push(flag);
+                }
+            }
+            if access == OpenFlag::O_RDONLY {
+                // Result of `O_RDONLY | O_TRUNC` is unspecified.
+                flags.retain(|flag| *flag != OpenFlag::O_TRUNC);
+            }
+            if rng.random_bool(0.1) {
+                let path = alive.dirs.choose(rng).unwrap().to_owned().1;
+                if access == OpenFlag::O_RDONLY {
+                    flags[0] = OpenFlag::O_WRONLY;
+                }
+                flags.retain(|flag| *flag != OpenFlag::O_TRUNC && *flag != OpenFlag::O_NOFOLLOW);
+                flags.push(OpenFlag::O_TMPFILE);
+                if rng.random_bool(0.3) {
+                    // File can't be linked after all.
+                    flags.push(OpenFlag::O_EXCL);
+                }
+                fs.open(path, flags, mode.clone()).permitted();
+            } else if rng.random_bool(0.1) {
+                // Descriptors of directories are opened the same way as by `opendir`.
+                let path = alive.dirs.choose(rng).unwrap().1.to_owned();
+                let flags = vec![OpenFlag::O_RDONLY, OpenFlag::O_DIRECTORY];
+                fs.open_dir(DirFd::Cwd, path, flags).permitted();
+            } else if alive_files.is_empty() || rng.random_bool(0.3) {
+                let path = alive.dirs.choose(rng).unwrap().to_owned().1;
+                flags.push(OpenFlag::O_CREAT);
+                if rng.random_bool(0.5) {
+                    flags.push(OpenFlag::O_EXCL);
+                }
+                let name = names.next_in(rng, &path, fs.casefolded(&path));
+                fs.open(path.join(name), flags, mode.clone()).permitted();
+            } else {
+                let path = alive_files.choose(rng).unwrap().to_owned();
+                if rng.random_bool(0.1) {
+                    // Fails with `ENOTDIR`.
+                    flags.push(OpenFlag::O_DIRECTORY);
+                }
+                fs.try_open(path, flags, vec![]).permitted();
+            }
         }
         OperationKind::Close => {
             let des = alive_open_files.choose(rng).unwrap().to_owned();
             fs.close(des).unwrap();
         }
-        OperationKind::Write => {
-            let des = alive_open_files.choose(rng).unwrap().to_owned();
+        OperationKind::Read => {
+            let des = alive_readable.choose(rng).unwrap().to_owned();
             fs.read(des, random_interesting_unsigned(rng)).unwrap();
         }
-        OperationKind::Read => {
-            let des = alive_open_files.choose(rng).unwrap().to_owned();
+        OperationKind::Write => {
+            let des = alive_writable.choose(rng).unwrap().to_owned();
             fs.write(
                 des,
                 random_interesting_unsigned(rng),
@@ -173,6 +638,35 @@ pub fn append_one(
             let des = alive_open_files.choose(rng).unwrap().to_owned();
             fs.fsync(des).unwrap();
         }
+        OperationKind::FDataSync => {
+            let des = alive_open_files.choose(rng).unwrap().to_owned();
+            fs.fdatasync(des).unwrap();
+        }
+        OperationKind::Sync => {
+            fs.sync();
+        }
+        OperationKind::SyncFs => {
+            let des = alive_open_files.choose(rng).unwrap().to_owned();
+            fs.syncfs(des).unwrap();
+        }
+        OperationKind::SyncFileRange => {
+            let des = alive_open_files.choose(rng).unwrap().to_owned();
+            let flags = [
+                SyncFileRangeFlag::SYNC_FILE_RANGE_WAIT_BEFORE,
+                SyncFileRangeFlag::SYNC_FILE_RANGE_WRITE,
+                SyncFileRangeFlag::SYNC_FILE_RANGE_WAIT_AFTER,
+            ]
+            .into_iter()
+            .filter(|_| rng.random_bool(0.5))
+            .collect();
+            fs.sync_file_range(
+                des,
+                random_interesting_unsigned(rng),
+                random_interesting_unsigned(rng),
+                flags,
+            )
+            .unwrap();
+        }
         OperationKind::Symlink => {
             let target: PathName = [
                 alive
@@ -191,23 +685,682 @@ pub fn append_one(
             .choose(rng)
             .unwrap()
             .to_owned();
-            let linkpath = alive.dirs.choose(rng).unwrap().1.clone();
-            fs.symlink(target, linkpath.join(gen_name())).unwrap();
+            let dir_path = alive.dirs.choose(rng).unwrap().1.clone();
+            let target = match rng.random_range(0..10) {
+                0..=2 => relative_path(&dir_path, &target),
+                // Name is never used again, so the link remains dangling.
+                3 => {
+                    let name = names.next_in(rng, &target, fs.casefolded(&target));
+                    target.join(name)
+                }
+                4 => {
+                    // Leaves the workspace regardless of symbolic links in path.
+                    let (dirs, _) = fs.resolve_dir(dir_path.clone()).unwrap();
+                    let mut path = "..".to_owned();
+                    for _ in 0..dirs.len() {
+                        path.push_str("/..");
+                    }
+                    PathName::from(path).join(names.next(rng))
+                }
+                _ => target,
+            };
+            let name = names.next_in(rng, &dir_path, fs.casefolded(&dir_path));
+            fs.symlink(target, dir_path.join(name)).permitted();
+        }
+        OperationKind::Truncate => {
+            let path = [alive.files.clone(), alive.files_symlinked.clone()]
+                .concat()
+                .choose(rng)
+                .unwrap()
+                .to_owned()
+                .1;
+            fs.truncate(path, random_interesting_unsigned(rng))
+                .permitted();
+        }
+        OperationKind::FTruncate => {
+            let des = alive_writable.choose(rng).unwrap().to_owned();
+            fs.ftruncate(des, random_interesting_unsigned(rng)).unwrap();
+        }
+        OperationKind::LSeek => {
+            let des = alive_open_files.choose(rng).unwrap().to_owned();
+            let whences = [
+                SeekWhence::SEEK_SET,
+                SeekWhence::SEEK_CUR,
+                SeekWhence::SEEK_END,
+                SeekWhence::SEEK_DATA,
+                SeekWhence::SEEK_HOLE,
+            ];
+            // Offset can be invalid for chosen whence, `SEEK_SET` with non-negative offset always succeeds.
+            loop {
+                let whence = *whences.choose(rng).unwrap();
+                let mut offset = random_interesting_unsigned(rng) as i64;
+                if whence != SeekWhence::SEEK_SET && rng.random_bool(0.5) {
+                    offset = -offset;
+                }
+                if fs.lseek(des, offset, whence).is_ok() {
+                    break;
+                }
+            }
+        }
+        OperationKind::PRead => {
+            let des = alive_readable.choose(rng).unwrap().to_owned();
+            fs.pread(
+                des,
+                random_interesting_unsigned(rng),
+                random_interesting_unsigned(rng),
+            )
+            .unwrap();
+        }
+        OperationKind::PWrite => {
+            let des = alive_writable.choose(rng).unwrap().to_owned();
+            fs.pwrite(
+                des,
+                random_interesting_unsigned(rng),
+                random_interesting_unsigned(rng),
+                random_interesting_unsigned(rng),
+            )
+            .unwrap();
+        }
+        OperationKind::Dup => {
+            let des = alive_open_files.choose(rng).unwrap().to_owned();
+            fs.dup(des).unwrap();
+        }
+        OperationKind::Dup2 => {
+            let old_des = alive_open_files.choose(rng).unwrap().to_owned();
+            let new_des = alive_dup_targets.choose(rng).unwrap().to_owned();
+            fs.dup2(old_des, new_des).unwrap();
+        }
+        OperationKind::Dup3 => {
+            let old_des = alive_open_files.choose(rng).unwrap().to_owned();
+            let targets: Vec<FileDescriptorIndex> = alive_dup_targets
+                .iter()
+                .filter(|des| **des != old_des)
+                .cloned()
+                .collect();
+            let new_des = targets.choose(rng).unwrap().to_owned();
+            let flags = if rng.random_bool(0.5) {
+                vec![OpenFlag::O_CLOEXEC]
+            } else {
+                vec![]
+            };
+            fs.dup3(old_des, new_des, flags).unwrap();
+        }
+        OperationKind::SetXattr => {
+            let path = alive_dirs_and_files.choose(rng).unwrap().to_owned();
+            let name = random_xattr_name(rng);
+            let src_offset = random_interesting_unsigned(rng);
+            let size = rng.random_range(0..=XATTR_VALUE_MAX_SIZE);
+            // Without flags value is always set.
+            let flags = [
+                vec![],
+                vec![XattrFlag::XATTR_CREATE],
+                vec![XattrFlag::XATTR_REPLACE],
+            ];
+            loop {
+                let flags = flags.choose(rng).unwrap().to_owned();
+                if fs
+                    .setxattr(path.clone(), name.clone(), src_offset, size, flags)
+                    .done()
+                {
+                    break;
+                }
+            }
+        }
+        OperationKind::GetXattr => {
+            let (path, name) = alive_xattrs.choose(rng).unwrap().to_owned();
+            // Zero size always succeeds.
+            loop {
+                let size = random_interesting_unsigned(rng);
+                if fs.getxattr(path.clone(), name.clone(), size).done() {
+                    break;
+                }
+            }
+        }
+        OperationKind::ListXattr => {
+            let path = alive_dirs_and_files.choose(rng).unwrap().to_owned();
+            loop {
+                let size = random_interesting_unsigned(rng);
+                if fs.listxattr(path.clone(), size).done() {
+                    break;
+                }
+            }
+        }
+        OperationKind::RemoveXattr => {
+            let (path, name) = alive_xattrs.choose(rng).unwrap().to_owned();
+            fs.removexattr(path, name).permitted();
+        }
+        OperationKind::Chmod => {
+            let path = alive_dirs_and_files.choose(rng).unwrap().to_owned();
+            let mut mode = random_chmod_mode(rng);
+            // New entries of directory with `S_ISGID` inherit its group.
+            if alive.dirs.iter().any(|(_, dir)| *dir == path) && rng.random_bool(0.2) {
+                mode.push(ModeFlag::S_ISGID);
+            }
+            fs.chmod(path, mode).permitted();
+        }
+        OperationKind::FChmod => {
+            let des = alive_open_files.choose(rng).unwrap().to_owned();
+            fs.fchmod(des, random_chmod_mode(rng)).permitted();
+        }
+        OperationKind::Chown => {
+            let path = alive_dirs_and_files.choose(rng).unwrap().to_owned();
+            fs.chown(path, random_id(rng), random_id(rng)).permitted();
+        }
+        OperationKind::FChown => {
+            let des = alive_open_files.choose(rng).unwrap().to_owned();
+            fs.fchown(des, random_id(rng), random_id(rng)).permitted();
+        }
+        OperationKind::UTimeNsAt => {
+            let path = alive_dirs_and_files.choose(rng).unwrap().to_owned();
+            fs.utimensat(path, random_timespec(rng), random_timespec(rng))
+                .permitted();
+        }
+        OperationKind::FUTimeNs => {
+            let des = alive_open_files.choose(rng).unwrap().to_owned();
+            fs.futimens(des, random_timespec(rng), random_timespec(rng))
+                .permitted();
+        }
+        OperationKind::FAllocate => {
+            let des = alive_writable.choose(rng).unwrap().to_owned();
+            let modes = [
+                vec![],
+                vec![FallocateFlag::FALLOC_FL_KEEP_SIZE],
+                vec![
+                    FallocateFlag::FALLOC_FL_PUNCH_HOLE,
+                    FallocateFlag::FALLOC_FL_KEEP_SIZE,
+                ],
+                vec![FallocateFlag::FALLOC_FL_ZERO_RANGE],
+                vec![
+                    FallocateFlag::FALLOC_FL_ZERO_RANGE,
+                    FallocateFlag::FALLOC_FL_KEEP_SIZE,
+                ],
+                vec![FallocateFlag::FALLOC_FL_COLLAPSE_RANGE],
+                vec![FallocateFlag::FALLOC_FL_INSERT_RANGE],
+            ];
+            // Range can be invalid for chosen mode, default mode with non-zero length always succeeds.
+            loop {
+                let mode = modes.choose(rng).unwrap().to_owned();
+                let mut offset = random_interesting_unsigned(rng);
+                let mut len = random_interesting_unsigned(rng);
+                if mode.contains(&FallocateFlag::FALLOC_FL_COLLAPSE_RANGE)
+                    || mode.contains(&FallocateFlag::FALLOC_FL_INSERT_RANGE)
+                {
+                    offset -= offset % BLOCK_SIZE;
+                    len -= len % BLOCK_SIZE;
+                }
+                if fs.fallocate(des, mode, offset, len).is_ok() {
+                    break;
+                }
+            }
+        }
+        OperationKind::MMap => {
+            let des = alive_mappable.choose(rng).unwrap().to_owned();
+            let offset = random_interesting_unsigned(rng);
+            let len = random_interesting_unsigned(rng).max(1);
+            fs.mmap(des, offset - offset % PAGE_SIZE, len).unwrap();
+        }
+        OperationKind::MWrite => {
+            let map = alive_mappings.choose(rng).unwrap().to_owned();
+            // Access can be outside of mapping or cross the end of file, empty access always succeeds.
+            loop {
+                if fs
+                    .mwrite(
+                        map,
+                        random_interesting_unsigned(rng),
+                        random_interesting_unsigned(rng),
+                        random_interesting_unsigned(rng),
+                    )
+                    .is_ok()
+                {
+                    break;
+                }
+            }
+        }
+        OperationKind::MSync => {
+            let map = alive_mappings.choose(rng).unwrap().to_owned();
+            let mut flags = vec![
+                *[MsyncFlag::MS_ASYNC, MsyncFlag::MS_SYNC]
+                    .choose(rng)
+                    .unwrap(),
+            ];
+            if rng.random_bool(0.3) {
+                flags.push(MsyncFlag::MS_INVALIDATE);
+            }
+            fs.msync(map, flags).unwrap();
+        }
+        OperationKind::MUnmap => {
+            let map = alive_mappings.choose(rng).unwrap().to_owned();
+            fs.munmap(map).unwrap();
+        }
+        kind @ (OperationKind::RenameAt2 | OperationKind::RenameWhiteout) => {
+            let paths = [
+                alive_dirs_except_root,
+                alive.files.iter().map(|(_, path)| path.clone()).collect(),
+                alive.symlinks.clone(),
+                alive_specials.clone(),
+            ]
+            .concat();
+            let old_path = paths.choose(rng).unwrap().to_owned();
+            if kind == OperationKind::RenameAt2 && rng.random_bool(0.5) {
+                // Exchange with itself always succeeds.
+                loop {
+                    let new_path = paths.choose(rng).unwrap().to_owned();
+                    if fs
+                        .renameat2(
+                            old_path.clone(),
+                            new_path,
+                            vec![RenameFlag::RENAME_EXCHANGE],
+                        )
+                        .done()
+                    {
+                        break;
+                    }
+                }
+            } else {
+                let mut flags = if rng.random_bool(0.5) {
+                    vec![RenameFlag::RENAME_NOREPLACE]
+                } else {
+                    vec![]
+                };
+                if kind == OperationKind::RenameWhiteout {
+                    flags.push(RenameFlag::RENAME_WHITEOUT);
+                }
+                let name = names.next(rng);
+                loop {
+                    let new_path = alive.dirs.choose(rng).unwrap().1.to_owned();
+                    if let Err(FsError::RenameToSubdirectoryError(..)) =
+                        fs.renameat2(old_path.clone(), new_path.join(name.clone()), flags.clone())
+                    {
+                        continue;
+                    }
+                    break;
+                }
+            }
+        }
+        OperationKind::Umask => {
+            fs.umask(random_umask(rng));
+        }
+        OperationKind::SetCasefold => {
+            let path = alive_casefoldable.choose(rng).unwrap().to_owned();
+            fs.set_casefold(path).permitted();
+        }
+        OperationKind::FLink => {
+            let des = alive_linkable.choose(rng).unwrap().to_owned();
+            let path = alive.dirs.choose(rng).unwrap().to_owned().1;
+            let name = names.next_in(rng, &path, fs.casefolded(&path));
+            fs.flink(des, path.join(name)).permitted();
+        }
+        OperationKind::ReadDir => {
+            let path = alive.dirs.choose(rng).unwrap().to_owned().1;
+            fs.readdir(path).permitted();
+        }
+        kind @ (OperationKind::CopyFileRange | OperationKind::SendFile | OperationKind::Splice) => {
+            let des_in = *alive_readable.choose(rng).unwrap();
+            let des_out = *alive_copy_writable.choose(rng).unwrap();
+            // Ranges may overlap if both descriptors refer to the same file.
+            loop {
+                let offset_in = random_offset(rng);
+                let offset_out = random_offset(rng);
+                let len = random_interesting_unsigned(rng);
+                let result = match kind {
+                    OperationKind::CopyFileRange => {
+                        fs.copy_file_range(des_in, offset_in, des_out, offset_out, len)
+                    }
+                    OperationKind::SendFile => fs.sendfile(des_out, des_in, offset_in, len),
+                    _ => fs.splice(des_in, offset_in, des_out, offset_out, len),
+                };
+                if result.is_ok() {
+                    break;
+                }
+            }
+        }
+        OperationKind::FIClone => {
+            let (des_in, des_out) = *alive_clonable.choose(rng).unwrap();
+            fs.ficlone(des_in, des_out).unwrap();
+        }
+        OperationKind::FICloneRange => {
+            let des_in = *alive_readable.choose(rng).unwrap();
+            let des_out = *alive_copy_writable.choose(rng).unwrap();
+            let size_in = descriptor_file_size(fs, des_in);
+            let size_out = descriptor_file_size(fs, des_out);
+            // Cloning the last block of input file after the end of output file always succeeds.
+            loop {
+                let offset_in = random_block_offset(rng, size_in);
+                let len = random_remap_len(rng, offset_in, size_in);
+                let offset_out = random_block_offset(rng, size_out.next_multiple_of(BLOCK_SIZE));
+                if fs
+                    .ficlonerange(des_in, offset_in, len, des_out, offset_out)
+                    .is_ok()
+                {
+                    break;
+                }
+            }
+        }
+        OperationKind::FIDedupeRange => {
+            let des_in = *alive_readable.choose(rng).unwrap();
+            let des_out = *alive_copy_writable.choose(rng).unwrap();
+            let size_in = descriptor_file_size(fs, des_in);
+            let size_out = descriptor_file_size(fs, des_out);
+            // Zero length always succeeds.
+            loop {
+                let offset_in = random_block_offset(rng, size_in);
+                let len = random_remap_len(rng, offset_in, size_in);
+                let offset_out = random_block_offset(rng, size_out);
+                if fs
+                    .fideduperange(des_in, offset_in, len, des_out, offset_out)
+                    .is_ok()
+                {
+                    break;
+                }
+            }
+        }
+        OperationKind::Flock => {
+            let des = *alive_open_files.choose(rng).unwrap();
+            let op = *[
+                FlockOperation::LOCK_SH,
+                FlockOperation::LOCK_EX,
+                FlockOperation::LOCK_UN,
+            ]
+            .choose(rng)
+            .unwrap();
+            fs.flock(des, op).unwrap();
+        }
+        kind @ (OperationKind::SetLk | OperationKind::OfdSetLk) => {
+            let des = *alive_open_files.choose(rng).unwrap();
+            let description = fs.description(&des).unwrap();
+            let mut lock_types = vec![LockType::F_UNLCK];
+            if description.readable() {
+                lock_types.push(LockType::F_RDLCK);
+            }
+            if description.writable() {
+                lock_types.push(LockType::F_WRLCK);
+            }
+            let lock_type = *lock_types.choose(rng).unwrap();
+            let start = *LOCK_OFFSETS.choose(rng).unwrap();
+            let len = *LOCK_OFFSETS.choose(rng).unwrap();
+            if kind == OperationKind::SetLk {
+                fs.setlk(des, lock_type, start, len).unwrap();
+            } else {
+                fs.ofd_setlk(des, lock_type, start, len).unwrap();
+            }
+        }
+        OperationKind::OpenDir => {
+            let path = alive.dirs.choose(rng).unwrap().1.to_owned();
+            let (dir, path) = random_at_path(rng, &alive_bases, &path);
+            let mut flags = vec![OpenFlag::O_DIRECTORY];
+            if rng.random_bool(0.5) {
+                flags.push(OpenFlag::O_PATH);
+            }
+            fs.open_dir(dir, path, flags).permitted();
+        }
+        OperationKind::CloseDir => {
+            let des = *alive_open_dirs.choose(rng).unwrap();
+            fs.close_dir(des).unwrap();
+        }
+        OperationKind::ChDir => {
+            let path = alive.dirs.choose(rng).unwrap().1.to_owned();
+            let cwd_base: Vec<(DirFd, PathName)> = alive_bases
+                .iter()
+                .filter(|(dir, _)| *dir == DirFd::Cwd)
+                .cloned()
+                .collect();
+            let (_, path) = random_at_path(rng, &cwd_base, &path);
+            fs.chdir(path).permitted();
+        }
+        OperationKind::FChDir => {
+            let des = *alive_open_dirs.choose(rng).unwrap();
+            fs.fchdir(des).permitted();
+        }
+        OperationKind::MkDirAt => {
+            let path = alive.dirs.choose(rng).unwrap().1.clone();
+            let path = path.join(names.next_in(rng, &path, fs.casefolded(&path)));
+            let (dir, path) = random_at_path(rng, &alive_bases, &path);
+            fs.mkdirat(dir, path, mode.clone()).permitted();
+        }
+        OperationKind::OpenAt => {
+            let access = *[OpenFlag::O_RDONLY, OpenFlag::O_WRONLY, OpenFlag::O_RDWR]
+                .choose(rng)
+                .unwrap();
+            let mut flags = vec![access];
+            if rng.random_bool(0.2) {
+                flags.push(OpenFlag::O_APPEND);
+            }
+            let path = if alive_files.is_empty() || rng.random_bool(0.3) {
+                flags.push(OpenFlag::O_CREAT);
+                let path = alive.dirs.choose(rng).unwrap().1.clone();
+                path.join(names.next_in(rng, &path, fs.casefolded(&path)))
+            } else {
+                alive_files.choose(rng).unwrap().to_owned()
+            };
+            let (dir, path) = random_at_path(rng, &alive_bases, &path);
+            fs.openat(dir, path, flags, mode.clone()).permitted();
+        }
+        OperationKind::UnlinkAt => {
+            let (path, flags) = [
+                alive
+                    .files
+                    .iter()
+                    .map(|(_, path)| (path.clone(), vec![]))
+                    .collect::<Vec<(PathName, AtFlags)>>(),
+                alive
+                    .symlinks
+                    .iter()
+                    .chain(alive_specials.iter())
+                    .map(|path| (path.clone(), vec![]))
+                    .collect(),
+                alive_empty_dirs
+                    .iter()
+                    .map(|path| (path.clone(), vec![AtFlag::AT_REMOVEDIR]))
+                    .collect(),
+            ]
+            .concat()
+            .choose(rng)
+            .unwrap()
+            .to_owned();
+            let (dir, path) = random_at_path(rng, &alive_bases, &path);
+            fs.unlinkat(dir, path, flags).permitted();
+        }
+        OperationKind::RenameAt => {
+            let paths = [
+                alive_dirs_except_root,
+                alive.files.iter().map(|(_, path)| path.clone()).collect(),
+                alive.symlinks.clone(),
+                alive_specials.clone(),
+            ]
+            .concat();
+            let old_path = paths.choose(rng).unwrap().to_owned();
+            let name = names.next(rng);
+            let replace = rng.random_bool(0.3);
+            // Existing node can't always be replaced, renaming to itself always succeeds.
+            loop {
+                let new_path = if replace {
+                    paths.choose(rng).unwrap().to_owned()
+                } else {
+                    alive.dirs.choose(rng).unwrap().1.join(name.clone())
+                };
+                let (old_dir, old_path) = random_at_path(rng, &alive_bases, &old_path);
+                let (new_dir, new_path) = random_at_path(rng, &alive_bases, &new_path);
+                match fs.renameat(old_dir, old_path, new_dir, new_path) {
+                    Err(FsError::RenameToSubdirectoryError(..)) => continue,
+                    Err(err) if is_rejected(&err) => break,
+                    Err(_) if replace => continue,
+                    _ => break,
+                }
+            }
+        }
+        OperationKind::FStatAt => {
+            if rng.random_bool(0.2) {
+                let dirs: Vec<DirFd> = iter::once(DirFd::Cwd)
+                    .chain(alive_open_dirs.iter().map(|des| DirFd::Des(*des)))
+                    .collect();
+                let dir = *dirs.choose(rng).unwrap();
+                fs.fstatat(dir, "".into(), vec![AtFlag::AT_EMPTY_PATH])
+                    .permitted();
+            } else {
+                let paths = [
+                    alive
+                        .dirs
+                        .iter()
+                        .map(|(_, path)| path.clone())
+                        .collect::<Vec<PathName>>(),
+                    alive_files.clone(),
+                    alive.symlinks.clone(),
+                    alive_specials.clone(),
+                ]
+                .concat();
+                // Symbolic link can be dangling.
+                loop {
+                    let path = paths.choose(rng).unwrap().to_owned();
+                    let (dir, path) = random_at_path(rng, &alive_bases, &path);
+                    let flags = if rng.random_bool(0.5) {
+                        vec![AtFlag::AT_SYMLINK_NOFOLLOW]
+                    } else {
+                        vec![]
+                    };
+                    if fs.fstatat(dir, path, flags).done() {
+                        break;
+                    }
+                }
+            }
+        }
+        OperationKind::ReadLink => {
+            let path = alive.symlinks.choose(rng).unwrap().to_owned();
+            fs.readlink(path).permitted();
+        }
+        OperationKind::Stat => {
+            let paths = [
+                alive
+                    .dirs
+                    .iter()
+                    .map(|(_, path)| path.clone())
+                    .collect::<Vec<PathName>>(),
+                alive_files.clone(),
+                alive.symlinks.clone(),
+                alive_specials.clone(),
+            ]
+            .concat();
+            // Symbolic link can be dangling.
+            loop {
+                let path = paths.choose(rng).unwrap().to_owned();
+                if fs.stat(path).done() {
+                    break;
+                }
+            }
+        }
+        OperationKind::LStat => {
+            let path = [
+                alive
+                    .dirs
+                    .iter()
+                    .map(|(_, path)| path.clone())
+                    .collect::<Vec<PathName>>(),
+                alive_files.clone(),
+                alive.symlinks.clone(),
+                alive_specials.clone(),
+            ]
+            .concat()
+            .choose(rng)
+            .unwrap()
+            .to_owned();
+            fs.lstat(path).permitted();
+        }
+        OperationKind::FStat => {
+            let des = alive_open_files.choose(rng).unwrap().to_owned();
+            fs.fstat(des).unwrap();
+        }
+        OperationKind::MkNod => {
+            let path = alive.dirs.choose(rng).unwrap().to_owned().1;
+            let file_type = *[
+                FileType::S_IFIFO,
+                FileType::S_IFSOCK,
+                FileType::S_IFCHR,
+                FileType::S_IFBLK,
+            ]
+            .choose(rng)
+            .unwrap();
+            let name = names.next_in(rng, &path, fs.casefolded(&path));
+            fs.mknod(path.join(name), mode.clone(), file_type)
+                .permitted();
         }
     }
 }
 
+/// Path relative to the directory, both paths must be absolute.
+fn relative_path(dir: &PathName, path: &PathName) -> PathName {
+    let dir = dir.segments();
+    let path = path.segments();
+    let common = dir
+        .iter()
+        .zip(path.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let segments: Vec<&str> = iter::repeat_n("..", dir.len() - common)
+        .chain(path[common..].iter().copied())
+        .collect();
+    if segments.is_empty() {
+        ".".into()
+    } else {
+        segments.join("/").into()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use rand::{SeedableRng, rngs::StdRng};
 
     use super::*;
 
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(
+            PathName::from("."),
+            relative_path(&"/foo".into(), &"/foo".into())
+        );
+        assert_eq!(
+            PathName::from("bar/baz"),
+            relative_path(&"/foo".into(), &"/foo/bar/baz".into())
+        );
+        assert_eq!(
+            PathName::from("../../boo"),
+            relative_path(&"/foo/bar/baz".into(), &"/foo/boo".into())
+        );
+        assert_eq!(
+            PathName::from(".."),
+            relative_path(&"/foo".into(), &"/".into())
+        );
+    }
+
     #[test]
     fn smoke_test_generate_new() {
         for i in 0..100 {
             let mut rng = StdRng::seed_from_u64(i);
-            generate_new(&mut rng, 300, &OperationWeights::uniform());
+            generate_new(
+                &mut rng,
+                300,
+                &OperationWeights::uniform(),
+                &NameWeights::uniform(),
+                Credentials::ROOT,
+            );
+        }
+    }
+
+    #[test]
+    fn smoke_test_generate_new_unprivileged() {
+        let credentials = Credentials {
+            uid: 1000,
+            gid: 1000,
+        };
+        for i in 0..100 {
+            let mut rng = StdRng::seed_from_u64(i);
+            let workload = generate_new(
+                &mut rng,
+                300,
+                &OperationWeights::uniform(),
+                &NameWeights::uniform(),
+                credentials,
+            );
+            let mut fs = AbstractFS::with_credentials(credentials);
+            fs.replay(&workload).unwrap();
         }
     }
 }
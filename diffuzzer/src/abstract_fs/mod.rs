@@ -8,6 +8,7 @@ pub mod flags;
 pub mod fs;
 pub mod generator;
 pub mod mutator;
+pub mod name;
 pub mod node;
 pub mod operation;
 pub mod pathname;
@@ -8,8 +8,9 @@ use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 use super::{
-    fs::AbstractFS,
+    fs::{AbstractFS, Credentials},
     generator::append_one,
+    name::{NameGenerator, NameWeights},
     operation::{Operation, OperationWeights},
     workload::Workload,
 };
@@ -35,10 +36,10 @@ impl MutationWeights {
 }
 
 /// Tries to remove operation from workload at the index.
-pub fn remove(workload: &Workload, index: usize) -> Option<Workload> {
+pub fn remove(workload: &Workload, index: usize, credentials: Credentials) -> Option<Workload> {
     let mut ops = workload.ops.clone();
     ops.remove(index);
-    let mut fs = AbstractFS::new();
+    let mut fs = AbstractFS::with_credentials(credentials);
     if fs.replay(&Workload { ops }).is_err() {
         None
     } else {
@@ -52,6 +53,8 @@ pub fn insert(
     workload: &Workload,
     index: usize,
     weights: &OperationWeights,
+    name_weights: &NameWeights,
+    credentials: Credentials,
 ) -> Option<Workload> {
     let mut used_names = HashSet::new();
     for op in workload.ops.iter() {
@@ -83,7 +86,12 @@ pub fn insert(
                     used_names.insert(segment);
                 }
             }
-            Operation::Open { path, des: _ } => {
+            Operation::Open {
+                path,
+                flags: _,
+                mode: _,
+                des: _,
+            } => {
                 for segment in path.segments() {
                     used_names.insert(segment);
                 }
@@ -96,6 +104,15 @@ pub fn insert(
                 size: _,
             } => {}
             Operation::FSync { des: _ } => {}
+            Operation::FDataSync { des: _ } => {}
+            Operation::Sync => {}
+            Operation::SyncFs { des: _ } => {}
+            Operation::SyncFileRange {
+                des: _,
+                offset: _,
+                nbytes: _,
+                flags: _,
+            } => {}
             Operation::Symlink { target, linkpath } => {
                 for segment in target.segments() {
                     used_names.insert(segment);
@@ -104,11 +121,302 @@ pub fn insert(
                     used_names.insert(segment);
                 }
             }
+            Operation::Truncate { path, size: _ } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::FTruncate { des: _, size: _ } => {}
+            Operation::LSeek {
+                des: _,
+                offset: _,
+                whence: _,
+            } => {}
+            Operation::PRead {
+                des: _,
+                size: _,
+                offset: _,
+            } => {}
+            Operation::PWrite {
+                des: _,
+                src_offset: _,
+                size: _,
+                offset: _,
+            } => {}
+            Operation::Dup {
+                old_des: _,
+                new_des: _,
+            } => {}
+            Operation::Dup2 {
+                old_des: _,
+                new_des: _,
+            } => {}
+            Operation::Dup3 {
+                old_des: _,
+                new_des: _,
+                flags: _,
+            } => {}
+            Operation::SetXattr {
+                path,
+                name: _,
+                src_offset: _,
+                size: _,
+                flags: _,
+            } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::GetXattr {
+                path,
+                name: _,
+                size: _,
+            } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::ListXattr { path, size: _ } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::RemoveXattr { path, name: _ } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::Chmod { path, mode: _ } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::FChmod { des: _, mode: _ } => {}
+            Operation::Chown {
+                path,
+                uid: _,
+                gid: _,
+            } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::FChown {
+                des: _,
+                uid: _,
+                gid: _,
+            } => {}
+            Operation::UTimeNsAt {
+                path,
+                atime: _,
+                mtime: _,
+            } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::FUTimeNs {
+                des: _,
+                atime: _,
+                mtime: _,
+            } => {}
+            Operation::FAllocate {
+                des: _,
+                mode: _,
+                offset: _,
+                len: _,
+            } => {}
+            Operation::MMap {
+                des: _,
+                offset: _,
+                len: _,
+                map: _,
+            } => {}
+            Operation::MWrite {
+                map: _,
+                src_offset: _,
+                size: _,
+                offset: _,
+            } => {}
+            Operation::MSync { map: _, flags: _ } => {}
+            Operation::MUnmap { map: _ } => {}
+            Operation::RenameAt2 {
+                old_path,
+                new_path,
+                flags: _,
+            } => {
+                for segment in old_path.segments() {
+                    used_names.insert(segment);
+                }
+                for segment in new_path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::FLink { des: _, path } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::MkNod {
+                path,
+                mode: _,
+                file_type: _,
+            } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::ReadDir { path } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::Stat { path } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::LStat { path } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::FStat { des: _ } => {}
+            Operation::ReadLink { path } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::CopyFileRange {
+                des_in: _,
+                offset_in: _,
+                des_out: _,
+                offset_out: _,
+                len: _,
+            } => {}
+            Operation::SendFile {
+                des_out: _,
+                des_in: _,
+                offset: _,
+                count: _,
+            } => {}
+            Operation::Splice {
+                des_in: _,
+                offset_in: _,
+                des_out: _,
+                offset_out: _,
+                len: _,
+            } => {}
+            Operation::FIClone {
+                des_in: _,
+                des_out: _,
+            } => {}
+            Operation::FICloneRange {
+                des_in: _,
+                offset_in: _,
+                len: _,
+                des_out: _,
+                offset_out: _,
+            } => {}
+            Operation::FIDedupeRange {
+                des_in: _,
+                offset_in: _,
+                len: _,
+                des_out: _,
+                offset_out: _,
+            } => {}
+            Operation::Flock { des: _, op: _ } => {}
+            Operation::SetLk {
+                des: _,
+                lock_type: _,
+                start: _,
+                len: _,
+            } => {}
+            Operation::OfdSetLk {
+                des: _,
+                lock_type: _,
+                start: _,
+                len: _,
+            } => {}
+            Operation::OpenDir {
+                dir: _,
+                path,
+                flags: _,
+                des: _,
+            } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::CloseDir { des: _ } => {}
+            Operation::ChDir { path } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::FChDir { des: _ } => {}
+            Operation::MkDirAt {
+                dir: _,
+                path,
+                mode: _,
+            } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::OpenAt {
+                dir: _,
+                path,
+                flags: _,
+                mode: _,
+                des: _,
+            } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::UnlinkAt {
+                dir: _,
+                path,
+                flags: _,
+            } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::RenameAt {
+                old_dir: _,
+                old_path,
+                new_dir: _,
+                new_path,
+            } => {
+                for segment in old_path.segments() {
+                    used_names.insert(segment);
+                }
+                for segment in new_path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::FStatAt {
+                dir: _,
+                path,
+                flags: _,
+            } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
+            Operation::Umask { mask: _ } => {}
+            Operation::SetCasefold { path } => {
+                for segment in path.segments() {
+                    used_names.insert(segment);
+                }
+            }
         }
     }
 
     let (before, after) = workload.ops.split_at(index);
-    let mut fs = AbstractFS::new();
+    let mut fs = AbstractFS::with_credentials(credentials);
     if fs
         .replay(&Workload {
             ops: before.to_vec(),
@@ -118,15 +426,11 @@ pub fn insert(
         return None;
     }
 
-    let mut name_idx: usize = 0;
-    let mut gen_name = || loop {
-        let name = name_idx.to_string();
-        name_idx += 1;
-        if !used_names.contains(name.as_str()) {
-            break name;
-        }
-    };
-    append_one(rng, &mut fs, weights, &mut gen_name);
+    let mut names = NameGenerator::with_used(
+        name_weights.clone(),
+        used_names.into_iter().map(|name| name.to_owned()).collect(),
+    );
+    append_one(rng, &mut fs, weights, &mut names);
     if fs
         .replay(&Workload {
             ops: after.to_vec(),
@@ -143,7 +447,10 @@ pub fn insert(
 mod tests {
     use rand::{SeedableRng, rngs::StdRng};
 
-    use crate::abstract_fs::{generator::generate_new, operation::OperationKind};
+    use crate::abstract_fs::{
+        flags::OpenFlag, generator::generate_new, node::FileDescriptorIndex,
+        operation::OperationKind,
+    };
 
     use super::*;
 
@@ -165,7 +472,7 @@ mod tests {
                 },
             ],
         };
-        assert_eq!(None, remove(&w, 0));
+        assert_eq!(None, remove(&w, 0, Credentials::ROOT));
         assert_eq!(
             Some(Workload {
                 ops: vec![
@@ -179,10 +486,45 @@ mod tests {
                     },
                 ],
             }),
-            remove(&w, 1)
+            remove(&w, 1, Credentials::ROOT)
         );
     }
 
+    #[test]
+    fn test_remove_rename_overwrite() {
+        let w = Workload {
+            ops: vec![
+                Operation::Create {
+                    path: "/foo".into(),
+                    mode: vec![],
+                },
+                Operation::Open {
+                    path: "/foo".into(),
+                    flags: vec![OpenFlag::O_RDWR],
+                    mode: vec![],
+                    des: FileDescriptorIndex(0),
+                },
+                Operation::Create {
+                    path: "/bar".into(),
+                    mode: vec![],
+                },
+                Operation::Rename {
+                    old_path: "/bar".into(),
+                    new_path: "/foo".into(),
+                },
+                Operation::Write {
+                    des: FileDescriptorIndex(0),
+                    src_offset: 0,
+                    size: 100,
+                },
+            ],
+        };
+        assert_eq!(None, remove(&w, 2, Credentials::ROOT));
+        let mut expected = w.clone();
+        expected.ops.remove(3);
+        assert_eq!(Some(expected), remove(&w, 3, Credentials::ROOT));
+    }
+
     #[test]
     fn test_append() {
         let mut rng = StdRng::seed_from_u64(123);
@@ -207,7 +549,9 @@ mod tests {
                 &mut rng,
                 &w,
                 1,
-                &OperationWeights::new(vec![(OperationKind::Remove, 100)])
+                &OperationWeights::new(vec![(OperationKind::Remove, 100)]),
+                &NameWeights::decimal(),
+                Credentials::ROOT,
             )
         );
         assert_eq!(
@@ -233,7 +577,9 @@ mod tests {
                 &mut rng,
                 &w,
                 3,
-                &OperationWeights::new(vec![(OperationKind::Remove, 100)])
+                &OperationWeights::new(vec![(OperationKind::Remove, 100)]),
+                &NameWeights::decimal(),
+                Credentials::ROOT,
             )
         );
     }
@@ -241,17 +587,30 @@ mod tests {
     #[test]
     fn smoke_test_mutate() {
         let mut rng = StdRng::seed_from_u64(123);
-        let mut w = generate_new(&mut rng, 100, &OperationWeights::uniform());
+        let mut w = generate_new(
+            &mut rng,
+            100,
+            &OperationWeights::uniform(),
+            &NameWeights::uniform(),
+            Credentials::ROOT,
+        );
         for _ in 0..1000 {
             let p: f64 = rng.random();
             if w.ops.is_empty() || p >= 0.5 {
                 let index = rng.random_range(0..=w.ops.len());
-                if let Some(workload) = insert(&mut rng, &w, index, &OperationWeights::uniform()) {
+                if let Some(workload) = insert(
+                    &mut rng,
+                    &w,
+                    index,
+                    &OperationWeights::uniform(),
+                    &NameWeights::uniform(),
+                    Credentials::ROOT,
+                ) {
                     w = workload;
                 }
             } else {
                 let index = rng.random_range(0..w.ops.len());
-                if let Some(workload) = remove(&w, index) {
+                if let Some(workload) = remove(&w, index, Credentials::ROOT) {
                     w = workload;
                 }
             }
@@ -0,0 +1,295 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::collections::BTreeSet;
+
+use rand::{Rng, seq::IndexedRandom};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    fs::{NAME_MAX, PATH_MAX, WORKSPACE_MAX},
+    pathname::{Name, PathName},
+};
+
+#[derive(PartialEq, Eq, Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum NameKind {
+    /// Decimal numbers (`0`, `1`, ...).
+    Decimal,
+    /// Names around `NAME_MAX` bytes, some of them are too long.
+    Long,
+    /// Names that bring the path around `PATH_MAX` bytes.
+    Deep,
+    /// Names with multibyte UTF-8 characters.
+    Unicode,
+    /// Names with spaces, tabs and newlines.
+    Whitespace,
+    /// Names with leading and trailing dots (except `.` and `..`).
+    Dots,
+    /// Names that differ from used names only in case or Unicode normalization form.
+    Collision,
+}
+
+/// Weights determine the likelihood of name kind to be picked.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NameWeights {
+    pub weights: Vec<(NameKind, u32)>,
+}
+
+#[allow(dead_code)]
+impl NameWeights {
+    pub fn new(weights: Vec<(NameKind, u32)>) -> Self {
+        Self { weights }
+    }
+
+    pub fn decimal() -> Self {
+        Self {
+            weights: vec![(NameKind::Decimal, 100)],
+        }
+    }
+
+    pub fn uniform() -> Self {
+        Self {
+            weights: vec![
+                (NameKind::Decimal, 100),
+                (NameKind::Long, 100),
+                (NameKind::Deep, 100),
+                (NameKind::Unicode, 100),
+                (NameKind::Whitespace, 100),
+                (NameKind::Dots, 100),
+                (NameKind::Collision, 100),
+            ],
+        }
+    }
+}
+
+/// Precomposed characters (NFC) and their canonical decompositions (NFD).
+const NORMALIZATION_FORMS: &[(&str, &str)] = &[
+    ("\u{e9}", "e\u{301}"),
+    ("\u{c5}", "A\u{30a}"),
+    ("\u{f1}", "n\u{303}"),
+];
+
+/// Characters of 2, 3 and 4 bytes, including zero width space.
+const MULTIBYTE: &[&str] = &[
+    "\u{e9}",
+    "\u{3a9}",
+    "\u{436}",
+    "\u{4e2d}",
+    "\u{200b}",
+    "\u{1f980}",
+];
+
+const WHITESPACE: &[&str] = &[" ", "  ", "\t", "\n"];
+
+/// Generates names that were never used before, so entry with the name does not exist.
+/// Names are unique even if they are too long and operation fails.
+pub struct NameGenerator {
+    weights: NameWeights,
+    used: BTreeSet<Name>,
+    /// Case folded used names, names in casefolded directories must differ from them.
+    folded: BTreeSet<Name>,
+    counter: usize,
+}
+
+impl NameGenerator {
+    pub fn new(weights: NameWeights) -> Self {
+        Self::with_used(weights, BTreeSet::new())
+    }
+
+    /// Used names (e.g. of workload that is mutated) are never generated.
+    pub fn with_used(weights: NameWeights, used: BTreeSet<Name>) -> Self {
+        let folded = used.iter().map(|name| casefold(name)).collect();
+        Self {
+            weights,
+            used,
+            folded,
+            counter: 0,
+        }
+    }
+
+    /// Name that is not bound to directory, so it is unique in casefolded directories too.
+    pub fn next(&mut self, rng: &mut impl Rng) -> Name {
+        self.next_in(rng, &"/".into(), true)
+    }
+
+    /// Name of new entry of the directory, name in casefolded directory
+    /// must not be a case variant of used names.
+    pub fn next_in(&mut self, rng: &mut impl Rng, dir: &PathName, casefold: bool) -> Name {
+        let kind = self
+            .weights
+            .weights
+            .choose_weighted(rng, |item| item.1)
+            .unwrap()
+            .0;
+        loop {
+            let base = self.counter.to_string();
+            self.counter += 1;
+            let name = match kind {
+                NameKind::Decimal => base,
+                NameKind::Long => {
+                    let len = *[
+                        NAME_MAX - 1,
+                        NAME_MAX,
+                        NAME_MAX + 1,
+                        rng.random_range(base.len()..NAME_MAX),
+                    ]
+                    .choose(rng)
+                    .unwrap();
+                    pad(base, len)
+                }
+                NameKind::Deep => {
+                    // Path of new entry is `<dir>/<name>`, root is not repeated.
+                    let dir_len = if dir.is_root() { 0 } else { dir.len() };
+                    let left = PATH_MAX.saturating_sub(WORKSPACE_MAX + dir_len + 2);
+                    let len = (left + rng.random_range(0..=2)).saturating_sub(1);
+                    pad(base, len.min(NAME_MAX))
+                }
+                NameKind::Unicode => {
+                    let mut name = base;
+                    for _ in 0..rng.random_range(1..=4) {
+                        name.push_str(MULTIBYTE.choose(rng).unwrap());
+                    }
+                    // Last character can cross the limit.
+                    if rng.random_bool(0.3) {
+                        let c = MULTIBYTE.choose(rng).unwrap();
+                        while name.len() < NAME_MAX - 1 {
+                            name.push_str(c);
+                        }
+                    }
+                    name
+                }
+                NameKind::Whitespace => {
+                    let ws = WHITESPACE.choose(rng).unwrap();
+                    match rng.random_range(0..3) {
+                        0 => format!("{}{}", ws, base),
+                        1 => format!("{}{}", base, ws),
+                        _ => format!("{}{}x", base, ws),
+                    }
+                }
+                NameKind::Dots => {
+                    let template = *["._", ".._", "..._", "_.", "_..", "._."]
+                        .choose(rng)
+                        .unwrap();
+                    template.replace('_', &base)
+                }
+                NameKind::Collision => {
+                    let used: Vec<&Name> = self.used.iter().collect();
+                    match used.choose(rng).and_then(|name| variant(name)) {
+                        Some(name) if !casefold && !self.used.contains(&name) => name,
+                        // Variants of the name can be generated later.
+                        _ => {
+                            let (nfc, _) = NORMALIZATION_FORMS.choose(rng).unwrap();
+                            format!("{}{}", base, *["a", "Z", nfc].choose(rng).unwrap())
+                        }
+                    }
+                }
+            };
+            let folded = self::casefold(&name);
+            if (casefold && self.folded.contains(&folded)) || self.used.contains(&name) {
+                continue;
+            }
+            self.used.insert(name.clone());
+            self.folded.insert(folded);
+            return name;
+        }
+    }
+}
+
+/// Case folded name, which is the same for names that are equal in casefolded directory.
+/// Only decompositions of [`NORMALIZATION_FORMS`] are applied, which is enough for generated names.
+pub fn casefold(name: &str) -> Name {
+    let mut folded = name.to_lowercase();
+    for (nfc, nfd) in NORMALIZATION_FORMS {
+        folded = folded.replace(&nfc.to_lowercase(), &nfd.to_lowercase());
+    }
+    folded
+}
+
+/// Pads name with `x` to the length in bytes.
+fn pad(name: Name, len: usize) -> Name {
+    let padding = len.saturating_sub(name.len());
+    name + &"x".repeat(padding)
+}
+
+/// Same name in other normalization form or with swapped case of ASCII letters.
+pub fn variant(name: &str) -> Option<Name> {
+    for (nfc, nfd) in NORMALIZATION_FORMS {
+        if name.contains(nfc) {
+            return Some(name.replacen(nfc, nfd, 1));
+        }
+        if name.contains(nfd) {
+            return Some(name.replacen(nfd, nfc, 1));
+        }
+    }
+    if !name.chars().any(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    Some(
+        name.chars()
+            .map(|c| {
+                if c.is_ascii_lowercase() {
+                    c.to_ascii_uppercase()
+                } else {
+                    c.to_ascii_lowercase()
+                }
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+
+    #[test]
+    fn test_decimal() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let mut names =
+            NameGenerator::with_used(NameWeights::decimal(), BTreeSet::from(["1".to_owned()]));
+        assert_eq!("0", names.next(&mut rng));
+        assert_eq!("2", names.next(&mut rng));
+    }
+
+    #[test]
+    fn test_casefold_unique() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let weights = NameWeights::new(vec![(NameKind::Collision, 100)]);
+        let mut names = NameGenerator::with_used(weights, BTreeSet::from(["1a".to_owned()]));
+        for _ in 0..100 {
+            let name = names.next(&mut rng);
+            assert_ne!(casefold("1a"), casefold(&name));
+        }
+    }
+
+    #[test]
+    fn test_variant() {
+        assert_eq!(Some("e\u{301}1".to_owned()), variant("\u{e9}1"));
+        assert_eq!(Some("\u{e9}1".to_owned()), variant("e\u{301}1"));
+        assert_eq!(Some("1xZ".to_owned()), variant("1Xz"));
+        assert_eq!(None, variant("1 2"));
+    }
+
+    #[test]
+    fn test_casefold() {
+        assert_eq!(casefold("Foo\u{c5}"), casefold("fOOa\u{30a}"));
+        assert_eq!(casefold("\u{3a9}"), casefold("\u{3c9}"));
+        assert_ne!(casefold("foo"), casefold("foo\u{200b}"));
+    }
+
+    #[test]
+    fn test_unique() {
+        let mut rng = StdRng::seed_from_u64(123);
+        let mut names = NameGenerator::new(NameWeights::uniform());
+        let dir = PathName::from("/").join("x".repeat(NAME_MAX));
+        let mut generated = BTreeSet::new();
+        for _ in 0..1000 {
+            let name = names.next_in(&mut rng, &dir, false);
+            assert!(!name.is_empty() && !name.contains('/') && name != "." && name != "..");
+            assert!(generated.insert(name));
+        }
+    }
+}
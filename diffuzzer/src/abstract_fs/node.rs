@@ -2,12 +2,17 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use std::{collections::HashMap, fmt::Display};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Display,
+};
 
 use serde::{Deserialize, Serialize};
 
 use super::{
     content::Content,
+    flags::{FileType, OpenFlag, OpenFlags},
+    name::casefold,
     pathname::{Name, PathName},
 };
 
@@ -20,6 +25,9 @@ pub struct DirIndex(pub usize);
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct SymlinkIndex(pub usize);
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SpecialIndex(pub usize);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct FileDescriptorIndex(pub usize);
 
@@ -29,30 +37,239 @@ impl Display for FileDescriptorIndex {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct DirDescriptorIndex(pub usize);
+
+impl Display for DirDescriptorIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Directory that relative path of `*at` operation is resolved from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DirFd {
+    /// Current working directory (`AT_FDCWD`).
+    Cwd,
+    Des(DirDescriptorIndex),
+}
+
+/// Descriptor of directory (opened with `O_DIRECTORY`).
+/// Descriptor stays valid after the directory is renamed or removed.
+#[derive(Debug, Clone)]
+pub struct DirDescriptor {
+    pub dir: DirIndex,
+    pub closed: bool,
+    /// Descriptor number, shared with file descriptors.
+    pub number: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct MappingIndex(pub usize);
+
+impl Display for MappingIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Shared memory mapping of file region, created by `mmap` with `MAP_SHARED`.
+/// Mapping stays valid after the descriptor is closed or the file is removed.
+#[derive(Debug, Clone)]
+pub struct Mapping {
+    pub file: FileIndex,
+    /// Mapping keeps a reference to the open file description (and its locks).
+    pub description: FileDescriptionIndex,
+    /// Offset in file, aligned to page size.
+    pub offset: u64,
+    pub len: u64,
+    pub unmapped: bool,
+}
+
+/// Extended attributes by name, values are slices of "source" data.
+pub type Xattrs = BTreeMap<String, Content>;
+
+/// Permission bits, owner and timestamps of file or directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metadata {
+    /// Permission bits, including `S_ISUID`, `S_ISGID` and `S_ISVTX` (without file type).
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    /// Last access, only updated explicitly (real value depends on mount options).
+    pub atime: Timestamp,
+    /// Last data modification.
+    pub mtime: Timestamp,
+    /// Last status change.
+    pub ctime: Timestamp,
+}
+
+/// Metadata reported by `stat`, normalized to be comparable between filesystems
+/// (size and link count of directories depend on filesystem, so they are always zero).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stat {
+    /// File type bits of mode (`S_IFMT`).
+    pub file_type: u32,
+    /// Permission bits, same as [`Metadata::mode`].
+    pub mode: u32,
+    pub nlink: u64,
+    pub size: u64,
+}
+
+/// Real time is unknown to the model, only the order of updates is tracked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timestamp {
+    /// Current time of the operation with the tick (see [`super::fs::AbstractFS::tick`]).
+    Now(usize),
+    /// Either current time of the operation with the tick or unchanged (behavior is not specified).
+    Unspecified(usize),
+    /// Explicitly set to the number of seconds since the Epoch.
+    Seconds(i64),
+}
+
 #[derive(Debug, Clone)]
 pub struct File {
-    pub descriptor: Option<FileDescriptorIndex>,
+    /// Descriptors that are currently open for the file.
+    pub descriptors: BTreeSet<FileDescriptorIndex>,
     pub content: Content,
+    pub xattrs: Xattrs,
+    pub meta: Metadata,
+    /// Unnamed file created with `O_TMPFILE` (without `O_EXCL`) that was never linked.
+    pub linkable: bool,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileDescriptionIndex(pub usize);
+
+/// Descriptors duplicated with `dup` refer to the same open file description.
 #[derive(Debug, Clone)]
 pub struct FileDescriptor {
+    pub description: FileDescriptionIndex,
+    /// Lowest number that was not taken by open descriptors, same as in Linux.
+    /// Workload does not open other descriptors, so numbers are relative to the first one.
+    pub number: usize,
+}
+
+/// Open file description, created by `open` and shared between duplicated descriptors.
+#[derive(Debug, Clone)]
+pub struct FileDescription {
     pub file: FileIndex,
     pub offset: u64,
+    /// Flags file was opened with.
+    pub flags: OpenFlags,
+}
+
+/// Whole file lock placed by `flock`, owned by the open file description.
+#[derive(Debug, Clone)]
+pub struct FileLock {
+    pub file: FileIndex,
+    pub description: FileDescriptionIndex,
+    pub exclusive: bool,
+}
+
+/// Owner of byte-range lock placed by `fcntl`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockOwner {
+    /// POSIX record locks (`F_SETLK`) are owned by the process, so they never conflict
+    /// with each other in a single process.
+    Process,
+    /// Open file description locks (`F_OFD_SETLK`).
+    Description(FileDescriptionIndex),
+}
+
+/// Byte-range lock placed by `fcntl`.
+#[derive(Debug, Clone)]
+pub struct RecordLock {
+    pub file: FileIndex,
+    pub owner: LockOwner,
+    pub exclusive: bool,
+    pub start: u64,
+    /// End of range (exclusive), `None` if lock extends to the end of file and beyond.
+    pub end: Option<u64>,
+}
+
+impl RecordLock {
+    /// Checks if lock range intersects with the range.
+    pub fn overlaps(&self, start: u64, end: Option<u64>) -> bool {
+        self.end.is_none_or(|e| start < e) && end.is_none_or(|e| self.start < e)
+    }
+}
+
+impl FileDescription {
+    /// Opened with `O_RDONLY` or `O_RDWR` (access mode defaults to `O_RDONLY`, which is zero on Linux).
+    pub fn readable(&self) -> bool {
+        !self.flags.contains(&OpenFlag::O_WRONLY)
+    }
+
+    /// Opened with `O_WRONLY` or `O_RDWR`.
+    pub fn writable(&self) -> bool {
+        self.flags.contains(&OpenFlag::O_WRONLY) || self.flags.contains(&OpenFlag::O_RDWR)
+    }
+
+    /// Opened with `O_APPEND`, every write is done at the end of file.
+    pub fn append(&self) -> bool {
+        self.flags.contains(&OpenFlag::O_APPEND)
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Dir {
     pub children: HashMap<Name, Node>,
+    pub xattrs: Xattrs,
+    pub meta: Metadata,
+    /// Names are case-insensitive (`FS_CASEFOLD_FL`), inherited by subdirectories.
+    pub casefold: bool,
+}
+
+impl Dir {
+    /// Name of entry that matches the name, in casefolded directory
+    /// names are compared after case folding.
+    fn entry_name(&self, name: &str) -> Option<Name> {
+        if self.children.contains_key(name) {
+            return Some(name.to_owned());
+        }
+        if !self.casefold {
+            return None;
+        }
+        let folded = casefold(name);
+        self.children
+            .keys()
+            .find(|child| casefold(child) == folded)
+            .cloned()
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<&Node> {
+        self.children.get(&self.entry_name(name)?)
+    }
+
+    pub fn unlink(&mut self, name: &str) -> Option<Node> {
+        let name = self.entry_name(name)?;
+        self.children.remove(&name)
+    }
+
+    /// Existing entry keeps its name, only the node is replaced.
+    pub fn link(&mut self, name: Name, node: Node) {
+        let name = self.entry_name(&name).unwrap_or(name);
+        self.children.insert(name, node);
+    }
 }
 
 pub struct Symlink {
     pub target: PathName,
 }
 
+/// FIFO, socket or device node created by `mknod`, it is never opened.
+#[derive(Debug, Clone)]
+pub struct Special {
+    pub file_type: FileType,
+    pub meta: Metadata,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Node {
     File(FileIndex),
     Dir(DirIndex),
     Symlink(SymlinkIndex),
+    Special(SpecialIndex),
 }
@@ -4,7 +4,14 @@
 
 use serde::{Deserialize, Serialize};
 
-use super::{flags::Mode, node::FileDescriptorIndex, pathname::PathName};
+use super::{
+    flags::{
+        AtFlags, FallocateMode, FileType, FlockOperation, LockType, Mode, MsyncFlags, OpenFlags,
+        RenameFlag, RenameFlags, SeekWhence, SyncFileRangeFlags, TimeSpec, XattrFlags,
+    },
+    node::{DirDescriptorIndex, DirFd, FileDescriptorIndex, MappingIndex},
+    pathname::PathName,
+};
 
 #[derive(Clone, Debug, Hash, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
@@ -30,6 +37,9 @@ pub enum Operation {
     },
     Open {
         path: PathName,
+        flags: OpenFlags,
+        /// Only used if file is created (`O_CREAT`).
+        mode: Mode,
         des: FileDescriptorIndex,
     },
     Close {
@@ -47,10 +57,283 @@ pub enum Operation {
     FSync {
         des: FileDescriptorIndex,
     },
+    FDataSync {
+        des: FileDescriptorIndex,
+    },
+    Sync,
+    SyncFs {
+        des: FileDescriptorIndex,
+    },
+    /// Zero number of bytes means the end of file.
+    SyncFileRange {
+        des: FileDescriptorIndex,
+        offset: u64,
+        nbytes: u64,
+        flags: SyncFileRangeFlags,
+    },
     Symlink {
         target: PathName,
         linkpath: PathName,
     },
+    Truncate {
+        path: PathName,
+        size: u64,
+    },
+    FTruncate {
+        des: FileDescriptorIndex,
+        size: u64,
+    },
+    LSeek {
+        des: FileDescriptorIndex,
+        offset: i64,
+        whence: SeekWhence,
+    },
+    PRead {
+        des: FileDescriptorIndex,
+        size: u64,
+        offset: u64,
+    },
+    PWrite {
+        des: FileDescriptorIndex,
+        src_offset: u64,
+        size: u64,
+        offset: u64,
+    },
+    Dup {
+        old_des: FileDescriptorIndex,
+        new_des: FileDescriptorIndex,
+    },
+    /// `new_des` is an open descriptor that is closed and reused.
+    Dup2 {
+        old_des: FileDescriptorIndex,
+        new_des: FileDescriptorIndex,
+    },
+    /// Same as `Dup2`, but descriptors must differ and `O_CLOEXEC` can be set.
+    Dup3 {
+        old_des: FileDescriptorIndex,
+        new_des: FileDescriptorIndex,
+        flags: OpenFlags,
+    },
+    /// Value is a slice of "source" data.
+    SetXattr {
+        path: PathName,
+        name: String,
+        src_offset: u64,
+        size: u64,
+        flags: XattrFlags,
+    },
+    GetXattr {
+        path: PathName,
+        name: String,
+        size: u64,
+    },
+    ListXattr {
+        path: PathName,
+        size: u64,
+    },
+    RemoveXattr {
+        path: PathName,
+        name: String,
+    },
+    Chmod {
+        path: PathName,
+        mode: Mode,
+    },
+    FChmod {
+        des: FileDescriptorIndex,
+        mode: Mode,
+    },
+    /// `None` leaves the id unchanged.
+    Chown {
+        path: PathName,
+        uid: Option<u32>,
+        gid: Option<u32>,
+    },
+    FChown {
+        des: FileDescriptorIndex,
+        uid: Option<u32>,
+        gid: Option<u32>,
+    },
+    UTimeNsAt {
+        path: PathName,
+        atime: TimeSpec,
+        mtime: TimeSpec,
+    },
+    FUTimeNs {
+        des: FileDescriptorIndex,
+        atime: TimeSpec,
+        mtime: TimeSpec,
+    },
+    FAllocate {
+        des: FileDescriptorIndex,
+        mode: FallocateMode,
+        offset: u64,
+        len: u64,
+    },
+    MMap {
+        des: FileDescriptorIndex,
+        offset: u64,
+        len: u64,
+        map: MappingIndex,
+    },
+    MWrite {
+        map: MappingIndex,
+        src_offset: u64,
+        size: u64,
+        offset: u64,
+    },
+    MSync {
+        map: MappingIndex,
+        flags: MsyncFlags,
+    },
+    MUnmap {
+        map: MappingIndex,
+    },
+    RenameAt2 {
+        old_path: PathName,
+        new_path: PathName,
+        flags: RenameFlags,
+    },
+    FLink {
+        des: FileDescriptorIndex,
+        path: PathName,
+    },
+    MkNod {
+        path: PathName,
+        mode: Mode,
+        file_type: FileType,
+    },
+    ReadDir {
+        path: PathName,
+    },
+    Stat {
+        path: PathName,
+    },
+    LStat {
+        path: PathName,
+    },
+    FStat {
+        des: FileDescriptorIndex,
+    },
+    ReadLink {
+        path: PathName,
+    },
+    /// Offset of descriptor is used if explicit offset is not specified.
+    CopyFileRange {
+        des_in: FileDescriptorIndex,
+        offset_in: Option<u64>,
+        des_out: FileDescriptorIndex,
+        offset_out: Option<u64>,
+        len: u64,
+    },
+    SendFile {
+        des_out: FileDescriptorIndex,
+        des_in: FileDescriptorIndex,
+        offset: Option<u64>,
+        count: u64,
+    },
+    Splice {
+        des_in: FileDescriptorIndex,
+        offset_in: Option<u64>,
+        des_out: FileDescriptorIndex,
+        offset_out: Option<u64>,
+        len: u64,
+    },
+    FIClone {
+        des_in: FileDescriptorIndex,
+        des_out: FileDescriptorIndex,
+    },
+    /// Zero length means the end of input file.
+    FICloneRange {
+        des_in: FileDescriptorIndex,
+        offset_in: u64,
+        len: u64,
+        des_out: FileDescriptorIndex,
+        offset_out: u64,
+    },
+    FIDedupeRange {
+        des_in: FileDescriptorIndex,
+        offset_in: u64,
+        len: u64,
+        des_out: FileDescriptorIndex,
+        offset_out: u64,
+    },
+    /// Lock is owned by the open file description, conflict is reported instead of blocking.
+    Flock {
+        des: FileDescriptorIndex,
+        op: FlockOperation,
+    },
+    /// POSIX record lock (`F_SETLK`), lock is owned by the process.
+    /// Zero length means the end of file (and beyond).
+    SetLk {
+        des: FileDescriptorIndex,
+        lock_type: LockType,
+        start: u64,
+        len: u64,
+    },
+    /// Open file description lock (`F_OFD_SETLK`).
+    /// Zero length means the end of file (and beyond).
+    OfdSetLk {
+        des: FileDescriptorIndex,
+        lock_type: LockType,
+        start: u64,
+        len: u64,
+    },
+    /// Opens directory descriptor, relative path is resolved from the directory.
+    OpenDir {
+        dir: DirFd,
+        path: PathName,
+        flags: OpenFlags,
+        des: DirDescriptorIndex,
+    },
+    CloseDir {
+        des: DirDescriptorIndex,
+    },
+    /// Relative path is resolved from the current working directory.
+    ChDir {
+        path: PathName,
+    },
+    FChDir {
+        des: DirDescriptorIndex,
+    },
+    MkDirAt {
+        dir: DirFd,
+        path: PathName,
+        mode: Mode,
+    },
+    OpenAt {
+        dir: DirFd,
+        path: PathName,
+        flags: OpenFlags,
+        /// Only used if file is created (`O_CREAT`).
+        mode: Mode,
+        des: FileDescriptorIndex,
+    },
+    UnlinkAt {
+        dir: DirFd,
+        path: PathName,
+        flags: AtFlags,
+    },
+    RenameAt {
+        old_dir: DirFd,
+        old_path: PathName,
+        new_dir: DirFd,
+        new_path: PathName,
+    },
+    /// Path is empty if `AT_EMPTY_PATH` is used.
+    FStatAt {
+        dir: DirFd,
+        path: PathName,
+        flags: AtFlags,
+    },
+    /// Only permission bits of mask are used.
+    Umask {
+        mask: Mode,
+    },
+    /// Sets casefold attribute (`FS_CASEFOLD_FL`) of the empty directory.
+    SetCasefold {
+        path: PathName,
+    },
 }
 
 #[derive(PartialEq, Eq, Hash, Serialize, Deserialize, Clone, Copy)]
@@ -67,6 +350,63 @@ pub enum OperationKind {
     Write,
     FSync,
     Symlink,
+    Truncate,
+    FTruncate,
+    LSeek,
+    PRead,
+    PWrite,
+    Dup,
+    Dup2,
+    Dup3,
+    SetXattr,
+    GetXattr,
+    ListXattr,
+    RemoveXattr,
+    Chmod,
+    FChmod,
+    Chown,
+    FChown,
+    UTimeNsAt,
+    FUTimeNs,
+    FAllocate,
+    MMap,
+    MWrite,
+    MSync,
+    MUnmap,
+    RenameAt2,
+    /// `renameat2` with `RENAME_WHITEOUT`, which is supported by some filesystems only.
+    RenameWhiteout,
+    FLink,
+    MkNod,
+    ReadDir,
+    Stat,
+    LStat,
+    FStat,
+    ReadLink,
+    CopyFileRange,
+    SendFile,
+    Splice,
+    FIClone,
+    FICloneRange,
+    FIDedupeRange,
+    FDataSync,
+    Sync,
+    SyncFs,
+    SyncFileRange,
+    Flock,
+    SetLk,
+    OfdSetLk,
+    OpenDir,
+    CloseDir,
+    ChDir,
+    FChDir,
+    MkDirAt,
+    OpenAt,
+    UnlinkAt,
+    RenameAt,
+    FStatAt,
+    Umask,
+    SetCasefold,
 }
 
 impl From<&Operation> for OperationKind {
@@ -83,6 +423,64 @@ impl From<&Operation> for OperationKind {
             Operation::Write { .. } => Self::Write,
             Operation::FSync { .. } => Self::FSync,
             Operation::Symlink { .. } => Self::Symlink,
+            Operation::Truncate { .. } => Self::Truncate,
+            Operation::FTruncate { .. } => Self::FTruncate,
+            Operation::LSeek { .. } => Self::LSeek,
+            Operation::PRead { .. } => Self::PRead,
+            Operation::PWrite { .. } => Self::PWrite,
+            Operation::Dup { .. } => Self::Dup,
+            Operation::Dup2 { .. } => Self::Dup2,
+            Operation::Dup3 { .. } => Self::Dup3,
+            Operation::SetXattr { .. } => Self::SetXattr,
+            Operation::GetXattr { .. } => Self::GetXattr,
+            Operation::ListXattr { .. } => Self::ListXattr,
+            Operation::RemoveXattr { .. } => Self::RemoveXattr,
+            Operation::Chmod { .. } => Self::Chmod,
+            Operation::FChmod { .. } => Self::FChmod,
+            Operation::Chown { .. } => Self::Chown,
+            Operation::FChown { .. } => Self::FChown,
+            Operation::UTimeNsAt { .. } => Self::UTimeNsAt,
+            Operation::FUTimeNs { .. } => Self::FUTimeNs,
+            Operation::FAllocate { .. } => Self::FAllocate,
+            Operation::MMap { .. } => Self::MMap,
+            Operation::MWrite { .. } => Self::MWrite,
+            Operation::MSync { .. } => Self::MSync,
+            Operation::MUnmap { .. } => Self::MUnmap,
+            Operation::RenameAt2 { flags, .. } if flags.contains(&RenameFlag::RENAME_WHITEOUT) => {
+                Self::RenameWhiteout
+            }
+            Operation::RenameAt2 { .. } => Self::RenameAt2,
+            Operation::FLink { .. } => Self::FLink,
+            Operation::MkNod { .. } => Self::MkNod,
+            Operation::ReadDir { .. } => Self::ReadDir,
+            Operation::Stat { .. } => Self::Stat,
+            Operation::LStat { .. } => Self::LStat,
+            Operation::FStat { .. } => Self::FStat,
+            Operation::ReadLink { .. } => Self::ReadLink,
+            Operation::CopyFileRange { .. } => Self::CopyFileRange,
+            Operation::SendFile { .. } => Self::SendFile,
+            Operation::Splice { .. } => Self::Splice,
+            Operation::FIClone { .. } => Self::FIClone,
+            Operation::FICloneRange { .. } => Self::FICloneRange,
+            Operation::FIDedupeRange { .. } => Self::FIDedupeRange,
+            Operation::FDataSync { .. } => Self::FDataSync,
+            Operation::Sync => Self::Sync,
+            Operation::SyncFs { .. } => Self::SyncFs,
+            Operation::SyncFileRange { .. } => Self::SyncFileRange,
+            Operation::Flock { .. } => Self::Flock,
+            Operation::SetLk { .. } => Self::SetLk,
+            Operation::OfdSetLk { .. } => Self::OfdSetLk,
+            Operation::OpenDir { .. } => Self::OpenDir,
+            Operation::CloseDir { .. } => Self::CloseDir,
+            Operation::ChDir { .. } => Self::ChDir,
+            Operation::FChDir { .. } => Self::FChDir,
+            Operation::MkDirAt { .. } => Self::MkDirAt,
+            Operation::OpenAt { .. } => Self::OpenAt,
+            Operation::UnlinkAt { .. } => Self::UnlinkAt,
+            Operation::RenameAt { .. } => Self::RenameAt,
+            Operation::FStatAt { .. } => Self::FStatAt,
+            Operation::Umask { .. } => Self::Umask,
+            Operation::SetCasefold { .. } => Self::SetCasefold,
         }
     }
 }
@@ -106,6 +504,11 @@ impl OperationWeights {
         Self { weights }
     }
 
+    /// Excludes operations, e.g. not supported by filesystem.
+    pub fn exclude(&mut self, kinds: &[OperationKind]) {
+        self.weights.retain(|(kind, _)| !kinds.contains(kind));
+    }
+
     pub fn uniform() -> Self {
         Self {
             weights: vec![
@@ -120,6 +523,62 @@ impl OperationWeights {
                 (OperationKind::Write, 100),
                 (OperationKind::FSync, 100),
                 (OperationKind::Symlink, 100),
+                (OperationKind::Truncate, 100),
+                (OperationKind::FTruncate, 100),
+                (OperationKind::LSeek, 100),
+                (OperationKind::PRead, 100),
+                (OperationKind::PWrite, 100),
+                (OperationKind::Dup, 100),
+                (OperationKind::Dup2, 100),
+                (OperationKind::Dup3, 100),
+                (OperationKind::SetXattr, 100),
+                (OperationKind::GetXattr, 100),
+                (OperationKind::ListXattr, 100),
+                (OperationKind::RemoveXattr, 100),
+                (OperationKind::Chmod, 100),
+                (OperationKind::FChmod, 100),
+                (OperationKind::Chown, 100),
+                (OperationKind::FChown, 100),
+                (OperationKind::UTimeNsAt, 100),
+                (OperationKind::FUTimeNs, 100),
+                (OperationKind::FAllocate, 100),
+                (OperationKind::MMap, 100),
+                (OperationKind::MWrite, 100),
+                (OperationKind::MSync, 100),
+                (OperationKind::MUnmap, 100),
+                (OperationKind::RenameAt2, 100),
+                (OperationKind::RenameWhiteout, 100),
+                (OperationKind::FLink, 100),
+                (OperationKind::MkNod, 100),
+                (OperationKind::ReadDir, 100),
+                (OperationKind::Stat, 100),
+                (OperationKind::LStat, 100),
+                (OperationKind::FStat, 100),
+                (OperationKind::ReadLink, 100),
+                (OperationKind::CopyFileRange, 100),
+                (OperationKind::SendFile, 100),
+                (OperationKind::Splice, 100),
+                (OperationKind::FIClone, 100),
+                (OperationKind::FICloneRange, 100),
+                (OperationKind::FIDedupeRange, 100),
+                (OperationKind::FDataSync, 100),
+                (OperationKind::Sync, 100),
+                (OperationKind::SyncFs, 100),
+                (OperationKind::SyncFileRange, 100),
+                (OperationKind::Flock, 100),
+                (OperationKind::SetLk, 100),
+                (OperationKind::OfdSetLk, 100),
+                (OperationKind::OpenDir, 100),
+                (OperationKind::CloseDir, 100),
+                (OperationKind::ChDir, 100),
+                (OperationKind::FChDir, 100),
+                (OperationKind::MkDirAt, 100),
+                (OperationKind::OpenAt, 100),
+                (OperationKind::UnlinkAt, 100),
+                (OperationKind::RenameAt, 100),
+                (OperationKind::FStatAt, 100),
+                (OperationKind::Umask, 100),
+                (OperationKind::SetCasefold, 100),
             ],
         }
     }
@@ -59,7 +59,22 @@ impl PathName {
         (!self.0.ends_with('/') || self.is_root()) && self.0.starts_with('/') && !self.0.is_empty()
     }
 
+    /// Symbolic link targets can be relative, other paths are always absolute.
+    pub fn is_absolute(&self) -> bool {
+        self.0.starts_with('/')
+    }
+
     pub fn is_root(&self) -> bool {
         self.0 == "/"
     }
+
+    /// Length in bytes.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Only used with `AT_EMPTY_PATH`.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
 }
@@ -37,6 +37,9 @@ pub struct Errno {
 
 pub const TRACE_FILENAME: &str = "trace.csv";
 
+/// Linux error code of operation that is not supported by filesystem.
+const EOPNOTSUPP: i32 = 95;
+
 type Result<T> = std::result::Result<T, TraceError>;
 
 #[derive(Error, Debug, PartialEq)]
@@ -110,12 +113,62 @@ impl Trace {
             .collect()
     }
 
+    /// Errors that happened before the first unsupported operation.
+    /// State of filesystem is not predicted by model after it
+    /// (e.g. descriptor opened with `O_TMPFILE` is not valid).
+    pub fn supported_errors(&self) -> Vec<TraceRow> {
+        self.rows
+            .iter()
+            .take_while(|row| !row.is_unsupported())
+            .filter(|row| row.errno.code != 0)
+            .cloned()
+            .collect()
+    }
+
     pub fn header() -> String {
         "Index,Command,ReturnCode,Errno,Extra".to_owned()
     }
 }
 
+/// Escapes value of the last column the same way as executor does
+/// (backslash, comma, whitespace and control characters become `\xHH`).
+pub fn escape_csv(value: &str) -> String {
+    let mut result = String::new();
+    for c in value.chars() {
+        if c == '\\' || c == ',' || c <= ' ' || c == '\x7f' {
+            result.push_str(&format!("\\x{:02x}", c as u8));
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Reverses [`escape_csv`], `None` if escape sequence is invalid.
+pub fn unescape_csv(value: &str) -> Option<String> {
+    let mut bytes = vec![];
+    let mut rest = value.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'\\' {
+            let hex = tail.get(..3).filter(|hex| hex[0] == b'x')?;
+            let hex = std::str::from_utf8(&hex[1..]).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[3..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
 impl TraceRow {
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+    pub fn extra(&self) -> &str {
+        &self.extra
+    }
     pub fn ignore_index_equal(&self, other: &TraceRow) -> bool {
         self.command == other.command
             && self.return_code == other.return_code
@@ -125,12 +178,25 @@ impl TraceRow {
     pub fn source(&self) -> String {
         self.source.clone()
     }
+    /// Operation is not supported by filesystem (e.g. some modes of `fallocate`).
+    pub fn is_unsupported(&self) -> bool {
+        self.errno.code == EOPNOTSUPP
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_escape_csv() {
+        let value = "a b,\\c\n\u{e9}";
+        assert_eq!("a\\x20b\\x2c\\x5cc\\x0a\u{e9}", escape_csv(value));
+        assert_eq!(Some(value.to_owned()), unescape_csv(&escape_csv(value)));
+        assert_eq!(None, unescape_csv("a\\x2"));
+        assert_eq!(None, unescape_csv("a\\y20"));
+    }
+
     #[test]
     fn test_empty_trace() {
         assert_eq!(Err(TraceError::Empty), Trace::try_parse("".to_owned()))
@@ -210,4 +276,19 @@ Index,Command,ReturnCode,Errno,Extra
             Trace::try_parse(trace.to_owned())
         )
     }
+
+    #[test]
+    fn test_unsupported() {
+        let trace = r#"
+Index,Command,ReturnCode,Errno,Extra
+    1,   FALLOCATE,       -1,Operation not supported(95),
+    2,   FALLOCATE,       -1,Invalid argument(22),
+"#
+        .trim();
+        let trace = Trace::try_parse(trace.to_owned()).unwrap();
+        assert!(trace.rows[0].is_unsupported());
+        assert!(!trace.rows[1].is_unsupported());
+        assert_eq!(2, trace.errors().len());
+        assert!(trace.supported_errors().is_empty());
+    }
 }
@@ -4,12 +4,16 @@
 
 use serde::{Deserialize, Serialize};
 
-use crate::abstract_fs::{mutator::MutationWeights, operation::OperationWeights};
+use crate::abstract_fs::{
+    fs::Credentials, mutator::MutationWeights, name::NameWeights, operation::OperationWeights,
+};
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Config {
     pub greybox: GreyboxConfig,
     pub operation_weights: OperationWeights,
+    /// Strategies of generating names of new entries
+    pub name_weights: NameWeights,
     pub mutation_weights: MutationWeights,
     pub max_workload_length: u16,
     /// Filesystem name that is used for mountpoint path
@@ -20,6 +24,8 @@ pub struct Config {
     pub timeout: u8,
     pub qemu: QemuConfig,
     pub dash: DashConfig,
+    pub timestamps: TimestampsConfig,
+    pub user: UserConfig,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -73,6 +79,39 @@ pub struct DashConfig {
     pub file_hardlink: bool,
     /// Consider hardlinks for directories when comparing
     pub dir_hardlink: bool,
-    /// Consider mode when comparing
-    pub mode: bool
+    /// Consider mode when comparing, permission bits are also checked against the model
+    pub mode: bool,
+    /// Consider extended attributes when comparing
+    pub xattr: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TimestampsConfig {
+    /// Check timestamp updates against the model
+    pub enabled: bool,
+    /// Updated timestamps must differ from previous values (holds for fine-grained timestamps)
+    pub strict: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct UserConfig {
+    /// Drop privileges of root after setup, so permission bits are checked by filesystem
+    pub unprivileged: bool,
+    /// User that test is executed with (if unprivileged)
+    pub uid: u32,
+    /// Group that test is executed with (if unprivileged)
+    pub gid: u32,
+}
+
+impl UserConfig {
+    pub fn credentials(&self) -> Credentials {
+        if self.unprivileged {
+            Credentials {
+                uid: self.uid,
+                gid: self.gid,
+            }
+        } else {
+            Credentials::ROOT
+        }
+    }
 }
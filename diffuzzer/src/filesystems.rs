@@ -2,7 +2,12 @@
  * https://creativecommons.org/publicdomain/zero/1.0/ */
 
 use crate::mount::{
-    FileSystemMount, bcachefs::BcacheFS, btrfs::Btrfs, ext4::Ext4, f2fs::F2FS, littlefs::LittleFS,
+    FileSystemMount,
+    bcachefs::BcacheFS,
+    btrfs::Btrfs,
+    ext4::{Ext4, Ext4Casefold},
+    f2fs::{F2FS, F2FSCasefold},
+    littlefs::LittleFS,
     xfs::Xfs,
 };
 
@@ -13,6 +18,8 @@ pub const FILESYSTEMS: &[&dyn FileSystemMount] = &[
     &Xfs::new(),
     &LittleFS::new(),
     &BcacheFS::new(),
+    &Ext4Casefold::new(),
+    &F2FSCasefold::new(),
     // your filesystem here
 ];
 
@@ -55,7 +55,7 @@ impl BlackBoxFuzzer {
     }
 
     pub fn create(
-        config: Config,
+        mut config: Config,
         fst_mount: &'static dyn FileSystemMount,
         snd_mount: &'static dyn FileSystemMount,
         crashes_path: LocalPath,
@@ -65,6 +65,12 @@ impl BlackBoxFuzzer {
         broker: BrokerHandle,
     ) -> anyhow::Result<Self> {
         let heartbeat_interval = config.heartbeat_interval;
+        config
+            .operation_weights
+            .exclude(&fst_mount.unsupported_operations());
+        config
+            .operation_weights
+            .exclude(&snd_mount.unsupported_operations());
         let runner = Runner::create(
             fst_mount,
             snd_mount,
@@ -96,11 +102,13 @@ impl Fuzzer for BlackBoxFuzzer {
             &mut self.rng,
             self.runner.config.max_workload_length.into(),
             &self.runner.config.operation_weights,
+            &self.runner.config.name_weights,
+            self.runner.config.user.credentials(),
         );
 
         let binary_path = self.runner().compile_test(&input)?;
 
-        match self.runner().run_harness(&binary_path)? {
+        match self.runner().run_harness(&input, &binary_path)? {
             DiffOutcome::DiffCompleted(diff) => {
                 if self.detect_errors(&input, &binary_path, &diff)? {
                     return Ok(());
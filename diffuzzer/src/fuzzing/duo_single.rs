@@ -69,7 +69,7 @@ impl Fuzzer for DuoSingleFuzzer {
 
         let binary_path = self.runner().compile_test(&input)?;
 
-        match self.runner().run_harness(&binary_path)? {
+        match self.runner().run_harness(&input, &binary_path)? {
             DiffOutcome::DiffCompleted(diff) => {
                 if self.detect_errors(&input, &binary_path, &diff)? {
                     return Ok(());
@@ -5,7 +5,11 @@
 use anyhow::Context;
 use log::warn;
 
-use crate::{abstract_fs::workload::Workload, path::RemotePath, reason::Reason};
+use crate::{
+    abstract_fs::{trace::TraceRow, workload::Workload},
+    path::RemotePath,
+    reason::Reason,
+};
 
 use super::{outcome::DiffCompleted, runner::Runner};
 
@@ -50,6 +54,21 @@ pub trait Fuzzer {
                 reason.md.heading("Dash Difference Found".to_owned());
                 reason.add_dash_diff(&diff.dash_diff);
             }
+            if diff.mode_interesting() {
+                reason.md.heading("Mode Mismatch Found".to_owned());
+                reason.add_mode_diff(&runner.fst_fs_name, &diff.fst_mode_diff);
+                reason.add_mode_diff(&runner.snd_fs_name, &diff.snd_mode_diff);
+            }
+            if diff.timestamps_interesting() {
+                reason.md.heading("Timestamp Violation Found".to_owned());
+                reason.add_timestamps_diff(&runner.fst_fs_name, &diff.fst_timestamps_diff);
+                reason.add_timestamps_diff(&runner.snd_fs_name, &diff.snd_timestamps_diff);
+            }
+            if diff.extra_interesting() {
+                reason.md.heading("Model Mismatch Found".to_owned());
+                reason.add_extra_diff(&runner.fst_fs_name, &diff.fst_extra_diff);
+                reason.add_extra_diff(&runner.snd_fs_name, &diff.snd_extra_diff);
+            }
             let dir_name = input.generate_name();
             runner
                 .report_diff(
@@ -75,8 +94,9 @@ pub trait Fuzzer {
         binary_path: &RemotePath,
         diff: &DiffCompleted,
     ) -> anyhow::Result<bool> {
-        let fst_errors = diff.fst_trace.errors();
-        let snd_errors = diff.snd_trace.errors();
+        // Model does not know which operations are supported by filesystem.
+        let fst_errors: Vec<TraceRow> = diff.fst_trace.supported_errors();
+        let snd_errors: Vec<TraceRow> = diff.snd_trace.supported_errors();
 
         if !fst_errors.is_empty() && !snd_errors.is_empty() {
             let reason_str = "Both traces contain errors, potential bug in model".to_owned();
@@ -84,7 +84,7 @@ impl GreyBoxFuzzer {
     }
 
     pub fn create(
-        config: Config,
+        mut config: Config,
         fst_mount: &'static dyn FileSystemMount,
         snd_mount: &'static dyn FileSystemMount,
         crashes_path: LocalPath,
@@ -94,12 +94,20 @@ impl GreyBoxFuzzer {
         local_tmp_dir: LocalPath,
         broker: BrokerHandle,
     ) -> anyhow::Result<Self> {
+        config
+            .operation_weights
+            .exclude(&fst_mount.unsupported_operations());
+        config
+            .operation_weights
+            .exclude(&snd_mount.unsupported_operations());
         let mutator = Mutator::new(
             StdRng::seed_from_u64(SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64),
             config.operation_weights.clone(),
+            config.name_weights.clone(),
             config.mutation_weights.clone(),
             config.max_workload_length,
             config.greybox.max_mutations,
+            config.user.credentials(),
         );
 
         let mut initial_corpus = Vec::new();
@@ -283,7 +291,7 @@ impl Fuzzer for GreyBoxFuzzer {
 
         let binary_path = self.runner().compile_test(&input)?;
 
-        match self.runner().run_harness(&binary_path)? {
+        match self.runner().run_harness(&input, &binary_path)? {
             DiffOutcome::DiffCompleted(diff) => {
                 if self.detect_errors(&input, &binary_path, &diff)? {
                     return Ok(());
@@ -5,7 +5,9 @@
 use rand::{rngs::StdRng, seq::IndexedRandom, Rng};
 
 use crate::abstract_fs::{
+    fs::Credentials,
     mutator::{MutationKind, MutationWeights, insert, remove},
+    name::NameWeights,
     operation::OperationWeights,
     workload::Workload,
 };
@@ -13,25 +15,31 @@ use crate::abstract_fs::{
 pub struct Mutator {
     rng: StdRng,
     operation_weights: OperationWeights,
+    name_weights: NameWeights,
     mutation_weights: MutationWeights,
     max_length: u16,
     max_mutations: u16,
+    credentials: Credentials,
 }
 
 impl Mutator {
     pub fn new(
         rng: StdRng,
         operation_weights: OperationWeights,
+        name_weights: NameWeights,
         mutation_weights: MutationWeights,
         max_length: u16,
         max_mutations: u16,
+        credentials: Credentials,
     ) -> Self {
         Self {
             rng,
             operation_weights,
+            name_weights,
             mutation_weights,
             max_length,
             max_mutations,
+            credentials,
         }
     }
 }
@@ -68,8 +76,14 @@ impl Mutator {
         {
             MutationKind::Insert => {
                 let index = self.rng.random_range(0..=input.ops.len());
-                if let Some(workload) = insert(&mut self.rng, input, index, &self.operation_weights)
-                {
+                if let Some(workload) = insert(
+                    &mut self.rng,
+                    input,
+                    index,
+                    &self.operation_weights,
+                    &self.name_weights,
+                    self.credentials,
+                ) {
                     *input = workload;
                     true
                 } else {
@@ -78,7 +92,7 @@ impl Mutator {
             }
             MutationKind::Remove => {
                 let index = self.rng.random_range(0..input.ops.len());
-                if let Some(workload) = remove(input, index) {
+                if let Some(workload) = remove(input, index, self.credentials) {
                     *input = workload;
                     true
                 } else {
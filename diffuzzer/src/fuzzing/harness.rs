@@ -4,7 +4,9 @@
 
 use anyhow::{Context, bail};
 
+use crate::abstract_fs::fs::Credentials;
 use crate::command::{CommandInterface, CommandWrapper, ExecError};
+use crate::config::Config;
 use crate::mount::FileSystemMount;
 use crate::path::{LocalPath, RemotePath};
 use crate::supervisor::Supervisor;
@@ -18,9 +20,27 @@ pub struct Harness {
     exec_dir: RemotePath,
     outcome_dir: LocalPath,
     timeout: u8,
+    options: ExecutorOptions,
     observers: ObserverList,
 }
 
+/// Options of the executor that are passed as arguments.
+pub struct ExecutorOptions {
+    /// Test drops privileges to the user after setup.
+    pub credentials: Credentials,
+    /// Executor saves timestamps after every operation.
+    pub timestamps: bool,
+}
+
+impl ExecutorOptions {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            credentials: config.user.credentials(),
+            timestamps: config.timestamps.enabled,
+        }
+    }
+}
+
 impl Harness {
     pub fn new(
         fs_mount: &'static dyn FileSystemMount,
@@ -28,6 +48,7 @@ impl Harness {
         exec_dir: RemotePath,
         outcome_dir: LocalPath,
         timeout: u8,
+        options: ExecutorOptions,
         observers: ObserverList,
     ) -> Self {
         Self {
@@ -36,6 +57,7 @@ impl Harness {
             exec_dir,
             outcome_dir,
             timeout,
+            options,
             observers,
         }
     }
@@ -67,7 +89,15 @@ impl Harness {
         }
 
         let mut exec = CommandWrapper::new(binary_path.base.as_ref());
+        if self.options.timestamps {
+            exec.arg("--timestamps");
+        }
         exec.arg(self.fs_dir.base.as_ref());
+        let credentials = &self.options.credentials;
+        if !credentials.is_root() {
+            exec.arg(credentials.uid.to_string())
+                .arg(credentials.gid.to_string());
+        }
 
         let output = cmdi.exec_in_dir(exec, &self.exec_dir, Some(self.timeout));
 
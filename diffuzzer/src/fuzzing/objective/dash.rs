@@ -2,16 +2,49 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, fmt::Display, rc::Rc};
 
-use crate::{config::Config, fuzzing::observer::dash::DashObserver};
+use crate::{
+    abstract_fs::{
+        flags::{S_IFDIR, S_IFLNK, S_IFMT, S_IFREG},
+        fs::{AbstractFS, Credentials},
+        node::Node,
+        workload::Workload,
+    },
+    config::Config,
+    fuzzing::observer::dash::DashObserver,
+};
 
-use dash::{FileDiff, HasherOptions, get_diff};
+use dash::{FileDiff, FileInfo, HasherOptions, get_diff};
+
+/// Permission bits of `st_mode` that are modeled.
+const PERMISSION_BITS: u32 = 0o7777;
+
+/// File type and permission bits of file that differ from the expectation of the model.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModeDiff {
+    pub path: String,
+    pub expected: u32,
+    pub actual: u32,
+}
+
+impl Display for ModeDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "'{}': expected {:06o}, actual {:06o}",
+            self.path, self.expected, self.actual
+        )
+    }
+}
 
 pub struct DashObjective {
     fst_observer: Rc<RefCell<DashObserver>>,
     snd_observer: Rc<RefCell<DashObserver>>,
     enabled: bool,
+    /// Check modes against the model (see [`DashObjective::mode_diff`]).
+    mode: bool,
+    credentials: Credentials,
     hasher_options: HasherOptions,
 }
 
@@ -23,6 +56,8 @@ impl DashObjective {
     ) -> Self {
         Self {
             enabled: config.dash.enabled,
+            mode: config.dash.mode,
+            credentials: config.user.credentials(),
             hasher_options: Default::default(),
             fst_observer,
             snd_observer,
@@ -46,4 +81,66 @@ impl DashObjective {
             &self.hasher_options,
         )
     }
+
+    /// Compares file type and permission bits of files, directories and special files
+    /// with the expectation of the model.
+    /// Returns differences for the first and the second filesystem.
+    pub fn mode_diff(&self, input: &Workload) -> (Vec<ModeDiff>, Vec<ModeDiff>) {
+        if !self.enabled || !self.mode {
+            return (vec![], vec![]);
+        }
+        let mut fs = AbstractFS::with_credentials(self.credentials);
+        if fs.replay(input).is_err() {
+            return (vec![], vec![]);
+        }
+        let alive = fs.alive();
+        // Dash paths are relative to the workspace root.
+        let expected: HashMap<String, u32> = alive
+            .dirs
+            .iter()
+            .filter(|(idx, _)| *idx != AbstractFS::root_index())
+            .map(|(_, path)| path)
+            .chain(alive.files.iter().map(|(_, path)| path))
+            .chain(alive.specials.iter().map(|(_, path)| path))
+            .filter_map(|path| {
+                let meta = fs.resolve_metadata(path.clone()).ok()?;
+                let file_type = match fs.resolve_node(path.clone(), true).ok()?.1 {
+                    Node::Dir(_) => S_IFDIR,
+                    Node::File(_) => S_IFREG,
+                    Node::Special(idx) => fs.special(&idx).file_type as u32,
+                    Node::Symlink(_) => return None,
+                };
+                Some((
+                    path.to_string().trim_start_matches('/').to_owned(),
+                    file_type | meta.mode,
+                ))
+            })
+            .collect();
+        (
+            mode_diff(&expected, self.fst_observer.borrow().fs_state()),
+            mode_diff(&expected, self.snd_observer.borrow().fs_state()),
+        )
+    }
+}
+
+/// Paths that are not known to the model are skipped, as well as symbolic links
+/// (paths through symlinked directories are not visited by Dash).
+fn mode_diff(expected: &HashMap<String, u32>, fs_state: &[FileInfo]) -> Vec<ModeDiff> {
+    fs_state
+        .iter()
+        .filter(|info| info.mode() & S_IFMT != S_IFLNK)
+        .filter_map(|info| {
+            let expected = *expected.get(info.rel_path())?;
+            let actual = info.mode() & (S_IFMT | PERMISSION_BITS);
+            if expected == actual {
+                None
+            } else {
+                Some(ModeDiff {
+                    path: info.rel_path().to_owned(),
+                    expected,
+                    actual,
+                })
+            }
+        })
+        .collect()
 }
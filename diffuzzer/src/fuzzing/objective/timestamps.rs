@@ -0,0 +1,595 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    fs,
+    num::ParseIntError,
+};
+
+use anyhow::Context;
+use thiserror::Error;
+
+use crate::{
+    abstract_fs::{
+        fs::{AbstractFS, Credentials},
+        node::{DirIndex, FileIndex, Metadata, Node, SpecialIndex, Timestamp},
+        trace::unescape_csv,
+        workload::Workload,
+    },
+    config::Config,
+    path::LocalPath,
+};
+
+pub const TIMESTAMPS_FILENAME: &str = "timestamps.csv";
+
+/// Seconds and nanoseconds since the Epoch.
+pub type Time = (i64, u32);
+
+/// Timestamps of file or directory observed after operation.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TimestampsRow {
+    pub index: usize,
+    pub atime: Time,
+    pub mtime: Time,
+    pub ctime: Time,
+    /// Path relative to workspace, starts with '/'.
+    pub path: String,
+}
+
+type Result<T> = std::result::Result<T, TimestampsError>;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum TimestampsError {
+    #[error("invalid timestamps, must not be empty")]
+    Empty,
+    #[error("invalid column number")]
+    InvalidColumnNumber,
+    #[error("invalid integer format")]
+    IntParse(ParseIntError),
+    #[error("invalid time string '{0}'")]
+    InvalidTime(String),
+    #[error("invalid path string '{0}'")]
+    InvalidPath(String),
+}
+
+impl From<ParseIntError> for TimestampsError {
+    fn from(err: ParseIntError) -> TimestampsError {
+        TimestampsError::IntParse(err)
+    }
+}
+
+/// Path is the last column, so it can contain commas.
+pub fn parse_timestamps(timestamps: &str) -> Result<Vec<TimestampsRow>> {
+    let lines: Vec<&str> = timestamps.split('\n').collect();
+    if lines.len() <= 1 {
+        return Err(TimestampsError::Empty);
+    }
+    let mut rows = vec![];
+    for line in &lines[1..] {
+        if line.trim().is_empty() {
+            break;
+        }
+        let columns: Vec<&str> = line.splitn(5, ',').collect();
+        if columns.len() != 5 {
+            return Err(TimestampsError::InvalidColumnNumber);
+        }
+        rows.push(TimestampsRow {
+            index: columns[0].trim().parse()?,
+            atime: parse_time(columns[1])?,
+            mtime: parse_time(columns[2])?,
+            ctime: parse_time(columns[3])?,
+            path: unescape_csv(columns[4])
+                .ok_or(TimestampsError::InvalidPath(columns[4].to_owned()))?,
+        });
+    }
+    Ok(rows)
+}
+
+fn parse_time(time: &str) -> Result<Time> {
+    let (sec, nsec) = time
+        .trim()
+        .split_once('.')
+        .ok_or(TimestampsError::InvalidTime(time.to_owned()))?;
+    Ok((sec.parse()?, nsec.parse()?))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimestampField {
+    Atime,
+    Mtime,
+    Ctime,
+}
+
+impl TimestampField {
+    const ALL: [TimestampField; 3] = [
+        TimestampField::Atime,
+        TimestampField::Mtime,
+        TimestampField::Ctime,
+    ];
+
+    fn of_model(&self, meta: &Metadata) -> Timestamp {
+        match self {
+            TimestampField::Atime => meta.atime,
+            TimestampField::Mtime => meta.mtime,
+            TimestampField::Ctime => meta.ctime,
+        }
+    }
+
+    fn of_row(&self, row: &TimestampsRow) -> Time {
+        match self {
+            TimestampField::Atime => row.atime,
+            TimestampField::Mtime => row.mtime,
+            TimestampField::Ctime => row.ctime,
+        }
+    }
+}
+
+impl Display for TimestampField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimestampField::Atime => write!(f, "atime"),
+            TimestampField::Mtime => write!(f, "mtime"),
+            TimestampField::Ctime => write!(f, "ctime"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TimestampViolation {
+    /// Changed, but operation must not update it (access time is not checked).
+    Unexpected,
+    /// Updated, but older than timestamps set by previous operations.
+    Outdated,
+    /// Must be updated, but left unchanged (only checked in strict mode, except access time).
+    NotUpdated,
+    /// Not equal to the explicitly set number of seconds.
+    NotSet(i64),
+}
+
+/// Timestamp that violates update rules after the operation with the index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimestampDiff {
+    pub index: usize,
+    pub path: String,
+    pub field: TimestampField,
+    pub violation: TimestampViolation,
+}
+
+impl TimestampDiff {
+    pub fn ignore_index_equal(&self, other: &TimestampDiff) -> bool {
+        self.path == other.path && self.field == other.field && self.violation == other.violation
+    }
+}
+
+impl Display for TimestampDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{} '{}' {}: ", self.index, self.path, self.field)?;
+        match self.violation {
+            TimestampViolation::Unexpected => write!(f, "unexpected update"),
+            TimestampViolation::Outdated => write!(f, "older than previous updates"),
+            TimestampViolation::NotUpdated => write!(f, "not updated"),
+            TimestampViolation::NotSet(sec) => write!(f, "expected {}.000000000", sec),
+        }
+    }
+}
+
+/// Checks that timestamps are updated according to the model.
+/// Real values are not known, so only relative order of updates is compared.
+pub struct TimestampsObjective {
+    enabled: bool,
+    strict: bool,
+    credentials: Credentials,
+}
+
+impl TimestampsObjective {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            enabled: config.timestamps.enabled,
+            strict: config.timestamps.strict,
+            credentials: config.user.credentials(),
+        }
+    }
+
+    pub fn diff(
+        &self,
+        input: &Workload,
+        outcome_dir: &LocalPath,
+    ) -> anyhow::Result<Vec<TimestampDiff>> {
+        if !self.enabled {
+            return Ok(vec![]);
+        }
+        let timestamps = fs::read_to_string(outcome_dir.join(TIMESTAMPS_FILENAME))
+            .with_context(|| "failed to read timestamps")?;
+        let rows = parse_timestamps(&timestamps).with_context(|| "failed to parse timestamps")?;
+        Ok(check_timestamps(
+            input,
+            &rows,
+            self.strict,
+            self.credentials,
+        ))
+    }
+}
+
+/// Metadata of all files, directories and special files, including unnamed and removed ones.
+fn model_metadata(fs: &AbstractFS) -> BTreeMap<Node, Metadata> {
+    let dirs = fs
+        .dirs
+        .iter()
+        .enumerate()
+        .map(|(idx, dir)| (Node::Dir(DirIndex(idx)), dir.meta));
+    let files = fs
+        .files
+        .iter()
+        .enumerate()
+        .map(|(idx, file)| (Node::File(FileIndex(idx)), file.meta));
+    let specials = fs
+        .specials
+        .iter()
+        .enumerate()
+        .map(|(idx, special)| (Node::Special(SpecialIndex(idx)), special.meta));
+    dirs.chain(files).chain(specials).collect()
+}
+
+/// Replays workload on the model and compares every update with observed timestamps.
+/// Executor only observes nodes touched by the operation and their parents,
+/// so every node is compared with its last observation.
+/// Nodes that were not observed (e.g. removed while opened) are skipped.
+fn check_timestamps(
+    input: &Workload,
+    rows: &[TimestampsRow],
+    strict: bool,
+    credentials: Credentials,
+) -> Vec<TimestampDiff> {
+    let mut snapshots: HashMap<usize, Vec<&TimestampsRow>> = HashMap::new();
+    for row in rows {
+        snapshots.entry(row.index).or_default().push(row);
+    }
+
+    let mut diffs = vec![];
+    let mut fs = AbstractFS::with_credentials(credentials);
+    let mut prev_meta = model_metadata(&fs);
+    // Metadata of the model and observed timestamps at the last observation of node.
+    let mut last_observed: BTreeMap<Node, (Metadata, &TimestampsRow)> = BTreeMap::new();
+    // Latest observed time that was set by operations before the operation with the tick
+    // (root is created before the first operation).
+    let mut latest_before: Vec<Option<Time>> = vec![None];
+    let mut latest: Option<Time> = None;
+    for (index, op) in input.ops.iter().enumerate() {
+        latest_before.push(latest);
+        if fs
+            .replay(&Workload {
+                ops: vec![op.clone()],
+            })
+            .is_err()
+        {
+            break;
+        }
+        let Some(snapshot) = snapshots.get(&index) else {
+            break;
+        };
+        let model = model_metadata(&fs);
+        let observed: BTreeMap<Node, &TimestampsRow> = snapshot
+            .iter()
+            .filter_map(|row| {
+                let (_, node) = fs.resolve_node(row.path.as_str().into(), false).ok()?;
+                (!matches!(node, Node::Symlink(_))).then_some((node, *row))
+            })
+            .collect();
+
+        for (node, row) in observed.iter() {
+            let meta = &model[node];
+            let last = last_observed.get(node);
+            for field in TimestampField::ALL {
+                let expected = field.of_model(meta);
+                let actual = field.of_row(row);
+                let prev_actual = last.map(|(_, row)| field.of_row(row));
+                let updated = match last {
+                    Some((last_meta, _)) => field.of_model(last_meta) != expected,
+                    // File linked with `linkat` may have been created earlier.
+                    None => prev_meta
+                        .get(node)
+                        .is_none_or(|prev_meta| field.of_model(prev_meta) != expected),
+                };
+                let violation = if !updated {
+                    match prev_actual {
+                        Some(prev_actual)
+                            if field != TimestampField::Atime && prev_actual != actual =>
+                        {
+                            Some(TimestampViolation::Unexpected)
+                        }
+                        _ => None,
+                    }
+                } else {
+                    match expected {
+                        Timestamp::Now(tick)
+                            if latest_before[tick].is_some_and(|latest| actual < latest) =>
+                        {
+                            Some(TimestampViolation::Outdated)
+                        }
+                        Timestamp::Now(_)
+                            if strict
+                                && field != TimestampField::Atime
+                                && prev_actual == Some(actual) =>
+                        {
+                            Some(TimestampViolation::NotUpdated)
+                        }
+                        Timestamp::Seconds(sec) if actual != (sec, 0) => {
+                            Some(TimestampViolation::NotSet(sec))
+                        }
+                        _ => None,
+                    }
+                };
+                if let Some(violation) = violation {
+                    diffs.push(TimestampDiff {
+                        index,
+                        path: row.path.clone(),
+                        field,
+                        violation,
+                    });
+                }
+            }
+        }
+
+        for (node, row) in observed {
+            let meta = model[&node];
+            for field in [TimestampField::Mtime, TimestampField::Ctime] {
+                if let Timestamp::Now(_) = field.of_model(&meta) {
+                    latest = latest.max(Some(field.of_row(row)));
+                }
+            }
+            last_observed.insert(node, (meta, row));
+        }
+        prev_meta = model;
+    }
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::abstract_fs::{
+        flags::{OpenFlag, TimeSpec},
+        node::FileDescriptorIndex,
+        operation::Operation,
+    };
+
+    use super::*;
+
+    fn row(index: usize, path: &str, atime: i64, mtime: i64, ctime: i64) -> TimestampsRow {
+        TimestampsRow {
+            index,
+            atime: (atime, 0),
+            mtime: (mtime, 0),
+            ctime: (ctime, 0),
+            path: path.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_parse_timestamps() {
+        assert_eq!(Err(TimestampsError::Empty), parse_timestamps(""));
+        assert_eq!(
+            Ok(vec![
+                TimestampsRow {
+                    index: 0,
+                    atime: (1, 500),
+                    mtime: (2, 0),
+                    ctime: (3, 999999999),
+                    path: "/".to_owned(),
+                },
+                TimestampsRow {
+                    index: 1,
+                    atime: (1, 0),
+                    mtime: (2, 0),
+                    ctime: (3, 0),
+                    path: "/foo,bar".to_owned(),
+                },
+                TimestampsRow {
+                    index: 1,
+                    atime: (1, 0),
+                    mtime: (2, 0),
+                    ctime: (3, 0),
+                    path: "/foo\nbar baz".to_owned(),
+                },
+            ]),
+            parse_timestamps(
+                "Index,ATime,MTime,CTime,Path\n\
+                 0,1.000000500,2.000000000,3.999999999,/\n\
+                 1,1.000000000,2.000000000,3.000000000,/foo,bar\n\
+                 1,1.000000000,2.000000000,3.000000000,/foo\\x0abar\\x20baz\n"
+            )
+        );
+        assert_eq!(
+            Err(TimestampsError::InvalidPath("/\\x".to_owned())),
+            parse_timestamps("Index,ATime,MTime,CTime,Path\n0,1.0,2.0,3.0,/\\x\n")
+        );
+        assert_eq!(
+            Err(TimestampsError::InvalidTime("1".to_owned())),
+            parse_timestamps("Index,ATime,MTime,CTime,Path\n0,1,2.0,3.0,/\n")
+        );
+    }
+
+    #[test]
+    fn test_check_timestamps() {
+        let input = Workload {
+            ops: vec![
+                Operation::Create {
+                    path: "/foo".into(),
+                    mode: vec![],
+                },
+                Operation::Chmod {
+                    path: "/foo".into(),
+                    mode: vec![],
+                },
+                Operation::UTimeNsAt {
+                    path: "/foo".into(),
+                    atime: TimeSpec::Omit,
+                    mtime: TimeSpec::Seconds(1000),
+                },
+            ],
+        };
+        let valid = vec![
+            row(0, "/", 1, 10, 10),
+            row(0, "/foo", 10, 10, 10),
+            row(1, "/", 1, 10, 10),
+            row(1, "/foo", 10, 10, 11),
+            row(2, "/", 1, 10, 10),
+            row(2, "/foo", 10, 1000, 12),
+        ];
+        assert_eq!(
+            Vec::<TimestampDiff>::new(),
+            check_timestamps(&input, &valid, true, Credentials::ROOT)
+        );
+
+        let invalid = vec![
+            row(0, "/", 1, 10, 10),
+            row(0, "/foo", 10, 10, 10),
+            row(1, "/", 1, 10, 10),
+            row(1, "/foo", 10, 11, 9),
+            row(2, "/", 1, 10, 10),
+            row(2, "/foo", 10, 1001, 9),
+        ];
+        assert_eq!(
+            vec![
+                TimestampDiff {
+                    index: 1,
+                    path: "/foo".to_owned(),
+                    field: TimestampField::Mtime,
+                    violation: TimestampViolation::Unexpected,
+                },
+                TimestampDiff {
+                    index: 1,
+                    path: "/foo".to_owned(),
+                    field: TimestampField::Ctime,
+                    violation: TimestampViolation::Outdated,
+                },
+                TimestampDiff {
+                    index: 2,
+                    path: "/foo".to_owned(),
+                    field: TimestampField::Mtime,
+                    violation: TimestampViolation::NotSet(1000),
+                },
+                TimestampDiff {
+                    index: 2,
+                    path: "/foo".to_owned(),
+                    field: TimestampField::Ctime,
+                    violation: TimestampViolation::Outdated,
+                },
+            ],
+            check_timestamps(&input, &invalid, false, Credentials::ROOT)
+        );
+
+        let unchanged = vec![
+            row(0, "/", 1, 10, 10),
+            row(0, "/foo", 10, 10, 10),
+            row(1, "/", 1, 10, 10),
+            row(1, "/foo", 10, 10, 10),
+        ];
+        assert_eq!(
+            Vec::<TimestampDiff>::new(),
+            check_timestamps(&input, &unchanged, false, Credentials::ROOT)
+        );
+        assert_eq!(
+            vec![TimestampDiff {
+                index: 1,
+                path: "/foo".to_owned(),
+                field: TimestampField::Ctime,
+                violation: TimestampViolation::NotUpdated,
+            }],
+            check_timestamps(&input, &unchanged, true, Credentials::ROOT)
+        );
+    }
+
+    #[test]
+    fn test_check_timestamps_flink() {
+        let input = Workload {
+            ops: vec![
+                Operation::Open {
+                    path: "/".into(),
+                    flags: vec![OpenFlag::O_RDWR, OpenFlag::O_TMPFILE],
+                    mode: vec![],
+                    des: FileDescriptorIndex(0),
+                },
+                Operation::Create {
+                    path: "/bar".into(),
+                    mode: vec![],
+                },
+                Operation::FLink {
+                    des: FileDescriptorIndex(0),
+                    path: "/foo".into(),
+                },
+            ],
+        };
+        let rows = vec![
+            row(0, "/", 1, 1, 1),
+            row(1, "/", 1, 11, 11),
+            row(1, "/bar", 11, 11, 11),
+            row(2, "/", 1, 12, 12),
+            row(2, "/bar", 11, 11, 11),
+            row(2, "/foo", 10, 10, 12),
+        ];
+        assert_eq!(
+            Vec::<TimestampDiff>::new(),
+            check_timestamps(&input, &rows, true, Credentials::ROOT)
+        );
+    }
+
+    #[test]
+    fn test_check_timestamps_last_observation() {
+        let input = Workload {
+            ops: vec![
+                Operation::Create {
+                    path: "/foo".into(),
+                    mode: vec![],
+                },
+                Operation::Hardlink {
+                    old_path: "/foo".into(),
+                    new_path: "/bar".into(),
+                },
+                Operation::Remove {
+                    path: "/bar".into(),
+                },
+                Operation::Stat {
+                    path: "/foo".into(),
+                },
+            ],
+        };
+        // File is not observed when its link is removed.
+        let rows = |ctime| {
+            vec![
+                row(0, "/", 1, 10, 10),
+                row(0, "/foo", 10, 10, 10),
+                row(1, "/", 1, 11, 11),
+                row(1, "/foo", 10, 10, 11),
+                row(1, "/bar", 10, 10, 11),
+                row(2, "/", 1, 12, 12),
+                row(3, "/", 1, 12, 12),
+                row(3, "/foo", 10, 10, ctime),
+            ]
+        };
+        assert_eq!(
+            Vec::<TimestampDiff>::new(),
+            check_timestamps(&input, &rows(12), true, Credentials::ROOT)
+        );
+        assert_eq!(
+            vec![TimestampDiff {
+                index: 3,
+                path: "/foo".to_owned(),
+                field: TimestampField::Ctime,
+                violation: TimestampViolation::NotUpdated,
+            }],
+            check_timestamps(&input, &rows(11), true, Credentials::ROOT)
+        );
+        assert_eq!(
+            vec![TimestampDiff {
+                index: 3,
+                path: "/foo".to_owned(),
+                field: TimestampField::Ctime,
+                violation: TimestampViolation::Outdated,
+            }],
+            check_timestamps(&input, &rows(9), false, Credentials::ROOT)
+        );
+    }
+}
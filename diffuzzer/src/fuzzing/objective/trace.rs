@@ -2,14 +2,46 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use std::collections::HashSet;
+use std::fmt::Display;
+
+use crate::abstract_fs::fs::{AbstractFS, Credentials};
+use crate::abstract_fs::node::{Node, Stat};
+use crate::abstract_fs::operation::Operation;
 use crate::abstract_fs::trace::TraceDiff::{DifferentLength, TraceRowIsDifferent};
-use crate::abstract_fs::trace::{Trace, TraceDiff};
+use crate::abstract_fs::trace::{Trace, TraceDiff, TraceRow, escape_csv};
+use crate::abstract_fs::workload::Workload;
+
+/// Values of `d_type` field of directory entry.
+const DT_DIR: u8 = 4;
+const DT_REG: u8 = 8;
+const DT_LNK: u8 = 10;
+
+/// Extra column of operation that differs from the expectation of the model.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtraDiff {
+    pub index: usize,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl Display for ExtraDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "#{}: expected '{}', actual '{}'",
+            self.index, self.expected, self.actual
+        )
+    }
+}
 
-pub struct TraceObjective {}
+pub struct TraceObjective {
+    credentials: Credentials,
+}
 
 impl TraceObjective {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(credentials: Credentials) -> Self {
+        Self { credentials }
     }
 }
 
@@ -25,6 +57,10 @@ impl TraceObjective {
         for i in 0..fst_trace.rows.len() {
             let fst_row = fst_trace.rows[i].clone();
             let snd_row = snd_trace.rows[i].clone();
+            // Filesystem states diverge after operation that is supported by only one of them.
+            if fst_row.is_unsupported() != snd_row.is_unsupported() {
+                break;
+            }
             if fst_row != snd_row {
                 trace_diff.push(TraceRowIsDifferent {
                     fst: fst_row,
@@ -34,4 +70,389 @@ impl TraceObjective {
         }
         trace_diff
     }
+
+    /// Compares results of operations that observe filesystem state (e.g. directory listing)
+    /// with the expectation of the model.
+    pub fn model_diff(&self, input: &Workload, trace: &Trace) -> Vec<ExtraDiff> {
+        let mut diffs = vec![];
+        let mut fs = AbstractFS::with_credentials(self.credentials);
+        for (index, op) in input.ops.iter().enumerate() {
+            // Outcome of locking depends on the state before the operation.
+            let lock_expected = expected_lock_extra(&fs, op);
+            if fs
+                .replay(&Workload {
+                    ops: vec![op.clone()],
+                })
+                .is_err()
+            {
+                break;
+            }
+            let Some(expected) = lock_expected.or_else(|| expected_extra(&fs, op)) else {
+                continue;
+            };
+            let Some(row) = trace.rows.iter().find(|row| row.index() as usize == index) else {
+                break;
+            };
+            if row.extra() != expected {
+                diffs.push(ExtraDiff {
+                    index,
+                    expected,
+                    actual: row.extra().to_owned(),
+                });
+            }
+        }
+        diffs
+    }
+
+    /// Errors of operations that the model does not expect to fail.
+    pub fn unexpected_errors(&self, input: &Workload, trace: &Trace) -> Vec<TraceRow> {
+        let mut expected = HashSet::new();
+        let mut fs = AbstractFS::with_credentials(self.credentials);
+        for (index, op) in input.ops.iter().enumerate() {
+            match fs.replay_op(op) {
+                Ok(None) => {}
+                Ok(Some(_)) => {
+                    expected.insert(index);
+                }
+                Err(_) => break,
+            }
+        }
+        trace
+            .errors()
+            .into_iter()
+            .filter(|row| !expected.contains(&(row.index() as usize)))
+            .collect()
+    }
+
+    /// Some operation is supported by only one of filesystems, so their final states are not comparable.
+    pub fn diverged(&self, fst_trace: &Trace, snd_trace: &Trace) -> bool {
+        fst_trace
+            .rows
+            .iter()
+            .zip(snd_trace.rows.iter())
+            .any(|(fst, snd)| fst.is_unsupported() != snd.is_unsupported())
+    }
+}
+
+/// Extra column of the operation reported by executor, `None` if it is not predicted by the model.
+fn expected_extra(fs: &AbstractFS, op: &Operation) -> Option<String> {
+    match op {
+        Operation::ReadDir { path } => {
+            // Entries are sorted by name, name is followed by zero byte and type.
+            let entries = fs.dir_entries(path.clone()).ok()?;
+            let mut digest = vec![];
+            for (name, node) in entries.iter() {
+                digest.extend_from_slice(name.as_bytes());
+                digest.push(0);
+                digest.push(dirent_type(fs, node));
+            }
+            Some(format!(
+                "entries={} hash={:x}",
+                entries.len(),
+                buffer_hashcode(&digest)
+            ))
+        }
+        Operation::Stat { path } => fs.stat_path(path.clone(), true).ok().map(stat_extra),
+        Operation::LStat { path } => fs.stat_path(path.clone(), false).ok().map(stat_extra),
+        Operation::FStat { des } => fs.stat_descriptor(des).ok().map(stat_extra),
+        Operation::FStatAt { dir, path, flags } => {
+            fs.stat_at(dir, path, flags).ok().map(stat_extra)
+        }
+        Operation::ReadLink { path } => fs
+            .link_target(path.clone())
+            .ok()
+            .map(|target| format!("target={}", escape_csv(&target.to_string()))),
+        _ => None,
+    }
+}
+
+/// Extra column of locking operation, executor reports conflict instead of `EAGAIN`.
+fn expected_lock_extra(fs: &AbstractFS, op: &Operation) -> Option<String> {
+    let conflict = match op {
+        Operation::Flock { des, op } => fs.flock_conflicts(des, *op),
+        Operation::SetLk {
+            des,
+            lock_type,
+            start,
+            len,
+        } => fs.record_lock_conflicts(des, false, *lock_type, *start, *len),
+        Operation::OfdSetLk {
+            des,
+            lock_type,
+            start,
+            len,
+        } => fs.record_lock_conflicts(des, true, *lock_type, *start, *len),
+        _ => return None,
+    };
+    conflict
+        .ok()
+        .map(|conflict| if conflict { "conflict" } else { "" }.to_owned())
+}
+
+fn stat_extra(stat: Stat) -> String {
+    format!(
+        "type={:o} mode={:o} nlink={} size={}",
+        stat.file_type, stat.mode, stat.nlink, stat.size
+    )
+}
+
+fn dirent_type(fs: &AbstractFS, node: &Node) -> u8 {
+    match node {
+        Node::File(_) => DT_REG,
+        Node::Dir(_) => DT_DIR,
+        Node::Symlink(_) => DT_LNK,
+        // Same as `IFTODT`.
+        Node::Special(idx) => (fs.special(idx).file_type as u32 >> 12) as u8,
+    }
+}
+
+/// Same as `buffer_hashcode` of executor (hashcode algorithm from Java, `char` is signed).
+fn buffer_hashcode(buffer: &[u8]) -> u64 {
+    buffer.iter().fold(1, |h: u64, b| {
+        h.wrapping_mul(31).wrapping_add(*b as i8 as u64)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::abstract_fs::{
+        flags::{FileType, FlockOperation, LockType, ModeFlag, OpenFlag},
+        node::FileDescriptorIndex,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_model_diff_readdir() {
+        let workload = Workload {
+            ops: vec![
+                Operation::MkDir {
+                    path: "/d".into(),
+                    mode: vec![],
+                },
+                Operation::Create {
+                    path: "/a".into(),
+                    mode: vec![],
+                },
+                Operation::Symlink {
+                    target: "/d".into(),
+                    linkpath: "/s".into(),
+                },
+                Operation::MkNod {
+                    path: "/fifo".into(),
+                    mode: vec![],
+                    file_type: FileType::S_IFIFO,
+                },
+                Operation::ReadDir { path: "/".into() },
+                Operation::ReadDir { path: "/s".into() },
+            ],
+        };
+        let trace = |extra: &str| {
+            Trace::try_parse(
+                format!(
+                    r#"
+Index,Command,ReturnCode,Errno,Extra
+   0,       MKDIR,       0,Success(0),
+   1,      CREATE,       3,Success(0),
+   2,     SYMLINK,       0,Success(0),
+   3,       MKNOD,       0,Success(0),
+   4,     READDIR,       4,Success(0),{}
+   5,     READDIR,       0,Success(0),entries=0 hash=1
+"#,
+                    extra
+                )
+                .trim()
+                .to_owned(),
+            )
+            .unwrap()
+        };
+        let objective = TraceObjective::new(Credentials::ROOT);
+        assert_eq!(
+            Vec::<ExtraDiff>::new(),
+            objective.model_diff(&workload, &trace("entries=4 hash=d53fa23cb41f00f0"))
+        );
+        assert_eq!(
+            vec![ExtraDiff {
+                index: 4,
+                expected: "entries=4 hash=d53fa23cb41f00f0".to_owned(),
+                actual: "entries=3 hash=42".to_owned(),
+            }],
+            objective.model_diff(&workload, &trace("entries=3 hash=42"))
+        );
+    }
+
+    #[test]
+    fn test_model_diff_stat() {
+        let workload = Workload {
+            ops: vec![
+                Operation::Create {
+                    path: "/a".into(),
+                    mode: vec![ModeFlag::S_IRUSR, ModeFlag::S_IWUSR],
+                },
+                Operation::Open {
+                    path: "/a".into(),
+                    flags: vec![OpenFlag::O_RDWR],
+                    mode: vec![],
+                    des: FileDescriptorIndex(0),
+                },
+                Operation::Hardlink {
+                    old_path: "/a".into(),
+                    new_path: "/b".into(),
+                },
+                Operation::Symlink {
+                    target: "/a".into(),
+                    linkpath: "/s".into(),
+                },
+                Operation::Stat { path: "/s".into() },
+                Operation::LStat { path: "/s".into() },
+                Operation::Remove { path: "/a".into() },
+                Operation::FStat {
+                    des: FileDescriptorIndex(0),
+                },
+            ],
+        };
+        let trace = Trace::try_parse(
+            r#"
+Index,Command,ReturnCode,Errno,Extra
+   0,      CREATE,       3,Success(0),
+   1,        OPEN,       3,Success(0),
+   2,    HARDLINK,       0,Success(0),
+   3,     SYMLINK,       0,Success(0),
+   4,        STAT,       0,Success(0),type=100000 mode=600 nlink=2 size=0
+   5,       LSTAT,       0,Success(0),type=120000 mode=777 nlink=1 size=2
+   6,      UNLINK,       0,Success(0),
+   7,       FSTAT,       0,Success(0),type=100000 mode=600 nlink=2 size=0
+"#
+            .trim()
+            .to_owned(),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![ExtraDiff {
+                index: 7,
+                expected: "type=100000 mode=600 nlink=1 size=0".to_owned(),
+                actual: "type=100000 mode=600 nlink=2 size=0".to_owned(),
+            }],
+            TraceObjective::new(Credentials::ROOT).model_diff(&workload, &trace)
+        );
+    }
+
+    #[test]
+    fn test_model_diff_lock() {
+        let open = |des| Operation::Open {
+            path: "/a".into(),
+            flags: vec![OpenFlag::O_RDWR],
+            mode: vec![],
+            des: FileDescriptorIndex(des),
+        };
+        let workload = Workload {
+            ops: vec![
+                Operation::Create {
+                    path: "/a".into(),
+                    mode: vec![],
+                },
+                open(0),
+                open(1),
+                Operation::Flock {
+                    des: FileDescriptorIndex(0),
+                    op: FlockOperation::LOCK_EX,
+                },
+                Operation::Flock {
+                    des: FileDescriptorIndex(1),
+                    op: FlockOperation::LOCK_SH,
+                },
+                Operation::SetLk {
+                    des: FileDescriptorIndex(1),
+                    lock_type: LockType::F_WRLCK,
+                    start: 0,
+                    len: 0,
+                },
+                Operation::OfdSetLk {
+                    des: FileDescriptorIndex(0),
+                    lock_type: LockType::F_RDLCK,
+                    start: 100,
+                    len: 100,
+                },
+                Operation::Close {
+                    des: FileDescriptorIndex(0),
+                },
+                Operation::Flock {
+                    des: FileDescriptorIndex(1),
+                    op: FlockOperation::LOCK_SH,
+                },
+            ],
+        };
+        let trace = Trace::try_parse(
+            r#"
+Index,Command,ReturnCode,Errno,Extra
+   0,      CREATE,       3,Success(0),
+   1,        OPEN,       3,Success(0),
+   2,        OPEN,       4,Success(0),
+   3,       FLOCK,       0,Success(0),
+   4,       FLOCK,       0,Success(0),conflict
+   5,       SETLK,       0,Success(0),
+   6,   OFD_SETLK,       0,Success(0),conflict
+   7,       CLOSE,       0,Success(0),
+   8,       FLOCK,       0,Success(0),conflict
+"#
+            .trim()
+            .to_owned(),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![ExtraDiff {
+                index: 8,
+                expected: "".to_owned(),
+                actual: "conflict".to_owned(),
+            }],
+            TraceObjective::new(Credentials::ROOT).model_diff(&workload, &trace)
+        );
+    }
+
+    #[test]
+    fn test_unexpected_errors() {
+        let workload = Workload {
+            ops: vec![
+                Operation::Create {
+                    path: "/a".into(),
+                    mode: vec![],
+                },
+                Operation::Symlink {
+                    target: "/a".into(),
+                    linkpath: "/s".into(),
+                },
+                Operation::Open {
+                    path: "/s".into(),
+                    flags: vec![OpenFlag::O_RDWR, OpenFlag::O_NOFOLLOW],
+                    mode: vec![],
+                    des: FileDescriptorIndex(0),
+                },
+                Operation::Open {
+                    path: "/a".into(),
+                    flags: vec![OpenFlag::O_RDONLY, OpenFlag::O_DIRECTORY],
+                    mode: vec![],
+                    des: FileDescriptorIndex(0),
+                },
+                Operation::Remove { path: "/a".into() },
+            ],
+        };
+        let trace = Trace::try_parse(
+            r#"
+Index,Command,ReturnCode,Errno,Extra
+   0,      CREATE,       3,Success(0),
+   1,     SYMLINK,       0,Success(0),
+   2,        OPEN,      -1,Too many levels of symbolic links(40),
+   3,        OPEN,      -1,Not a directory(20),
+   4,      UNLINK,      -1,No such file or directory(2),
+"#
+            .trim()
+            .to_owned(),
+        )
+        .unwrap();
+        let errors = TraceObjective::new(Credentials::ROOT).unexpected_errors(&workload, &trace);
+        assert_eq!(
+            vec![4],
+            errors.iter().map(|row| row.index()).collect::<Vec<u32>>()
+        );
+    }
 }
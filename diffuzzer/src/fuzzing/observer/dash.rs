@@ -89,6 +89,7 @@ impl DashObserver {
         if config.dash.size { flags.push("-s".to_string()) }
         if config.dash.dir_hardlink { flags.push("-d".to_string()) }
         if config.dash.file_hardlink { flags.push("-f".to_string()) }
+        if config.dash.xattr { flags.push("-x".to_string()) }
 
         Ok(Self {
             dash_path,
@@ -9,6 +9,8 @@ use crate::{
     path::LocalPath,
 };
 
+use super::objective::{dash::ModeDiff, timestamps::TimestampDiff, trace::ExtraDiff};
+
 pub struct Completed {
     pub stdout: String,
     pub stderr: String,
@@ -38,6 +40,15 @@ pub enum Outcome {
 pub struct DiffCompleted {
     pub dash_diff: Vec<FileDiff>,
     pub trace_diff: Vec<TraceDiff>,
+    /// Modes that differ from the model expectation.
+    pub fst_mode_diff: Vec<ModeDiff>,
+    pub snd_mode_diff: Vec<ModeDiff>,
+    /// Timestamps that violate update rules of the model.
+    pub fst_timestamps_diff: Vec<TimestampDiff>,
+    pub snd_timestamps_diff: Vec<TimestampDiff>,
+    /// Results of operations that differ from the model expectation (e.g. directory listings).
+    pub fst_extra_diff: Vec<ExtraDiff>,
+    pub snd_extra_diff: Vec<ExtraDiff>,
     pub fst_outcome: Completed,
     pub snd_outcome: Completed,
     pub fst_trace: Trace,
@@ -46,7 +57,11 @@ pub struct DiffCompleted {
 
 impl DiffCompleted {
     pub fn any_interesting(&self) -> bool {
-        self.dash_interesting() || self.trace_interesting()
+        self.dash_interesting()
+            || self.trace_interesting()
+            || self.mode_interesting()
+            || self.timestamps_interesting()
+            || self.extra_interesting()
     }
 
     pub fn dash_interesting(&self) -> bool {
@@ -56,10 +71,22 @@ impl DiffCompleted {
     pub fn trace_interesting(&self) -> bool {
         !self.trace_diff.is_empty()
     }
+
+    pub fn mode_interesting(&self) -> bool {
+        !self.fst_mode_diff.is_empty() || !self.snd_mode_diff.is_empty()
+    }
+
+    pub fn timestamps_interesting(&self) -> bool {
+        !self.fst_timestamps_diff.is_empty() || !self.snd_timestamps_diff.is_empty()
+    }
+
+    pub fn extra_interesting(&self) -> bool {
+        !self.fst_extra_diff.is_empty() || !self.snd_extra_diff.is_empty()
+    }
 }
 
 pub enum DiffOutcome {
-    DiffCompleted(DiffCompleted),
+    DiffCompleted(Box<DiffCompleted>),
     FirstTimedOut { fs_name: String, timeout: u8 },
     SecondTimedOut { fs_name: String, timeout: u8 },
     FirstPanicked { fs_name: String },
@@ -10,7 +10,7 @@ use log::{info, warn};
 use crate::{
     abstract_fs::{mutator::remove, trace::TraceDiff, workload::Workload},
     config::Config,
-    fuzzing::outcome::DiffOutcome,
+    fuzzing::{objective::timestamps::TimestampDiff, outcome::DiffOutcome},
     mount::FileSystemMount,
     path::LocalPath,
     reason::Reason,
@@ -63,10 +63,10 @@ impl Reducer {
 
         let binary_path = self.runner.compile_test(&input)?;
 
-        match self.runner.run_harness(&binary_path)? {
+        match self.runner.run_harness(&input, &binary_path)? {
             DiffOutcome::DiffCompleted(diff) => {
                 if diff.any_interesting() {
-                    self.reduce_by_diff(input, output_dir, diff)?;
+                    self.reduce_by_diff(input, output_dir, *diff)?;
                 } else {
                     warn!("no diff found");
                 }
@@ -86,10 +86,14 @@ impl Reducer {
         let mut idx_to_remove = bugcase.ops.len() - 1;
         loop {
             info!("trying to remove operation at index {}", idx_to_remove);
-            if let Some(reduced) = remove(&bugcase, idx_to_remove) {
+            if let Some(reduced) = remove(
+                &bugcase,
+                idx_to_remove,
+                self.runner.config.user.credentials(),
+            ) {
                 let binary_path = self.runner.compile_test(&reduced)?;
                 let variation_name = format!("variation-{}", idx_to_remove);
-                match self.runner.run_harness(&binary_path)? {
+                match self.runner.run_harness(&reduced, &binary_path)? {
                     DiffOutcome::DiffCompleted(next_diff) => {
                         if next_diff.any_interesting() {
                             if same_diff(&original_diff, &next_diff) {
@@ -103,6 +107,28 @@ impl Reducer {
                                     reason.md.heading("Dash Difference Found".to_owned());
                                     reason.add_dash_diff(&next_diff.dash_diff);
                                 }
+                                if next_diff.mode_interesting() {
+                                    reason.md.heading("Mode Mismatch Found".to_owned());
+                                    reason.add_mode_diff(
+                                        &self.runner.fst_fs_name,
+                                        &next_diff.fst_mode_diff,
+                                    );
+                                    reason.add_mode_diff(
+                                        &self.runner.snd_fs_name,
+                                        &next_diff.snd_mode_diff,
+                                    );
+                                }
+                                if next_diff.timestamps_interesting() {
+                                    reason.md.heading("Timestamp Violation Found".to_owned());
+                                    reason.add_timestamps_diff(
+                                        &self.runner.fst_fs_name,
+                                        &next_diff.fst_timestamps_diff,
+                                    );
+                                    reason.add_timestamps_diff(
+                                        &self.runner.snd_fs_name,
+                                        &next_diff.snd_timestamps_diff,
+                                    );
+                                }
                                 self.runner
                                     .report_diff(
                                         &bugcase,
@@ -123,6 +149,28 @@ impl Reducer {
                                     reason.md.heading("Dash Difference Found".to_owned());
                                     reason.add_dash_diff(&next_diff.dash_diff);
                                 }
+                                if next_diff.mode_interesting() {
+                                    reason.md.heading("Mode Mismatch Found".to_owned());
+                                    reason.add_mode_diff(
+                                        &self.runner.fst_fs_name,
+                                        &next_diff.fst_mode_diff,
+                                    );
+                                    reason.add_mode_diff(
+                                        &self.runner.snd_fs_name,
+                                        &next_diff.snd_mode_diff,
+                                    );
+                                }
+                                if next_diff.timestamps_interesting() {
+                                    reason.md.heading("Timestamp Violation Found".to_owned());
+                                    reason.add_timestamps_diff(
+                                        &self.runner.fst_fs_name,
+                                        &next_diff.fst_timestamps_diff,
+                                    );
+                                    reason.add_timestamps_diff(
+                                        &self.runner.snd_fs_name,
+                                        &next_diff.snd_timestamps_diff,
+                                    );
+                                }
                                 self.runner
                                     .report_diff(
                                         &reduced,
@@ -210,4 +258,16 @@ fn same_diff(old: &DiffCompleted, new: &DiffCompleted) -> bool {
         }
     }
     old.dash_diff == new.dash_diff
+        && old.fst_mode_diff == new.fst_mode_diff
+        && old.snd_mode_diff == new.snd_mode_diff
+        && same_timestamps_diff(&old.fst_timestamps_diff, &new.fst_timestamps_diff)
+        && same_timestamps_diff(&old.snd_timestamps_diff, &new.snd_timestamps_diff)
+}
+
+fn same_timestamps_diff(old: &[TimestampDiff], new: &[TimestampDiff]) -> bool {
+    old.len() == new.len()
+        && old
+            .iter()
+            .zip(new.iter())
+            .all(|(old, new)| old.ignore_index_equal(new))
 }
@@ -19,8 +19,9 @@ use std::path::Path;
 use std::rc::Rc;
 
 use super::broker::BrokerHandle;
-use super::harness::Harness;
+use super::harness::{ExecutorOptions, Harness};
 use super::objective::dash::DashObjective;
+use super::objective::timestamps::TimestampsObjective;
 use super::objective::trace::TraceObjective;
 use super::observer::ObserverList;
 use super::observer::dash::DashObserver;
@@ -42,6 +43,7 @@ pub struct Runner {
 
     pub trace_objective: TraceObjective,
     pub dash_objective: DashObjective,
+    pub timestamps_objective: TimestampsObjective,
 
     pub fst_fs_name: String,
     pub snd_fs_name: String,
@@ -112,7 +114,8 @@ impl Runner {
         observers.1.push(snd_dash_observer.clone());
 
         let dash_objective = DashObjective::new(&config, fst_dash_observer, snd_dash_observer);
-        let trace_objective = TraceObjective::new();
+        let trace_objective = TraceObjective::new(config.user.credentials());
+        let timestamps_objective = TimestampsObjective::new(&config);
 
         let fst_harness = Harness::new(
             fst_mount,
@@ -120,6 +123,7 @@ impl Runner {
             exec_dir.clone(),
             local_tmp_dir.join("outcome-1"),
             config.timeout,
+            ExecutorOptions::new(&config),
             observers.0,
         );
         let snd_harness = Harness::new(
@@ -128,6 +132,7 @@ impl Runner {
             exec_dir.clone(),
             local_tmp_dir.join("outcome-2"),
             config.timeout,
+            ExecutorOptions::new(&config),
             observers.1,
         );
 
@@ -144,6 +149,7 @@ impl Runner {
 
             dash_objective,
             trace_objective,
+            timestamps_objective,
 
             fst_fs_name,
             snd_fs_name,
@@ -171,7 +177,11 @@ impl Runner {
         Ok(binary_path)
     }
 
-    pub fn run_harness(&mut self, binary_path: &RemotePath) -> anyhow::Result<DiffOutcome> {
+    pub fn run_harness(
+        &mut self,
+        input: &Workload,
+        binary_path: &RemotePath,
+    ) -> anyhow::Result<DiffOutcome> {
         let fst_outcome = self
             .fst_harness
             .run(
@@ -227,9 +237,11 @@ impl Runner {
             Outcome::Completed(completed) => completed,
         };
 
-        Ok(DiffOutcome::DiffCompleted(
-            self.diff(fst_outcome, snd_outcome)?,
-        ))
+        Ok(DiffOutcome::DiffCompleted(Box::new(self.diff(
+            input,
+            fst_outcome,
+            snd_outcome,
+        )?)))
     }
 
     pub fn report_diff(
@@ -282,6 +294,7 @@ impl Runner {
 
     fn diff(
         &mut self,
+        input: &Workload,
         fst_outcome: Completed,
         snd_outcome: Completed,
     ) -> anyhow::Result<DiffCompleted> {
@@ -295,17 +308,55 @@ impl Runner {
             .is_interesting()
             .with_context(|| "failed to do dash objective")?;
 
-        let dash_diff = if dash_interesting {
-            self.dash_objective.diff()
-        } else {
-            vec![]
-        };
+        let dash_diff =
+            if dash_interesting && !self.trace_objective.diverged(&fst_trace, &snd_trace) {
+                self.dash_objective.diff()
+            } else {
+                vec![]
+            };
 
         let trace_diff = self.trace_objective.diff(&fst_trace, &snd_trace);
 
+        // Model does not describe filesystem state after unexpectedly failed operations.
+        let (mut fst_mode_diff, mut snd_mode_diff) = self.dash_objective.mode_diff(input);
+        let mut fst_timestamps_diff = self
+            .timestamps_objective
+            .diff(input, &fst_outcome.dir)
+            .with_context(|| "failed to do first timestamps objective")?;
+        let mut snd_timestamps_diff = self
+            .timestamps_objective
+            .diff(input, &snd_outcome.dir)
+            .with_context(|| "failed to do second timestamps objective")?;
+        let mut fst_extra_diff = self.trace_objective.model_diff(input, &fst_trace);
+        let mut snd_extra_diff = self.trace_objective.model_diff(input, &snd_trace);
+        if !self
+            .trace_objective
+            .unexpected_errors(input, &fst_trace)
+            .is_empty()
+        {
+            fst_mode_diff.clear();
+            fst_timestamps_diff.clear();
+            fst_extra_diff.clear();
+        }
+        if !self
+            .trace_objective
+            .unexpected_errors(input, &snd_trace)
+            .is_empty()
+        {
+            snd_mode_diff.clear();
+            snd_timestamps_diff.clear();
+            snd_extra_diff.clear();
+        }
+
         Ok(DiffCompleted {
             dash_diff,
             trace_diff,
+            fst_mode_diff,
+            snd_mode_diff,
+            fst_timestamps_diff,
+            snd_timestamps_diff,
+            fst_extra_diff,
+            snd_extra_diff,
             fst_outcome,
             snd_outcome,
             fst_trace,
@@ -14,7 +14,11 @@ use log::info;
 use crate::{
     abstract_fs::workload::Workload,
     config::Config,
-    fuzzing::{broker::BrokerHandle, harness::Harness, outcome::Outcome},
+    fuzzing::{
+        broker::BrokerHandle,
+        harness::{ExecutorOptions, Harness},
+        outcome::Outcome,
+    },
     mount::FileSystemMount,
     path::{LocalPath, RemotePath},
     reason::Reason,
@@ -64,6 +68,7 @@ pub fn run(
         exec_dir,
         local_tmp_dir.join("outcome-single"),
         config.timeout,
+        ExecutorOptions::new(&config),
         vec![],
     );
 
@@ -8,6 +8,7 @@ use anyhow::Context;
 use regex::RegexSet;
 
 use crate::{
+    abstract_fs::operation::OperationKind,
     command::CommandWrapper,
     fuzzing::greybox::feedback::CoverageType,
     mount::{DEVICE, setup_modprobe},
@@ -54,6 +55,14 @@ impl FileSystemMount for BcacheFS {
     fn coverage_type(&self) -> CoverageType {
         CoverageType::KCov
     }
+    fn unsupported_operations(&self) -> Vec<OperationKind> {
+        // Only clone of shared extents is implemented, `RENAME_WHITEOUT` is rejected.
+        vec![
+            OperationKind::FIDedupeRange,
+            OperationKind::SetCasefold,
+            OperationKind::RenameWhiteout,
+        ]
+    }
 }
 
 impl BcacheFS {
@@ -6,9 +6,9 @@ use std::fmt::Display;
 
 use regex::RegexSet;
 
-use crate::fuzzing::greybox::feedback::CoverageType;
+use crate::{abstract_fs::operation::OperationKind, fuzzing::greybox::feedback::CoverageType};
 
-use super::FileSystemMount;
+use super::{CASEFOLD_OPERATIONS, FileSystemMount, REMAP_OPERATIONS};
 
 pub struct Ext4;
 
@@ -31,6 +31,9 @@ impl FileSystemMount for Ext4 {
     fn coverage_type(&self) -> CoverageType {
         CoverageType::KCov
     }
+    fn unsupported_operations(&self) -> Vec<OperationKind> {
+        [REMAP_OPERATIONS, CASEFOLD_OPERATIONS].concat()
+    }
 }
 
 impl Ext4 {
@@ -38,3 +41,39 @@ impl Ext4 {
         Self {}
     }
 }
+
+/// Ext4 with casefold feature, so `SetCasefold` is supported.
+pub struct Ext4Casefold;
+
+impl Display for Ext4Casefold {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Ext4Casefold")
+    }
+}
+
+impl FileSystemMount for Ext4Casefold {
+    fn mkfs_cmd(&self) -> String {
+        "mkfs.ext4".to_owned()
+    }
+    fn mkfs_opts(&self) -> Option<String> {
+        Some("casefold".to_owned())
+    }
+    fn mount_t(&self) -> String {
+        "ext4".to_owned()
+    }
+    fn get_internal_dirs(&self) -> RegexSet {
+        RegexSet::new([r"^/?lost\+found($|/)"]).unwrap()
+    }
+    fn coverage_type(&self) -> CoverageType {
+        CoverageType::KCov
+    }
+    fn unsupported_operations(&self) -> Vec<OperationKind> {
+        REMAP_OPERATIONS.to_vec()
+    }
+}
+
+impl Ext4Casefold {
+    pub const fn new() -> Self {
+        Self {}
+    }
+}
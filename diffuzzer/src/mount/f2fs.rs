@@ -6,9 +6,9 @@ use std::fmt::Display;
 
 use regex::RegexSet;
 
-use crate::fuzzing::greybox::feedback::CoverageType;
+use crate::{abstract_fs::operation::OperationKind, fuzzing::greybox::feedback::CoverageType};
 
-use super::FileSystemMount;
+use super::{CASEFOLD_OPERATIONS, FileSystemMount, REMAP_OPERATIONS};
 
 pub struct F2FS;
 
@@ -37,6 +37,9 @@ impl FileSystemMount for F2FS {
     fn coverage_type(&self) -> CoverageType {
         CoverageType::KCov
     }
+    fn unsupported_operations(&self) -> Vec<OperationKind> {
+        [REMAP_OPERATIONS, CASEFOLD_OPERATIONS].concat()
+    }
 }
 
 impl F2FS {
@@ -44,3 +47,45 @@ impl F2FS {
         Self {}
     }
 }
+
+/// F2FS with casefold feature and UTF-8 encoding, so `SetCasefold` is supported.
+pub struct F2FSCasefold;
+
+impl Display for F2FSCasefold {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "F2FSCasefold")
+    }
+}
+
+impl FileSystemMount for F2FSCasefold {
+    fn mkfs_cmd(&self) -> String {
+        "mkfs.f2fs".to_owned()
+    }
+    fn mkfs_opts(&self) -> Option<String> {
+        Some("extra_attr,inode_checksum,sb_checksum,casefold".to_owned())
+    }
+    fn mkfs_args(&self) -> Vec<String> {
+        vec!["-C".to_owned(), "utf8".to_owned()]
+    }
+    fn mount_t(&self) -> String {
+        "f2fs".to_owned()
+    }
+    fn mount_opts(&self) -> Option<String> {
+        Some("atgc,gc_merge,lazytime".to_owned())
+    }
+    fn get_internal_dirs(&self) -> RegexSet {
+        RegexSet::new([r"^/?lost\+found($|/)"]).unwrap()
+    }
+    fn coverage_type(&self) -> CoverageType {
+        CoverageType::KCov
+    }
+    fn unsupported_operations(&self) -> Vec<OperationKind> {
+        REMAP_OPERATIONS.to_vec()
+    }
+}
+
+impl F2FSCasefold {
+    pub const fn new() -> Self {
+        Self {}
+    }
+}
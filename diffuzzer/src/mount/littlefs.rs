@@ -7,13 +7,14 @@ use std::{fmt::Display, path::Path};
 use anyhow::Context;
 
 use crate::{
+    abstract_fs::operation::OperationKind,
     command::{CommandInterface, CommandWrapper},
     fuzzing::greybox::feedback::CoverageType,
     mount::{DEVICE, setup_modprobe},
     path::RemotePath,
 };
 
-use super::FileSystemMount;
+use super::{CASEFOLD_OPERATIONS, FileSystemMount, REMAP_OPERATIONS, WHITEOUT_OPERATIONS};
 
 pub struct LittleFS;
 
@@ -42,6 +43,9 @@ impl FileSystemMount for LittleFS {
 
         let mut mount = CommandWrapper::new(lfs_path.base.as_ref());
         mount.arg(DEVICE).arg(path.base.as_ref());
+        // Unprivileged test can't access FUSE mount without `allow_other`,
+        // permissions are checked by kernel with `default_permissions`.
+        mount.arg("-o").arg("allow_other,default_permissions");
         cmdi.exec(mount, None)
             .with_context(|| format!("failed to mount filesystem at '{}'", path))?;
 
@@ -53,6 +57,9 @@ impl FileSystemMount for LittleFS {
     fn source_dir(&self) -> Option<RemotePath> {
         Some(RemotePath::new(Path::new("/root/littlefs-fuse")))
     }
+    fn unsupported_operations(&self) -> Vec<OperationKind> {
+        [REMAP_OPERATIONS, CASEFOLD_OPERATIONS, WHITEOUT_OPERATIONS].concat()
+    }
 }
 
 impl LittleFS {
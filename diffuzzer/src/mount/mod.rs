@@ -15,6 +15,7 @@ use anyhow::Context;
 use regex::RegexSet;
 
 use crate::{
+    abstract_fs::operation::OperationKind,
     command::{CommandInterface, CommandWrapper},
     fuzzing::greybox::feedback::CoverageType,
     path::RemotePath,
@@ -23,6 +24,19 @@ use crate::{
 const RAM_DISK_SIZE: usize = 1_000_000;
 const DEVICE: &str = "/dev/ram0";
 
+/// Clone and dedupe of shared extents (reflinks).
+const REMAP_OPERATIONS: &[OperationKind] = &[
+    OperationKind::FIClone,
+    OperationKind::FICloneRange,
+    OperationKind::FIDedupeRange,
+];
+
+/// Rename that leaves whiteout in place of the old name (used by overlayfs).
+const WHITEOUT_OPERATIONS: &[OperationKind] = &[OperationKind::RenameWhiteout];
+
+/// Case-insensitive directories, require filesystem made with casefold feature.
+const CASEFOLD_OPERATIONS: &[OperationKind] = &[OperationKind::SetCasefold];
+
 pub trait FileSystemMount: Display + Sync {
     fn setup(&self, cmdi: &dyn CommandInterface, path: &RemotePath) -> anyhow::Result<()> {
         cmdi.create_dir_all(path)
@@ -35,6 +49,9 @@ pub trait FileSystemMount: Display + Sync {
             mkfs.arg("-O");
             mkfs.arg(opts);
         }
+        for arg in self.mkfs_args() {
+            mkfs.arg(arg);
+        }
         mkfs.arg(DEVICE);
         cmdi.exec(mkfs, None)
             .with_context(|| "failed to make filesystem")?;
@@ -81,6 +98,12 @@ pub trait FileSystemMount: Display + Sync {
         None
     }
 
+    /// Used in default implementation: extra `mkfs` arguments.
+    /// Example: `["-C", "utf8"]`
+    fn mkfs_args(&self) -> Vec<String> {
+        vec![]
+    }
+
     /// Used in default implementation: `mount -t` argument.
     /// Example: `"ext4"` or `"btrfs"`
     fn mount_t(&self) -> String {
@@ -104,6 +127,12 @@ pub trait FileSystemMount: Display + Sync {
     fn source_dir(&self) -> Option<RemotePath> {
         None
     }
+
+    /// Operations that are not supported by filesystem (e.g. reflinks).
+    /// They are excluded from workloads, so `EOPNOTSUPP` is not reported as a difference.
+    fn unsupported_operations(&self) -> Vec<OperationKind> {
+        CASEFOLD_OPERATIONS.to_vec()
+    }
 }
 
 fn setup_modprobe(cmdi: &dyn CommandInterface) -> anyhow::Result<()> {
@@ -10,6 +10,7 @@ use dash::FileDiff::OnlyOneExists;
 
 use crate::{
     abstract_fs::trace::{Trace, TraceDiff, TraceRow},
+    fuzzing::objective::{dash::ModeDiff, timestamps::TimestampDiff, trace::ExtraDiff},
     markdown::{Language, Markdown},
 };
 
@@ -64,6 +65,48 @@ impl Reason {
             };
         }
     }
+    pub fn add_mode_diff(&mut self, fs_name: &str, diff: &[ModeDiff]) {
+        if diff.is_empty() {
+            return;
+        }
+        self.md
+            .paragraph(format!("Unexpected modes in {}:", fs_name));
+        self.md.codeblock(
+            Language::of("text"),
+            diff.iter()
+                .map(|diff| diff.to_string())
+                .collect::<Vec<String>>()
+                .join("\n"),
+        );
+    }
+    pub fn add_timestamps_diff(&mut self, fs_name: &str, diff: &[TimestampDiff]) {
+        if diff.is_empty() {
+            return;
+        }
+        self.md
+            .paragraph(format!("Unexpected timestamps in {}:", fs_name));
+        self.md.codeblock(
+            Language::of("text"),
+            diff.iter()
+                .map(|diff| diff.to_string())
+                .collect::<Vec<String>>()
+                .join("\n"),
+        );
+    }
+    pub fn add_extra_diff(&mut self, fs_name: &str, diff: &[ExtraDiff]) {
+        if diff.is_empty() {
+            return;
+        }
+        self.md
+            .paragraph(format!("Unexpected results of operations in {}:", fs_name));
+        self.md.codeblock(
+            Language::of("text"),
+            diff.iter()
+                .map(|diff| diff.to_string())
+                .collect::<Vec<String>>()
+                .join("\n"),
+        );
+    }
 }
 
 impl Display for Reason {
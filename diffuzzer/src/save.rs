@@ -8,6 +8,7 @@ use anyhow::Context;
 
 use crate::command::CommandInterface;
 use crate::compile::{TEST_EXE_FILENAME, TEST_SOURCE_FILENAME};
+use crate::fuzzing::objective::timestamps::TIMESTAMPS_FILENAME;
 use crate::fuzzing::outcome::Completed;
 use crate::path::LocalPath;
 use crate::reason::Reason;
@@ -60,6 +61,17 @@ pub fn save_completed(
             trace_path, trace_copy_path
         )
     })?;
+    // Timestamps are saved by executor only if they are checked.
+    let timestamps_path = outcome.dir.join(TIMESTAMPS_FILENAME);
+    if timestamps_path.as_ref().exists() {
+        let timestamps_copy_path = output_dir.join(format!("{}.{}", fs_name, TIMESTAMPS_FILENAME));
+        fs::copy(timestamps_path.as_ref(), timestamps_copy_path.as_ref()).with_context(|| {
+            format!(
+                "failed to copy timestamps from '{}' to '{}'",
+                timestamps_path, timestamps_copy_path
+            )
+        })?;
+    }
 
     let stdout_path = output_dir.join(format!("{}.stdout.txt", fs_name));
     fs::write(&stdout_path, outcome.stdout.clone())
@@ -6,20 +6,30 @@
 
 #include <dirent.h>
 #include <fcntl.h>
+#include <grp.h>
+#include <limits.h>
+#include <linux/fs.h>
 #include <linux/types.h>
+#include <setjmp.h>
+#include <signal.h>
 #include <stddef.h>
 #include <stdint.h>
 #include <stdio.h>
 #include <stdlib.h>
+#include <sys/file.h>
 #include <sys/ioctl.h>
 #include <sys/mman.h>
 #include <sys/mount.h>
+#include <sys/sendfile.h>
 #include <sys/stat.h>
 #include <sys/statfs.h>
+#include <sys/sysmacros.h>
 #include <sys/types.h>
+#include <sys/wait.h>
 #include <sys/xattr.h>
 #include <unistd.h>
 
+#include <algorithm>
 #include <cassert>
 #include <cerrno>
 #include <cstddef>
@@ -28,6 +38,7 @@
 #include <cstring>
 #include <filesystem>
 #include <random>
+#include <set>
 #include <string>
 #include <utility>
 #include <vector>
@@ -64,6 +75,8 @@
 /// Size of read and write buffers.
 const size_t BUFFER_SIZE = 1024 * 1024;
 const size_t RANDOM_SEED = 123;
+/// Device number of character and block special files (same as `/dev/null`).
+const dev_t SPECIAL_DEVICE = makedev(1, 3);
 
 const char *MKDIR = "MKDIR";
 const char *RMDIR = "RMDIR";
@@ -79,6 +92,63 @@ const char *OPEN = "OPEN";
 const char *WRITE = "WRITE";
 const char *READ = "READ";
 const char *FSYNC = "FSYNC";
+const char *FDATASYNC = "FDATASYNC";
+const char *SYNC = "SYNC";
+const char *SYNCFS = "SYNCFS";
+const char *SYNC_FILE_RANGE = "SYNC_FILE_RANGE";
+const char *TRUNCATE = "TRUNCATE";
+const char *FTRUNCATE = "FTRUNCATE";
+const char *LSEEK = "LSEEK";
+const char *PREAD = "PREAD";
+const char *PWRITE = "PWRITE";
+const char *DUP = "DUP";
+const char *DUP2 = "DUP2";
+const char *DUP3 = "DUP3";
+const char *SETXATTR = "SETXATTR";
+const char *GETXATTR = "GETXATTR";
+const char *LISTXATTR = "LISTXATTR";
+const char *REMOVEXATTR = "REMOVEXATTR";
+const char *CHMOD = "CHMOD";
+const char *FCHMOD = "FCHMOD";
+const char *CHOWN = "CHOWN";
+const char *FCHOWN = "FCHOWN";
+const char *UTIMENSAT = "UTIMENSAT";
+const char *FUTIMENS = "FUTIMENS";
+const char *FALLOCATE = "FALLOCATE";
+const char *MMAP = "MMAP";
+const char *MWRITE = "MWRITE";
+const char *MSYNC = "MSYNC";
+const char *MUNMAP = "MUNMAP";
+const char *RENAMEAT2 = "RENAMEAT2";
+const char *FLINK = "FLINK";
+const char *MKNOD = "MKNOD";
+const char *READDIR = "READDIR";
+const char *LSTAT = "LSTAT";
+const char *FSTAT = "FSTAT";
+const char *READLINK = "READLINK";
+const char *COPY_FILE_RANGE = "COPY_FILE_RANGE";
+const char *SENDFILE = "SENDFILE";
+const char *SPLICE = "SPLICE";
+const char *CLONE = "CLONE";
+const char *CLONE_RANGE = "CLONE_RANGE";
+const char *DEDUPE_RANGE = "DEDUPE_RANGE";
+const char *FLOCK = "FLOCK";
+const char *SETLK = "SETLK";
+const char *OFD_SETLK = "OFD_SETLK";
+const char *OPENDIR = "OPENDIR";
+const char *CHDIR = "CHDIR";
+const char *FCHDIR = "FCHDIR";
+const char *MKDIRAT = "MKDIRAT";
+const char *OPENAT = "OPENAT";
+const char *UNLINKAT = "UNLINKAT";
+const char *RENAMEAT = "RENAMEAT";
+const char *FSTATAT = "FSTATAT";
+const char *UMASK_OP = "UMASK";
+const char *SET_CASEFOLD = "SET_CASEFOLD";
+
+#ifndef FS_CASEFOLD_FL
+#define FS_CASEFOLD_FL 0x40000000
+#endif
 
 enum ExitCode : int {
   /// Test finished.
@@ -98,16 +168,283 @@ struct Trace {
 
 std::vector<Trace> traces;
 
+/// Timestamps of file or directory after operation.
+struct Timestamps {
+  int idx;
+  /// Path relative to workspace, starts with '/'.
+  std::string path;
+  struct timespec atime;
+  struct timespec mtime;
+  struct timespec ctime;
+};
+
+std::vector<Timestamps> timestamps;
+
+/// Directory where files will be created/deleted/etc.
+const char *workspace = nullptr;
+// Same value is assumed by abstract model.
+const size_t WORKSPACE_MAX = 128;
+
+/// Workload is executed by the user, privileges are dropped completely
+/// (real and saved ids too), so root can't be recovered by the workload.
+static bool unprivileged = false;
+static uid_t test_uid = 0;
+static gid_t test_gid = 0;
+
+/// Names can contain any bytes except '/' and null, so separators and
+/// whitespace are escaped as `\xHH` to keep one record per line.
+static std::string escape_csv(const std::string &value) {
+  std::string result;
+  for (unsigned char c : value) {
+    if (c == '\\' || c == ',' || c <= ' ' || c == 0x7f) {
+      char buf[5];
+      snprintf(buf, sizeof(buf), "\\x%02x", c);
+      result += buf;
+    } else {
+      result += c;
+    }
+  }
+  return result;
+}
+
+/// Node that is touched by the current operation, its timestamps are saved
+/// after the operation together with timestamps of its parent directory.
+struct Touched {
+  /// Directory descriptor of `*at` operation or `AT_FDCWD`,
+  /// descriptor of the node itself if path is empty.
+  int fd;
+  /// Patched path.
+  std::string path;
+};
+
+static std::vector<Touched> touched;
+
+/// Timestamps are saved only if requested, because snapshots slow down tests.
+static bool timestamps_enabled = false;
+
+/// Coverage is collected from the workload only, so it is paused while
+/// executor does its own work (e.g. snapshots), `-1` if it is disabled.
+static int kcov_filed = -1;
+
+static void pause_coverage() {
+  if (kcov_filed != -1) {
+    ioctl(kcov_filed, KCOV_DISABLE, 0);
+  }
+}
+
+static void resume_coverage() {
+  if (kcov_filed != -1) {
+    ioctl(kcov_filed, KCOV_ENABLE, KCOV_TRACE_PC);
+  }
+}
+
+/// Saves timestamps of the node opened by descriptor, unless it is a symbolic
+/// link or it is not linked in workspace (e.g. removed while opened).
+static void append_timestamps(int idx, int fd, std::set<std::string> &saved) {
+  struct stat st;
+  if (fstat(fd, &st) || S_ISLNK(st.st_mode)) {
+    return;
+  }
+  char link[PATH_MAX];
+  const std::string fd_path = "/proc/self/fd/" + std::to_string(fd);
+  ssize_t len = readlink(fd_path.c_str(), link, sizeof(link));
+  if (len == -1) {
+    return;
+  }
+  const std::string path(link, len);
+  const std::string root(workspace);
+  std::string rel_path;
+  if (path == root) {
+    rel_path = "/";
+  } else if (!path.compare(0, root.size() + 1, root + "/")) {
+    rel_path = path.substr(root.size());
+  } else {
+    return;
+  }
+  // Path of removed node is suffixed with " (deleted)".
+  struct stat linked;
+  if (lstat(path.c_str(), &linked) || linked.st_dev != st.st_dev ||
+      linked.st_ino != st.st_ino || !saved.insert(rel_path).second) {
+    return;
+  }
+  timestamps.push_back(
+      Timestamps{idx, rel_path, st.st_atim, st.st_mtim, st.st_ctim});
+}
+
+static void append_timestamps_at(int idx, int dirfd, const std::string &path,
+                                 int flags, std::set<std::string> &saved) {
+  int fd = openat(dirfd, path.c_str(), O_PATH | flags);
+  if (fd != -1) {
+    append_timestamps(idx, fd, saved);
+    close(fd);
+  }
+}
+
+static std::string parent_path(const std::string &path) {
+  size_t pos = path.find_last_of('/');
+  if (pos == std::string::npos) {
+    return ".";
+  }
+  return pos ? path.substr(0, pos) : "/";
+}
+
+/// Timestamps are saved by the helper process, which keeps privileges of root,
+/// so every node is reachable. Workload sends touched nodes to the helper
+/// after every operation and waits until they are saved.
+static pid_t helper_pid = -1;
+static int helper_requests = -1;
+static int helper_acks = -1;
+
+static bool read_full(int fd, void *buf, size_t len) {
+  char *pos = static_cast<char *>(buf);
+  while (len > 0) {
+    ssize_t n = read(fd, pos, len);
+    if (n <= 0) {
+      return false;
+    }
+    pos += n;
+    len -= n;
+  }
+  return true;
+}
+
+static bool write_full(int fd, const void *buf, size_t len) {
+  const char *pos = static_cast<const char *>(buf);
+  while (len > 0) {
+    ssize_t n = write(fd, pos, len);
+    if (n <= 0) {
+      return false;
+    }
+    pos += n;
+    len -= n;
+  }
+  return true;
+}
+
+/// Saves timestamps of workspace root, touched nodes (and targets of touched
+/// symbolic links) and their parent directories.
+/// Nodes are only opened with `O_PATH`, so access times are not updated.
+/// Descriptors and working directory of workload are opened through `/proc`.
+static void save_timestamps(pid_t workload, int idx,
+                            const std::vector<Touched> &nodes) {
+  const std::string proc = "/proc/" + std::to_string(workload);
+  std::set<std::string> saved;
+  append_timestamps_at(idx, AT_FDCWD, workspace, 0, saved);
+  for (const Touched &t : nodes) {
+    if (!t.path.empty() && t.path[0] == '/') {
+      append_timestamps_at(idx, AT_FDCWD, t.path, O_NOFOLLOW, saved);
+      append_timestamps_at(idx, AT_FDCWD, t.path, 0, saved);
+      append_timestamps_at(idx, AT_FDCWD, parent_path(t.path), 0, saved);
+      continue;
+    }
+    const std::string base = t.fd == AT_FDCWD
+                                 ? proc + "/cwd"
+                                 : proc + "/fd/" + std::to_string(t.fd);
+    int base_fd = open(base.c_str(), O_PATH);
+    if (base_fd == -1) {
+      continue;
+    }
+    if (t.path.empty()) {
+      append_timestamps(idx, base_fd, saved);
+    } else {
+      append_timestamps_at(idx, base_fd, t.path, O_NOFOLLOW, saved);
+      append_timestamps_at(idx, base_fd, t.path, 0, saved);
+      append_timestamps_at(idx, base_fd, parent_path(t.path), 0, saved);
+    }
+    close(base_fd);
+  }
+}
+
+/// Output of helper is not printed to keep the order of output of workload.
+static bool dump_timestamps(const std::filesystem::path &timestamps_p) {
+  FILE *timestamps_dump_fp = fopen(timestamps_p.c_str(), "w");
+  if (!timestamps_dump_fp) {
+    DPRINTF("[ERROR] when opening timestamps dump file: %s", strerror(errno));
+    return false;
+  }
+  // Path is escaped, because names can contain commas and newlines.
+  fprintf(timestamps_dump_fp, "Index,ATime,MTime,CTime,Path\n");
+  for (const Timestamps &t : timestamps) {
+    fprintf(timestamps_dump_fp, "%d,%lld.%09ld,%lld.%09ld,%lld.%09ld,%s\n",
+            t.idx, (long long)t.atime.tv_sec, t.atime.tv_nsec,
+            (long long)t.mtime.tv_sec, t.mtime.tv_nsec,
+            (long long)t.ctime.tv_sec, t.ctime.tv_nsec,
+            escape_csv(t.path).c_str());
+  }
+  if (fclose(timestamps_dump_fp)) {
+    DPRINTF("[ERROR] when closing timestamps dump file: %s", strerror(errno));
+    return false;
+  }
+  return true;
+}
+
+/// Main loop of the helper, timestamps are dumped when workload is finished.
+static int run_timestamps_helper(pid_t workload,
+                                 const std::filesystem::path &timestamps_p) {
+  int idx;
+  while (read_full(helper_requests, &idx, sizeof(idx))) {
+    size_t n;
+    if (!read_full(helper_requests, &n, sizeof(n))) {
+      break;
+    }
+    std::vector<Touched> nodes(n);
+    for (Touched &t : nodes) {
+      size_t len;
+      if (!read_full(helper_requests, &t.fd, sizeof(t.fd)) ||
+          !read_full(helper_requests, &len, sizeof(len))) {
+        return ERROR;
+      }
+      t.path.resize(len);
+      if (!read_full(helper_requests, t.path.data(), len)) {
+        return ERROR;
+      }
+    }
+    save_timestamps(workload, idx, nodes);
+    if (!write_full(helper_acks, "", 1)) {
+      return ERROR;
+    }
+  }
+  return dump_timestamps(timestamps_p) ? OK : ERROR;
+}
+
+static void snapshot_timestamps(int idx) {
+  int saved_errno = errno;
+  pause_coverage();
+  std::string request;
+  size_t n = touched.size();
+  request.append(reinterpret_cast<const char *>(&idx), sizeof(idx));
+  request.append(reinterpret_cast<const char *>(&n), sizeof(n));
+  for (const Touched &t : touched) {
+    size_t len = t.path.size();
+    request.append(reinterpret_cast<const char *>(&t.fd), sizeof(t.fd));
+    request.append(reinterpret_cast<const char *>(&len), sizeof(len));
+    request += t.path;
+  }
+  char ack;
+  if (!write_full(helper_requests, request.data(), request.size()) ||
+      !read_full(helper_acks, &ack, 1)) {
+    DPRINTF("[ERROR] when saving timestamps: %s", strerror(errno));
+    exit(ERROR);
+  }
+  resume_coverage();
+  errno = saved_errno;
+}
+
 static void append_trace(int idx, const char *cmd, int ret_code, int err,
                          std::string extra) {
   traces.push_back(Trace{idx, cmd, ret_code, err, extra});
+  if (timestamps_enabled) {
+    snapshot_timestamps(idx);
+  }
+  touched.clear();
 }
-
-/// Directory where files will be created/deleted/etc.
-const char *workspace = nullptr;
+// Same value is assumed by abstract model.
+const mode_t UMASK = 0022;
 
 static int failure_n = 0;
 static int success_n = 0;
+static int unsupported_n = 0;
+static int signaled_n = 0;
 
 /// Used by write operations as data source.
 const char *write_buffer;
@@ -124,18 +461,32 @@ static uint64_t buffer_hashcode(const char *buffer, size_t len) {
 }
 
 int main(int argc, char *argv[]) {
-  if (argc != 2) {
-    DPRINTF("[USAGE] CMD <workspace>");
+  if (argc > 1 && !strcmp(argv[1], "--timestamps")) {
+    timestamps_enabled = true;
+    argc--;
+    argv++;
+  }
+  if (argc != 2 && argc != 4) {
+    DPRINTF("[USAGE] CMD [--timestamps] <workspace> [<uid> <gid>]");
     return ERROR;
   }
 
-  workspace = argv[1];
-  if (!workspace) {
+  if (!argv[1]) {
     DPRINTF("[ERROR] <workspace> argument is NULL");
     return ERROR;
   }
+  // Workspace is current working directory of workload, so it must not depend on it.
+  const std::string workspace_abs = std::filesystem::absolute(argv[1]);
+  workspace = workspace_abs.c_str();
+  if (workspace_abs.size() > WORKSPACE_MAX) {
+    DPRINTF("[ERROR] workspace path '%s' is longer than %zu", workspace,
+            WORKSPACE_MAX);
+    return ERROR;
+  }
 
   GOAL("prepare workspace '%s'", workspace);
+  SUBGOAL("set umask %04o", UMASK);
+  umask(UMASK);
   SUBGOAL("mkdir '%s'", workspace);
   if (mkdir(workspace, S_IRWXU | S_IRWXG | S_IROTH | S_IXOTH) == -1) {
     if (errno == EEXIST) {
@@ -145,11 +496,53 @@ int main(int argc, char *argv[]) {
       return ERROR;
     }
   }
+  if (argc == 4) {
+    unprivileged = true;
+    test_uid = std::stoul(argv[2]);
+    test_gid = std::stoul(argv[3]);
+    SUBGOAL("chown '%s' to %u:%u", workspace, test_uid, test_gid);
+    if (chown(workspace, test_uid, test_gid) == -1 ||
+        setgroups(0, nullptr) == -1) {
+      DPRINTF("[ERROR] when preparing workspace for user: %s",
+              strerror(errno));
+      return ERROR;
+    }
+  }
+
+  const std::filesystem::path timestamps_p =
+      std::filesystem::absolute("timestamps.csv");
+  if (timestamps_enabled) {
+    GOAL("start timestamps helper");
+    int requests[2], acks[2];
+    if (pipe(requests) == -1 || pipe(acks) == -1) {
+      DPRINTF("[ERROR] when creating pipes: %s", strerror(errno));
+      return ERROR;
+    }
+    pid_t workload = getpid();
+    // Buffered output must not be printed twice.
+    fflush(stdout);
+    helper_pid = fork();
+    if (helper_pid == -1) {
+      DPRINTF("[ERROR] when starting helper: %s", strerror(errno));
+      return ERROR;
+    }
+    if (helper_pid == 0) {
+      close(requests[1]);
+      close(acks[0]);
+      helper_requests = requests[0];
+      helper_acks = acks[1];
+      exit(run_timestamps_helper(workload, timestamps_p));
+    }
+    close(requests[0]);
+    close(acks[1]);
+    helper_requests = requests[1];
+    helper_acks = acks[0];
+    SUBGOAL("done");
+  }
 
   GOAL("set up kcov");
   // https://docs.kernel.org/dev-tools/kcov.html
   bool coverage_enabled = true;
-  int kcov_filed;
   unsigned long *cover;
   kcov_filed = open("/sys/kernel/debug/kcov", O_RDWR);
   if (kcov_filed == -1) {
@@ -190,7 +583,40 @@ int main(int argc, char *argv[]) {
     read_buffer[i] = 0;
   }
 
+  GOAL("open dumps");
+  // Dumps are opened before privileges are dropped, because the user may not
+  // be allowed to write to the working directory.
+  const std::filesystem::path kcov_p = std::filesystem::absolute("kcov.dat");
+  FILE *kcov_dump_fp = nullptr;
+  if (coverage_enabled) {
+    kcov_dump_fp = fopen(kcov_p.c_str(), "w");
+    if (!kcov_dump_fp) {
+      DPRINTF("[ERROR] when opening kcov dump file: %s", strerror(errno));
+      return ERROR;
+    }
+  }
+  const std::filesystem::path trace_p = std::filesystem::absolute("trace.csv");
+  FILE *trace_dump_fp = fopen(trace_p.c_str(), "w");
+  if (!trace_dump_fp) {
+    DPRINTF("[ERROR] when opening trace dump file: %s", strerror(errno));
+    return ERROR;
+  }
+
   GOAL("test workload");
+  // Relative paths of workload are resolved from workspace.
+  if (chdir(workspace) == -1) {
+    DPRINTF("[ERROR] when changing directory to workspace: %s",
+            strerror(errno));
+    return ERROR;
+  }
+  if (unprivileged) {
+    SUBGOAL("drop privileges to %u:%u", test_uid, test_gid);
+    if (setresgid(test_gid, test_gid, test_gid) == -1 ||
+        setresuid(test_uid, test_uid, test_uid) == -1) {
+      DPRINTF("[ERROR] when dropping privileges: %s", strerror(errno));
+      return ERROR;
+    }
+  }
   test_workload();
   SUBGOAL("done");
 
@@ -202,19 +628,12 @@ int main(int argc, char *argv[]) {
     }
     GOAL("dump kcov coverage");
     // read number of PCs collected
-    std::filesystem::path kcov_p = "kcov.dat";
-    FILE *trace_dump_fp = fopen(kcov_p.c_str(), "w");
-    if (!trace_dump_fp) {
-      DPRINTF("[ERROR] when opening kcov dump file: %s", strerror(errno));
-      return ERROR;
-    }
     unsigned long n = __atomic_load_n(&cover[0], __ATOMIC_RELAXED);
     for (unsigned long i = 0; i < n; i++) {
-      fprintf(trace_dump_fp, "0x%lx\n", cover[i + 1]);
+      fprintf(kcov_dump_fp, "0x%lx\n", cover[i + 1]);
     }
-    if (!fclose(trace_dump_fp)) {
-      SUBGOAL("kcov dump saved at '%s'",
-              std::filesystem::absolute(kcov_p).c_str());
+    if (!fclose(kcov_dump_fp)) {
+      SUBGOAL("kcov dump saved at '%s'", kcov_p.c_str());
     } else {
       DPRINTF("[ERROR] when closing kcov dump file: %s", strerror(errno));
       return ERROR;
@@ -232,27 +651,35 @@ int main(int argc, char *argv[]) {
   }
 
   GOAL("dump trace");
-  std::filesystem::path trace_p = "trace.csv";
-  FILE *trace_dump_fp = fopen(trace_p.c_str(), "w");
-  if (!trace_dump_fp) {
-    DPRINTF("[ERROR] when opening trace dump file: %s", strerror(errno));
-    return ERROR;
-  }
   fprintf(trace_dump_fp, "Index,Command,ReturnCode,Errno,Extra\n");
   for (const Trace &t : traces) {
     fprintf(trace_dump_fp, "%4d,%12s,%8d,%s(%d),%s\n", t.idx, t.cmd.c_str(),
             t.ret_code, strerror(t.err), t.err, t.extra.c_str());
   }
   if (!fclose(trace_dump_fp)) {
-    SUBGOAL("trace dump saved at '%s'",
-            std::filesystem::absolute(trace_p).c_str());
+    SUBGOAL("trace dump saved at '%s'", trace_p.c_str());
   } else {
     DPRINTF("[ERROR] when closing trace dump file: %s", strerror(errno));
     return ERROR;
   }
 
+  if (timestamps_enabled) {
+    GOAL("dump timestamps");
+    // Helper dumps timestamps when requests are finished.
+    close(helper_requests);
+    int status;
+    if (waitpid(helper_pid, &status, 0) == -1 || !WIFEXITED(status) ||
+        WEXITSTATUS(status) != OK) {
+      DPRINTF("[ERROR] timestamps helper failed");
+      return ERROR;
+    }
+    SUBGOAL("timestamps dump saved at '%s'", timestamps_p.c_str());
+  }
+
   GOAL("summary");
-  printf("#SUCCESS: %d | #FAILURE: %d\n", success_n, failure_n);
+  printf(
+      "#SUCCESS: %d | #FAILURE: %d | #UNSUPPORTED: %d | #SIGNALED: %d\n",
+      success_n, failure_n, unsupported_n, signaled_n);
 
   return OK;
 }
@@ -267,6 +694,63 @@ static std::string patch_path(const std::string &path) {
   return workspace + path;
 }
 
+/// Absolute targets of symbolic links are patched, but workspace differs
+/// between filesystems.
+static std::string unpatch_target(const std::string &target) {
+  size_t prefix = strlen(workspace);
+  if (!target.compare(0, prefix, workspace)) {
+    return target.substr(prefix);
+  }
+  return target;
+}
+
+/// Path of `*at` operation, only absolute path is patched
+/// (relative path is resolved from directory descriptor or working directory).
+static std::string patch_at_path(const std::string &path) {
+  return path[0] == '/' ? patch_path(path) : path;
+}
+
+static void touch_path(const char *path) {
+  touched.push_back(Touched{AT_FDCWD, patch_path(path)});
+}
+
+static void touch_at(int dirfd, const char *path) {
+  touched.push_back(Touched{dirfd, patch_at_path(path)});
+}
+
+static void touch_fd(int fd) { touched.push_back(Touched{fd, ""}); }
+
+/// Files of mappings, opened with `O_PATH` to save their timestamps.
+static std::vector<std::pair<const char *, int>> mapped_files;
+/// Descriptors of mapped files must not take numbers of workload descriptors.
+const int MAPPED_FD_MIN = 512;
+
+static void remember_mapping(const char *map, int fd) {
+  if (!timestamps_enabled) {
+    return;
+  }
+  pause_coverage();
+  const std::string fd_path = "/proc/self/fd/" + std::to_string(fd);
+  int path_fd = open(fd_path.c_str(), O_PATH);
+  if (path_fd != -1) {
+    int mapped_fd = fcntl(path_fd, F_DUPFD_CLOEXEC, MAPPED_FD_MIN);
+    if (mapped_fd != -1) {
+      mapped_files.push_back({map, mapped_fd});
+    }
+    close(path_fd);
+  }
+  resume_coverage();
+}
+
+static void touch_map(const char *map) {
+  for (auto it = mapped_files.rbegin(); it != mapped_files.rend(); it++) {
+    if (it->first == map) {
+      touch_fd(it->second);
+      return;
+    }
+  }
+}
+
 static std::string path_join(const std::string &prefix,
                              const std::string &file_name) {
   return prefix + "/" + file_name;
@@ -287,6 +771,23 @@ static void failure(int status, const char *cmd, const char *path,
   failure_n += 1;
 }
 
+/// Operation is not supported by filesystem (`EOPNOTSUPP`), which is expected for some of them.
+static void unsupported(int status, const char *cmd, const char *path,
+                        std::string extra) {
+  append_trace(idx, cmd, status, errno, extra);
+  DPRINTF("[INFO] %s('%s') is not supported", cmd, path);
+  unsupported_n += 1;
+}
+
+/// Operation was interrupted by signal (e.g. `SIGBUS` when accessing mapping
+/// beyond the end of file), which is expected for some of them.
+static void signaled(int sig, const char *cmd, const char *path) {
+  std::string name = sig == SIGBUS ? "SIGBUS" : "SIGSEGV";
+  append_trace(idx, cmd, -1, 0, "signal=" + name);
+  DPRINTF("[INFO] %s('%s') got %s", cmd, path, name.c_str());
+  signaled_n += 1;
+}
+
 static void failure2(int status, const char *cmd, const char *fst_path,
                      const char *snd_path, std::string extra) {
   append_trace(idx, cmd, status, errno, extra);
@@ -295,12 +796,18 @@ static void failure2(int status, const char *cmd, const char *fst_path,
   failure_n += 1;
 }
 
+/// Current offset of descriptor, to be saved in trace.
+static std::string offset_extra(int fd) {
+  return "offset=" + std::to_string(lseek(fd, 0, SEEK_CUR));
+}
+
 static void minor_failure(const char *cmd, const char *path) {
   DPRINTF("[WARNING] %s('%s') FAIL(%s) <minor>", cmd, path, strerror(errno));
 }
 
 int do_mkdir(const char *path, mode_t param) {
   idx++;
+  touch_path(path);
   int status = mkdir(patch_path(path).c_str(), param);
   if (status == -1) {
     failure(status, MKDIR, path, "");
@@ -312,6 +819,7 @@ int do_mkdir(const char *path, mode_t param) {
 
 int do_create(const char *path, mode_t param) {
   idx++;
+  touch_path(path);
   int status = creat(patch_path(path).c_str(), param);
   if (status == -1) {
     failure(status, CREATE, path, "");
@@ -373,6 +881,7 @@ static int remove_dir(const char *p) {
 
 int do_remove(const char *p) {
   idx++;
+  touch_path(p);
   const std::string path = patch_path(p);
   struct stat file_stat;
   int status = 0;
@@ -418,6 +927,8 @@ int do_remove(const char *p) {
 
 int do_hardlink(const char *old_path, const char *new_path) {
   idx++;
+  touch_path(old_path);
+  touch_path(new_path);
   int status = link(patch_path(old_path).c_str(), patch_path(new_path).c_str());
   if (status == -1) {
     failure2(status, HARDLINK, old_path, new_path, "");
@@ -429,8 +940,11 @@ int do_hardlink(const char *old_path, const char *new_path) {
 
 int do_symlink(const char *target, const char *linkpath) {
   idx++;
-  int status =
-      symlink(patch_path(target).c_str(), patch_path(linkpath).c_str());
+  touch_path(linkpath);
+  // relative target is resolved from directory of the link
+  const std::string target_path =
+      target[0] == '/' ? patch_path(target) : std::string(target);
+  int status = symlink(target_path.c_str(), patch_path(linkpath).c_str());
   if (status == -1) {
     failure2(status, SYMLINK, target, linkpath, "");
   } else {
@@ -439,8 +953,147 @@ int do_symlink(const char *target, const char *linkpath) {
   return status;
 }
 
+int do_mknod(const char *path, mode_t mode) {
+  idx++;
+  touch_path(path);
+  dev_t dev = S_ISCHR(mode) || S_ISBLK(mode) ? SPECIAL_DEVICE : 0;
+  int status = mknod(patch_path(path).c_str(), mode, dev);
+  if (status == -1) {
+    failure(status, MKNOD, path, "");
+  } else {
+    success(status, MKNOD, "");
+  }
+  return status;
+}
+
+int do_readdir(const char *path) {
+  idx++;
+  touch_path(path);
+  int fd = open(patch_path(path).c_str(), O_RDONLY | O_DIRECTORY);
+  if (fd == -1) {
+    failure(fd, READDIR, path, "");
+    return fd;
+  }
+  // order of entries is not specified
+  std::vector<std::string> entries;
+  ssize_t nr;
+  while ((nr = getdents64(fd, read_buffer, BUFFER_SIZE)) > 0) {
+    for (ssize_t pos = 0; pos < nr;) {
+      struct dirent64 *d =
+          reinterpret_cast<struct dirent64 *>(read_buffer + pos);
+      pos += d->d_reclen;
+      if (!strcmp(d->d_name, ".") || !strcmp(d->d_name, "..")) {
+        continue;
+      }
+      unsigned char type = d->d_type;
+      // some filesystems do not fill `d_type`
+      struct stat st;
+      if (type == DT_UNKNOWN &&
+          !fstatat(fd, d->d_name, &st, AT_SYMLINK_NOFOLLOW)) {
+        type = IFTODT(st.st_mode);
+      }
+      entries.push_back(std::string(d->d_name) + '\0' +
+                        static_cast<char>(type));
+    }
+  }
+  if (nr == -1) {
+    failure(nr, READDIR, path, "");
+    close(fd);
+    return nr;
+  }
+  if (close(fd)) {
+    minor_failure(CLOSE, path);
+  }
+  std::sort(entries.begin(), entries.end());
+  std::string digest;
+  for (const std::string &entry : entries) {
+    digest += entry;
+  }
+  std::stringstream extra;
+  extra << "entries=" << entries.size() << " hash=" << std::hex
+        << buffer_hashcode(digest.data(), digest.size());
+  success(entries.size(), READDIR, extra.str());
+  return entries.size();
+}
+
+/// Size and link count of directories depend on filesystem, so they are
+/// reported as zero.
+static std::string stat_extra(const struct stat &st, off_t size) {
+  bool is_dir = S_ISDIR(st.st_mode);
+  std::stringstream extra;
+  extra << "type=" << std::oct << (st.st_mode & S_IFMT)
+        << " mode=" << (st.st_mode & 07777) << std::dec
+        << " nlink=" << (is_dir ? 0 : st.st_nlink)
+        << " size=" << (is_dir ? 0 : size);
+  return extra.str();
+}
+
+int do_stat(const char *path) {
+  idx++;
+  touch_path(path);
+  struct stat st;
+  int status = stat(patch_path(path).c_str(), &st);
+  if (status == -1) {
+    failure(status, STAT, path, "");
+  } else {
+    success(status, STAT, stat_extra(st, st.st_size));
+  }
+  return status;
+}
+
+int do_lstat(const char *path) {
+  idx++;
+  touch_path(path);
+  const std::string patched = patch_path(path);
+  struct stat st;
+  int status = lstat(patched.c_str(), &st);
+  if (status == -1) {
+    failure(status, LSTAT, path, "");
+    return status;
+  }
+  off_t size = st.st_size;
+  if (S_ISLNK(st.st_mode)) {
+    ssize_t len = readlink(patched.c_str(), read_buffer, BUFFER_SIZE);
+    if (len == -1) {
+      minor_failure(LSTAT, path);
+    } else {
+      size = unpatch_target(std::string(read_buffer, len)).size();
+    }
+  }
+  success(status, LSTAT, stat_extra(st, size));
+  return status;
+}
+
+int do_readlink(const char *path) {
+  idx++;
+  touch_path(path);
+  ssize_t len = readlink(patch_path(path).c_str(), read_buffer, BUFFER_SIZE);
+  if (len == -1) {
+    failure(len, READLINK, path, "");
+    return len;
+  }
+  const std::string target = unpatch_target(std::string(read_buffer, len));
+  success(target.size(), READLINK, "target=" + escape_csv(target));
+  return target.size();
+}
+
+int do_fstat(int fd) {
+  idx++;
+  touch_fd(fd);
+  struct stat st;
+  int status = fstat(fd, &st);
+  if (status == -1) {
+    failure(status, FSTAT, std::to_string(fd).c_str(), "");
+  } else {
+    success(status, FSTAT, stat_extra(st, st.st_size));
+  }
+  return status;
+}
+
 int do_rename(const char *old_path, const char *new_path) {
   idx++;
+  touch_path(old_path);
+  touch_path(new_path);
   int status =
       rename(patch_path(old_path).c_str(), patch_path(new_path).c_str());
   if (status == -1) {
@@ -451,73 +1104,877 @@ int do_rename(const char *old_path, const char *new_path) {
   return status;
 }
 
-int do_open(const char *path) {
+int do_renameat2(const char *old_path, const char *new_path,
+                 unsigned int flags) {
   idx++;
-  int fd = open(patch_path(path).c_str(), O_RDWR);
-  if (fd == -1) {
-    failure(fd, OPEN, path, "");
+  touch_path(old_path);
+  touch_path(new_path);
+  int status = renameat2(AT_FDCWD, patch_path(old_path).c_str(), AT_FDCWD,
+                         patch_path(new_path).c_str(), flags);
+  if (status == -1) {
+    failure2(status, RENAMEAT2, old_path, new_path, "");
   } else {
-    success(fd, OPEN, "");
+    success(status, RENAMEAT2, "");
   }
-  return fd;
+  return status;
 }
 
-int do_close(int fd) {
+int do_flink(int fd, const char *path) {
   idx++;
-  int status = close(fd);
+  touch_fd(fd);
+  touch_path(path);
+  int status =
+      linkat(fd, "", AT_FDCWD, patch_path(path).c_str(), AT_EMPTY_PATH);
   if (status == -1) {
-    failure(status, CLOSE, std::to_string(fd).c_str(), "");
+    failure(status, FLINK, path, "");
   } else {
-    success(status, CLOSE, "");
+    success(status, FLINK, "");
   }
   return status;
 }
 
-int do_write(int fd, size_t src_offset, size_t size) {
+int do_opendir(int dirfd, const char *path, int flags) {
   idx++;
-  if (src_offset + size > BUFFER_SIZE) {
-    DPRINTF(
-        "[ERROR] offset %ld + %ld is too big to write from (buffer size is "
-        "%ld)",
-        src_offset, size, BUFFER_SIZE);
-    exit(ERROR);
-  }
-  int nw = write(fd, &write_buffer[src_offset], size);
-  if (nw == -1) {
-    failure(nw, WRITE, std::to_string(fd).c_str(), "");
-    return -1;
+  touch_at(dirfd, path);
+  int fd = openat(dirfd, patch_at_path(path).c_str(), flags);
+  if (fd == -1) {
+    failure(fd, OPENDIR, path, "");
   } else {
-    success(nw, WRITE, "");
-    return nw;
+    success(fd, OPENDIR, "");
   }
+  return fd;
 }
 
-int do_read(int fd, size_t size) {
+int do_chdir(const char *path) {
   idx++;
-  if (size > BUFFER_SIZE) {
-    DPRINTF("[ERROR] size %ld is too big to read to (buffer size is %ld)", size,
-            BUFFER_SIZE);
-    exit(ERROR);
+  // relative path is saved from the new working directory
+  touch_at(AT_FDCWD, ".");
+  int status = chdir(patch_at_path(path).c_str());
+  if (status == -1) {
+    failure(status, CHDIR, path, "");
+  } else {
+    success(status, CHDIR, "");
   }
-  int nr = read(fd, read_buffer, size);
-  if (nr == -1 || std::cmp_greater(nr, size)) {
-    failure(nr, READ, std::to_string(fd).c_str(), "");
-    return -1;
+  return status;
+}
+
+int do_fchdir(int fd) {
+  idx++;
+  touch_fd(fd);
+  int status = fchdir(fd);
+  if (status == -1) {
+    failure(status, FCHDIR, std::to_string(fd).c_str(), "");
   } else {
-    std::stringstream extra;
-    extra << "hash=" << std::hex << buffer_hashcode(read_buffer, nr);
-    success(nr, READ, extra.str());
-    return nr;
+    success(status, FCHDIR, "");
   }
+  return status;
 }
 
-int do_fsync(int fd) {
+int do_mkdirat(int dirfd, const char *path, mode_t param) {
   idx++;
-  int status = fsync(fd);
+  touch_at(dirfd, path);
+  int status = mkdirat(dirfd, patch_at_path(path).c_str(), param);
   if (status == -1) {
-    failure(status, FSYNC, std::to_string(fd).c_str(), "");
+    failure(status, MKDIRAT, path, "");
   } else {
-    success(status, FSYNC, "");
+    success(status, MKDIRAT, "");
+  }
+  return status;
+}
+
+int do_openat(int dirfd, const char *path, int flags, mode_t mode) {
+  idx++;
+  touch_at(dirfd, path);
+  int fd = openat(dirfd, patch_at_path(path).c_str(), flags, mode);
+  if (fd == -1) {
+    if (errno == EOPNOTSUPP) {
+      unsupported(fd, OPENAT, path, "");
+    } else {
+      failure(fd, OPENAT, path, "");
+    }
+  } else {
+    success(fd, OPENAT, "");
+  }
+  return fd;
+}
+
+int do_unlinkat(int dirfd, const char *path, int flags) {
+  idx++;
+  touch_at(dirfd, path);
+  int status = unlinkat(dirfd, patch_at_path(path).c_str(), flags);
+  if (status == -1) {
+    failure(status, UNLINKAT, path, "");
+  } else {
+    success(status, UNLINKAT, "");
+  }
+  return status;
+}
+
+int do_renameat(int olddirfd, const char *old_path, int newdirfd,
+                const char *new_path) {
+  idx++;
+  touch_at(olddirfd, old_path);
+  touch_at(newdirfd, new_path);
+  int status = renameat(olddirfd, patch_at_path(old_path).c_str(), newdirfd,
+                        patch_at_path(new_path).c_str());
+  if (status == -1) {
+    failure2(status, RENAMEAT, old_path, new_path, "");
+  } else {
+    success(status, RENAMEAT, "");
+  }
+  return status;
+}
+
+int do_fstatat(int dirfd, const char *path, int flags) {
+  idx++;
+  touch_at(dirfd, path);
+  const std::string patched = patch_at_path(path);
+  struct stat st;
+  int status = fstatat(dirfd, patched.c_str(), &st, flags);
+  if (status == -1) {
+    failure(status, FSTATAT, path, "");
+    return status;
+  }
+  off_t size = st.st_size;
+  if (S_ISLNK(st.st_mode)) {
+    ssize_t len = readlinkat(dirfd, patched.c_str(), read_buffer, BUFFER_SIZE);
+    if (len == -1) {
+      minor_failure(FSTATAT, path);
+    } else {
+      size = unpatch_target(std::string(read_buffer, len)).size();
+    }
+  }
+  success(status, FSTATAT, stat_extra(st, size));
+  return status;
+}
+
+int do_umask(mode_t mask) {
+  idx++;
+  umask(mask);
+  success(0, UMASK_OP, "");
+  return 0;
+}
+
+int do_set_casefold(const char *path) {
+  idx++;
+  touch_path(path);
+  int fd = open(patch_path(path).c_str(), O_RDONLY | O_DIRECTORY);
+  if (fd == -1) {
+    failure(fd, SET_CASEFOLD, path, "");
+    return fd;
+  }
+  int flags = 0;
+  int status = ioctl(fd, FS_IOC_GETFLAGS, &flags);
+  if (status != -1) {
+    flags |= FS_CASEFOLD_FL;
+    status = ioctl(fd, FS_IOC_SETFLAGS, &flags);
+  }
+  int saved_errno = errno;
+  close(fd);
+  errno = saved_errno;
+  if (status == -1) {
+    if (errno == EOPNOTSUPP) {
+      unsupported(status, SET_CASEFOLD, path, "");
+    } else {
+      failure(status, SET_CASEFOLD, path, "");
+    }
+  } else {
+    success(status, SET_CASEFOLD, "");
+  }
+  return status;
+}
+
+int do_open(const char *path, int flags, mode_t mode) {
+  idx++;
+  touch_path(path);
+  int fd = open(patch_path(path).c_str(), flags, mode);
+  if (fd == -1) {
+    if (errno == EOPNOTSUPP) {
+      unsupported(fd, OPEN, path, "");
+    } else {
+      failure(fd, OPEN, path, "");
+    }
+  } else {
+    success(fd, OPEN, "");
+  }
+  return fd;
+}
+
+int do_close(int fd) {
+  idx++;
+  touch_fd(fd);
+  int status = close(fd);
+  if (status == -1) {
+    failure(status, CLOSE, std::to_string(fd).c_str(), "");
+  } else {
+    success(status, CLOSE, "");
+  }
+  return status;
+}
+
+int do_write(int fd, size_t src_offset, size_t size) {
+  idx++;
+  touch_fd(fd);
+  if (src_offset + size > BUFFER_SIZE) {
+    DPRINTF(
+        "[ERROR] offset %ld + %ld is too big to write from (buffer size is "
+        "%ld)",
+        src_offset, size, BUFFER_SIZE);
+    exit(ERROR);
+  }
+  int nw = write(fd, &write_buffer[src_offset], size);
+  if (nw == -1) {
+    failure(nw, WRITE, std::to_string(fd).c_str(), "");
+    return -1;
+  } else {
+    success(nw, WRITE, "");
+    return nw;
+  }
+}
+
+int do_read(int fd, size_t size) {
+  idx++;
+  touch_fd(fd);
+  if (size > BUFFER_SIZE) {
+    DPRINTF("[ERROR] size %ld is too big to read to (buffer size is %ld)", size,
+            BUFFER_SIZE);
+    exit(ERROR);
+  }
+  int nr = read(fd, read_buffer, size);
+  if (nr == -1 || std::cmp_greater(nr, size)) {
+    failure(nr, READ, std::to_string(fd).c_str(), "");
+    return -1;
+  } else {
+    std::stringstream extra;
+    extra << "hash=" << std::hex << buffer_hashcode(read_buffer, nr);
+    success(nr, READ, extra.str());
+    return nr;
+  }
+}
+
+int do_fsync(int fd) {
+  idx++;
+  touch_fd(fd);
+  int status = fsync(fd);
+  if (status == -1) {
+    failure(status, FSYNC, std::to_string(fd).c_str(), "");
+  } else {
+    success(status, FSYNC, "");
+  }
+  return status;
+}
+
+int do_fdatasync(int fd) {
+  idx++;
+  touch_fd(fd);
+  int status = fdatasync(fd);
+  if (status == -1) {
+    failure(status, FDATASYNC, std::to_string(fd).c_str(), "");
+  } else {
+    success(status, FDATASYNC, "");
+  }
+  return status;
+}
+
+int do_sync() {
+  idx++;
+  sync();
+  success(0, SYNC, "");
+  return 0;
+}
+
+int do_syncfs(int fd) {
+  idx++;
+  touch_fd(fd);
+  int status = syncfs(fd);
+  if (status == -1) {
+    failure(status, SYNCFS, std::to_string(fd).c_str(), "");
+  } else {
+    success(status, SYNCFS, "");
+  }
+  return status;
+}
+
+int do_sync_file_range(int fd, off_t offset, off_t nbytes, unsigned int flags) {
+  idx++;
+  touch_fd(fd);
+  int status = sync_file_range(fd, offset, nbytes, flags);
+  if (status == -1) {
+    failure(status, SYNC_FILE_RANGE, std::to_string(fd).c_str(), "");
+  } else {
+    success(status, SYNC_FILE_RANGE, "");
+  }
+  return status;
+}
+
+int do_truncate(const char *path, off_t size) {
+  idx++;
+  touch_path(path);
+  int status = truncate(patch_path(path).c_str(), size);
+  if (status == -1) {
+    failure(status, TRUNCATE, path, "");
+  } else {
+    success(status, TRUNCATE, "");
+  }
+  return status;
+}
+
+int do_ftruncate(int fd, off_t size) {
+  idx++;
+  touch_fd(fd);
+  int status = ftruncate(fd, size);
+  if (status == -1) {
+    failure(status, FTRUNCATE, std::to_string(fd).c_str(), "");
+  } else {
+    success(status, FTRUNCATE, "");
+  }
+  return status;
+}
+
+off_t do_lseek(int fd, off_t offset, int whence) {
+  idx++;
+  touch_fd(fd);
+  off_t result = lseek(fd, offset, whence);
+  if (result == -1) {
+    failure(-1, LSEEK, std::to_string(fd).c_str(), "");
+  } else {
+    // Offset doesn't fit into status, so only extra contains it.
+    success(0, LSEEK, offset_extra(fd));
+  }
+  return result;
+}
+
+int do_pread(int fd, size_t size, off_t offset) {
+  idx++;
+  touch_fd(fd);
+  if (size > BUFFER_SIZE) {
+    DPRINTF("[ERROR] size %ld is too big to read to (buffer size is %ld)", size,
+            BUFFER_SIZE);
+    exit(ERROR);
+  }
+  int nr = pread(fd, read_buffer, size, offset);
+  if (nr == -1 || std::cmp_greater(nr, size)) {
+    failure(nr, PREAD, std::to_string(fd).c_str(), "");
+    return -1;
+  } else {
+    std::stringstream extra;
+    extra << "hash=" << std::hex << buffer_hashcode(read_buffer, nr) << " "
+          << offset_extra(fd);
+    success(nr, PREAD, extra.str());
+    return nr;
+  }
+}
+
+int do_pwrite(int fd, size_t src_offset, size_t size, off_t offset) {
+  idx++;
+  touch_fd(fd);
+  if (src_offset + size > BUFFER_SIZE) {
+    DPRINTF(
+        "[ERROR] offset %ld + %ld is too big to write from (buffer size is "
+        "%ld)",
+        src_offset, size, BUFFER_SIZE);
+    exit(ERROR);
+  }
+  int nw = pwrite(fd, &write_buffer[src_offset], size, offset);
+  if (nw == -1) {
+    failure(nw, PWRITE, std::to_string(fd).c_str(), "");
+    return -1;
+  } else {
+    success(nw, PWRITE, offset_extra(fd));
+    return nw;
+  }
+}
+
+/// Offsets of both descriptors after copying, to be saved in trace.
+static std::string copy_extra(int fd_in, int fd_out) {
+  return "offset_in=" + std::to_string(lseek(fd_in, 0, SEEK_CUR)) +
+         " offset_out=" + std::to_string(lseek(fd_out, 0, SEEK_CUR));
+}
+
+int do_copy_file_range(int fd_in, off_t off_in, int fd_out, off_t off_out,
+                       size_t len) {
+  idx++;
+  touch_fd(fd_in);
+  touch_fd(fd_out);
+  ssize_t nc = copy_file_range(fd_in, off_in < 0 ? nullptr : &off_in, fd_out,
+                               off_out < 0 ? nullptr : &off_out, len, 0);
+  if (nc == -1) {
+    failure(nc, COPY_FILE_RANGE, std::to_string(fd_in).c_str(), "");
+  } else {
+    success(nc, COPY_FILE_RANGE, copy_extra(fd_in, fd_out));
+  }
+  return nc;
+}
+
+int do_sendfile(int out_fd, int in_fd, off_t offset, size_t count) {
+  idx++;
+  touch_fd(in_fd);
+  touch_fd(out_fd);
+  ssize_t nc = sendfile(out_fd, in_fd, offset < 0 ? nullptr : &offset, count);
+  if (nc == -1) {
+    failure(nc, SENDFILE, std::to_string(in_fd).c_str(), "");
+  } else {
+    success(nc, SENDFILE, copy_extra(in_fd, out_fd));
+  }
+  return nc;
+}
+
+int do_splice(int fd_in, off_t off_in, int fd_out, off_t off_out,
+              size_t len) {
+  idx++;
+  touch_fd(fd_in);
+  touch_fd(fd_out);
+  int pipefd[2];
+  if (pipe(pipefd)) {
+    failure(-1, SPLICE, std::to_string(fd_in).c_str(), "");
+    return -1;
+  }
+  // data is moved through pipe, which has limited capacity
+  ssize_t total = 0;
+  while (static_cast<size_t>(total) < len) {
+    ssize_t nr = splice(fd_in, off_in < 0 ? nullptr : &off_in, pipefd[1],
+                        nullptr, len - total, 0);
+    if (nr <= 0) {
+      total = nr == -1 ? -1 : total;
+      break;
+    }
+    ssize_t nw = 0;
+    while (nw < nr) {
+      ssize_t n = splice(pipefd[0], nullptr, fd_out,
+                         off_out < 0 ? nullptr : &off_out, nr - nw, 0);
+      if (n <= 0) {
+        nw = -1;
+        break;
+      }
+      nw += n;
+    }
+    if (nw == -1) {
+      total = -1;
+      break;
+    }
+    total += nr;
+  }
+  int err = errno;
+  close(pipefd[0]);
+  close(pipefd[1]);
+  errno = err;
+  if (total == -1) {
+    failure(total, SPLICE, std::to_string(fd_in).c_str(), "");
+  } else {
+    success(total, SPLICE, copy_extra(fd_in, fd_out));
+  }
+  return total;
+}
+
+int do_ficlone(int fd_out, int fd_in) {
+  idx++;
+  touch_fd(fd_in);
+  touch_fd(fd_out);
+  int status = ioctl(fd_out, FICLONE, fd_in);
+  if (status == -1) {
+    if (errno == EOPNOTSUPP) {
+      unsupported(status, CLONE, std::to_string(fd_out).c_str(), "");
+    } else {
+      failure(status, CLONE, std::to_string(fd_out).c_str(), "");
+    }
+  } else {
+    success(status, CLONE, "");
+  }
+  return status;
+}
+
+int do_ficlonerange(int fd_out, int fd_in, off_t src_offset, off_t src_length,
+                    off_t dest_offset) {
+  idx++;
+  touch_fd(fd_in);
+  touch_fd(fd_out);
+  struct file_clone_range range = {
+      .src_fd = fd_in,
+      .src_offset = (__u64)src_offset,
+      .src_length = (__u64)src_length,
+      .dest_offset = (__u64)dest_offset,
+  };
+  int status = ioctl(fd_out, FICLONERANGE, &range);
+  if (status == -1) {
+    if (errno == EOPNOTSUPP) {
+      unsupported(status, CLONE_RANGE, std::to_string(fd_out).c_str(), "");
+    } else {
+      failure(status, CLONE_RANGE, std::to_string(fd_out).c_str(), "");
+    }
+  } else {
+    success(status, CLONE_RANGE, "");
+  }
+  return status;
+}
+
+int do_fideduperange(int fd_in, off_t src_offset, off_t src_length, int fd_out,
+                     off_t dest_offset) {
+  idx++;
+  touch_fd(fd_in);
+  touch_fd(fd_out);
+  std::vector<char> buffer(sizeof(struct file_dedupe_range) +
+                           sizeof(struct file_dedupe_range_info));
+  auto range = reinterpret_cast<struct file_dedupe_range *>(buffer.data());
+  range->src_offset = src_offset;
+  range->src_length = src_length;
+  range->dest_count = 1;
+  range->info[0].dest_fd = fd_out;
+  range->info[0].dest_offset = dest_offset;
+  int status = ioctl(fd_in, FIDEDUPERANGE, range);
+  // Error of destination is reported in its status.
+  if (status != -1 && range->info[0].status < 0) {
+    errno = -range->info[0].status;
+    status = -1;
+  }
+  if (status == -1) {
+    if (errno == EOPNOTSUPP) {
+      unsupported(status, DEDUPE_RANGE, std::to_string(fd_in).c_str(), "");
+    } else {
+      failure(status, DEDUPE_RANGE, std::to_string(fd_in).c_str(), "");
+    }
+  } else if (range->info[0].status == FILE_DEDUPE_RANGE_DIFFERS) {
+    success(status, DEDUPE_RANGE, "differs");
+  } else {
+    success(status, DEDUPE_RANGE,
+            "deduped=" + std::to_string(range->info[0].bytes_deduped));
+  }
+  return status;
+}
+
+int do_flock(int fd, int operation) {
+  idx++;
+  touch_fd(fd);
+  int status = flock(fd, operation | LOCK_NB);
+  if (status == -1 && errno == EWOULDBLOCK) {
+    success(status, FLOCK, "conflict");
+  } else if (status == -1) {
+    failure(status, FLOCK, std::to_string(fd).c_str(), "");
+  } else {
+    success(status, FLOCK, "");
+  }
+  return status;
+}
+
+static int set_lock(int fd, int cmd, const char *name, short type, off_t start,
+                    off_t len) {
+  idx++;
+  touch_fd(fd);
+  struct flock lock = {};
+  lock.l_type = type;
+  lock.l_whence = SEEK_SET;
+  lock.l_start = start;
+  lock.l_len = len;
+  int status = fcntl(fd, cmd, &lock);
+  // POSIX allows `EACCES` for conflicting lock, Linux uses `EAGAIN`.
+  if (status == -1 && (errno == EAGAIN || errno == EACCES)) {
+    success(status, name, "conflict");
+  } else if (status == -1) {
+    failure(status, name, std::to_string(fd).c_str(), "");
+  } else {
+    success(status, name, "");
+  }
+  return status;
+}
+
+int do_setlk(int fd, short type, off_t start, off_t len) {
+  return set_lock(fd, F_SETLK, SETLK, type, start, len);
+}
+
+int do_ofd_setlk(int fd, short type, off_t start, off_t len) {
+  return set_lock(fd, F_OFD_SETLK, OFD_SETLK, type, start, len);
+}
+
+int do_dup(int oldfd) {
+  idx++;
+  touch_fd(oldfd);
+  int fd = dup(oldfd);
+  if (fd == -1) {
+    failure(fd, DUP, std::to_string(oldfd).c_str(), "");
+  } else {
+    success(fd, DUP, offset_extra(fd));
+  }
+  return fd;
+}
+
+int do_dup2(int oldfd, int newfd) {
+  idx++;
+  touch_fd(oldfd);
+  int fd = dup2(oldfd, newfd);
+  if (fd == -1) {
+    failure(fd, DUP2, std::to_string(oldfd).c_str(), "");
+  } else {
+    success(fd, DUP2, offset_extra(fd));
+  }
+  return fd;
+}
+
+int do_dup3(int oldfd, int newfd, int flags) {
+  idx++;
+  touch_fd(oldfd);
+  int fd = dup3(oldfd, newfd, flags);
+  if (fd == -1) {
+    failure(fd, DUP3, std::to_string(oldfd).c_str(), "");
+  } else {
+    success(fd, DUP3, offset_extra(fd));
+  }
+  return fd;
+}
+
+int do_setxattr(const char *path, const char *name, size_t src_offset,
+                size_t size, int flags) {
+  idx++;
+  touch_path(path);
+  if (src_offset + size > BUFFER_SIZE) {
+    DPRINTF(
+        "[ERROR] offset %ld + %ld is too big to write from (buffer size is "
+        "%ld)",
+        src_offset, size, BUFFER_SIZE);
+    exit(ERROR);
+  }
+  int status = setxattr(patch_path(path).c_str(), name,
+                        &write_buffer[src_offset], size, flags);
+  if (status == -1) {
+    failure(status, SETXATTR, path, "");
+  } else {
+    success(status, SETXATTR, "");
+  }
+  return status;
+}
+
+int do_getxattr(const char *path, const char *name, size_t size) {
+  idx++;
+  touch_path(path);
+  if (size > BUFFER_SIZE) {
+    DPRINTF("[ERROR] size %ld is too big to read to (buffer size is %ld)", size,
+            BUFFER_SIZE);
+    exit(ERROR);
+  }
+  ssize_t nr = getxattr(patch_path(path).c_str(), name, read_buffer, size);
+  if (nr == -1) {
+    failure(nr, GETXATTR, path, "");
+  } else {
+    std::stringstream extra;
+    // value is not returned if size is zero
+    size_t len = size == 0 ? 0 : nr;
+    extra << "hash=" << std::hex << buffer_hashcode(read_buffer, len);
+    success(nr, GETXATTR, extra.str());
+  }
+  return nr;
+}
+
+int do_listxattr(const char *path, size_t size) {
+  idx++;
+  touch_path(path);
+  if (size > BUFFER_SIZE) {
+    DPRINTF("[ERROR] size %ld is too big to read to (buffer size is %ld)", size,
+            BUFFER_SIZE);
+    exit(ERROR);
+  }
+  ssize_t nr = listxattr(patch_path(path).c_str(), read_buffer, size);
+  if (nr == -1) {
+    failure(nr, LISTXATTR, path, "");
+  } else {
+    // order of names is not specified
+    std::vector<std::string> names;
+    if (size != 0) {
+      for (ssize_t i = 0; i < nr; i += strlen(&read_buffer[i]) + 1) {
+        names.push_back(&read_buffer[i]);
+      }
+    }
+    std::sort(names.begin(), names.end());
+    std::string extra = "names=";
+    for (size_t i = 0; i < names.size(); i++) {
+      extra += (i == 0 ? "" : " ") + names[i];
+    }
+    success(nr, LISTXATTR, extra);
+  }
+  return nr;
+}
+
+int do_removexattr(const char *path, const char *name) {
+  idx++;
+  touch_path(path);
+  int status = removexattr(patch_path(path).c_str(), name);
+  if (status == -1) {
+    failure(status, REMOVEXATTR, path, "");
+  } else {
+    success(status, REMOVEXATTR, "");
+  }
+  return status;
+}
+
+int do_chmod(const char *path, mode_t mode) {
+  idx++;
+  touch_path(path);
+  int status = chmod(patch_path(path).c_str(), mode);
+  if (status == -1) {
+    failure(status, CHMOD, path, "");
+  } else {
+    success(status, CHMOD, "");
+  }
+  return status;
+}
+
+int do_fchmod(int fd, mode_t mode) {
+  idx++;
+  touch_fd(fd);
+  int status = fchmod(fd, mode);
+  if (status == -1) {
+    failure(status, FCHMOD, std::to_string(fd).c_str(), "");
+  } else {
+    success(status, FCHMOD, "");
+  }
+  return status;
+}
+
+int do_chown(const char *path, uid_t owner, gid_t group) {
+  idx++;
+  touch_path(path);
+  int status = chown(patch_path(path).c_str(), owner, group);
+  if (status == -1) {
+    failure(status, CHOWN, path, "");
+  } else {
+    success(status, CHOWN, "");
+  }
+  return status;
+}
+
+int do_fchown(int fd, uid_t owner, gid_t group) {
+  idx++;
+  touch_fd(fd);
+  int status = fchown(fd, owner, group);
+  if (status == -1) {
+    failure(status, FCHOWN, std::to_string(fd).c_str(), "");
+  } else {
+    success(status, FCHOWN, "");
+  }
+  return status;
+}
+
+int do_utimensat(const char *path, time_t atime_sec, long atime_nsec,
+                 time_t mtime_sec, long mtime_nsec) {
+  idx++;
+  touch_path(path);
+  const struct timespec times[2] = {{atime_sec, atime_nsec},
+                                    {mtime_sec, mtime_nsec}};
+  int status = utimensat(AT_FDCWD, patch_path(path).c_str(), times, 0);
+  if (status == -1) {
+    failure(status, UTIMENSAT, path, "");
+  } else {
+    success(status, UTIMENSAT, "");
+  }
+  return status;
+}
+
+int do_futimens(int fd, time_t atime_sec, long atime_nsec, time_t mtime_sec,
+                long mtime_nsec) {
+  idx++;
+  touch_fd(fd);
+  const struct timespec times[2] = {{atime_sec, atime_nsec},
+                                    {mtime_sec, mtime_nsec}};
+  int status = futimens(fd, times);
+  if (status == -1) {
+    failure(status, FUTIMENS, std::to_string(fd).c_str(), "");
+  } else {
+    success(status, FUTIMENS, "");
+  }
+  return status;
+}
+
+int do_fallocate(int fd, int mode, off_t offset, off_t len) {
+  idx++;
+  touch_fd(fd);
+  int status = fallocate(fd, mode, offset, len);
+  if (status == -1) {
+    if (errno == EOPNOTSUPP) {
+      unsupported(status, FALLOCATE, std::to_string(fd).c_str(), "");
+    } else {
+      failure(status, FALLOCATE, std::to_string(fd).c_str(), "");
+    }
+  } else {
+    success(status, FALLOCATE, "");
+  }
+  return status;
+}
+
+/// Address of mapping, used only for logging.
+static std::string mapping_str(const char *map) {
+  char buf[32];
+  snprintf(buf, sizeof(buf), "%p", static_cast<const void *>(map));
+  return buf;
+}
+
+char *do_mmap(int fd, size_t len, off_t offset) {
+  idx++;
+  touch_fd(fd);
+  void *addr =
+      mmap(nullptr, len, PROT_READ | PROT_WRITE, MAP_SHARED, fd, offset);
+  if (addr == MAP_FAILED) {
+    failure(-1, MMAP, std::to_string(fd).c_str(), "");
+    return nullptr;
+  }
+  remember_mapping(static_cast<char *>(addr), fd);
+  success(0, MMAP, "");
+  return static_cast<char *>(addr);
+}
+
+static sigjmp_buf mapping_access_env;
+
+static void mapping_access_handler(int sig) {
+  siglongjmp(mapping_access_env, sig);
+}
+
+int do_mwrite(char *map, size_t src_offset, size_t size, size_t offset) {
+  idx++;
+  touch_map(map);
+  if (src_offset + size > BUFFER_SIZE) {
+    DPRINTF(
+        "[ERROR] offset %ld + %ld is too big to write from (buffer size is "
+        "%ld)",
+        src_offset, size, BUFFER_SIZE);
+    exit(ERROR);
+  }
+  // Access beyond the end of file raises `SIGBUS`, and access to failed
+  // mapping raises `SIGSEGV`, both are trapped to continue the test.
+  struct sigaction sa = {}, old_bus, old_segv;
+  sa.sa_handler = mapping_access_handler;
+  sigemptyset(&sa.sa_mask);
+  sigaction(SIGBUS, &sa, &old_bus);
+  sigaction(SIGSEGV, &sa, &old_segv);
+  int sig = sigsetjmp(mapping_access_env, 1);
+  if (sig == 0) {
+    memcpy(map + offset, &write_buffer[src_offset], size);
+  }
+  sigaction(SIGBUS, &old_bus, nullptr);
+  sigaction(SIGSEGV, &old_segv, nullptr);
+  if (sig != 0) {
+    signaled(sig, MWRITE, mapping_str(map).c_str());
+    return -1;
+  }
+  success(size, MWRITE, "");
+  return size;
+}
+
+int do_msync(char *map, size_t len, int flags) {
+  idx++;
+  touch_map(map);
+  int status = msync(map, len, flags);
+  if (status == -1) {
+    failure(status, MSYNC, mapping_str(map).c_str(), "");
+  } else {
+    success(status, MSYNC, "");
+  }
+  return status;
+}
+
+int do_munmap(char *map, size_t len) {
+  idx++;
+  touch_map(map);
+  int status = munmap(map, len);
+  if (status == -1) {
+    failure(status, MUNMAP, mapping_str(map).c_str(), "");
+  } else {
+    success(status, MUNMAP, "");
   }
   return status;
 }
@@ -5,7 +5,10 @@
 #pragma once
 
 #include <fcntl.h>
+#include <sys/file.h>
+#include <sys/mman.h>
 #include <sys/stat.h>
+#include <sys/xattr.h>
 
 #include <cerrno>
 #include <cstdio>
@@ -23,18 +26,148 @@ int do_create(const char *path, mode_t param);
 int do_remove(const char *path);
 /// `link` operation.
 int do_hardlink(const char *old_path, const char *new_path);
-/// `symlink` operation.
+/// `symlink` operation, only absolute target is patched.
 int do_symlink(const char *target, const char *linkpath);
+/// `mknod` operation, mode includes file type, device nodes are created with fixed device number.
+int do_mknod(const char *path, mode_t mode);
+/// `getdents64` until the end of directory, sorted names and types (`d_type`)
+/// of entries are hashed.
+int do_readdir(const char *path);
+/// `stat` operation, normalized type, mode, link count and size are reported.
+int do_stat(const char *path);
+/// `lstat` operation, size of symlink does not include workspace.
+int do_lstat(const char *path);
+/// `readlink` operation, target is reported without workspace.
+int do_readlink(const char *path);
+/// `fstat` operation.
+int do_fstat(int fd);
 /// `rename` operation.
 int do_rename(const char *old_path, const char *new_path);
-/// `open` operation. TODO: flags
-int do_open(const char *path);
+/// `renameat2` operation, `RENAME_*` flags can be used.
+int do_renameat2(const char *old_path, const char *new_path,
+                 unsigned int flags);
+/// `linkat` operation with `AT_EMPTY_PATH`, links file of the descriptor
+/// (e.g. opened with `O_TMPFILE`).
+int do_flink(int fd, const char *path);
+/// `open` operation, mode is only used when file is created.
+/// `EOPNOTSUPP` is reported if filesystem does not support `O_TMPFILE`.
+int do_open(const char *path, int flags, mode_t mode);
 /// `close` operation.
 int do_close(int fd);
+/// `openat` operation for directories (`O_DIRECTORY`, possibly with `O_PATH`),
+/// descriptor is closed with `do_close`.
+int do_opendir(int dirfd, const char *path, int flags);
+/// `chdir` operation, relative path is resolved from current working directory
+/// (workspace initially).
+int do_chdir(const char *path);
+/// `fchdir` operation.
+int do_fchdir(int fd);
+/// `mkdirat` operation, `dirfd` is `AT_FDCWD` or directory descriptor.
+/// Absolute path is patched, relative path is resolved from `dirfd`.
+int do_mkdirat(int dirfd, const char *path, mode_t param);
+/// `openat` operation, same as `do_open` otherwise.
+int do_openat(int dirfd, const char *path, int flags, mode_t mode);
+/// `unlinkat` operation, `AT_REMOVEDIR` can be used.
+int do_unlinkat(int dirfd, const char *path, int flags);
+/// `renameat` operation.
+int do_renameat(int olddirfd, const char *old_path, int newdirfd,
+                const char *new_path);
+/// `fstatat` operation, `AT_SYMLINK_NOFOLLOW` and `AT_EMPTY_PATH` can be used.
+/// Reported same as `do_stat`/`do_lstat`.
+int do_fstatat(int dirfd, const char *path, int flags);
+/// `umask` operation, always succeeds.
+int do_umask(mode_t mask);
+/// `ioctl(FS_IOC_SETFLAGS)` operation with `FS_CASEFOLD_FL`, directory must be empty.
+int do_set_casefold(const char *path);
 /// `write` operation, but instead of char buffer, position inside some "source" buffer is used.
 int do_write(int fd, size_t src_offset, size_t size);
 /// `read` operation, but same read buffer is used.
 int do_read(int fd, size_t size);
 /// `fsync` operation.
 int do_fsync(int fd);
+/// `fdatasync` operation.
+int do_fdatasync(int fd);
+/// `sync` operation, always succeeds.
+int do_sync();
+/// `syncfs` operation.
+int do_syncfs(int fd);
+/// `sync_file_range` operation, `SYNC_FILE_RANGE_*` flags can be used.
+int do_sync_file_range(int fd, off_t offset, off_t nbytes, unsigned int flags);
+/// `truncate` operation.
+int do_truncate(const char *path, off_t size);
+/// `ftruncate` operation.
+int do_ftruncate(int fd, off_t size);
+/// `lseek` operation.
+off_t do_lseek(int fd, off_t offset, int whence);
+/// `pread` operation, but same read buffer is used.
+int do_pread(int fd, size_t size, off_t offset);
+/// `pwrite` operation, but instead of char buffer, position inside some "source" buffer is used.
+int do_pwrite(int fd, size_t src_offset, size_t size, off_t offset);
+/// `copy_file_range` operation, offset of descriptor is used if explicit
+/// offset is negative.
+int do_copy_file_range(int fd_in, off_t off_in, int fd_out, off_t off_out,
+                       size_t len);
+/// `sendfile` operation, offset of input descriptor is used if explicit offset
+/// is negative.
+int do_sendfile(int out_fd, int in_fd, off_t offset, size_t count);
+/// `splice` from file to pipe and from pipe to file until all data is moved,
+/// offset of descriptor is used if explicit offset is negative.
+int do_splice(int fd_in, off_t off_in, int fd_out, off_t off_out, size_t len);
+/// `ioctl(FICLONE)` operation, whole file is cloned.
+int do_ficlone(int fd_out, int fd_in);
+/// `ioctl(FICLONERANGE)` operation, zero length means the end of input file.
+int do_ficlonerange(int fd_out, int fd_in, off_t src_offset, off_t src_length,
+                    off_t dest_offset);
+/// `ioctl(FIDEDUPERANGE)` operation with single destination, number of
+/// deduplicated bytes or difference of ranges is reported.
+int do_fideduperange(int fd_in, off_t src_offset, off_t src_length, int fd_out,
+                     off_t dest_offset);
+/// `flock` operation, `LOCK_NB` is always added and conflicting lock is
+/// reported instead of `EWOULDBLOCK`.
+int do_flock(int fd, int operation);
+/// `fcntl(F_SETLK)` operation, start of lock is relative to the beginning of
+/// file (`SEEK_SET`), conflicting lock is reported instead of `EAGAIN`.
+int do_setlk(int fd, short type, off_t start, off_t len);
+/// `fcntl(F_OFD_SETLK)` operation, same as `do_setlk`, but lock is owned by
+/// open file description.
+int do_ofd_setlk(int fd, short type, off_t start, off_t len);
+/// `dup` operation.
+int do_dup(int oldfd);
+/// `dup2` operation.
+int do_dup2(int oldfd, int newfd);
+/// `dup3` operation.
+int do_dup3(int oldfd, int newfd, int flags);
+/// `setxattr` operation, but instead of value buffer, position inside some "source" buffer is used.
+int do_setxattr(const char *path, const char *name, size_t src_offset,
+                size_t size, int flags);
+/// `getxattr` operation, but same read buffer is used.
+int do_getxattr(const char *path, const char *name, size_t size);
+/// `listxattr` operation, but same read buffer is used.
+int do_listxattr(const char *path, size_t size);
+/// `removexattr` operation.
+int do_removexattr(const char *path, const char *name);
+/// `chmod` operation.
+int do_chmod(const char *path, mode_t mode);
+/// `fchmod` operation.
+int do_fchmod(int fd, mode_t mode);
+/// `chown` operation, `-1` leaves the id unchanged.
+int do_chown(const char *path, uid_t owner, gid_t group);
+/// `fchown` operation, `-1` leaves the id unchanged.
+int do_fchown(int fd, uid_t owner, gid_t group);
+/// `utimensat` operation, `UTIME_NOW` and `UTIME_OMIT` can be used as nanoseconds.
+int do_utimensat(const char *path, time_t atime_sec, long atime_nsec,
+                 time_t mtime_sec, long mtime_nsec);
+/// `futimens` operation, `UTIME_NOW` and `UTIME_OMIT` can be used as nanoseconds.
+int do_futimens(int fd, time_t atime_sec, long atime_nsec, time_t mtime_sec,
+                long mtime_nsec);
+/// `fallocate` operation, `EOPNOTSUPP` is reported for modes unsupported by filesystem.
+int do_fallocate(int fd, int mode, off_t offset, off_t len);
+/// `mmap` operation, file region is mapped with `MAP_SHARED` for reading and writing.
+char *do_mmap(int fd, size_t len, off_t offset);
+/// Write to mapping from "source" buffer, `SIGBUS` and `SIGSEGV` are reported instead of crashing.
+int do_mwrite(char *map, size_t src_offset, size_t size, size_t offset);
+/// `msync` operation.
+int do_msync(char *map, size_t len, int flags);
+/// `munmap` operation.
+int do_munmap(char *map, size_t len);
 }